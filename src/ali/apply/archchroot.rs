@@ -1,4 +1,7 @@
-use crate::ali::Manifest;
+use crate::ali::{
+    Manifest,
+    ManifestCmd,
+};
 use crate::constants::defaults;
 use crate::errors::AliError;
 use crate::hooks;
@@ -16,12 +19,15 @@ pub fn chroot_ali(
 ) -> Result<Vec<ActionChrootAli>, AliError> {
     let mut actions = Vec::new();
 
-    let (action_tz, cmd_tz) = cmd_link_timezone(&manifest.timezone);
-    if let Err(err) = shell::arch_chroot(location, &cmd_tz) {
-        return Err(map_err_chroot_ali(err, action_tz, actions));
-    }
+    // Without /etc/localtime, the new system uses UTC
+    if let Some(tz) = &manifest.timezone {
+        let (action_tz, cmd_tz) = cmd_link_timezone(tz);
+        if let Err(err) = shell::arch_chroot(location, &cmd_tz) {
+            return Err(map_err_chroot_ali(err, action_tz, actions));
+        }
 
-    actions.push(action_tz);
+        actions.push(action_tz);
+    }
 
     let cmd_locale_gen = cmd_locale_gen();
     let action_locale_gen = ActionChrootAli::LocaleGen;
@@ -34,32 +40,68 @@ pub fn chroot_ali(
     Ok(actions)
 }
 
+/// Runs user `chroot` commands and hooks in order.
+/// Actions of commands in labeled groups are wrapped in
+/// [`ActionChrootUser::Group`], so that failures name the group.
 pub fn chroot_user<'a, I>(
     cmds: I,
     location: &str,
 ) -> Result<Vec<ActionChrootUser>, AliError>
 where
-    I: Iterator<Item = &'a String>,
+    I: Iterator<Item = (Option<&'a str>, &'a ManifestCmd)>,
 {
     let mut actions = Vec::new();
 
-    for cmd in cmds {
+    for (label, manifest_cmd) in cmds {
+        let cmd = manifest_cmd.cmd();
+
+        let with_label = |action: ActionChrootUser| {
+            match label {
+                Some(label) => {
+                    ActionChrootUser::Group {
+                        label: label.to_string(),
+                        action: Box::new(action),
+                    }
+                }
+                None => action,
+            }
+        };
+
         if hooks::is_hook(cmd) {
-            let action_hook = hooks::apply_hook(
+            match hooks::apply_hook(
                 cmd,
                 hooks::Caller::ManifestChroot,
                 location,
-            )?;
-
-            actions.push(ActionChrootUser::Hook(action_hook));
+            ) {
+                Ok(action_hook) => {
+                    actions
+                        .push(with_label(ActionChrootUser::Hook(action_hook)))
+                }
+
+                // Hook errors are only wrapped when there's a group to name
+                Err(err) if label.is_some() => {
+                    let action_failed =
+                        with_label(ActionChrootUser::HookCmd(cmd.to_string()));
+
+                    return Err(map_err_chroot_user(
+                        err,
+                        action_failed,
+                        actions,
+                    ));
+                }
+
+                Err(err) => return Err(err),
+            }
 
             continue;
         }
 
         let action_user_cmd =
-            ActionChrootUser::UserArchChrootCmd(cmd.to_string());
+            with_label(ActionChrootUser::UserArchChrootCmd(cmd.to_string()));
 
-        if let Err(err) = shell::arch_chroot(location, cmd) {
+        if let Err(err) =
+            shell::arch_chroot_env(location, cmd, manifest_cmd.env())
+        {
             return Err(map_err_chroot_user(err, action_user_cmd, actions));
         }
 
@@ -69,17 +111,70 @@ where
     Ok(actions)
 }
 
-fn cmd_link_timezone(tz: &Option<String>) -> (ActionChrootAli, String) {
-    let tz = tz.clone().unwrap_or(defaults::TIMEZONE.to_string());
+pub(super) fn cmd_link_timezone(tz: &str) -> (ActionChrootAli, String) {
     let tz_cmd = format!("ln -s /usr/share/zoneinfo/{} /etc/localtime", tz);
 
-    (ActionChrootAli::LinkTimezone(tz), tz_cmd)
+    (ActionChrootAli::LinkTimezone(tz.to_string()), tz_cmd)
 }
 
-// Appends defaults::DEFAULT_LOCALE_GEN to /etc/locale.gen
-fn cmd_locale_gen() -> String {
-    format!(
+// Appends defaults::DEFAULT_LOCALE_GEN to /etc/locale.gen,
+// wrapped in sh -c so that both commands run inside the chroot
+pub(super) fn cmd_locale_gen() -> String {
+    shell::cmd_sh_c(&format!(
         "echo {} >> /etc/locale.gen && locale-gen",
-        defaults::LOCALE_GEN
-    )
+        shell::quote(defaults::LOCALE_GEN),
+    ))
+}
+
+#[test]
+fn test_chroot_user_group_failure() {
+    use crate::types::action::Action;
+
+    let cmds = [(Some("network"), ManifestCmd::from("ali-rs-no-such-command"))];
+
+    let result = chroot_user(
+        cmds.iter().map(|(label, cmd)| (*label, cmd)),
+        "/ali-rs-no-such-location",
+    );
+
+    let Err(AliError::ApplyError { action_failed, .. }) = result else {
+        panic!("unexpected result: {result:?}");
+    };
+
+    match *action_failed {
+        Action::ChrootUser(ActionChrootUser::Group { label, action }) => {
+            assert_eq!("network", label);
+            assert!(matches!(
+                *action,
+                ActionChrootUser::UserArchChrootCmd(ref cmd) if cmd == "ali-rs-no-such-command"
+            ));
+        }
+        action => panic!("unexpected failed action {action:?}"),
+    }
+}
+
+#[test]
+fn test_chroot_user_group_hook_failure() {
+    use crate::types::action::Action;
+
+    let cmds = [(Some("network"), ManifestCmd::from("@ali-rs-no-such-hook"))];
+
+    let result = chroot_user(
+        cmds.iter().map(|(label, cmd)| (*label, cmd)),
+        "/ali-rs-no-such-location",
+    );
+
+    let Err(AliError::ApplyError { action_failed, .. }) = result else {
+        panic!("unexpected result: {result:?}");
+    };
+
+    match *action_failed {
+        Action::ChrootUser(ActionChrootUser::Group { action, .. }) => {
+            assert!(matches!(
+                *action,
+                ActionChrootUser::HookCmd(ref cmd) if cmd == "@ali-rs-no-such-hook"
+            ));
+        }
+        action => panic!("unexpected failed action {action:?}"),
+    }
 }
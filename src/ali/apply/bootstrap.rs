@@ -1,29 +1,95 @@
-use std::collections::HashSet;
+use std::collections::{
+    BTreeSet,
+    HashSet,
+};
 
 use crate::errors::AliError;
-use crate::utils::shell;
+use crate::linux::pacman;
+use crate::utils::{
+    fs,
+    shell,
+};
 
 pub fn pacstrap_to_location(
     pacstraps: &Option<HashSet<String>>,
     location: &str,
+    strict_signatures: bool,
+    cachedir: Option<&str>,
 ) -> Result<(), AliError> {
-    // Collect packages, with base as bare-minimum
-    let mut packages = HashSet::from(["base".to_string()]);
+    if !strict_signatures {
+        return shell::sh_c(&cmd_pacstrap(pacstraps, location, None, cachedir));
+    }
+
+    // Private dir, so that no other user can swap the conf
+    let dir = fs::mktemp_dir()?;
+    let conf = format!("{dir}/pacman.conf");
+
+    let result = pacman::prepare_strict_conf(pacman::PACMAN_CONF, &conf)
+        .and_then(|()| {
+            shell::sh_c(&cmd_pacstrap(
+                pacstraps,
+                location,
+                Some(&conf),
+                cachedir,
+            ))
+        });
+
+    _ = std::fs::remove_dir_all(&dir);
+
+    result
+}
+
+/// Returns pacstrap command, with packages sorted
+/// and `base` as bare-minimum.
+///
+/// `cachedir` is passed on to pacman as `--cachedir`,
+/// so it must come after `location`.
+pub fn cmd_pacstrap(
+    pacstraps: &Option<HashSet<String>>,
+    location: &str,
+    pacman_conf: Option<&str>,
+    cachedir: Option<&str>,
+) -> String {
+    let mut packages = BTreeSet::from(["base".to_string()]);
 
     if let Some(pacstraps) = pacstraps.clone() {
         packages.extend(pacstraps);
     }
 
-    let cmd_pacstrap = {
-        let mut cmd_parts = vec![
-            "pacstrap".to_string(),
-            "-K".to_string(),
-            location.to_string(),
-        ];
+    let mut cmd_parts = vec!["pacstrap".to_string()];
+    if let Some(conf) = pacman_conf {
+        cmd_parts.extend(["-C".to_string(), conf.to_string()]);
+    }
+
+    cmd_parts.extend(["-K".to_string(), location.to_string()]);
+    if let Some(cachedir) = cachedir {
+        cmd_parts.extend(["--cachedir".to_string(), cachedir.to_string()]);
+    }
+
+    cmd_parts.extend(packages);
+    cmd_parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        cmd_parts.extend(packages);
-        cmd_parts.join(" ")
-    };
+    #[test]
+    fn test_cmd_pacstrap() {
+        let pacstraps = Some(HashSet::from(["vim".to_string()]));
 
-    shell::sh_c(&cmd_pacstrap)
+        assert_eq!(
+            "pacstrap -K /alitarget base vim",
+            cmd_pacstrap(&pacstraps, "/alitarget", None, None),
+        );
+        assert_eq!(
+            "pacstrap -C /tmp/pacman.conf -K /alitarget --cachedir /srv/pkg base vim",
+            cmd_pacstrap(
+                &pacstraps,
+                "/alitarget",
+                Some("/tmp/pacman.conf"),
+                Some("/srv/pkg"),
+            ),
+        );
+    }
 }
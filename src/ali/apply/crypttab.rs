@@ -0,0 +1,199 @@
+use std::io::Write;
+
+use crate::ali::{
+    Dm,
+    Manifest,
+    ManifestLuks,
+};
+use crate::errors::AliError;
+
+/// Appends crypttab(5) entries for manifest LUKS devices
+/// with `crypttab` enabled to `{install_location}/etc/crypttab`
+pub fn append_crypttab(
+    manifest: &Manifest,
+    install_location: &str,
+) -> Result<(), AliError> {
+    let entries = crypttab_entries(manifest);
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let etc_crypttab = format!("{install_location}/etc/crypttab");
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&etc_crypttab)
+        .and_then(|mut f| write!(f, "{entries}"))
+        .map_err(|err| {
+            AliError::FileError(
+                err,
+                format!("failed to append to {etc_crypttab}"),
+            )
+        })
+}
+
+/// Copies detached header files of LUKS devices with `crypttab`
+/// enabled to the same paths in the new system at `install_location`,
+/// since crypttab(5) `header=` is looked up there on boot
+pub fn copy_headers(
+    manifest: &Manifest,
+    install_location: &str,
+) -> Result<(), AliError> {
+    for header in header_files(manifest) {
+        let target = format!("{install_location}{header}");
+
+        if let Some(parent) = std::path::Path::new(&target).parent() {
+            std::fs::create_dir_all(parent).map_err(|err| {
+                AliError::FileError(
+                    err,
+                    format!("failed to create dir {}", parent.display()),
+                )
+            })?;
+        }
+
+        std::fs::copy(header, &target).map_err(|err| {
+            AliError::FileError(
+                err,
+                format!("failed to copy luks header {header} to {target}"),
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Returns detached header files of LUKS devices with `crypttab`
+/// enabled. Headers on block devices are used as is.
+pub fn header_files(manifest: &Manifest) -> Vec<&str> {
+    manifest
+        .device_mappers
+        .iter()
+        .flatten()
+        .filter_map(|dm| {
+            match dm {
+                Dm::Luks(luks) if luks.crypttab.unwrap_or(false) => {
+                    luks.header.as_deref()
+                }
+                _ => None,
+            }
+        })
+        .filter(|header| !header.starts_with("/dev/"))
+        .collect()
+}
+
+/// Returns crypttab(5) entries for LUKS devices with `crypttab` enabled.
+/// Passphrases are always prompted for on boot.
+pub fn crypttab_entries(manifest: &Manifest) -> String {
+    manifest
+        .device_mappers
+        .iter()
+        .flatten()
+        .filter_map(|dm| {
+            match dm {
+                Dm::Luks(luks) if luks.crypttab.unwrap_or(false) => {
+                    Some(crypttab_entry(luks))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Returns crypttab(5) entry for `luks`. LUKS devices with detached
+/// headers have no LUKS UUID, so the device is referred to by path.
+fn crypttab_entry(luks: &ManifestLuks) -> String {
+    let options = match &luks.header {
+        Some(header) => format!("luks,header={header}"),
+        None => "luks".to_string(),
+    };
+
+    format!("{}\t{}\tnone\t{options}\n", luks.name, luks.device)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crypttab_entries() {
+        let manifest = Manifest::from_yaml(
+            r#"
+device_mappers:
+  - type: luks
+    device: /dev/sda2
+    name: cryptroot
+  - type: luks
+    device: /dev/sdb1
+    name: cryptdata
+    header: /dev/disk/by-id/usb-key-part1
+    crypttab: true
+  - type: luks
+    device: /dev/sdc1
+    name: cryptswap
+    crypttab: true
+rootfs:
+  device: /dev/mapper/cryptroot
+  fstype: ext4
+"#,
+        )
+        .expect("failed to parse manifest");
+
+        let entries = crypttab_entries(&manifest);
+        let lines: Vec<&str> = entries.lines().collect();
+
+        assert_eq!(
+            vec![
+                "cryptdata\t/dev/sdb1\tnone\tluks,header=/dev/disk/by-id/usb-key-part1",
+                "cryptswap\t/dev/sdc1\tnone\tluks",
+            ],
+            lines,
+        );
+
+        assert!(!entries.contains("cryptroot"));
+    }
+
+    #[test]
+    fn test_copy_headers() {
+        let dir = std::env::temp_dir().join("ali-rs-test-luks-headers");
+        _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let header = dir.join("data.header");
+        std::fs::write(&header, "luks header").unwrap();
+        let header = header.to_str().unwrap();
+
+        let manifest = Manifest::from_yaml(&format!(
+            r#"
+device_mappers:
+  - type: luks
+    device: /dev/sdb1
+    name: cryptdata
+    header: {header}
+    crypttab: true
+  - type: luks
+    device: /dev/sdc1
+    name: cryptusb
+    header: /dev/disk/by-id/usb-key-part1
+    crypttab: true
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+"#
+        ))
+        .expect("failed to parse manifest");
+
+        // Header devices are not copied
+        assert_eq!(vec![header], header_files(&manifest));
+
+        let location = dir.join("alitarget");
+        let location = location.to_str().unwrap();
+        copy_headers(&manifest, location).expect("failed to copy headers");
+
+        assert_eq!(
+            "luks header",
+            std::fs::read_to_string(format!("{location}{header}")).unwrap(),
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
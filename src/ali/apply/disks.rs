@@ -54,6 +54,7 @@ pub fn apply_disk(
     // Actions:
     // 1. Create partition
     // 2. Set partition type
+    // 3. Set partition GUID (GPT only)
     for (n, part) in disk.partitions.iter().enumerate() {
         let partition_number = n + 1;
         let cmd_create_part =
@@ -75,6 +76,14 @@ pub fn apply_disk(
 
         actions.push(action_create_partition);
 
+        // fdisk already created extended partition with its type
+        let extended = disk.table == ali::PartitionTable::Mbr
+            && fdisk::is_extended_type(&part.part_type);
+
+        if extended {
+            continue;
+        }
+
         let action_set_part_type = ActionMountpoints::SetPartitionType {
             device: disk.device.clone(),
             number: partition_number,
@@ -94,6 +103,28 @@ pub fn apply_disk(
         }
 
         actions.push(action_set_part_type);
+
+        let Some(ref uuid) = part.uuid else {
+            continue;
+        };
+
+        let action_set_part_uuid = ActionMountpoints::SetPartitionUuid {
+            device: disk.device.clone(),
+            number: partition_number,
+            uuid: uuid.clone(),
+        };
+
+        let cmd_set_uuid =
+            fdisk::set_partition_uuid_cmd(partition_number, uuid);
+        if let Err(err) = fdisk::run_fdisk_cmd(&disk.device, &cmd_set_uuid) {
+            return Err(map_err_mountpoints(
+                err,
+                action_set_part_uuid,
+                actions,
+            ));
+        }
+
+        actions.push(action_set_part_uuid);
     }
 
     Ok(actions)
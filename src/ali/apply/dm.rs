@@ -1,3 +1,9 @@
+use std::path::Path;
+use std::time::{
+    Duration,
+    Instant,
+};
+
 use crate::ali::{
     Dm,
     ManifestLuks,
@@ -6,9 +12,15 @@ use crate::ali::{
 use crate::errors::AliError;
 use crate::linux;
 use crate::types::action::ActionMountpoints;
+use crate::types::blockdev::vg_lv_name;
+use crate::utils::secret;
 
 use super::map_err::map_err_mountpoints;
 
+// How long to wait for udev to create device nodes for new DMs
+const NODES_TIMEOUT: Duration = Duration::from_secs(10);
+const NODES_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 pub fn apply_dms(dms: &[Dm]) -> Result<Vec<ActionMountpoints>, AliError> {
     let mut actions = Vec::new();
 
@@ -32,17 +44,32 @@ pub fn apply_dms(dms: &[Dm]) -> Result<Vec<ActionMountpoints>, AliError> {
 pub fn apply_dm(dm: &Dm) -> Result<Vec<ActionMountpoints>, AliError> {
     let mut actions = Vec::new();
     match dm {
-        Dm::Luks(ManifestLuks {
-            device,
-            passphrase,
-            name,
-        }) => {
-            let passphrase = passphrase.as_ref().map(|p| p.as_str());
+        Dm::Luks(luks) => {
+            let ManifestLuks {
+                device,
+                passphrase,
+                name,
+                header,
+                ..
+            } = luks;
+
             let action_create = ActionMountpoints::CreateDmLuks {
                 device: device.clone(),
             };
 
-            linux::luks::format(device, passphrase)?;
+            let passphrase =
+                match passphrase.as_deref().map(secret::resolve).transpose() {
+                    Ok(passphrase) => passphrase,
+                    Err(err) => {
+                        return Err(map_err_mountpoints(
+                            err,
+                            action_create,
+                            actions,
+                        ));
+                    }
+                };
+
+            linux::luks::format(luks)?;
             actions.push(action_create);
 
             let action_open = ActionMountpoints::OpenDmLuks {
@@ -50,7 +77,12 @@ pub fn apply_dm(dm: &Dm) -> Result<Vec<ActionMountpoints>, AliError> {
                 name: name.clone(),
             };
 
-            linux::luks::open(device, passphrase, name)?;
+            linux::luks::open(
+                device,
+                passphrase.as_deref(),
+                name,
+                header.as_deref(),
+            )?;
             actions.push(action_open);
         }
 
@@ -95,5 +127,163 @@ pub fn apply_dm(dm: &Dm) -> Result<Vec<ActionMountpoints>, AliError> {
         }
     }
 
+    // Later stages (e.g. mkfs) need the new device nodes to exist
+    let nodes = dm_nodes(dm);
+    if !nodes.is_empty() {
+        let settle = || {
+            linux::udevadm::settle()?;
+
+            if matches!(dm, Dm::Lvm(_)) {
+                linux::lvm::scan_vgs()?;
+            }
+
+            Ok(())
+        };
+
+        let result = wait_for_nodes(
+            &nodes,
+            settle,
+            |node| Path::new(node).exists(),
+            NODES_TIMEOUT,
+            NODES_POLL_INTERVAL,
+        );
+
+        let action_settle = ActionMountpoints::SettleDevices(nodes);
+        if let Err(err) = result {
+            return Err(map_err_mountpoints(err, action_settle, actions));
+        }
+
+        actions.push(action_settle);
+    }
+
     Ok(actions)
 }
+
+/// Returns device nodes expected to appear after `dm` is applied
+fn dm_nodes(dm: &Dm) -> Vec<String> {
+    match dm {
+        Dm::Luks(luks) => vec![format!("/dev/mapper/{}", luks.name)],
+        Dm::Lvm(lvm) => {
+            lvm.lvs
+                .iter()
+                .flatten()
+                .map(|lv| vg_lv_name(lv).1)
+                .collect()
+        }
+    }
+}
+
+/// Runs `settle`, and then polls with `exists` until all `nodes`
+/// exist. Nodes still missing after `timeout` are returned in error.
+fn wait_for_nodes<S, E>(
+    nodes: &[String],
+    mut settle: S,
+    exists: E,
+    timeout: Duration,
+    interval: Duration,
+) -> Result<(), AliError>
+where
+    S: FnMut() -> Result<(), AliError>,
+    E: Fn(&str) -> bool,
+{
+    settle()?;
+
+    let start = Instant::now();
+    loop {
+        let missing: Vec<&str> = nodes
+            .iter()
+            .map(|node| node.as_str())
+            .filter(|node| !exists(node))
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        if start.elapsed() >= timeout {
+            return Err(AliError::NoSuchDevice(format!(
+                "device nodes {missing:?} did not appear after {timeout:?}"
+            )));
+        }
+
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::ali::Manifest;
+
+    #[test]
+    fn test_wait_for_nodes() {
+        let manifest = Manifest::from_yaml(
+            r#"
+device_mappers:
+  - type: lvm
+    pvs:
+      - /dev/fake1p2
+    vgs:
+      - name: fakevg
+        pvs:
+          - /dev/fake1p2
+    lvs:
+      - name: swaplv
+        vg: fakevg
+        size: 8GiB
+      - name: rootlv
+        vg: fakevg
+rootfs:
+  device: /dev/fakevg/rootlv
+  fstype: btrfs
+"#,
+        )
+        .expect("failed to parse manifest");
+
+        let dm = &manifest.device_mappers.unwrap()[0];
+        let nodes = dm_nodes(dm);
+        assert_eq!(vec!["/dev/fakevg/swaplv", "/dev/fakevg/rootlv"], nodes);
+
+        // Nodes only appear some time after settle is invoked
+        let settled = Cell::new(false);
+        let polls = Cell::new(0);
+        let result = wait_for_nodes(
+            &nodes,
+            || {
+                settled.set(true);
+                Ok(())
+            },
+            |_| {
+                assert!(settled.get(), "polled before settle");
+                polls.set(polls.get() + 1);
+                polls.get() > 3
+            },
+            Duration::from_secs(5),
+            Duration::from_millis(1),
+        );
+
+        if let Err(err) = result {
+            panic!("unexpected error: {err}");
+        }
+
+        assert!(settled.get());
+
+        let result = wait_for_nodes(
+            &nodes,
+            || Ok(()),
+            |node| node != "/dev/fakevg/rootlv",
+            Duration::from_millis(20),
+            Duration::from_millis(1),
+        );
+
+        match result {
+            Err(AliError::NoSuchDevice(msg)) => {
+                assert!(msg.contains("/dev/fakevg/rootlv"));
+                assert!(!msg.contains("/dev/fakevg/swaplv"));
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+}
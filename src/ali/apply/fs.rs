@@ -1,31 +1,240 @@
 use crate::ali::{
+    Manifest,
     ManifestFs,
     ManifestMountpoint,
+    ManifestOverlay,
+    ManifestSwap,
 };
 use crate::errors::AliError;
 use crate::linux;
 use crate::types::action::ActionMountpoints;
+use crate::types::warning::Warning;
+use crate::utils::{
+    self,
+    shell,
+};
 
 use super::map_err::map_err_mountpoints;
 
 pub fn create_filesystem(
     filesystem: &ManifestFs,
+    warnings: &mut Vec<Warning>,
 ) -> Result<ActionMountpoints, AliError> {
-    linux::mkfs::create_fs(filesystem)?;
+    #[cfg(feature = "loop-image")]
+    let output = match linux::losetup::is_image(&filesystem.device) {
+        true => create_fs_image(filesystem)?,
+        false => linux::mkfs::create_fs(filesystem)?,
+    };
+
+    #[cfg(not(feature = "loop-image"))]
+    let output = linux::mkfs::create_fs(filesystem)?;
+
+    let uuid = created_uuid(
+        &filesystem.device,
+        output.as_deref(),
+        linux::blkid::fs_uuid,
+        warnings,
+    );
 
     Ok(ActionMountpoints::CreateFs {
         device: filesystem.device.clone(),
         fs_type: filesystem.fs_type.clone(),
         fs_opts: filesystem.fs_opts.clone(),
+        uuid,
+        output: output.as_deref().map(linux::mkfs::output_summary),
     })
 }
 
+/// Returns UUID of the filesystem just created on `device`, parsed from
+/// mkfs `output`. If mkfs output was not captured or has no UUID, e.g.
+/// when mkfs ran on a terminal, the UUID is read with `blkid` instead,
+/// and `blkid` errors are pushed to `warnings`.
+fn created_uuid(
+    device: &str,
+    output: Option<&str>,
+    blkid: impl FnOnce(&str) -> Result<Option<String>, AliError>,
+    warnings: &mut Vec<Warning>,
+) -> Option<String> {
+    if let Some(uuid) = output.and_then(linux::mkfs::parse_uuid) {
+        return Some(uuid);
+    }
+
+    match blkid(device) {
+        Ok(uuid) => uuid,
+        Err(err) => {
+            warnings.push(Warning::new(
+                "mkfs",
+                format!("failed to read UUID of {device}: {err}"),
+            ));
+
+            None
+        }
+    }
+}
+
+/// Runs tune2fs(8) on `filesystem.device` if `tune2fs_args` is set.
+/// Must be called after the filesystem is created.
+pub fn tune_filesystem(
+    filesystem: &ManifestFs,
+) -> Result<Option<ActionMountpoints>, AliError> {
+    let Some(args) = &filesystem.tune2fs_args else {
+        return Ok(None);
+    };
+
+    linux::tune2fs::tune(&filesystem.device, args)?;
+
+    Ok(Some(ActionMountpoints::TuneFs {
+        device: filesystem.device.clone(),
+        args: args.clone(),
+    }))
+}
+
+/// Creates btrfs subvolumes on rootfs and other filesystems
+/// with `subvolumes`, and sets their `default_subvol`.
+/// Must be called after all filesystems are created.
+pub fn create_subvolumes(
+    manifest: &Manifest,
+) -> Result<Vec<ActionMountpoints>, AliError> {
+    let rootfs: ManifestFs = manifest.rootfs.clone().into();
+    let filesystems = std::iter::once(&rootfs)
+        .chain(manifest.filesystems.iter().flatten())
+        .filter(|fs| fs.subvolumes.is_some());
+
+    let mut actions = Vec::new();
+    for fs in filesystems {
+        // Private mountpoint, so that nothing else can meddle with it
+        let dir = match utils::fs::mktemp_dir() {
+            Ok(dir) => dir,
+            Err(err) => {
+                let tmp = std::env::temp_dir().to_string_lossy().to_string();
+                let action_mkdir = ActionMountpoints::MkdirFs(tmp);
+
+                return Err(map_err_mountpoints(err, action_mkdir, actions));
+            }
+        };
+
+        let mnt = subvol_mountpoint(fs, &dir);
+        let action_mount = ActionMountpoints::MountFs {
+            src: mnt.device.clone(),
+            dst: mnt.dest.clone(),
+            opts: mnt.mnt_opts.clone(),
+        };
+
+        if let Err(err) = linux::mount::mount_fs(&mnt, None, "") {
+            _ = std::fs::remove_dir(&dir);
+            return Err(map_err_mountpoints(err, action_mount, actions));
+        }
+
+        // Always unmount, even if creating subvolumes failed
+        let result = btrfs_subvolumes(fs, &mnt.dest, actions);
+        let umounted = shell::exec("umount", &[&mnt.dest]);
+
+        actions = result?;
+
+        if let Err(err) = umounted {
+            return Err(map_err_mountpoints(err, action_mount, actions));
+        }
+
+        _ = std::fs::remove_dir(&dir);
+    }
+
+    Ok(actions)
+}
+
+/// Returns mountpoint for mounting `fs` on `dir` to create subvolumes
+pub(super) fn subvol_mountpoint(
+    fs: &ManifestFs,
+    dir: &str,
+) -> ManifestMountpoint {
+    let mnt = ManifestMountpoint {
+        device: fs.device.clone(),
+        dest: dir.to_string(),
+        mnt_opts: None,
+    };
+
+    #[cfg(feature = "loop-image")]
+    let mnt = ManifestMountpoint {
+        mnt_opts: linux::losetup::is_image(&mnt.device)
+            .then(|| "loop".to_string()),
+        ..mnt
+    };
+
+    mnt
+}
+
+fn btrfs_subvolumes(
+    fs: &ManifestFs,
+    mountpoint: &str,
+    mut actions: Vec<ActionMountpoints>,
+) -> Result<Vec<ActionMountpoints>, AliError> {
+    for subvol in fs.subvolumes.iter().flatten() {
+        let action_create_subvol = ActionMountpoints::BtrfsCreateSubvol {
+            device: fs.device.clone(),
+            subvol: subvol.clone(),
+        };
+
+        if let Err(err) = linux::btrfs::subvolume_create(mountpoint, subvol) {
+            return Err(map_err_mountpoints(
+                err,
+                action_create_subvol,
+                actions,
+            ));
+        }
+
+        actions.push(action_create_subvol);
+    }
+
+    if let Some(subvol) = &fs.default_subvol {
+        let action_set_default = ActionMountpoints::BtrfsSetDefaultSubvol {
+            device: fs.device.clone(),
+            subvol: subvol.clone(),
+        };
+
+        if let Err(err) =
+            linux::btrfs::subvolume_set_default(mountpoint, subvol)
+        {
+            return Err(map_err_mountpoints(err, action_set_default, actions));
+        }
+
+        actions.push(action_set_default);
+    }
+
+    Ok(actions)
+}
+
 // mount_filesystem lets callers override mountpoint with `mountpoint`.
+// Filesystems with mount helpers (see `fs_type`) are mounted by the helper.
 pub fn mount_filesystem(
     mnt: &ManifestMountpoint,
+    fs_type: Option<&str>,
     base: &str,
 ) -> Result<ActionMountpoints, AliError> {
-    linux::mount::mount(mnt, base)?;
+    #[cfg(feature = "loop-image")]
+    if linux::losetup::is_image(&mnt.device) {
+        // mount(8) sets up and auto-clears the loop device
+        let opts = match &mnt.mnt_opts {
+            Some(opts) => format!("loop,{opts}"),
+            None => "loop".to_string(),
+        };
+
+        linux::mount::mount_fs(
+            &ManifestMountpoint {
+                device: mnt.device.clone(),
+                dest: mnt.dest.clone(),
+                mnt_opts: Some(opts),
+            },
+            None,
+            base,
+        )?;
+
+        return Ok(ActionMountpoints::MountFs {
+            src: mnt.device.clone(),
+            dst: mnt.dest.clone(),
+            opts: mnt.mnt_opts.clone(),
+        });
+    }
+
+    linux::mount::mount_fs(mnt, fs_type, base)?;
 
     Ok(ActionMountpoints::MountFs {
         src: mnt.device.clone(),
@@ -34,8 +243,45 @@ pub fn mount_filesystem(
     })
 }
 
+/// Creates filesystem on image file `filesystem.device`,
+/// via a loop device which is detached afterwards.
+#[cfg(feature = "loop-image")]
+fn create_fs_image(
+    filesystem: &ManifestFs,
+) -> Result<Option<String>, AliError> {
+    let loop_dev = linux::losetup::attach(&filesystem.device)?;
+    let result = linux::mkfs::create_fs(&ManifestFs {
+        device: loop_dev.clone(),
+        ..filesystem.clone()
+    });
+
+    linux::losetup::detach(&loop_dev).and(result)
+}
+
+pub fn create_swaps(
+    swaps: &[ManifestSwap],
+) -> Result<Vec<ActionMountpoints>, AliError> {
+    let mut actions = Vec::new();
+
+    for swap in swaps {
+        let action_create_swap = ActionMountpoints::CreateSwap {
+            device: swap.device().to_string(),
+            label: swap.label().map(String::from),
+        };
+
+        if let Err(err) = linux::swap::create_swap(swap) {
+            return Err(map_err_mountpoints(err, action_create_swap, actions));
+        }
+
+        actions.push(action_create_swap);
+    }
+
+    Ok(actions)
+}
+
 pub fn create_filesystems(
     filesystems: &[ManifestFs],
+    warnings: &mut Vec<Warning>,
 ) -> Result<Vec<ActionMountpoints>, AliError> {
     let mut actions = Vec::new();
 
@@ -44,9 +290,11 @@ pub fn create_filesystems(
             device: fs.device.clone(),
             fs_type: fs.fs_type.clone(),
             fs_opts: fs.fs_opts.clone(),
+            uuid: None,
+            output: None,
         };
 
-        match create_filesystem(fs) {
+        match create_filesystem(fs, warnings) {
             Err(err) => {
                 return Err(map_err_mountpoints(
                     err,
@@ -56,6 +304,24 @@ pub fn create_filesystems(
             }
             Ok(action) => actions.push(action),
         }
+
+        if let Some(args) = &fs.tune2fs_args {
+            let action_tune_fs = ActionMountpoints::TuneFs {
+                device: fs.device.clone(),
+                args: args.clone(),
+            };
+
+            match tune_filesystem(fs) {
+                Err(err) => {
+                    return Err(map_err_mountpoints(
+                        err,
+                        action_tune_fs,
+                        actions,
+                    ));
+                }
+                Ok(action) => actions.extend(action),
+            }
+        }
     }
 
     Ok(actions)
@@ -64,18 +330,23 @@ pub fn create_filesystems(
 // mount_filesystem lets callers defined base dir
 // for all filesystems to be mounted under.
 pub fn mount_filesystems(
+    manifest: &Manifest,
     mountpoints: &[ManifestMountpoint],
     base: &str,
 ) -> Result<Vec<ActionMountpoints>, AliError> {
     let mut actions = Vec::new();
     for mnt in mountpoints {
+        // e.g. XFS logdev= must be given on every mount
+        let mnt = &manifest.effective_mountpoint(mnt);
         let action_mount_fs = ActionMountpoints::MountFs {
             src: mnt.device.clone(),
             dst: mnt.dest.clone(),
             opts: mnt.mnt_opts.clone(),
         };
 
-        match mount_filesystem(mnt, base) {
+        let fs_type = manifest.fs_type(&mnt.device);
+
+        match mount_filesystem(mnt, fs_type, base) {
             Err(err) => {
                 return Err(map_err_mountpoints(err, action_mount_fs, actions));
             }
@@ -87,3 +358,189 @@ pub fn mount_filesystems(
 
     Ok(actions)
 }
+
+/// Creates dirs for, and mounts `overlays` under `base`.
+/// Must be called after all other filesystems are mounted,
+/// since overlay dirs may live on any of them.
+pub fn mount_overlays(
+    overlays: &[ManifestOverlay],
+    base: &str,
+) -> Result<Vec<ActionMountpoints>, AliError> {
+    let mut actions = Vec::new();
+    for overlay in overlays {
+        let dirs = overlay.lower_dirs().chain([
+            overlay.upper_dir.as_str(),
+            overlay.work_dir.as_str(),
+            overlay.mnt.as_str(),
+        ]);
+
+        for dir in dirs {
+            let action_mkdir = ActionMountpoints::MkdirOverlay(dir.to_string());
+            let path = linux::mount::prepend_base(base, dir);
+
+            if let Err(err) = shell::exec("mkdir", &["-p", &path]) {
+                return Err(map_err_mountpoints(err, action_mkdir, actions));
+            }
+
+            actions.push(action_mkdir);
+        }
+
+        let action_mount_overlay = ActionMountpoints::MountOverlay {
+            dst: overlay.mnt.clone(),
+            opts: overlay.mnt_opts(""),
+        };
+
+        if let Err(err) = linux::mount::mount_overlay(overlay, base) {
+            return Err(map_err_mountpoints(
+                err,
+                action_mount_overlay,
+                actions,
+            ));
+        }
+
+        actions.push(action_mount_overlay);
+    }
+
+    Ok(actions)
+}
+
+/// Enables btrfs quota groups on every mountpoint of filesystems
+/// with `quota` set. Must be called after all filesystems are mounted.
+pub fn enable_quotas(
+    manifest: &Manifest,
+    root_location: &str,
+) -> Result<Vec<ActionMountpoints>, AliError> {
+    let mut actions = Vec::new();
+    for mountpoint in quota_mountpoints(manifest, root_location) {
+        let result = linux::btrfs::quota_enable(&mountpoint);
+        let action_quota = ActionMountpoints::BtrfsQuotaEnable(mountpoint);
+
+        if let Err(err) = result {
+            return Err(map_err_mountpoints(err, action_quota, actions));
+        }
+
+        actions.push(action_quota);
+    }
+
+    Ok(actions)
+}
+
+/// Returns mountpoints under `root_location` of filesystems with `quota`
+pub(super) fn quota_mountpoints(
+    manifest: &Manifest,
+    root_location: &str,
+) -> Vec<String> {
+    let mut mountpoints = Vec::new();
+
+    if manifest.rootfs.quota.unwrap_or(false) {
+        mountpoints.push(root_location.to_string());
+    }
+
+    for fs in manifest.filesystems.iter().flatten() {
+        if !fs.quota.unwrap_or(false) {
+            continue;
+        }
+
+        for mnt in manifest.mountpoints.iter().flatten() {
+            if mnt.device == fs.device {
+                mountpoints
+                    .push(linux::mount::prepend_base(root_location, &mnt.dest));
+            }
+        }
+    }
+
+    mountpoints
+}
+
+#[cfg(feature = "loop-image")]
+#[test]
+#[ignore = "requires root, losetup and mkfs.ext4"]
+fn test_fs_image() {
+    let dir = std::env::temp_dir().join("ali-rs-test-fs-image");
+    let image = dir.join("ext4.img");
+    let mnt = dir.join("mnt");
+
+    std::fs::create_dir_all(&mnt).expect("failed to create mountpoint");
+    std::fs::File::create(&image)
+        .and_then(|f| f.set_len(64 * 1024 * 1024))
+        .expect("failed to create image file");
+
+    let image = image.to_str().unwrap().to_string();
+    let base = dir.to_str().unwrap().to_string();
+
+    let filesystem = ManifestFs {
+        device: image.clone(),
+        fs_type: "ext4".into(),
+        fs_opts: None,
+        fstab_dump: None,
+        fstab_pass: None,
+        log_device: None,
+        rt_device: None,
+        quota: None,
+        tune2fs_args: None,
+        mkfs_discard: None,
+        mkfs_output: None,
+        subvolumes: None,
+        default_subvol: None,
+    };
+
+    let mountpoint = ManifestMountpoint {
+        device: image,
+        dest: "/mnt".into(),
+        mnt_opts: None,
+    };
+
+    create_filesystem(&filesystem, &mut Vec::new())
+        .expect("failed to create fs on image");
+    mount_filesystem(&mountpoint, Some("ext4"), &base)
+        .expect("failed to mount image");
+
+    let mounted = mnt.join("lost+found").exists();
+    shell::exec("umount", &[mnt.to_str().unwrap()])
+        .expect("failed to unmount image");
+    std::fs::remove_dir_all(&dir).expect("failed to clean up");
+
+    assert!(mounted);
+}
+
+#[test]
+fn test_created_uuid() {
+    let uuid = "6f5a7c6c-0d7f-4d8f-9b7c-1b0e0a3f2d11";
+    let output =
+        format!("Label:              (null)\nUUID:               {uuid}\n");
+
+    // UUID from mkfs output, without asking blkid
+    let mut warnings = Vec::new();
+    let created = created_uuid(
+        "/dev/fake1p1",
+        Some(&output),
+        |_| panic!("unexpected blkid call"),
+        &mut warnings,
+    );
+
+    assert_eq!(Some(uuid.to_string()), created);
+    assert!(warnings.is_empty());
+
+    // mkfs output not captured, e.g. on a terminal
+    let created = created_uuid(
+        "/dev/fake1p1",
+        None,
+        |_| Ok(Some(uuid.to_string())),
+        &mut warnings,
+    );
+
+    assert_eq!(Some(uuid.to_string()), created);
+    assert!(warnings.is_empty());
+
+    // blkid errors are not fatal, but recorded
+    let created = created_uuid(
+        "/dev/fake1p1",
+        Some("no uuid here"),
+        |device| Err(AliError::NoSuchDevice(device.to_string())),
+        &mut warnings,
+    );
+
+    assert_eq!(None, created);
+    assert_eq!(1, warnings.len());
+    assert!(warnings[0].message.contains("/dev/fake1p1"));
+}
@@ -0,0 +1,572 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::ali::{
+    Manifest,
+    ManifestOverlay,
+    ManifestSwap,
+};
+use crate::errors::AliError;
+use crate::linux;
+
+/// fstab(5) dump and pass overrides, keyed by mountpoint
+type Overrides = HashMap<String, (Option<u8>, Option<u8>)>;
+
+/// Rewrites dump and pass fields of entries in `{install_location}/etc/fstab`
+/// for filesystems with `fstab_dump` or `fstab_pass` set in manifest.
+///
+/// Entries not covered by the manifest are left as generated.
+pub fn override_dump_pass(
+    manifest: &Manifest,
+    install_location: &str,
+) -> Result<(), AliError> {
+    let overrides = collect_overrides(manifest);
+    if overrides.is_empty() {
+        return Ok(());
+    }
+
+    let etc_fstab = format!("{install_location}/etc/fstab");
+    let fstab = std::fs::read_to_string(&etc_fstab).map_err(|err| {
+        AliError::FileError(err, format!("failed to read {etc_fstab}"))
+    })?;
+
+    std::fs::write(&etc_fstab, apply_overrides(&fstab, &overrides)).map_err(
+        |err| AliError::FileError(err, format!("failed to write {etc_fstab}")),
+    )
+}
+
+/// Rewrites fstype fields of entries in `{install_location}/etc/fstab`
+/// for filesystems mounted by mount helpers, which genfstab(8)
+/// only sees as e.g. `fuseblk`.
+pub fn override_fs_types(
+    manifest: &Manifest,
+    install_location: &str,
+) -> Result<(), AliError> {
+    let fs_types = collect_fs_types(manifest);
+    if fs_types.is_empty() {
+        return Ok(());
+    }
+
+    let etc_fstab = format!("{install_location}/etc/fstab");
+    let fstab = std::fs::read_to_string(&etc_fstab).map_err(|err| {
+        AliError::FileError(err, format!("failed to read {etc_fstab}"))
+    })?;
+
+    std::fs::write(&etc_fstab, apply_fs_types(&fstab, &fs_types)).map_err(
+        |err| AliError::FileError(err, format!("failed to write {etc_fstab}")),
+    )
+}
+
+/// Returns fstab fstypes of filesystems with mount helpers,
+/// keyed by mountpoint
+pub fn collect_fs_types(manifest: &Manifest) -> HashMap<String, &'static str> {
+    let rootfs = (&manifest.rootfs.device, "/");
+    let mountpoints = manifest
+        .mountpoints
+        .iter()
+        .flatten()
+        .map(|mnt| (&mnt.device, mnt.dest.as_str()));
+
+    std::iter::once(rootfs)
+        .chain(mountpoints)
+        .filter_map(|(device, dest)| {
+            let helper = linux::mount::mount_helper(manifest.fs_type(device)?)?;
+
+            Some((normalize_mountpoint(dest), helper.fstab_type))
+        })
+        .collect()
+}
+
+/// Replaces fstype of fstab entries in `fstab` whose mountpoint is in `fs_types`
+fn apply_fs_types(fstab: &str, fs_types: &HashMap<String, &str>) -> String {
+    let mut result = String::with_capacity(fstab.len());
+
+    for line in fstab.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let fs_type = match line.trim_start().starts_with('#') {
+            true => None,
+            false => {
+                fields
+                    .get(1)
+                    .and_then(|dest| fs_types.get(&normalize_mountpoint(dest)))
+            }
+        };
+
+        match (fs_type, fields.len()) {
+            (Some(fs_type), 4..) => {
+                let mut fields = fields;
+                fields[2] = fs_type;
+
+                result.push_str(&format!(
+                    "{}\t{}\t{}\t{}",
+                    fields[0], fields[1], fields[2], fields[3]
+                ));
+
+                if fields.len() > 4 {
+                    result.push_str(&format!("\t{}", fields[4..].join(" ")));
+                }
+            }
+            _ => result.push_str(line),
+        }
+
+        result.push('\n');
+    }
+
+    result
+}
+
+/// Adds mount options required by manifest filesystems, e.g. XFS
+/// `logdev=`, to entries in `{install_location}/etc/fstab`,
+/// so that such filesystems can be mounted on boot.
+pub fn override_mnt_opts(
+    manifest: &Manifest,
+    install_location: &str,
+) -> Result<(), AliError> {
+    let mnt_opts = collect_mnt_opts(manifest);
+    if mnt_opts.is_empty() {
+        return Ok(());
+    }
+
+    let etc_fstab = format!("{install_location}/etc/fstab");
+    let fstab = std::fs::read_to_string(&etc_fstab).map_err(|err| {
+        AliError::FileError(err, format!("failed to read {etc_fstab}"))
+    })?;
+
+    std::fs::write(&etc_fstab, apply_mnt_opts(&fstab, &mnt_opts)).map_err(
+        |err| AliError::FileError(err, format!("failed to write {etc_fstab}")),
+    )
+}
+
+/// Returns mount options required by manifest filesystems,
+/// keyed by mountpoint
+fn collect_mnt_opts(manifest: &Manifest) -> HashMap<String, Vec<String>> {
+    let mut mnt_opts = HashMap::new();
+
+    let (Some(filesystems), Some(mountpoints)) =
+        (&manifest.filesystems, &manifest.mountpoints)
+    else {
+        return mnt_opts;
+    };
+
+    for fs in filesystems {
+        let opts = fs.required_mnt_opts();
+        if opts.is_empty() {
+            continue;
+        }
+
+        for mnt in mountpoints.iter().filter(|m| m.device == fs.device) {
+            mnt_opts.insert(normalize_mountpoint(&mnt.dest), opts.clone());
+        }
+    }
+
+    mnt_opts
+}
+
+/// Appends options in `mnt_opts` missing from fstab entries in `fstab`
+fn apply_mnt_opts(
+    fstab: &str,
+    mnt_opts: &HashMap<String, Vec<String>>,
+) -> String {
+    let mut result = String::with_capacity(fstab.len());
+
+    for line in fstab.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let required = match line.trim_start().starts_with('#') {
+            true => None,
+            false => {
+                fields
+                    .get(1)
+                    .and_then(|dest| mnt_opts.get(&normalize_mountpoint(dest)))
+            }
+        };
+
+        match (required, fields.len()) {
+            (Some(required), 4..) => {
+                let mut opts: Vec<&str> = fields[3].split(',').collect();
+                for opt in required {
+                    if !opts.contains(&opt.as_str()) {
+                        opts.push(opt);
+                    }
+                }
+
+                result.push_str(&format!(
+                    "{}\t{}\t{}\t{}",
+                    fields[0],
+                    fields[1],
+                    fields[2],
+                    opts.join(","),
+                ));
+
+                if fields.len() > 4 {
+                    result.push_str(&format!("\t{}", fields[4..].join(" ")));
+                }
+            }
+            _ => result.push_str(line),
+        }
+
+        result.push('\n');
+    }
+
+    result
+}
+
+/// Appends fstab entries for manifest swaps to `{install_location}/etc/fstab`,
+/// because genfstab(8) only knows about active swaps.
+pub fn append_swaps(
+    manifest: &Manifest,
+    install_location: &str,
+) -> Result<(), AliError> {
+    let Some(swaps) = &manifest.swap else {
+        return Ok(());
+    };
+
+    let etc_fstab = format!("{install_location}/etc/fstab");
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&etc_fstab)
+        .and_then(|mut f| write!(f, "{}", swap_entries(swaps)))
+        .map_err(|err| {
+            AliError::FileError(err, format!("failed to append to {etc_fstab}"))
+        })
+}
+
+/// Returns fstab entries for `swaps`, using `LABEL=` for labeled swaps
+pub fn swap_entries(swaps: &[ManifestSwap]) -> String {
+    swaps
+        .iter()
+        .map(|swap| {
+            let spec = match swap.label() {
+                Some(label) => format!("LABEL={label}"),
+                None => swap.device().to_string(),
+            };
+
+            format!("# {}\n{spec}\tnone\tswap\tdefaults\t0 0\n", swap.device())
+        })
+        .collect()
+}
+
+/// Appends fstab entries for manifest overlays to `{install_location}/etc/fstab`
+pub fn append_overlays(
+    manifest: &Manifest,
+    install_location: &str,
+) -> Result<(), AliError> {
+    let Some(overlays) = &manifest.overlays else {
+        return Ok(());
+    };
+
+    let etc_fstab = format!("{install_location}/etc/fstab");
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&etc_fstab)
+        .and_then(|mut f| write!(f, "{}", overlay_entries(overlays)))
+        .map_err(|err| {
+            AliError::FileError(err, format!("failed to append to {etc_fstab}"))
+        })
+}
+
+/// Returns fstab entries for `overlays`, with dirs as seen from the new system
+pub fn overlay_entries(overlays: &[ManifestOverlay]) -> String {
+    overlays
+        .iter()
+        .map(|overlay| {
+            format!(
+                "# overlay on {}\noverlay\t{}\toverlay\t{}\t0 0\n",
+                overlay.mnt,
+                overlay.mnt,
+                overlay.mnt_opts(""),
+            )
+        })
+        .collect()
+}
+
+/// awk(1) program removing overlay entries from genfstab(8) output,
+/// whose dirs still point to the install location,
+/// along with their comment lines.
+const AWK_DROP_OVERLAYS: &str = r#"/^[ \t]*#/ { if (held) print prev; prev = $0; held = 1; next }
+$3 == "overlay" { held = 0; next }
+{ if (held) print prev; held = 0; print }
+END { if (held) print prev }"#;
+
+/// Returns awk(1) command for filtering out overlay entries
+/// from genfstab(8) output piped to it
+pub fn cmd_drop_overlay_entries() -> (&'static str, [&'static str; 1]) {
+    ("awk", [AWK_DROP_OVERLAYS])
+}
+
+fn collect_overrides(manifest: &Manifest) -> Overrides {
+    let mut overrides = Overrides::new();
+
+    let rootfs = &manifest.rootfs;
+    if rootfs.fstab_dump.is_some() || rootfs.fstab_pass.is_some() {
+        overrides.insert("/".into(), (rootfs.fstab_dump, rootfs.fstab_pass));
+    }
+
+    let (Some(filesystems), Some(mountpoints)) =
+        (&manifest.filesystems, &manifest.mountpoints)
+    else {
+        return overrides;
+    };
+
+    for fs in filesystems {
+        if fs.fstab_dump.is_none() && fs.fstab_pass.is_none() {
+            continue;
+        }
+
+        for mnt in mountpoints.iter().filter(|m| m.device == fs.device) {
+            overrides.insert(
+                normalize_mountpoint(&mnt.dest),
+                (fs.fstab_dump, fs.fstab_pass),
+            );
+        }
+    }
+
+    overrides
+}
+
+/// Rewrites fstab entries in `fstab` whose mountpoint is in `overrides`.
+/// Comments, blank lines and malformed entries are kept verbatim.
+fn apply_overrides(fstab: &str, overrides: &Overrides) -> String {
+    let mut result = String::with_capacity(fstab.len());
+
+    for line in fstab.lines() {
+        result.push_str(&override_line(line, overrides).unwrap_or(line.into()));
+        result.push('\n');
+    }
+
+    result
+}
+
+fn override_line(line: &str, overrides: &Overrides) -> Option<String> {
+    if line.trim_start().starts_with('#') {
+        return None;
+    }
+
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 4 {
+        return None;
+    }
+
+    let (dump, pass) = overrides.get(&normalize_mountpoint(fields[1]))?;
+
+    // Missing dump and pass fields default to 0 (see fstab(5))
+    let dump = dump
+        .map(|d| d.to_string())
+        .unwrap_or(fields.get(4).unwrap_or(&"0").to_string());
+    let pass = pass
+        .map(|p| p.to_string())
+        .unwrap_or(fields.get(5).unwrap_or(&"0").to_string());
+
+    Some(format!(
+        "{}\t{}\t{}\t{}\t{dump} {pass}",
+        fields[0], fields[1], fields[2], fields[3],
+    ))
+}
+
+fn normalize_mountpoint(dest: &str) -> String {
+    match dest.trim_end_matches('/') {
+        "" => "/".into(),
+        dest => dest.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::shell;
+
+    #[test]
+    fn test_apply_overrides() {
+        let fstab = r#"# /dev/sda2
+UUID=aaaa	/         	ext4      	rw,relatime	0 1
+
+# /dev/sda1
+UUID=bbbb	/boot     	vfat      	rw,relatime	0 2
+
+# /dev/sdb1
+UUID=cccc	/data     	xfs       	rw,relatime	0 2
+"#;
+
+        let overrides = Overrides::from([
+            ("/data".to_string(), (Some(1), Some(0))),
+            ("/boot".to_string(), (None, Some(0))),
+        ]);
+
+        let expected = r#"# /dev/sda2
+UUID=aaaa	/         	ext4      	rw,relatime	0 1
+
+# /dev/sda1
+UUID=bbbb	/boot	vfat	rw,relatime	0 0
+
+# /dev/sdb1
+UUID=cccc	/data	xfs	rw,relatime	1 0
+"#;
+
+        assert_eq!(expected, apply_overrides(fstab, &overrides));
+    }
+
+    #[test]
+    fn test_collect_overrides() {
+        let manifest = Manifest::from_yaml(
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+  fstab_pass: 1
+filesystems:
+  - device: /dev/sda1
+    fstype: vfat
+  - device: /dev/sdb1
+    fstype: xfs
+    fstab_dump: 1
+    fstab_pass: 0
+mountpoints:
+  - device: /dev/sda1
+    dest: /boot
+  - device: /dev/sdb1
+    dest: /data/
+"#,
+        )
+        .expect("failed to parse manifest");
+
+        let expected = Overrides::from([
+            ("/".to_string(), (None, Some(1))),
+            ("/data".to_string(), (Some(1), Some(0))),
+        ]);
+
+        assert_eq!(expected, collect_overrides(&manifest));
+    }
+
+    #[test]
+    fn test_swap_entries() {
+        let swaps = vec![
+            ManifestSwap::Labeled {
+                device: "/dev/myvg/swaplv".into(),
+                label: "swap".into(),
+            },
+            ManifestSwap::from("/dev/sda3"),
+        ];
+
+        let expected = r#"# /dev/myvg/swaplv
+LABEL=swap	none	swap	defaults	0 0
+# /dev/sda3
+/dev/sda3	none	swap	defaults	0 0
+"#;
+
+        assert_eq!(expected, swap_entries(&swaps));
+    }
+
+    #[test]
+    fn test_override_fs_types() {
+        let manifest = Manifest::from_yaml(
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sdb1
+    fstype: ntfs
+mountpoints:
+  - device: /dev/sdb1
+    dest: /data/
+"#,
+        )
+        .expect("failed to parse manifest");
+
+        let fs_types = collect_fs_types(&manifest);
+        assert_eq!(HashMap::from([("/data".to_string(), "ntfs-3g")]), fs_types);
+
+        let fstab = r#"# /dev/sda2
+UUID=aaaa	/	ext4	rw,relatime	0 1
+
+# /dev/sdb1
+UUID=bbbb	/data	fuseblk	rw,user_id=0,group_id=0,allow_other	0 0
+"#;
+
+        let expected = r#"# /dev/sda2
+UUID=aaaa	/	ext4	rw,relatime	0 1
+
+# /dev/sdb1
+UUID=bbbb	/data	ntfs-3g	rw,user_id=0,group_id=0,allow_other	0 0
+"#;
+
+        assert_eq!(expected, apply_fs_types(fstab, &fs_types));
+    }
+
+    #[test]
+    fn test_override_mnt_opts() {
+        let manifest = Manifest::from_yaml(
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sdb1
+    fstype: xfs
+    log_device: /dev/nvme0n1p3
+    rt_device: /dev/sdc1
+mountpoints:
+  - device: /dev/sdb1
+    dest: /data/
+"#,
+        )
+        .expect("failed to parse manifest");
+
+        let fstab = r#"# /dev/sda2
+UUID=aaaa	/         	ext4      	rw,relatime	0 1
+
+# /dev/sdb1
+UUID=cccc	/data     	xfs       	rw,relatime,logdev=/dev/nvme0n1p3	0 2
+"#;
+
+        let expected = r#"# /dev/sda2
+UUID=aaaa	/         	ext4      	rw,relatime	0 1
+
+# /dev/sdb1
+UUID=cccc	/data	xfs	rw,relatime,logdev=/dev/nvme0n1p3,rtdev=/dev/sdc1	0 2
+"#;
+
+        let mnt_opts = collect_mnt_opts(&manifest);
+        assert_eq!(expected, apply_mnt_opts(fstab, &mnt_opts));
+    }
+
+    #[test]
+    fn test_overlay_entries() {
+        let overlays = vec![ManifestOverlay {
+            lower_dir: "/usr/lib/os:/opt/base".into(),
+            upper_dir: "/var/overlay/upper".into(),
+            work_dir: "/var/overlay/work".into(),
+            mnt: "/srv".into(),
+        }];
+
+        let expected = r#"# overlay on /srv
+overlay	/srv	overlay	lowerdir=/usr/lib/os:/opt/base,upperdir=/var/overlay/upper,workdir=/var/overlay/work	0 0
+"#;
+
+        assert_eq!(expected, overlay_entries(&overlays));
+
+        let genfstab = r#"# /dev/sda2
+UUID=foo	/	btrfs	rw,relatime	0 1
+
+# overlay
+overlay	/alitarget/srv	overlay	rw,lowerdir=/alitarget/usr/lib/os,upperdir=/alitarget/var/overlay/upper,workdir=/alitarget/var/overlay/work	0 0
+
+"#;
+
+        let expected = r#"# /dev/sda2
+UUID=foo	/	btrfs	rw,relatime	0 1"#;
+
+        let (cmd, [program]) = cmd_drop_overlay_entries();
+        let pipeline = format!(
+            "printf '%s' {} | {cmd} {}",
+            shell::quote(genfstab),
+            shell::quote(program),
+        );
+
+        let output =
+            shell::output("sh", &["-c", &pipeline]).expect("failed to run awk");
+
+        assert_eq!(expected, output);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::ali::Manifest;
+use crate::errors::AliError;
+use crate::linux::is_on_disk;
+
+const PROC_MOUNTS: &str = "/proc/mounts";
+
+/// Refuses to continue if any block device used by `manifest`
+/// backs the running system's `/`, e.g. when ali-rs is run from
+/// an installed system instead of a live medium.
+pub(super) fn check_live_root(manifest: &Manifest) -> Result<(), AliError> {
+    let proc_mounts = std::fs::read_to_string(PROC_MOUNTS).map_err(|err| {
+        AliError::FileError(err, format!("failed to read {PROC_MOUNTS}"))
+    })?;
+
+    check_live_root_mounts(manifest, &proc_mounts, sys_slaves)
+}
+
+/// Checks manifest devices against the live root device, and devices
+/// underneath it as reported by `slaves`, e.g. LUKS or LVM on a partition
+fn check_live_root_mounts<F>(
+    manifest: &Manifest,
+    proc_mounts: &str,
+    slaves: F,
+) -> Result<(), AliError>
+where
+    F: Fn(&str) -> Vec<String>,
+{
+    let Some(root_dev) = live_root_device(proc_mounts) else {
+        return Ok(());
+    };
+
+    let root_dev = canonical(root_dev);
+
+    let mut root_devs = vec![root_dev.clone()];
+    let mut i = 0;
+    while let Some(dev) = root_devs.get(i) {
+        let lower: Vec<String> =
+            slaves(dev).iter().map(|dev| canonical(dev)).collect();
+
+        root_devs.extend(lower);
+        i += 1;
+    }
+
+    for dev in &root_devs {
+        for disk in manifest.disks.iter().flatten() {
+            if is_on_disk(dev, &canonical(&disk.device)) {
+                return Err(err_live_root(&disk.device, &root_dev));
+            }
+        }
+
+        for m_dev in manifest.devices() {
+            if canonical(&m_dev) == *dev {
+                return Err(err_live_root(&m_dev, &root_dev));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns devices directly underneath device mapper `dev`
+/// from /sys/class/block/<dev>/slaves, e.g. /dev/dm-0 -> /dev/sda2
+fn sys_slaves(dev: &str) -> Vec<String> {
+    let Some(name) = std::path::Path::new(dev).file_name() else {
+        return Vec::new();
+    };
+
+    let dir = std::path::Path::new("/sys/class/block")
+        .join(name)
+        .join("slaves");
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| format!("/dev/{}", entry.file_name().to_string_lossy()))
+        .collect()
+}
+
+/// Returns the device mounted at `/` in `proc_mounts`.
+/// If `/` was mounted over, the last entry wins.
+fn live_root_device(proc_mounts: &str) -> Option<&str> {
+    proc_mounts.lines().rev().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        match (fields.next(), fields.next()) {
+            (Some(dev), Some("/")) => Some(dev),
+            _ => None,
+        }
+    })
+}
+
+// Resolves symlinks such as /dev/archvg/rootlv -> /dev/dm-0,
+// falling back to dev if it cannot be resolved
+fn canonical(dev: &str) -> String {
+    std::fs::canonicalize(dev)
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or(dev.to_string())
+}
+
+fn err_live_root(dev: &str, root_dev: &str) -> AliError {
+    AliError::BadManifest(format!(
+        "manifest device {dev} backs the running root filesystem {root_dev}, use --i-know-what-im-doing to apply anyway"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_slaves(_dev: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[test]
+    fn test_check_live_root() {
+        let manifest = Manifest::from_yaml(
+            r#"
+disks:
+  - device: /dev/fake1
+    table: gpt
+    partitions:
+      - label: efi
+        size: 500M
+        type: ef
+      - label: root
+        type: 8e
+  - device: /dev/fakenvme0n1
+    table: gpt
+    partitions:
+      - label: data
+        type: 83
+  - device: /dev/fakesda
+    table: mbr
+    partitions:
+      - label: data
+        type: 83
+device_mappers:
+  - type: lvm
+    pvs:
+      - /dev/fake1p2
+    vgs:
+      - name: fakevg
+        pvs:
+          - /dev/fake1p2
+    lvs:
+      - name: rootlv
+        vg: fakevg
+rootfs:
+  device: /dev/fakevg/rootlv
+  fstype: btrfs
+filesystems:
+  - device: /dev/fake2
+    fstype: ext4
+"#,
+        )
+        .expect("failed to parse manifest");
+
+        let mounts = |root: &str| {
+            format!(
+                "proc /proc proc rw,nosuid 0 0\n{root} / ext4 rw,relatime 0 0\ntmpfs /tmp tmpfs rw 0 0\n"
+            )
+        };
+
+        let should_ok = vec![
+            mounts("airootfs"),
+            mounts("/dev/fake3"),
+            mounts("/dev/fake12"),
+            mounts("/dev/fakenvme0n10"),
+            mounts("/dev/fakesdab"),
+            mounts("/dev/fakesdap1"),
+            "proc /proc proc rw 0 0\n".to_string(),
+        ];
+
+        let should_err = vec![
+            mounts("/dev/fake1"),
+            mounts("/dev/fake1p1"),
+            mounts("/dev/fakenvme0n1p1"),
+            mounts("/dev/fakesda3"),
+            mounts("/dev/fakevg/rootlv"),
+            mounts("/dev/fake2"),
+            format!("{}/dev/fake1p1 / xfs rw 0 0\n", mounts("airootfs")),
+        ];
+
+        for proc_mounts in should_ok {
+            if let Err(err) =
+                check_live_root_mounts(&manifest, &proc_mounts, no_slaves)
+            {
+                panic!("unexpected error for {proc_mounts}: {err}");
+            }
+        }
+
+        for proc_mounts in should_err {
+            if check_live_root_mounts(&manifest, &proc_mounts, no_slaves)
+                .is_ok()
+            {
+                panic!("unexpected ok result for {proc_mounts}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_live_root_dm() {
+        let manifest = Manifest::from_yaml(
+            r#"
+disks:
+  - device: /dev/fake1
+    table: gpt
+    partitions:
+      - label: efi
+        size: 500M
+        type: ef
+      - label: root
+        type: 83
+device_mappers:
+  - type: luks
+    device: /dev/fake1p2
+    name: fakeroot
+rootfs:
+  device: /dev/mapper/fakeroot
+  fstype: ext4
+"#,
+        )
+        .expect("failed to parse manifest");
+
+        // Live root is LVM on LUKS on another disk's partition
+        let slaves = |disk: &'static str| {
+            move |dev: &str| {
+                match dev {
+                    "/dev/mapper/livevg-root" => vec!["/dev/fakedm-1".into()],
+                    "/dev/fakedm-1" => vec![disk.to_string()],
+                    _ => Vec::new(),
+                }
+            }
+        };
+
+        let proc_mounts = "/dev/mapper/livevg-root / ext4 rw 0 0\n";
+
+        assert!(check_live_root_mounts(
+            &manifest,
+            proc_mounts,
+            slaves("/dev/fake2p2"),
+        )
+        .is_ok());
+
+        assert!(check_live_root_mounts(
+            &manifest,
+            proc_mounts,
+            slaves("/dev/fake1p2"),
+        )
+        .is_err());
+
+        // Without slaves, dm-backed root only matches dm devices
+        assert!(
+            check_live_root_mounts(&manifest, proc_mounts, no_slaves).is_ok()
+        );
+    }
+}
@@ -4,6 +4,7 @@ use crate::types::action::{
     ActionChrootAli,
     ActionChrootUser,
     ActionMountpoints,
+    ActionReboot,
     ActionRoutine,
 };
 
@@ -66,3 +67,18 @@ pub(super) fn map_err_chroot_user(
             .collect(),
     }
 }
+
+pub(super) fn map_err_reboot(
+    err: AliError,
+    action_failed: ActionReboot,
+    actions_performed: Vec<ActionReboot>,
+) -> AliError {
+    AliError::ApplyError {
+        error: Box::new(err),
+        action_failed: Box::new(Action::Reboot(action_failed)),
+        actions_performed: actions_performed
+            .into_iter()
+            .map(Action::Reboot)
+            .collect(),
+    }
+}
@@ -1,14 +1,27 @@
 mod archchroot;
 mod bootstrap;
+mod crypttab;
 mod disks;
 mod dm;
 mod fs;
+mod fstab;
+mod live_root;
 mod map_err;
+mod modules;
+mod progress;
+mod reboot;
+mod resolv_conf;
 mod routines;
+mod script;
 mod stages;
+mod teardown;
 
 use std::collections::HashSet;
 
+pub use self::progress::OnAction;
+pub use self::script::render_script;
+
+use self::progress::Progress;
 use crate::ali::Manifest;
 use crate::errors::AliError;
 use crate::types::stage::{
@@ -16,24 +29,69 @@ use crate::types::stage::{
     Stage,
     StageActions,
 };
+use crate::types::warning::Warning;
+use crate::utils::{
+    interrupt,
+    shell,
+};
 
-type ApplyFn = fn(&Manifest, &str, &mut StageActions) -> Result<(), AliError>;
+type ApplyFn = fn(&Manifest, &str, &mut Progress) -> Result<(), AliError>;
 
 /// Use `manifest` to install a new system to `install_location`
 /// skipping any stages in `skip`, and maps `AliError::ApplyError`
 /// to `AliError::InstallError` with StageActions embedded.
+///
+/// If `fs_ready_devs` (from validation) is given, every filesystem device
+/// must be in it, otherwise we abort before touching any block device.
+///
+/// Unless `allow_live_root` is set, we also abort if any manifest
+/// block device backs the running system's root filesystem.
+///
+/// Each action is passed to `on_action` as soon as it is performed.
+///
+/// Non-fatal issues are pushed to `warnings`.
 pub fn apply_manifest(
     manifest: &Manifest,
     install_location: &str,
     skip: HashSet<Stage>,
+    fs_ready_devs: Option<&HashSet<String>>,
+    allow_live_root: bool,
+    warnings: &mut Vec<Warning>,
+    on_action: OnAction,
 ) -> Result<Box<StageActions>, AliError> {
-    let mut progress = Box::default();
+    if !skip.contains(&Stage::Mountpoints) {
+        if !allow_live_root {
+            live_root::check_live_root(manifest)?;
+        }
+
+        match fs_ready_devs {
+            Some(devs) => check_fs_devs(manifest, devs)?,
+            None => {
+                warnings.push(Warning::new(
+                    "apply",
+                    "manifest was not validated, filesystem devices are not checked",
+                ));
+            }
+        }
+    }
+
+    let mut progress = Progress::new(Box::default(), on_action);
 
     for stage in stage::STAGES {
         if skip.contains(&stage) {
             continue;
         }
 
+        // Stop before starting a new stage after SIGINT
+        if interrupt::interrupted() {
+            return Err(AliError::InstallError {
+                error: Box::new(AliError::Interrupted(format!(
+                    "stopped before stage {stage}"
+                ))),
+                stages_performed: progress.actions,
+            });
+        }
+
         let f: ApplyFn = match stage {
             Stage::Mountpoints => stages::mountpoints,
             Stage::Bootstrap => stages::bootstrap,
@@ -43,13 +101,137 @@ pub fn apply_manifest(
             Stage::PostInstallUser => stages::postinstall_user,
         };
 
-        if let Err(err) = f(manifest, install_location, &mut progress) {
+        let result = f(manifest, install_location, &mut progress);
+        warnings.append(&mut progress.warnings);
+
+        if let Err(err) = result {
+            progress.stream_err(&err);
+
             return Err(AliError::InstallError {
                 error: Box::new(err),
-                stages_performed: progress,
+                stages_performed: progress.actions,
             });
         }
     }
 
-    Ok(progress)
+    Ok(progress.actions)
+}
+
+/// Unmounts the new system at `install_location` and reboots.
+/// Callers must only call this after all stages were applied successfully.
+pub fn reboot(
+    install_location: &str,
+    stages_performed: Box<StageActions>,
+    on_action: OnAction,
+) -> Result<Box<StageActions>, AliError> {
+    let mut progress = Progress::new(stages_performed, on_action);
+
+    let cmd_reboot: (&str, &[&str]) = match shell::in_path("systemctl") {
+        true => ("systemctl", &["reboot"]),
+        false => ("reboot", &[]),
+    };
+
+    let result = reboot::reboot(
+        install_location,
+        ("umount", &["-R", install_location]),
+        cmd_reboot,
+    );
+
+    match result {
+        Ok(actions) => {
+            progress.extend(actions);
+            Ok(progress.actions)
+        }
+        Err(err) => {
+            progress.stream_err(&err);
+
+            Err(AliError::InstallError {
+                error: Box::new(err),
+                stages_performed: progress.actions,
+            })
+        }
+    }
+}
+
+/// Unmounts and closes devices opened by stages in `progress`, e.g. after
+/// the install was interrupted. Returns failed teardown commands.
+pub fn teardown(
+    install_location: &str,
+    progress: &StageActions,
+) -> Vec<String> {
+    teardown::teardown(install_location, progress, shell::exec)
+}
+
+/// Ensures that all devices to be formatted by `manifest` were
+/// validated as fs-ready, i.e. they are created by the manifest
+/// or already exist on the system.
+fn check_fs_devs(
+    manifest: &Manifest,
+    fs_ready_devs: &HashSet<String>,
+) -> Result<(), AliError> {
+    let mut devs = vec![&manifest.rootfs.device];
+
+    for fs in manifest.filesystems.iter().flatten() {
+        devs.push(&fs.device);
+        devs.extend(fs.log_device.iter());
+        devs.extend(fs.rt_device.iter());
+    }
+
+    for dev in devs {
+        if !fs_ready_devs.contains(dev) {
+            return Err(AliError::BadManifest(format!(
+                "filesystem device {dev} is not a validated fs-ready device"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_undeclared_fs_device() {
+        let manifest = Manifest::from_yaml(
+            r#"
+rootfs:
+  device: /dev/fake1p1
+  fstype: btrfs
+filesystems:
+  - device: /dev/ali-rs-undeclared
+    fstype: ext4
+"#,
+        )
+        .expect("failed to parse manifest");
+
+        let fs_ready_devs = HashSet::from(["/dev/fake1p1".to_string()]);
+        let result = apply_manifest(
+            &manifest,
+            "/alitarget",
+            HashSet::new(),
+            Some(&fs_ready_devs),
+            false,
+            &mut Vec::new(),
+            &mut |_| {},
+        );
+
+        match result {
+            Err(AliError::BadManifest(msg)) => {
+                assert!(msg.contains("/dev/ali-rs-undeclared"));
+            }
+            Err(err) => panic!("unexpected error: {err}"),
+            Ok(_) => panic!("unexpected ok result"),
+        }
+
+        let fs_ready_devs = HashSet::from([
+            "/dev/fake1p1".to_string(),
+            "/dev/ali-rs-undeclared".to_string(),
+        ]);
+
+        if let Err(err) = check_fs_devs(&manifest, &fs_ready_devs) {
+            panic!("unexpected error: {err}");
+        }
+    }
 }
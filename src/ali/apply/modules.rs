@@ -0,0 +1,28 @@
+use crate::errors::AliError;
+use crate::linux;
+use crate::types::action::ActionMountpoints;
+
+use super::map_err::map_err_mountpoints;
+
+/// Loads kernel modules into the live system with modprobe(8).
+///
+/// Unlike mkinitcpio MODULES, these modules only affect the
+/// running installer, e.g. drivers for exotic storage controllers
+/// that must be present before partitioning.
+pub fn load_modules(
+    modules: &[String],
+) -> Result<Vec<ActionMountpoints>, AliError> {
+    let mut actions = Vec::new();
+
+    for module in modules {
+        let action_load = ActionMountpoints::LoadKernelModule(module.clone());
+
+        if let Err(err) = linux::modprobe::load(module) {
+            return Err(map_err_mountpoints(err, action_load, actions));
+        }
+
+        actions.push(action_load);
+    }
+
+    Ok(actions)
+}
@@ -0,0 +1,122 @@
+use crate::errors::AliError;
+use crate::types::action::Action;
+use crate::types::stage::StageActions;
+use crate::types::warning::Warning;
+
+/// Callback run on each action as soon as it is performed,
+/// e.g. to stream actions to a UI during long installs
+pub type OnAction<'a> = &'a mut dyn FnMut(&Action);
+
+/// Actions performed so far, with each new action
+/// passed to `on_action` as it is recorded
+pub(super) struct Progress<'a> {
+    pub(super) actions: Box<StageActions>,
+
+    /// Non-fatal issues found while performing actions
+    pub(super) warnings: Vec<Warning>,
+
+    on_action: OnAction<'a>,
+}
+
+impl<'a> Progress<'a> {
+    pub(super) fn new(
+        actions: Box<StageActions>,
+        on_action: OnAction<'a>,
+    ) -> Self {
+        Self {
+            actions,
+            warnings: Vec::new(),
+            on_action,
+        }
+    }
+
+    pub(super) fn push(&mut self, action: impl Into<Action>) {
+        let action = action.into();
+        (self.on_action)(&action);
+        self.actions.push(action);
+    }
+
+    pub(super) fn extend<A: Into<Action>>(
+        &mut self,
+        actions: impl IntoIterator<Item = A>,
+    ) {
+        for action in actions {
+            self.push(action);
+        }
+    }
+
+    /// Passes actions performed before a failed action in `err`
+    /// to `on_action`, without recording them as stages performed.
+    /// Actions in nested errors were performed after outer ones.
+    pub(super) fn stream_err(&mut self, err: &AliError) {
+        let AliError::ApplyError {
+            error,
+            actions_performed,
+            ..
+        } = err
+        else {
+            return;
+        };
+
+        for action in actions_performed {
+            (self.on_action)(action);
+        }
+
+        self.stream_err(error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::action::ActionMountpoints;
+
+    #[test]
+    fn test_stream_err() {
+        let err_dm = AliError::ApplyError {
+            error: Box::new(AliError::NoSuchDevice("/dev/fake1p2".into())),
+            action_failed: Box::new(Action::Mountpoints(
+                ActionMountpoints::SettleDevices(vec![
+                    "/dev/mapper/cryptroot".into()
+                ]),
+            )),
+            actions_performed: vec![Action::Mountpoints(
+                ActionMountpoints::CreateDmLuks {
+                    device: "/dev/fake1p2".into(),
+                },
+            )],
+        };
+
+        let err = AliError::ApplyError {
+            error: Box::new(err_dm),
+            action_failed: Box::new(Action::Mountpoints(
+                ActionMountpoints::ApplyDms,
+            )),
+            actions_performed: vec![Action::Mountpoints(
+                ActionMountpoints::CreateDmLvmPv("/dev/fake1p1".into()),
+            )],
+        };
+
+        let mut streamed = Vec::new();
+        let mut on_action = |action: &Action| streamed.push(action.clone());
+        let mut progress = Progress::new(Box::default(), &mut on_action);
+
+        progress.stream_err(&err);
+        assert!(progress.actions.mountpoints.is_empty());
+
+        let [pv, luks] = streamed.as_slice() else {
+            panic!("unexpected actions streamed: {streamed:?}");
+        };
+
+        assert!(matches!(
+            pv,
+            Action::Mountpoints(ActionMountpoints::CreateDmLvmPv(pv))
+                if pv == "/dev/fake1p1",
+        ));
+        assert!(matches!(
+            luks,
+            Action::Mountpoints(ActionMountpoints::CreateDmLuks { device })
+                if device == "/dev/fake1p2",
+        ));
+    }
+}
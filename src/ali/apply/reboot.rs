@@ -0,0 +1,65 @@
+use crate::errors::AliError;
+use crate::types::action::ActionReboot;
+use crate::utils::shell;
+
+use super::map_err::map_err_reboot;
+
+/// Unmounts everything under `install_location` with `cmd_umount`,
+/// and then reboots with `cmd_reboot`.
+///
+/// Note: Takes in the commands as (cmd, &[arg1, arg2, ..]) to allow tests.
+pub(super) fn reboot(
+    install_location: &str,
+    cmd_umount: (&str, &[&str]),
+    cmd_reboot: (&str, &[&str]),
+) -> Result<Vec<ActionReboot>, AliError> {
+    let mut actions = Vec::new();
+
+    let action_umount = ActionReboot::UnmountAll(install_location.to_string());
+    if let Err(err) = shell::exec(cmd_umount.0, cmd_umount.1) {
+        return Err(map_err_reboot(err, action_umount, actions));
+    }
+    actions.push(action_umount);
+
+    // Push reboot action before actually rebooting,
+    // as we may not live to see the command return
+    actions.push(ActionReboot::Reboot);
+    if let Err(err) = shell::exec(cmd_reboot.0, cmd_reboot.1) {
+        let performed = actions[..actions.len() - 1].to_vec();
+        return Err(map_err_reboot(err, ActionReboot::Reboot, performed));
+    }
+
+    Ok(actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::action::Action;
+
+    #[test]
+    fn test_reboot() {
+        let actions = reboot("/alitarget", ("true", &[]), ("true", &[]))
+            .expect("unexpected error from stubbed reboot");
+
+        assert_eq!(2, actions.len());
+        assert!(matches!(actions.last(), Some(ActionReboot::Reboot)));
+
+        // Failed unmount must never reboot
+        match reboot("/alitarget", ("false", &[]), ("true", &[])) {
+            Err(AliError::ApplyError {
+                action_failed,
+                actions_performed,
+                ..
+            }) => {
+                assert!(matches!(
+                    *action_failed,
+                    Action::Reboot(ActionReboot::UnmountAll(_))
+                ));
+                assert!(actions_performed.is_empty());
+            }
+            Err(err) => panic!("unexpected error: {err}"),
+            Ok(actions) => panic!("unexpected ok result: {actions:?}"),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::io::ErrorKind;
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use crate::errors::AliError;
+
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// resolv.conf(5) in the new system before [`setup`] replaced it
+#[derive(Debug, PartialEq)]
+enum Original {
+    /// Usable as is, so nothing was done
+    Usable,
+
+    /// Missing, and the copy of host's must be removed
+    Missing,
+
+    /// Symlink to a file missing in the new system, e.g.
+    /// systemd-resolved stub, which must be linked again
+    Symlink(PathBuf),
+}
+
+/// Runs `f` with a working resolv.conf(5) in the new system at
+/// `location`, so that chroot commands and hooks can resolve hosts.
+///
+/// If the new system has no usable resolv.conf, host's resolv.conf
+/// is copied in and the original is restored once `f` returns.
+pub(super) fn with_resolv_conf<T, F>(
+    location: &str,
+    f: F,
+) -> Result<T, AliError>
+where
+    F: FnOnce() -> Result<T, AliError>,
+{
+    with_host_resolv_conf(location, RESOLV_CONF, f)
+}
+
+fn with_host_resolv_conf<T, F>(
+    location: &str,
+    host: &str,
+    f: F,
+) -> Result<T, AliError>
+where
+    F: FnOnce() -> Result<T, AliError>,
+{
+    let target = format!("{location}{RESOLV_CONF}");
+    let original = setup(location, &target, host)?;
+
+    let result = f();
+    let restored = restore(&target, original);
+
+    // Errors from f take precedence
+    let value = result?;
+    restored?;
+
+    Ok(value)
+}
+
+/// Copies `host` resolv.conf to `target` in the new system at `location`,
+/// if the new system has no usable resolv.conf
+fn setup(
+    location: &str,
+    target: &str,
+    host: &str,
+) -> Result<Original, AliError> {
+    // Nothing to do if there's no new system, or nothing to copy
+    if !Path::new(location).join("etc").is_dir() || !Path::new(host).exists() {
+        return Ok(Original::Usable);
+    }
+
+    let original = match std::fs::symlink_metadata(target) {
+        Err(err) if err.kind() == ErrorKind::NotFound => Original::Missing,
+        Err(err) => {
+            return Err(AliError::FileError(
+                err,
+                format!("failed to stat {target}"),
+            ));
+        }
+        Ok(meta) if !meta.is_symlink() => Original::Usable,
+        Ok(_) => {
+            let link = std::fs::read_link(target).map_err(|err| {
+                AliError::FileError(
+                    err,
+                    format!("failed to read link {target}"),
+                )
+            })?;
+
+            // Absolute links are resolved inside the new system
+            let resolved = match link.strip_prefix("/") {
+                Ok(rel) => Path::new(location).join(rel),
+                Err(_) => Path::new(target).with_file_name(&link),
+            };
+
+            if resolved.exists() {
+                return Ok(Original::Usable);
+            }
+
+            std::fs::remove_file(target).map_err(|err| {
+                AliError::FileError(err, format!("failed to remove {target}"))
+            })?;
+
+            Original::Symlink(link)
+        }
+    };
+
+    if original == Original::Usable {
+        return Ok(original);
+    }
+
+    std::fs::copy(host, target).map_err(|err| {
+        AliError::FileError(err, format!("failed to copy {host} to {target}"))
+    })?;
+
+    Ok(original)
+}
+
+/// Restores `original` resolv.conf at `target`
+fn restore(target: &str, original: Original) -> Result<(), AliError> {
+    if original == Original::Usable {
+        return Ok(());
+    }
+
+    std::fs::remove_file(target).map_err(|err| {
+        AliError::FileError(err, format!("failed to remove {target}"))
+    })?;
+
+    if let Original::Symlink(link) = original {
+        std::os::unix::fs::symlink(&link, target).map_err(|err| {
+            AliError::FileError(
+                err,
+                format!("failed to link {target} to {}", link.display()),
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_with_resolv_conf() {
+    let dir = std::env::temp_dir().join("ali-rs-test-resolv-conf");
+    _ = std::fs::remove_dir_all(&dir);
+
+    let location = dir.join("alitarget");
+    std::fs::create_dir_all(location.join("etc")).unwrap();
+
+    let host = dir.join("resolv.conf");
+    std::fs::write(&host, "nameserver 192.0.2.53\n").unwrap();
+
+    let location = location.to_str().unwrap();
+    let host = host.to_str().unwrap();
+    let target = format!("{location}{RESOLV_CONF}");
+
+    // Simulated chroot command that needs DNS
+    let resolve = || {
+        std::fs::read_to_string(&target)
+            .map_err(|err| AliError::FileError(err, target.clone()))
+    };
+
+    // Missing: copied for the command, then removed
+    let resolv_conf = with_host_resolv_conf(location, host, resolve).unwrap();
+    assert_eq!("nameserver 192.0.2.53\n", resolv_conf);
+    assert!(!Path::new(&target).exists());
+
+    // Dangling stub symlink: copied for the command, then linked again
+    let stub = "/run/systemd/resolve/stub-resolv.conf";
+    std::os::unix::fs::symlink(stub, &target).unwrap();
+
+    let resolv_conf = with_host_resolv_conf(location, host, resolve).unwrap();
+    assert_eq!("nameserver 192.0.2.53\n", resolv_conf);
+    assert_eq!(Path::new(stub), std::fs::read_link(&target).unwrap());
+
+    // Restored even if the command failed
+    let result: Result<(), AliError> =
+        with_host_resolv_conf(location, host, || {
+            Err(AliError::HookError("no network".into()))
+        });
+
+    assert!(matches!(result, Err(AliError::HookError(_))));
+    assert_eq!(Path::new(stub), std::fs::read_link(&target).unwrap());
+
+    // Usable resolv.conf is left as is
+    std::fs::remove_file(&target).unwrap();
+    std::fs::write(&target, "nameserver 198.51.100.1\n").unwrap();
+
+    let resolv_conf = with_host_resolv_conf(location, host, resolve).unwrap();
+    assert_eq!("nameserver 198.51.100.1\n", resolv_conf);
+    assert_eq!(
+        "nameserver 198.51.100.1\n",
+        std::fs::read_to_string(&target).unwrap(),
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,10 +1,21 @@
-use crate::ali::Manifest;
+use std::io::Write;
+
+use crate::ali::{
+    FstabIdentifier,
+    Manifest,
+    ManifestZram,
+};
 use crate::constants::defaults;
 use crate::errors::AliError;
+use crate::linux::zram;
 use crate::types::action::ActionRoutine;
 use crate::utils::shell;
 
 use super::map_err::map_err_routine;
+use super::{
+    crypttab,
+    fstab,
+};
 
 pub fn ali_routines(
     manifest: &Manifest,
@@ -19,16 +30,38 @@ pub fn ali_routines(
     actions.push(action_rootpasswd);
 
     let action_genfstab = ActionRoutine::GenFstab;
-    if let Err(err) = genfstab_uuid(install_location) {
+    if let Err(err) = genfstab(manifest, install_location)
+        .and_then(|_| fstab::override_dump_pass(manifest, install_location))
+        .and_then(|_| fstab::override_fs_types(manifest, install_location))
+        .and_then(|_| fstab::override_mnt_opts(manifest, install_location))
+        .and_then(|_| fstab::append_swaps(manifest, install_location))
+        .and_then(|_| fstab::append_overlays(manifest, install_location))
+    {
         return Err(map_err_routine(err, action_genfstab, actions));
     }
     actions.push(action_genfstab);
 
-    let action_set_hostname = ActionRoutine::SetHostname;
-    if let Err(err) = hostname(&manifest.hostname, install_location) {
-        return Err(map_err_routine(err, action_set_hostname, actions));
+    if !crypttab::crypttab_entries(manifest).is_empty() {
+        let action_crypttab = ActionRoutine::Crypttab;
+        if let Err(err) = crypttab::append_crypttab(manifest, install_location)
+            .and_then(|_| crypttab::copy_headers(manifest, install_location))
+        {
+            return Err(map_err_routine(err, action_crypttab, actions));
+        }
+        actions.push(action_crypttab);
+    }
+
+    match set_hostname(&manifest.hostname, install_location) {
+        Ok(Some(action_set_hostname)) => actions.push(action_set_hostname),
+        Ok(None) => {}
+        Err(err) => {
+            return Err(map_err_routine(
+                err,
+                ActionRoutine::SetHostname,
+                actions,
+            ));
+        }
     }
-    actions.push(action_set_hostname);
 
     let action_locale_conf = ActionRoutine::LocaleConf;
     if let Err(err) = locale_conf(install_location) {
@@ -36,18 +69,45 @@ pub fn ali_routines(
     }
     actions.push(action_locale_conf);
 
+    if let Some(zram) = &manifest.zram {
+        let action_zram_conf = ActionRoutine::ZramConf;
+        if let Err(err) = zram_conf(zram, install_location) {
+            return Err(map_err_routine(err, action_zram_conf, actions));
+        }
+        actions.push(action_zram_conf);
+    }
+
     Ok(actions)
 }
 
-fn genfstab_uuid(install_location: &str) -> Result<(), AliError> {
-    shell::sh_c(&cmd_genfstab_uuid(install_location))
+/// Appends genfstab(8) output to `{install_location}/etc/fstab`
+fn genfstab(
+    manifest: &Manifest,
+    install_location: &str,
+) -> Result<(), AliError> {
+    let cmd = cmd_genfstab_fstab(manifest, install_location);
+    let fstab = shell::output("sh", &["-c", &cmd])?;
+
+    let etc_fstab = format!("{install_location}/etc/fstab");
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&etc_fstab)
+        .and_then(|mut f| writeln!(f, "{fstab}"))
+        .map_err(|err| {
+            AliError::FileError(err, format!("failed to append to {etc_fstab}"))
+        })
 }
 
-fn hostname(
+/// Writes `hostname` to `{install_location}/etc/hostname`.
+/// If `hostname` is None, nothing is written and no action is returned.
+fn set_hostname(
     hostname: &Option<String>,
     install_location: &str,
-) -> Result<(), AliError> {
-    let hostname = hostname.clone().unwrap_or(defaults::HOSTNAME.to_string());
+) -> Result<Option<ActionRoutine>, AliError> {
+    let Some(hostname) = hostname else {
+        return Ok(None);
+    };
 
     let etc_hostname = format!("{install_location}/etc/hostname");
 
@@ -56,7 +116,9 @@ fn hostname(
             err,
             format!("failed to write hostname to {etc_hostname}"),
         )
-    })
+    })?;
+
+    Ok(Some(ActionRoutine::SetHostname))
 }
 
 fn locale_conf(install_location: &str) -> Result<(), AliError> {
@@ -70,6 +132,18 @@ fn locale_conf(install_location: &str) -> Result<(), AliError> {
     })
 }
 
+/// Writes zram-generator.conf(5) to the new system
+fn zram_conf(
+    zram: &ManifestZram,
+    install_location: &str,
+) -> Result<(), AliError> {
+    let dst = format!("{install_location}{}", zram::GENERATOR_CONF);
+
+    std::fs::write(&dst, zram::generator_conf(zram)?).map_err(|err| {
+        AliError::FileError(err, format!("failed to write zram config {dst}"))
+    })
+}
+
 fn root_password(
     hashed_root_passwd: &Option<String>,
     install_location: &str,
@@ -78,12 +152,156 @@ fn root_password(
         .clone()
         .unwrap_or(defaults::hashed_password());
 
-    let cmd = format!("echo 'username:{password}' | chpasswd -e");
+    shell::arch_chroot(install_location, &cmd_root_password(&password))
+}
+
+/// Returns chpasswd(8) command for setting root's hashed password.
+/// The whole pipeline is wrapped in `sh -c`, so that chpasswd
+/// runs inside arch-chroot(8) and not on the live system.
+pub(super) fn cmd_root_password(hashed_password: &str) -> String {
+    let entry = shell::quote(&format!("root:{hashed_password}"));
 
-    shell::arch_chroot(install_location, &cmd)
+    shell::cmd_sh_c(&format!("echo {entry} | chpasswd -e"))
 }
 
-#[inline(always)]
-fn cmd_genfstab_uuid(install_location: &str) -> String {
-    format!("genfstab -U {install_location} >> {install_location}/etc/fstab")
+/// Like [`cmd_root_password`], but with plaintext `password`
+pub(super) fn cmd_root_password_plain(password: &str) -> String {
+    let entry = shell::quote(&format!("root:{password}"));
+
+    shell::cmd_sh_c(&format!("echo {entry} | chpasswd"))
+}
+
+/// Returns genfstab(8) command line for `manifest`, with its output
+/// piped through [`fstab::cmd_drop_overlay_entries`] if there're overlays
+pub(super) fn cmd_genfstab_fstab(
+    manifest: &Manifest,
+    install_location: &str,
+) -> String {
+    let args = cmd_genfstab(
+        manifest.fstab_identifier.unwrap_or(FstabIdentifier::Uuid),
+        manifest.fstab_pseudofs.unwrap_or(false),
+        install_location,
+    );
+
+    let genfstab = std::iter::once("genfstab")
+        .chain(args.iter().map(|arg| arg.as_str()))
+        .map(shell::quote)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if manifest.overlays.is_none() {
+        return genfstab;
+    }
+
+    let (cmd, [program]) = fstab::cmd_drop_overlay_entries();
+    format!("{genfstab} | {cmd} {}", shell::quote(program))
+}
+
+/// Returns genfstab(8) arguments for `identifier` and `pseudofs`
+fn cmd_genfstab(
+    identifier: FstabIdentifier,
+    pseudofs: bool,
+    install_location: &str,
+) -> Vec<String> {
+    let mut args: Vec<String> = match identifier {
+        FstabIdentifier::Uuid => vec!["-U".into()],
+        FstabIdentifier::Label => vec!["-L".into()],
+        FstabIdentifier::PartUuid => vec!["-t".into(), "PARTUUID".into()],
+        FstabIdentifier::PartLabel => vec!["-t".into(), "PARTLABEL".into()],
+    };
+
+    args.push(if pseudofs { "-P" } else { "-p" }.into());
+    args.push(install_location.into());
+
+    args
+}
+
+#[test]
+fn test_cmd_genfstab() {
+    let tests = vec![
+        ((FstabIdentifier::Uuid, false), vec!["-U", "-p", "/mnt"]),
+        ((FstabIdentifier::Label, false), vec!["-L", "-p", "/mnt"]),
+        (
+            (FstabIdentifier::PartUuid, true),
+            vec!["-t", "PARTUUID", "-P", "/mnt"],
+        ),
+        (
+            (FstabIdentifier::PartLabel, false),
+            vec!["-t", "PARTLABEL", "-p", "/mnt"],
+        ),
+    ];
+
+    for ((identifier, pseudofs), expected) in tests {
+        assert_eq!(expected, cmd_genfstab(identifier, pseudofs, "/mnt"));
+    }
+
+    let manifest = Manifest::from_yaml(
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+fstab_identifier: uuid
+"#,
+    )
+    .expect("failed to parse manifest");
+
+    let args = cmd_genfstab(
+        manifest.fstab_identifier.unwrap(),
+        manifest.fstab_pseudofs.unwrap_or(false),
+        "/mnt",
+    );
+    assert!(args.contains(&"-U".to_string()));
+    assert_eq!("genfstab -U -p /mnt", cmd_genfstab_fstab(&manifest, "/mnt"));
+
+    // genfstab output is filtered with awk if there're overlays
+    let manifest = Manifest {
+        overlays: Some(vec![]),
+        ..manifest
+    };
+
+    let cmd = cmd_genfstab_fstab(&manifest, "/mnt");
+    assert!(cmd.starts_with("genfstab -U -p /mnt | awk '"), "{cmd}");
+}
+
+#[test]
+fn test_cmd_root_password() {
+    let tests = vec![
+        (
+            cmd_root_password("$6$salt$hash"),
+            r"sh -c 'echo '\''root:$6$salt$hash'\'' | chpasswd -e'",
+        ),
+        (
+            cmd_root_password_plain("archalirs"),
+            "sh -c 'echo root:archalirs | chpasswd'",
+        ),
+    ];
+
+    for (cmd, expected) in tests {
+        assert_eq!(expected, cmd);
+    }
+
+    // Password reaches chpasswd unexpanded, with only 1 level of quoting
+    let cmd = cmd_root_password_plain("it's $HOME").replace("chpasswd", "cat");
+    let out = shell::output("sh", &["-c", &cmd]).unwrap();
+    assert_eq!("root:it's $HOME", out);
+}
+
+#[test]
+fn test_set_hostname() {
+    let location = std::env::temp_dir().join("ali-rs-test-set-hostname");
+    _ = std::fs::remove_dir_all(&location);
+    std::fs::create_dir_all(location.join("etc")).unwrap();
+
+    let location = location.to_str().unwrap();
+    let etc_hostname = format!("{location}/etc/hostname");
+
+    let action = set_hostname(&None, location).unwrap();
+    assert!(action.is_none());
+    assert!(!std::path::Path::new(&etc_hostname).exists());
+
+    let action = set_hostname(&Some("foo".into()), location).unwrap();
+    assert!(matches!(action, Some(ActionRoutine::SetHostname)));
+    assert_eq!("foo", std::fs::read_to_string(&etc_hostname).unwrap());
+
+    std::fs::remove_dir_all(location).unwrap();
 }
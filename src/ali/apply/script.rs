@@ -0,0 +1,645 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use super::{
+    archchroot,
+    bootstrap,
+    crypttab,
+    fs,
+    fstab,
+    routines,
+};
+use crate::ali::{
+    Dm,
+    Manifest,
+    ManifestFs,
+    ManifestMountpoint,
+};
+use crate::constants::defaults;
+use crate::errors::AliError;
+use crate::types::stage::{
+    self,
+    Stage,
+};
+use crate::utils::shell::quote;
+use crate::{
+    hooks,
+    linux,
+    utils,
+};
+
+const HEADER: &str = r#"#!/bin/sh
+#
+# Generated by ali-rs from manifest, do not edit.
+#
+# WARNING: running this script partitions and formats disks
+# declared in the manifest, and destroys all data on them.
+# Unlike ali-rs apply, this script does not validate anything.
+
+set -e
+"#;
+
+/// Renders shell script equivalent of applying `manifest`
+/// to `install_location`, skipping any stages in `skip`.
+///
+/// Commands are rendered in the same order as
+/// [`apply_manifest`](super::apply_manifest) runs them,
+/// and hooks are rendered as `ali-rs hooks` commands.
+pub fn render_script(
+    manifest: &Manifest,
+    install_location: &str,
+    skip: &HashSet<Stage>,
+) -> Result<String, AliError> {
+    let mut script = HEADER.to_string();
+
+    for stage in stage::STAGES {
+        if skip.contains(&stage) {
+            continue;
+        }
+
+        let lines = match stage {
+            Stage::Mountpoints => mountpoints(manifest, install_location)?,
+            Stage::Bootstrap => {
+                let mut lines = Vec::new();
+                if manifest.strict_signatures.unwrap_or(false) {
+                    lines.push(
+                        "# strict_signatures: pacman.conf SigLevel is not checked by this script"
+                            .to_string(),
+                    );
+                }
+
+                lines.push(bootstrap::cmd_pacstrap(
+                    &manifest.pacstraps,
+                    install_location,
+                    None,
+                    manifest.pacman_cache.as_deref(),
+                ));
+                lines
+            }
+            Stage::Routines => routines(manifest, install_location)?,
+            Stage::ChrootAli => chroot_ali(manifest, install_location),
+            Stage::ChrootUser => chroot_user(manifest, install_location),
+            Stage::PostInstallUser => {
+                postinstall_user(manifest, install_location)
+            }
+        };
+
+        script.push_str(&format!("\n# Stage {stage}\n"));
+        for line in lines {
+            script.push_str(&line);
+            script.push('\n');
+        }
+    }
+
+    Ok(script)
+}
+
+fn mountpoints(
+    manifest: &Manifest,
+    root_location: &str,
+) -> Result<Vec<String>, AliError> {
+    let mut lines = Vec::new();
+
+    for module in manifest.preload_modules.iter().flatten() {
+        let (cmd, args) = linux::modprobe::cmd_modprobe(module);
+        lines.push(join(cmd, &args));
+    }
+
+    for disk in manifest.disks.iter().flatten() {
+        let table = linux::fdisk::create_table_cmd(&disk.table);
+        lines.push(fdisk(&disk.device, &table));
+
+        for (n, part) in disk.partitions.iter().enumerate() {
+            let partition_number = n + 1;
+            let create = linux::fdisk::create_partition_cmd(
+                &disk.table,
+                partition_number,
+                part,
+            );
+            lines.push(fdisk(&disk.device, &create));
+
+            let extended = disk.table == crate::ali::PartitionTable::Mbr
+                && linux::fdisk::is_extended_type(&part.part_type);
+
+            if extended {
+                continue;
+            }
+
+            let set_type =
+                linux::fdisk::set_partition_type_cmd(partition_number, part);
+            lines.push(fdisk(&disk.device, &set_type));
+
+            if let Some(uuid) = &part.uuid {
+                let set_uuid = linux::fdisk::set_partition_uuid_cmd(
+                    partition_number,
+                    uuid,
+                );
+                lines.push(fdisk(&disk.device, &set_uuid));
+            }
+        }
+    }
+
+    for dm in manifest.device_mappers.iter().flatten() {
+        match dm {
+            Dm::Luks(luks) => {
+                if luks.passphrase.is_some() {
+                    lines.push(format!(
+                        "# passphrase for {} omitted, cryptsetup will prompt",
+                        luks.device,
+                    ));
+                }
+
+                let format = linux::luks::cmd_format(luks);
+                lines.push(format.join(" "));
+                lines.push(linux::luks::cmd_open(
+                    &luks.device,
+                    &luks.name,
+                    luks.header.as_deref(),
+                ));
+
+                let (cmd, args) = linux::udevadm::cmd_settle();
+                lines.push(join(cmd, &args));
+            }
+
+            Dm::Lvm(lvm) => {
+                for pv in lvm.pvs.iter().flatten() {
+                    let (cmd, args) = linux::lvm::cmd_create_pv(pv);
+                    lines.push(join(cmd, &args));
+                }
+
+                for vg in lvm.vgs.iter().flatten() {
+                    let (cmd, args) = linux::lvm::cmd_create_vg(vg);
+                    lines.push(join(cmd, &args));
+                }
+
+                for lv in lvm.lvs.iter().flatten() {
+                    let (cmd, args) = linux::lvm::cmd_create_lv(lv);
+                    let args: Vec<&str> =
+                        args.iter().map(|arg| arg.as_str()).collect();
+                    lines.push(join(cmd, &args));
+                }
+
+                let (cmd, args) = linux::udevadm::cmd_settle();
+                lines.push(join(cmd, &args));
+                let (cmd, args) = linux::lvm::cmd_scan_vgs();
+                lines.push(join(cmd, &args));
+            }
+        }
+    }
+
+    let rootfs: ManifestFs = manifest.rootfs.clone().into();
+    for fs in
+        std::iter::once(&rootfs).chain(manifest.filesystems.iter().flatten())
+    {
+        let (cmd, args) = linux::mkfs::cmd_mkfs(fs)?;
+        let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+        lines.push(join(&cmd, &args));
+
+        if let Some(tune2fs_args) = &fs.tune2fs_args {
+            let (cmd, args) =
+                linux::tune2fs::cmd_tune2fs(&fs.device, tune2fs_args);
+            lines.push(join(cmd, &args));
+        }
+    }
+
+    let subvol_filesystems = std::iter::once(&rootfs)
+        .chain(manifest.filesystems.iter().flatten())
+        .filter(|fs| fs.subvolumes.is_some());
+
+    for filesystem in subvol_filesystems {
+        let (cmd, args) = utils::fs::cmd_mktemp_dir();
+        lines.push(format!("subvol_mnt=\"$({})\"", join(cmd, &args)));
+
+        let mnt = "$subvol_mnt";
+        let mnt_subvol =
+            fs::subvol_mountpoint(filesystem, &format!("\"{mnt}\""));
+        lines.push(linux::mount::cmd_mount_fs(&mnt_subvol, None, ""));
+
+        for subvol in filesystem.subvolumes.iter().flatten() {
+            let path = linux::btrfs::subvol_path(mnt, subvol);
+            let (cmd, args) = linux::btrfs::cmd_subvolume_create(&path);
+            lines.push(join_vars(cmd, &args));
+        }
+
+        if let Some(subvol) = &filesystem.default_subvol {
+            // Subvolume ID is only known after it is created
+            let path = linux::btrfs::subvol_path(mnt, subvol);
+            let (cmd, args) = linux::btrfs::cmd_subvolume_show(&path);
+            lines.push(format!(
+                "subvol_id=\"$({} | awk '/Subvolume ID:/ {{print $3}}')\"",
+                join_vars(cmd, &args),
+            ));
+
+            let (cmd, args) =
+                linux::btrfs::cmd_subvolume_set_default("$subvol_id", mnt);
+            lines.push(join_vars(cmd, &args));
+        }
+
+        lines.push(join_vars("umount", &[mnt]));
+        lines.push(join_vars("rmdir", &[mnt]));
+    }
+
+    for swap in manifest.swap.iter().flatten() {
+        let (cmd, args) = linux::swap::cmd_mkswap(swap);
+        lines.push(join(cmd, &args));
+    }
+
+    lines.push(join("mkdir", &["-p", root_location]));
+
+    let mnt_root: ManifestMountpoint = manifest.rootfs.clone().into();
+    lines.push(linux::mount::cmd_mount_fs(
+        &mnt_root,
+        Some(&manifest.rootfs.fs_type),
+        root_location,
+    ));
+
+    let mounts = manifest.mountpoints.iter().flatten();
+    for mnt in mounts.clone() {
+        let dir = linux::mount::prepend_base(root_location, &mnt.dest);
+        lines.push(join("mkdir", &["-p", &dir]));
+    }
+
+    for mnt in mounts {
+        let fs_type = manifest.fs_type(&mnt.device);
+        let mnt = manifest.effective_mountpoint(mnt);
+        lines.push(linux::mount::cmd_mount_fs(&mnt, fs_type, root_location));
+    }
+
+    for overlay in manifest.overlays.iter().flatten() {
+        let dirs = overlay.lower_dirs().chain([
+            overlay.upper_dir.as_str(),
+            overlay.work_dir.as_str(),
+            overlay.mnt.as_str(),
+        ]);
+
+        for dir in dirs {
+            let dir = linux::mount::prepend_base(root_location, dir);
+            lines.push(join("mkdir", &["-p", &dir]));
+        }
+
+        let (cmd, args) =
+            linux::mount::cmd_mount_overlay(overlay, root_location);
+        let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+        lines.push(join(cmd, &args));
+    }
+
+    for mountpoint in fs::quota_mountpoints(manifest, root_location) {
+        let (cmd, args) = linux::btrfs::cmd_quota_enable(&mountpoint);
+        lines.push(join(cmd, &args));
+    }
+
+    Ok(lines)
+}
+
+fn routines(
+    manifest: &Manifest,
+    install_location: &str,
+) -> Result<Vec<String>, AliError> {
+    let mut lines = Vec::new();
+
+    // Without rootpasswd, apply sets the default password. It is set
+    // in plaintext here, so that rendering stays deterministic
+    let cmd_root_password = match &manifest.rootpasswd {
+        Some(password) => routines::cmd_root_password(password),
+        None => routines::cmd_root_password_plain(defaults::ROOT_PASSWD),
+    };
+
+    lines.push(format!(
+        "arch-chroot {install_location} {cmd_root_password}"
+    ));
+
+    lines.push(format!(
+        "{} >> {}",
+        routines::cmd_genfstab_fstab(manifest, install_location),
+        quote(&format!("{install_location}/etc/fstab")),
+    ));
+
+    let has_dump_pass = std::iter::once((
+        manifest.rootfs.fstab_dump,
+        manifest.rootfs.fstab_pass,
+    ))
+    .chain(
+        manifest
+            .filesystems
+            .iter()
+            .flatten()
+            .map(|fs| (fs.fstab_dump, fs.fstab_pass)),
+    )
+    .any(|(dump, pass)| dump.is_some() || pass.is_some());
+
+    if let Some(swaps) = &manifest.swap {
+        lines.push(format!(
+            "printf '%s' {} >> {}",
+            quote(&fstab::swap_entries(swaps)),
+            quote(&format!("{install_location}/etc/fstab")),
+        ));
+    }
+
+    if let Some(overlays) = &manifest.overlays {
+        lines.push(format!(
+            "printf '%s' {} >> {}",
+            quote(&fstab::overlay_entries(overlays)),
+            quote(&format!("{install_location}/etc/fstab")),
+        ));
+    }
+
+    let crypttab = crypttab::crypttab_entries(manifest);
+    if !crypttab.is_empty() {
+        lines.push(format!(
+            "printf '%s' {} >> {}",
+            quote(&crypttab),
+            quote(&format!("{install_location}/etc/crypttab")),
+        ));
+    }
+
+    for header in crypttab::header_files(manifest) {
+        let target = format!("{install_location}{header}");
+        lines.push(join("install", &["-D", "-m", "600", header, &target]));
+    }
+
+    if has_dump_pass {
+        lines.push(
+            "# fstab dump and pass overrides are only applied by ali-rs"
+                .to_string(),
+        );
+    }
+
+    if !fstab::collect_fs_types(manifest).is_empty() {
+        lines.push(
+            "# fstab fstypes of mount helper filesystems are only set by ali-rs"
+                .to_string(),
+        );
+    }
+
+    if let Some(hostname) = &manifest.hostname {
+        lines.push(write_file(
+            hostname,
+            &format!("{install_location}/etc/hostname"),
+        ));
+    }
+
+    lines.push(write_file(
+        defaults::LOCALE_CONF,
+        &format!("{install_location}/etc/locale.conf"),
+    ));
+
+    if let Some(zram) = &manifest.zram {
+        lines.push(write_file(
+            &linux::zram::generator_conf(zram)?,
+            &format!("{install_location}{}", linux::zram::GENERATOR_CONF),
+        ));
+    }
+
+    Ok(lines)
+}
+
+fn chroot_ali(manifest: &Manifest, install_location: &str) -> Vec<String> {
+    let cmd_tz = manifest
+        .timezone
+        .as_deref()
+        .map(|tz| archchroot::cmd_link_timezone(tz).1);
+
+    cmd_tz
+        .into_iter()
+        .chain([archchroot::cmd_locale_gen()])
+        .map(|cmd| format!("arch-chroot {install_location} {cmd}"))
+        .collect()
+}
+
+fn chroot_user(manifest: &Manifest, install_location: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut prev_label = None;
+
+    for (label, manifest_cmd) in manifest.chroot_cmds() {
+        if let Some(label) = label.filter(|_| label != prev_label) {
+            lines.push(format!("# Group {label}"));
+        }
+        prev_label = label;
+
+        let cmd = manifest_cmd.cmd();
+        match hooks::is_hook(cmd) {
+            true => lines.push(hook(cmd, install_location)),
+            false => {
+                lines.push(format!(
+                    "{}arch-chroot {install_location} {cmd}",
+                    env_prefix(manifest_cmd.env()),
+                ))
+            }
+        }
+    }
+
+    lines
+}
+
+fn postinstall_user(
+    manifest: &Manifest,
+    install_location: &str,
+) -> Vec<String> {
+    manifest
+        .postinstall
+        .iter()
+        .flatten()
+        .map(|manifest_cmd| {
+            let cmd = manifest_cmd.cmd();
+
+            match (hooks::is_hook(cmd), manifest_cmd.env()) {
+                (true, _) => hook(cmd, install_location),
+                (false, None) => cmd.to_string(),
+                (false, Some(env)) => {
+                    format!("{}sh -c {}", env_prefix(Some(env)), quote(cmd))
+                }
+            }
+        })
+        .collect()
+}
+
+/// Renders `env` as `env K=V .. ` prefix, sorted by key
+fn env_prefix(env: Option<&HashMap<String, String>>) -> String {
+    let mut vars: Vec<_> = env.into_iter().flatten().collect();
+    if vars.is_empty() {
+        return String::new();
+    }
+
+    vars.sort();
+
+    let vars: Vec<String> = vars
+        .into_iter()
+        .map(|(k, v)| quote(&format!("{k}={v}")))
+        .collect();
+
+    format!("env {} ", vars.join(" "))
+}
+
+fn hook(cmd: &str, install_location: &str) -> String {
+    join("ali-rs", &["hooks", "--mountpoint", install_location, cmd])
+}
+
+/// Renders fdisk `cmd` piped with printf, like
+/// [`run_fdisk_cmd`](linux::fdisk::run_fdisk_cmd)
+fn fdisk(device: &str, cmd: &str) -> String {
+    let escaped = cmd
+        .replace('\\', r"\\")
+        .replace('%', "%%")
+        .replace('\n', r"\n");
+
+    format!("printf {} | fdisk {}", quote(&escaped), quote(device))
+}
+
+fn write_file(content: &str, path: &str) -> String {
+    format!("printf '%s' {} > {}", quote(content), quote(path))
+}
+
+/// Like [`join`], but args starting with `$` are expanded by sh(1),
+/// e.g. `$mnt/@home` is rendered as `"$mnt"/@home`
+fn join_vars(cmd: &str, args: &[&str]) -> String {
+    std::iter::once(quote(cmd))
+        .chain(args.iter().map(|arg| {
+            let Some(var) = arg.strip_prefix('$') else {
+                return quote(arg);
+            };
+
+            match var.split_once('/') {
+                Some((var, rest)) => {
+                    format!("\"${var}\"{}", quote(&format!("/{rest}")))
+                }
+                None => format!("\"${var}\""),
+            }
+        }))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn join(cmd: &str, args: &[&str]) -> String {
+    std::iter::once(cmd)
+        .chain(args.iter().copied())
+        .map(quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_script_lvm() {
+        let manifest = Manifest::from_yaml(
+            r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: boot
+        size: 500M
+        type: uefi
+      - label: lvm
+        type: lvm
+device_mappers:
+  - type: lvm
+    pvs:
+      - /dev/sda2
+    vgs:
+      - name: myvg
+        pvs:
+          - /dev/sda2
+    lvs:
+      - name: swaplv
+        vg: myvg
+        size: 8G
+      - name: rootlv
+        vg: myvg
+rootfs:
+  device: /dev/myvg/rootlv
+  fstype: btrfs
+filesystems:
+  - device: /dev/sda1
+    fstype: vfat
+mountpoints:
+  - device: /dev/sda1
+    dest: /boot
+pacstraps:
+  - lvm2
+  - btrfs-progs
+"#,
+        )
+        .expect("failed to parse manifest");
+
+        let script =
+            render_script(&manifest, "/alitarget", &HashSet::new()).unwrap();
+
+        let expected_in_order = [
+            "printf 'g\\nw\\n' | fdisk /dev/sda",
+            "pvcreate /dev/sda2",
+            "vgcreate myvg /dev/sda2",
+            "lvcreate -L 8G myvg -n swaplv",
+            "lvcreate -l 100%FREE myvg -n rootlv",
+            "udevadm settle",
+            "vgscan --mknodes",
+            "mkfs.btrfs /dev/myvg/rootlv",
+            "mkfs.vfat /dev/sda1",
+            "mount /dev/myvg/rootlv /alitarget/",
+            "mkdir -p /alitarget/boot",
+            "mount /dev/sda1 /alitarget/boot",
+            "pacstrap -K /alitarget base btrfs-progs lvm2",
+            "arch-chroot /alitarget sh -c 'echo root:archalirs | chpasswd'",
+            "genfstab -U -p /alitarget >> /alitarget/etc/fstab",
+            r"arch-chroot /alitarget sh -c 'echo '\''en_US.UTF-8 UTF-8'\'' >> /etc/locale.gen && locale-gen'",
+        ];
+
+        let lines: Vec<&str> = script.lines().collect();
+        let mut prev = 0;
+        for expected in expected_in_order {
+            let Some(i) = lines.iter().position(|line| *line == expected)
+            else {
+                panic!("missing line {expected:?} in script:\n{script}");
+            };
+
+            assert!(i >= prev, "line {expected:?} out of order:\n{script}");
+            prev = i;
+        }
+
+        // Rendering is deterministic
+        assert_eq!(
+            script,
+            render_script(&manifest, "/alitarget", &HashSet::new()).unwrap(),
+        );
+
+        let skip = HashSet::from(stage::STAGES_PACSTRAP);
+        let script = render_script(&manifest, "/alitarget", &skip).unwrap();
+        assert!(script.contains("lvcreate"));
+        assert!(!script.contains("pacstrap"));
+    }
+
+    #[test]
+    fn test_render_script_subvolumes() {
+        let manifest = Manifest::from_yaml(
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+  subvolumes: ["@", "@snapshots", "@snapshots/root"]
+  default_subvol: "@"
+"#,
+        )
+        .expect("failed to parse manifest");
+
+        let script =
+            render_script(&manifest, "/alitarget", &HashSet::new()).unwrap();
+
+        let expected = r#"mkfs.btrfs /dev/sda2
+subvol_mnt="$(mktemp -d -t ali-rs.XXXXXXXXXX)"
+mount /dev/sda2 "$subvol_mnt"
+btrfs subvolume create "$subvol_mnt"/@
+btrfs subvolume create "$subvol_mnt"/@snapshots
+btrfs subvolume create "$subvol_mnt"/@snapshots/root
+subvol_id="$(btrfs subvolume show "$subvol_mnt"/@ | awk '/Subvolume ID:/ {print $3}')"
+btrfs subvolume set-default "$subvol_id" "$subvol_mnt"
+umount "$subvol_mnt"
+rmdir "$subvol_mnt"
+"#;
+
+        assert!(script.contains(expected), "unexpected script:\n{script}");
+    }
+}
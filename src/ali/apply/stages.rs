@@ -1,11 +1,14 @@
 use std::collections::HashSet;
 
+use super::progress::Progress;
 use super::{
     archchroot,
     bootstrap,
     disks,
     dm,
     fs,
+    modules,
+    resolv_conf,
     routines,
 };
 use crate::ali::{
@@ -14,75 +17,103 @@ use crate::ali::{
     ManifestMountpoint,
 };
 use crate::errors::AliError;
-use crate::hooks;
 use crate::types::action::{
     ActionBootstrap,
     ActionMountpoints,
     ActionPostInstallUser,
 };
-use crate::types::stage::StageActions;
 use crate::utils::shell;
+use crate::{
+    hooks,
+    linux,
+};
 
 /// Prepare mountpoints for the new system on live system
 pub fn mountpoints(
     manifest: &Manifest,
     root_location: &str,
-    stages: &mut StageActions,
+    stages: &mut Progress,
 ) -> Result<(), AliError> {
+    // Load kernel modules needed by the live system before touching disks
+    if let Some(ref m_modules) = manifest.preload_modules {
+        let actions_modules = modules::load_modules(m_modules)?;
+        stages.extend(actions_modules);
+    }
+
     // Format and partition disks
     if let Some(ref m_disks) = manifest.disks {
         let actions_disks = disks::apply_disks(m_disks)?;
-        stages.mountpoints.extend(actions_disks);
+        stages.extend(actions_disks);
     }
 
     // Format and create device mappers
     if let Some(ref m_dms) = manifest.device_mappers {
         let actions_dms = dm::apply_dms(m_dms)?;
-        stages.mountpoints.extend(actions_dms);
+        stages.extend(actions_dms);
     }
 
     // Create rootfs
     let rootfs: ManifestFs = manifest.rootfs.clone().into();
-    let action_create_rootfs = fs::create_filesystem(&rootfs)?;
-    stages.mountpoints.push(action_create_rootfs);
+    let action_create_rootfs =
+        fs::create_filesystem(&rootfs, &mut stages.warnings)?;
+    stages.push(action_create_rootfs);
+    stages.extend(fs::tune_filesystem(&rootfs)?);
 
     // Create other filesystems
     if let Some(filesystems) = &manifest.filesystems {
-        let actions_create_filesystems = fs::create_filesystems(filesystems)?;
-        stages.mountpoints.extend(actions_create_filesystems);
+        let actions_create_filesystems =
+            fs::create_filesystems(filesystems, &mut stages.warnings)?;
+        stages.extend(actions_create_filesystems);
+    }
+
+    // Create btrfs subvolumes, before any of them is mounted
+    let actions_subvol = fs::create_subvolumes(manifest)?;
+    stages.extend(actions_subvol);
+
+    // Create swaps
+    if let Some(swaps) = &manifest.swap {
+        let actions_create_swaps = fs::create_swaps(swaps)?;
+        stages.extend(actions_create_swaps);
     }
 
     // mkdir rootfs chroot mount
     shell::exec("mkdir", &["-p", root_location])?;
-    stages.mountpoints.push(ActionMountpoints::MkdirRootFs);
+    stages.push(ActionMountpoints::MkdirRootFs);
 
     // Mount rootfs
     let mnt_root: ManifestMountpoint = manifest.rootfs.clone().into();
-    let action_mnt_rootfs = fs::mount_filesystem(&mnt_root, root_location)?;
-    stages.mountpoints.push(action_mnt_rootfs);
+    let action_mnt_rootfs = fs::mount_filesystem(
+        &mnt_root,
+        Some(&manifest.rootfs.fs_type),
+        root_location,
+    )?;
+    stages.push(action_mnt_rootfs);
 
     // Mount other filesystems to /{DEFAULT_CHROOT_LOC}
     if let Some(mounts) = &manifest.mountpoints {
-        // Collect filesystems mountpoints and actions.
-        // The mountpoints will be prepended with default base
-        let mountpoints: Vec<(String, ActionMountpoints)> = mounts
-            .iter()
-            .map(|m| {
-                (m.dest.clone(), ActionMountpoints::MkdirFs(m.dest.clone()))
-            })
-            .collect();
-
         // mkdir -p /{DEFAULT_CHROOT_LOC}/{mkdir_path}
-        for (dir, action_mkdir) in mountpoints {
-            shell::exec("mkdir", &[&dir])?;
-            stages.mountpoints.push(action_mkdir);
+        for m in mounts {
+            let dir = linux::mount::prepend_base(root_location, &m.dest);
+            shell::exec("mkdir", &["-p", &dir])?;
+            stages.push(ActionMountpoints::MkdirFs(m.dest.clone()));
         }
 
         // Mount other filesystems under /{DEFAULT_CHROOT_LOC}
-        let actions_mnt = fs::mount_filesystems(mounts, root_location)?;
-        stages.mountpoints.extend(actions_mnt);
+        let actions_mnt =
+            fs::mount_filesystems(manifest, mounts, root_location)?;
+        stages.extend(actions_mnt);
     }
 
+    // Mount overlays on top of mounted filesystems
+    if let Some(overlays) = &manifest.overlays {
+        let actions_overlay = fs::mount_overlays(overlays, root_location)?;
+        stages.extend(actions_overlay);
+    }
+
+    // Enable btrfs quota groups on mounted filesystems
+    let actions_quota = fs::enable_quotas(manifest, root_location)?;
+    stages.extend(actions_quota);
+
     Ok(())
 }
 
@@ -90,7 +121,7 @@ pub fn mountpoints(
 pub fn bootstrap(
     manifest: &Manifest,
     install_location: &str,
-    stages: &mut StageActions,
+    stages: &mut Progress,
 ) -> Result<(), AliError> {
     // Collect packages, with base as bare-minimum
     let mut packages = HashSet::from(["base".to_string()]);
@@ -100,8 +131,13 @@ pub fn bootstrap(
 
     // Install packages (manifest.pacstraps) to install_location
     let action_pacstrap = ActionBootstrap::InstallPackages { packages };
-    bootstrap::pacstrap_to_location(&manifest.pacstraps, install_location)?;
-    stages.bootstrap.push(action_pacstrap);
+    bootstrap::pacstrap_to_location(
+        &manifest.pacstraps,
+        install_location,
+        manifest.strict_signatures.unwrap_or(false),
+        manifest.pacman_cache.as_deref(),
+    )?;
+    stages.push(action_pacstrap);
 
     Ok(())
 }
@@ -109,11 +145,11 @@ pub fn bootstrap(
 pub fn routines(
     manifest: &Manifest,
     install_location: &str,
-    stages: &mut StageActions,
+    stages: &mut Progress,
 ) -> Result<(), AliError> {
     // Apply ALI routines installation outside of arch-chroot
     let actions_routine = routines::ali_routines(manifest, install_location)?;
-    stages.routines.extend(actions_routine);
+    stages.extend(actions_routine);
 
     Ok(())
 }
@@ -121,13 +157,13 @@ pub fn routines(
 pub fn chroot_ali(
     manifest: &Manifest,
     install_location: &str,
-    stages: &mut StageActions,
+    stages: &mut Progress,
 ) -> Result<(), AliError> {
     // Apply ALI routine installation in arch-chroot
     let actions_archchroot =
         archchroot::chroot_ali(manifest, install_location)?;
 
-    stages.chroot_ali.extend(actions_archchroot);
+    stages.extend(actions_archchroot);
 
     Ok(())
 }
@@ -135,17 +171,19 @@ pub fn chroot_ali(
 pub fn chroot_user(
     manifest: &Manifest,
     install_location: &str,
-    stages: &mut StageActions,
+    stages: &mut Progress,
 ) -> Result<(), AliError> {
     if manifest.chroot.is_none() {
         return Ok(());
     }
 
-    let commands = manifest.chroot.as_ref().unwrap();
+    // User commands and hooks may need DNS, e.g. @pacman-install
     let actions_user_cmds =
-        archchroot::chroot_user(commands.iter(), install_location)?;
+        resolv_conf::with_resolv_conf(install_location, || {
+            archchroot::chroot_user(manifest.chroot_cmds(), install_location)
+        })?;
 
-    stages.chroot_user.extend(actions_user_cmds);
+    stages.extend(actions_user_cmds);
 
     Ok(())
 }
@@ -153,10 +191,12 @@ pub fn chroot_user(
 pub fn postinstall_user(
     manifest: &Manifest,
     install_location: &str,
-    stages: &mut StageActions,
+    stages: &mut Progress,
 ) -> Result<(), AliError> {
     // Read postinstall and exec hooks or shell commands
-    for cmd in manifest.postinstall.as_ref().unwrap_or(&vec![]) {
+    for manifest_cmd in manifest.postinstall.iter().flatten() {
+        let cmd = manifest_cmd.cmd();
+
         if hooks::is_hook(cmd) {
             let action_hook = hooks::apply_hook(
                 cmd,
@@ -164,21 +204,72 @@ pub fn postinstall_user(
                 install_location,
             )?;
 
-            stages
-                .postinstall_user
-                .push(ActionPostInstallUser::Hook(action_hook));
+            stages.push(ActionPostInstallUser::Hook(action_hook));
 
             continue;
         }
 
         // Apply manifest.postinstall with sh -c 'cmd'
-        shell::sh_c(cmd)?;
+        shell::sh_c_env(cmd, manifest_cmd.env())?;
 
         let action_postinstall_cmd =
-            ActionPostInstallUser::UserPostInstallCmd(cmd.clone());
+            ActionPostInstallUser::UserPostInstallCmd(cmd.to_string());
 
-        stages.postinstall_user.push(action_postinstall_cmd);
+        stages.push(action_postinstall_cmd);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::action::Action;
+
+    #[test]
+    fn test_preload_modules_abort() {
+        let manifest = Manifest::from_yaml(
+            r#"
+preload_modules:
+  - ali-rs-no-such-module
+disks:
+  - device: ./fake-preload-modules.img
+    table: gpt
+    partitions:
+      - label: root
+        type: linux
+rootfs:
+  device: ./fake-preload-modules.img1
+  fstype: ext4
+"#,
+        )
+        .expect("failed to parse manifest");
+
+        let mut on_action = |_: &Action| {};
+        let mut stages = Progress::new(Box::default(), &mut on_action);
+        let result = mountpoints(&manifest, "/alitarget", &mut stages);
+
+        match result {
+            Err(AliError::ApplyError {
+                action_failed,
+                actions_performed,
+                ..
+            }) => {
+                let module = match *action_failed {
+                    Action::Mountpoints(
+                        ActionMountpoints::LoadKernelModule(module),
+                    ) => module,
+                    action => panic!("unexpected failed action {action:?}"),
+                };
+
+                assert_eq!("ali-rs-no-such-module", module);
+                assert!(actions_performed.is_empty());
+            }
+            Err(err) => panic!("unexpected error: {err}"),
+            Ok(_) => panic!("unexpected ok result"),
+        }
+
+        // No disk operation should have been performed
+        assert!(stages.actions.mountpoints.is_empty());
+    }
+}
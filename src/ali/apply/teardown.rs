@@ -0,0 +1,129 @@
+use crate::errors::AliError;
+use crate::types::action::ActionMountpoints;
+use crate::types::stage::StageActions;
+
+/// Returns commands undoing mounts and device mappers in `progress`,
+/// in reverse order of application: everything under `install_location`
+/// is unmounted, then VGs are deactivated and LUKS devices closed.
+pub(super) fn teardown_cmds(
+    install_location: &str,
+    progress: &StageActions,
+) -> Vec<(&'static str, Vec<String>)> {
+    let mut cmds = Vec::new();
+    let mut unmounted = false;
+
+    for action in progress.mountpoints.iter().rev() {
+        match action {
+            ActionMountpoints::MountRootFs
+            | ActionMountpoints::MountFs { .. }
+                if !unmounted =>
+            {
+                unmounted = true;
+                cmds.push((
+                    "umount",
+                    vec!["-R".to_string(), install_location.to_string()],
+                ));
+            }
+
+            ActionMountpoints::CreateDmLvmVg { vg, .. } => {
+                cmds.push(("vgchange", vec!["-an".to_string(), vg.clone()]));
+            }
+
+            ActionMountpoints::OpenDmLuks { name, .. } => {
+                cmds.push((
+                    "cryptsetup",
+                    vec!["close".to_string(), name.clone()],
+                ));
+            }
+
+            _ => {}
+        }
+    }
+
+    cmds
+}
+
+/// Runs teardown commands for `progress` with `exec`,
+/// continuing past failures. Returns failed commands and their errors.
+pub(super) fn teardown(
+    install_location: &str,
+    progress: &StageActions,
+    exec: fn(&str, &[&str]) -> Result<(), AliError>,
+) -> Vec<String> {
+    let mut failed = Vec::new();
+
+    for (cmd, args) in teardown_cmds(install_location, progress) {
+        let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+
+        if let Err(err) = exec(cmd, &args) {
+            failed.push(format!("{cmd} {}: {err}", args.join(" ")));
+        }
+    }
+
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_teardown() {
+        let progress = StageActions {
+            mountpoints: vec![
+                ActionMountpoints::OpenDmLuks {
+                    device: "/dev/sda2".into(),
+                    name: "cryptlvm".into(),
+                },
+                ActionMountpoints::CreateDmLvmPv("/dev/mapper/cryptlvm".into()),
+                ActionMountpoints::CreateDmLvmVg {
+                    pvs: vec!["/dev/mapper/cryptlvm".into()],
+                    vg: "/dev/myvg".into(),
+                },
+                ActionMountpoints::CreateDmLvmLv {
+                    vg: "/dev/myvg".into(),
+                    lv: "/dev/myvg/rootlv".into(),
+                },
+                ActionMountpoints::MountRootFs,
+                ActionMountpoints::MountFs {
+                    src: "/dev/sda1".into(),
+                    dst: "/alitarget/boot".into(),
+                    opts: None,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let cmds: Vec<String> = teardown_cmds("/alitarget", &progress)
+            .into_iter()
+            .map(|(cmd, args)| format!("{cmd} {}", args.join(" ")))
+            .collect();
+
+        assert_eq!(
+            vec![
+                "umount -R /alitarget",
+                "vgchange -an /dev/myvg",
+                "cryptsetup close cryptlvm",
+            ],
+            cmds,
+        );
+
+        assert!(teardown("/alitarget", &progress, |_, _| Ok(())).is_empty());
+
+        // Teardown continues past failed commands
+        let failed = teardown("/alitarget", &progress, |cmd, _| {
+            match cmd {
+                "umount" => {
+                    Err(AliError::NotImplemented("fake umount".to_string()))
+                }
+                _ => Ok(()),
+            }
+        });
+
+        assert_eq!(1, failed.len());
+        assert!(failed[0].starts_with("umount -R /alitarget"));
+
+        let nothing = StageActions::default();
+        assert!(teardown_cmds("/alitarget", &nothing).is_empty());
+    }
+}
@@ -1,7 +1,11 @@
 pub mod apply;
+mod overrides;
 pub mod validation;
 
-use std::collections::HashSet;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 
 use serde::{
     Deserialize,
@@ -9,10 +13,19 @@ use serde::{
 };
 
 use crate::errors::AliError;
+use crate::types::blockdev::{
+    vg_lv_name,
+    BlockDev,
+};
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct Manifest {
-    #[serde(alias = "location", alias = "install_location")]
+    // Install location, overridden by --location and ALI_LOC
+    #[serde(
+        alias = "location",
+        alias = "install_location",
+        alias = "install_root"
+    )]
     pub location: Option<String>,
 
     #[serde(alias = "name", alias = "host")]
@@ -35,7 +48,14 @@ pub struct Manifest {
     #[serde(alias = "mountpoint", alias = "mnt")]
     pub mountpoints: Option<Vec<ManifestMountpoint>>,
 
-    pub swap: Option<Vec<String>>,
+    pub swap: Option<Vec<ManifestSwap>>,
+
+    // overlayfs(5) mounts, mounted after all mountpoints
+    #[serde(alias = "overlay", alias = "overlayfs")]
+    pub overlays: Option<Vec<ManifestOverlay>>,
+
+    // Swap on zram, configured with zram-generator(8)
+    pub zram: Option<ManifestZram>,
 
     #[serde(
         alias = "pacstrap",
@@ -45,6 +65,20 @@ pub struct Manifest {
     )]
     pub pacstraps: Option<HashSet<String>>,
 
+    // Require pacman package signatures during pacstrap
+    #[serde(alias = "strict-signatures")]
+    pub strict_signatures: Option<bool>,
+
+    // Check that a pacman mirror is reachable before touching disks,
+    // defaults to true if pacstrap is to be run
+    #[serde(alias = "require-network")]
+    pub require_network: Option<bool>,
+
+    // Extra package cache dir on the live system for pacstrap,
+    // e.g. with prefetched packages for offline installs
+    #[serde(alias = "pacman-cache", alias = "cachedir")]
+    pub pacman_cache: Option<String>,
+
     #[serde(
         alias = "password",
         alias = "passwd",
@@ -54,10 +88,31 @@ pub struct Manifest {
     pub rootpasswd: Option<String>,
 
     #[serde(alias = "arch-chroot")]
-    pub chroot: Option<Vec<String>>,
+    pub chroot: Option<Vec<ManifestChroot>>,
 
     #[serde(alias = "post-install")]
-    pub postinstall: Option<Vec<String>>,
+    pub postinstall: Option<Vec<ManifestCmd>>,
+
+    // Kernel modules to be loaded on the live system
+    // before any block device is touched
+    #[serde(alias = "preload-modules")]
+    pub preload_modules: Option<Vec<String>>,
+
+    // Reboot after a fully successful install
+    #[serde(alias = "reboot-after", alias = "reboot")]
+    pub reboot_after: Option<bool>,
+
+    // Device identifier used by genfstab(8), defaults to UUID
+    #[serde(alias = "fstab-identifier", alias = "fstab_id")]
+    pub fstab_identifier: Option<FstabIdentifier>,
+
+    // Whether genfstab(8) should include pseudofs mounts
+    #[serde(alias = "fstab-pseudofs")]
+    pub fstab_pseudofs: Option<bool>,
+
+    // Minimum ESP size, e.g. 300M, defaults to 256MiB
+    #[serde(alias = "esp-min-size")]
+    pub esp_min_size: Option<String>,
 }
 
 impl Manifest {
@@ -65,6 +120,218 @@ impl Manifest {
     pub fn from_yaml(manifest_yaml: &str) -> Result<Self, AliError> {
         parse(manifest_yaml)
     }
+
+    /// Serializes manifest back to YAML, which parses to an equal manifest
+    #[cfg(test)]
+    pub fn to_yaml(&self) -> Result<String, AliError> {
+        serde_yaml::to_string(self).map_err(|err| {
+            AliError::AliRsBug(format!("failed to serialize manifest: {err}"))
+        })
+    }
+
+    /// Applies `PATH=VALUE` overrides, e.g. from `--set` CLI flags
+    #[inline]
+    pub fn apply_overrides(
+        &mut self,
+        overrides: &[String],
+    ) -> Result<(), AliError> {
+        overrides::apply(self, overrides)
+    }
+
+    /// Returns fstype of manifest filesystem on `device`, if any
+    pub fn fs_type(&self, device: &str) -> Option<&str> {
+        std::iter::once((&self.rootfs.device, &self.rootfs.fs_type))
+            .chain(
+                self.filesystems
+                    .iter()
+                    .flatten()
+                    .map(|fs| (&fs.device, &fs.fs_type)),
+            )
+            .find(|(dev, _)| *dev == device)
+            .map(|(_, fs_type)| fs_type.as_str())
+    }
+
+    /// Returns `mnt` with mount options required by its manifest
+    /// filesystem appended, e.g. `logdev=` for XFS with external log
+    pub fn effective_mountpoint(
+        &self,
+        mnt: &ManifestMountpoint,
+    ) -> ManifestMountpoint {
+        let fs_opts = self
+            .filesystems
+            .iter()
+            .flatten()
+            .find(|fs| fs.device == mnt.device)
+            .map(ManifestFs::required_mnt_opts)
+            .unwrap_or_default();
+
+        let mut opts: Vec<&str> = mnt
+            .mnt_opts
+            .iter()
+            .flat_map(|opts| opts.split(','))
+            .filter(|opt| !opt.is_empty())
+            .collect();
+
+        // Options given in manifest mountpoint take precedence
+        for opt in &fs_opts {
+            let key = opt.split('=').next();
+            if !opts.iter().any(|o| o.split('=').next() == key) {
+                opts.push(opt);
+            }
+        }
+
+        ManifestMountpoint {
+            device: mnt.device.clone(),
+            dest: mnt.dest.clone(),
+            mnt_opts: match opts.is_empty() {
+                true => None,
+                false => Some(opts.join(",")),
+            },
+        }
+    }
+
+    /// Returns non-disk devices to be formatted or overwritten,
+    /// including devices created by device mappers
+    pub fn devices(&self) -> Vec<String> {
+        let mut devs = vec![self.rootfs.device.clone()];
+
+        for fs in self.filesystems.iter().flatten() {
+            devs.push(fs.device.clone());
+        }
+
+        for swap in self.swap.iter().flatten() {
+            devs.push(swap.device().to_string());
+        }
+
+        for dm in self.device_mappers.iter().flatten() {
+            match dm {
+                Dm::Luks(luks) => {
+                    devs.push(luks.device.clone());
+                    devs.push(BlockDev::from(luks).device);
+                }
+                Dm::Lvm(lvm) => {
+                    devs.extend(lvm.pvs.iter().flatten().cloned());
+
+                    for vg in lvm.vgs.iter().flatten() {
+                        devs.extend(vg.pvs.iter().cloned());
+                    }
+
+                    for lv in lvm.lvs.iter().flatten() {
+                        devs.push(vg_lv_name(lv).1);
+                    }
+                }
+            }
+        }
+
+        devs
+    }
+
+    /// Returns all `chroot` commands in order,
+    /// along with the label of their group, if any
+    pub fn chroot_cmds(
+        &self,
+    ) -> impl Iterator<Item = (Option<&str>, &ManifestCmd)> {
+        self.chroot.iter().flatten().flat_map(|chroot| {
+            let (label, cmds) = match chroot {
+                ManifestChroot::Cmd(cmd) => (None, std::slice::from_ref(cmd)),
+                ManifestChroot::Group { label, cmds } => {
+                    (Some(label.as_str()), cmds.as_slice())
+                }
+            };
+
+            cmds.iter().map(move |cmd| (label, cmd))
+        })
+    }
+}
+
+/// An entry in manifest key `chroot`, either a plain command,
+/// or a labeled group of commands, e.g. `{label: network, cmds: [...]}`
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ManifestChroot {
+    Cmd(ManifestCmd),
+
+    Group {
+        label: String,
+
+        #[serde(alias = "commands")]
+        cmds: Vec<ManifestCmd>,
+    },
+}
+
+/// A `chroot` or `postinstall` command, either a plain command,
+/// or a command with extra environment variables for its invocation,
+/// e.g. `{cmd: mkinitcpio -P, env: {FOO: bar}}`
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ManifestCmd {
+    Cmd(String),
+
+    WithEnv {
+        #[serde(alias = "command")]
+        cmd: String,
+
+        #[serde(alias = "environment")]
+        env: HashMap<String, String>,
+    },
+}
+
+impl ManifestCmd {
+    pub fn cmd(&self) -> &str {
+        match self {
+            Self::Cmd(cmd) | Self::WithEnv { cmd, .. } => cmd,
+        }
+    }
+
+    pub fn env(&self) -> Option<&HashMap<String, String>> {
+        match self {
+            Self::Cmd(_) => None,
+            Self::WithEnv { env, .. } => Some(env),
+        }
+    }
+}
+
+impl From<&str> for ManifestCmd {
+    fn from(cmd: &str) -> Self {
+        Self::Cmd(cmd.to_string())
+    }
+}
+
+/// An entry in manifest key `swap`, either a plain device,
+/// or a device with a swap label, e.g. `{device: /dev/sda2, label: swap}`
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ManifestSwap {
+    Device(String),
+
+    Labeled { device: String, label: String },
+}
+
+impl ManifestSwap {
+    pub fn device(&self) -> &str {
+        match self {
+            Self::Device(device) | Self::Labeled { device, .. } => device,
+        }
+    }
+
+    pub fn device_mut(&mut self) -> &mut String {
+        match self {
+            Self::Device(device) | Self::Labeled { device, .. } => device,
+        }
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            Self::Device(_) => None,
+            Self::Labeled { label, .. } => Some(label),
+        }
+    }
+}
+
+impl From<&str> for ManifestSwap {
+    fn from(device: &str) -> Self {
+        Self::Device(device.to_string())
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -76,6 +343,33 @@ pub enum PartitionTable {
     Mbr,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum FstabIdentifier {
+    #[serde(rename = "uuid")]
+    Uuid,
+
+    #[serde(rename = "label")]
+    Label,
+
+    #[serde(rename = "partuuid")]
+    PartUuid,
+
+    #[serde(rename = "partlabel")]
+    PartLabel,
+}
+
+/// How mkfs output is shown when creating a filesystem
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum MkfsOutput {
+    /// Shown as usual, with mkfs attached to the terminal if any
+    #[serde(rename = "verbose")]
+    Verbose,
+
+    /// Only captured to the report, with stdin closed
+    #[serde(rename = "quiet")]
+    Quiet,
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct ManifestDisk {
     pub device: String,
@@ -90,6 +384,10 @@ pub struct ManifestPartition {
 
     #[serde(rename = "type")]
     pub part_type: String,
+
+    // GPT unique partition GUID, i.e. PARTUUID
+    #[serde(alias = "partuuid", alias = "guid")]
+    pub uuid: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -101,6 +399,46 @@ pub struct ManifestFs {
 
     #[serde(alias = "fsopts", alias = "filesystem_options")]
     pub fs_opts: Option<String>,
+
+    // Overrides fstab(5) dump field (fs_freq) generated for this fs
+    #[serde(alias = "fstab-dump", alias = "dump")]
+    pub fstab_dump: Option<u8>,
+
+    // Overrides fstab(5) pass field (fs_passno) generated for this fs
+    #[serde(alias = "fstab-pass", alias = "pass")]
+    pub fstab_pass: Option<u8>,
+
+    // External XFS log section device (mkfs.xfs -l logdev=)
+    #[serde(alias = "log-device", alias = "logdev")]
+    pub log_device: Option<String>,
+
+    // XFS realtime section device (mkfs.xfs -r rtdev=)
+    #[serde(alias = "rt-device", alias = "rtdev")]
+    pub rt_device: Option<String>,
+
+    // Enable btrfs quota groups once mounted
+    #[serde(alias = "qgroups")]
+    pub quota: Option<bool>,
+
+    // tune2fs(8) arguments for ext2/3/4, applied after mkfs
+    #[serde(alias = "tune2fs")]
+    pub tune2fs_args: Option<Vec<String>>,
+
+    // Whether mkfs discards (TRIMs) the device, defaults to mkfs default
+    #[serde(alias = "mkfs-discard")]
+    pub mkfs_discard: Option<bool>,
+
+    // Whether mkfs output is shown or only captured, defaults to verbose
+    #[serde(alias = "mkfs-output")]
+    pub mkfs_output: Option<MkfsOutput>,
+
+    // btrfs subvolumes created right after mkfs, relative to the top level
+    #[serde(alias = "subvols")]
+    pub subvolumes: Option<Vec<String>>,
+
+    // One of subvolumes, set as the default subvolume mounted without subvol=
+    #[serde(alias = "default-subvol", alias = "default_subvolume")]
+    pub default_subvol: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -114,6 +452,55 @@ pub struct ManifestMountpoint {
     pub mnt_opts: Option<String>,
 }
 
+/// An overlayfs(5) mount. All dirs are paths on the new system,
+/// and `lower_dir` may list several dirs separated by `:`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ManifestOverlay {
+    #[serde(alias = "lowerdir", alias = "lower")]
+    pub lower_dir: String,
+
+    #[serde(alias = "upperdir", alias = "upper")]
+    pub upper_dir: String,
+
+    #[serde(alias = "workdir", alias = "work")]
+    pub work_dir: String,
+
+    #[serde(alias = "mount", alias = "mountpoint", alias = "dest")]
+    pub mnt: String,
+}
+
+impl ManifestFs {
+    /// Returns mount options required on every mount of this fs,
+    /// i.e. XFS external log and realtime devices
+    pub fn required_mnt_opts(&self) -> Vec<String> {
+        let log = self.log_device.iter().map(|dev| format!("logdev={dev}"));
+        let rt = self.rt_device.iter().map(|dev| format!("rtdev={dev}"));
+
+        log.chain(rt).collect()
+    }
+}
+
+impl ManifestOverlay {
+    pub fn lower_dirs(&self) -> impl Iterator<Item = &str> {
+        self.lower_dir.split(':')
+    }
+
+    /// Returns overlay mount options, with all dirs under `base`
+    pub fn mnt_opts(&self, base: &str) -> String {
+        let lower_dirs: Vec<String> = self
+            .lower_dirs()
+            .map(|dir| format!("{base}{dir}"))
+            .collect();
+
+        format!(
+            "lowerdir={},upperdir={base}{},workdir={base}{}",
+            lower_dirs.join(":"),
+            self.upper_dir,
+            self.work_dir,
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ManifestRootFs {
     pub device: String,
@@ -126,6 +513,41 @@ pub struct ManifestRootFs {
 
     #[serde(alias = "mntopts", alias = "mount_options")]
     pub mnt_opts: Option<String>,
+
+    #[serde(alias = "fstab-dump", alias = "dump")]
+    pub fstab_dump: Option<u8>,
+
+    #[serde(alias = "fstab-pass", alias = "pass")]
+    pub fstab_pass: Option<u8>,
+
+    #[serde(alias = "qgroups")]
+    pub quota: Option<bool>,
+
+    #[serde(alias = "tune2fs")]
+    pub tune2fs_args: Option<Vec<String>>,
+
+    #[serde(alias = "mkfs-discard")]
+    pub mkfs_discard: Option<bool>,
+
+    #[serde(alias = "mkfs-output")]
+    pub mkfs_output: Option<MkfsOutput>,
+
+    #[serde(alias = "subvols")]
+    pub subvolumes: Option<Vec<String>>,
+
+    #[serde(alias = "default-subvol", alias = "default_subvolume")]
+    pub default_subvol: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ManifestZram {
+    // Fraction of RAM like ram/2, or absolute size like 4GiB
+    #[serde(alias = "zram-size", alias = "zram_size")]
+    pub size: Option<String>,
+
+    // e.g. zstd or lz4, defaults to kernel default
+    #[serde(alias = "compression-algorithm", alias = "algorithm")]
+    pub compression_algorithm: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -134,9 +556,25 @@ pub struct ManifestLuks {
     pub name: String,
 
     // If passphrase is None, let cryptsetup prompt user for password,
-    // if it is Some(pass), pipe pass to cryptsetup
+    // if it is Some(pass), pipe pass to cryptsetup.
+    // pass can also be `env:NAME` or `file:PATH`, see utils::secret
     #[serde(alias = "key")]
     pub passphrase: Option<String>,
+
+    // PBKDF tuning for luksFormat, see cryptsetup(8) --iter-time,
+    // --pbkdf-memory (KiB) and --pbkdf-parallel (argon2 only)
+    #[serde(alias = "iter_time")]
+    pub pbkdf_time_ms: Option<u32>,
+    pub pbkdf_memory: Option<u32>,
+    pub pbkdf_parallel: Option<u32>,
+
+    // Detached LUKS header file or device, used as is
+    // by cryptsetup and by crypttab(5) on the new system
+    #[serde(alias = "detached_header")]
+    pub header: Option<String>,
+
+    // Whether to add an entry for this LUKS to /etc/crypttab
+    pub crypttab: Option<bool>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -150,6 +588,13 @@ pub struct ManifestLvmLv {
     pub name: String,
     pub vg: String,
     pub size: Option<String>,
+
+    // LVM RAID type, e.g. raid1 or raid5, see lvmraid(7).
+    // Mirrors and stripes are passed to lvcreate as -m and -i
+    #[serde(alias = "raid")]
+    pub raid_type: Option<String>,
+    pub mirrors: Option<u8>,
+    pub stripes: Option<u8>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -175,6 +620,16 @@ impl From<ManifestRootFs> for ManifestFs {
             device: rootfs.device,
             fs_type: rootfs.fs_type,
             fs_opts: rootfs.fs_opts,
+            fstab_dump: rootfs.fstab_dump,
+            fstab_pass: rootfs.fstab_pass,
+            log_device: None,
+            rt_device: None,
+            quota: rootfs.quota,
+            tune2fs_args: rootfs.tune2fs_args,
+            mkfs_discard: rootfs.mkfs_discard,
+            mkfs_output: rootfs.mkfs_output,
+            subvolumes: rootfs.subvolumes,
+            default_subvol: rootfs.default_subvol,
         }
     }
 }
@@ -202,3 +657,180 @@ fn test_parse() {
 
     println!("{:?}", manifest);
 }
+
+#[test]
+fn test_parse_chroot_groups() {
+    let manifest = Manifest::from_yaml(
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+chroot:
+  - bootctl install
+  - label: network
+    cmds:
+      - systemctl enable systemd-networkd
+      - "@quicknet eth0"
+  - echo done
+"#,
+    )
+    .unwrap();
+
+    let expected = vec![
+        ManifestChroot::Cmd("bootctl install".into()),
+        ManifestChroot::Group {
+            label: "network".into(),
+            cmds: vec![
+                "systemctl enable systemd-networkd".into(),
+                "@quicknet eth0".into(),
+            ],
+        },
+        ManifestChroot::Cmd("echo done".into()),
+    ];
+
+    assert_eq!(Some(&expected), manifest.chroot.as_ref());
+
+    let cmds: Vec<_> = manifest
+        .chroot_cmds()
+        .map(|(label, cmd)| (label, cmd.cmd()))
+        .collect();
+    assert_eq!(
+        vec![
+            (None, "bootctl install"),
+            (Some("network"), "systemctl enable systemd-networkd"),
+            (Some("network"), "@quicknet eth0"),
+            (None, "echo done"),
+        ],
+        cmds,
+    );
+
+    // Flat form
+    let manifest =
+        parse(include_str!("./examples/uefi-root-on-lvm.yaml")).unwrap();
+    assert!(manifest.chroot_cmds().all(|(label, _)| label.is_none()));
+    assert_eq!(5, manifest.chroot_cmds().count());
+}
+
+#[test]
+fn test_parse_cmd_env() {
+    let manifest = Manifest::from_yaml(
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+chroot:
+  - cmd: mkinitcpio -P
+    env:
+      FOO: bar
+  - label: boot
+    cmds:
+      - bootctl install
+      - command: bootctl update
+        environment: {}
+postinstall:
+  - echo done
+  - cmd: echo $FOO
+    env: {FOO: bar}
+"#,
+    )
+    .unwrap();
+
+    let env = HashMap::from([("FOO".to_string(), "bar".to_string())]);
+    let chroot_cmds: Vec<_> =
+        manifest.chroot_cmds().map(|(_, cmd)| cmd).collect();
+
+    assert_eq!(
+        &ManifestCmd::WithEnv {
+            cmd: "mkinitcpio -P".into(),
+            env: env.clone(),
+        },
+        chroot_cmds[0],
+    );
+    assert_eq!(&ManifestCmd::from("bootctl install"), chroot_cmds[1]);
+    assert_eq!(Some(&HashMap::new()), chroot_cmds[2].env());
+
+    let postinstall = manifest.postinstall.unwrap();
+    assert_eq!(None, postinstall[0].env());
+    assert_eq!(Some(&env), postinstall[1].env());
+    assert_eq!("echo $FOO", postinstall[1].cmd());
+}
+
+#[test]
+fn test_to_yaml_round_trip() {
+    let representative = r#"
+location: /mnt
+hostname: foo
+disks:
+  - device: /dev/fake1
+    table: gpt
+    partitions:
+      - label: efi
+        size: 500M
+        type: ef
+      - label: root
+        type: 8e
+  - device: /dev/fake2
+    table: dos
+    partitions:
+      - label: data
+        type: 83
+device_mappers:
+  - type: luks
+    device: /dev/fake1p2
+    name: cryptroot
+  - type: lvm
+    pvs:
+      - /dev/mapper/cryptroot
+    vgs:
+      - name: myvg
+        pvs:
+          - /dev/mapper/cryptroot
+    lvs:
+      - name: rootlv
+        vg: myvg
+        size: 50%VG
+rootfs:
+  device: /dev/myvg/rootlv
+  fstype: btrfs
+filesystems:
+  - device: /dev/fake1p1
+    fstype: vfat
+mountpoints:
+  - device: /dev/fake1p1
+    dest: /boot
+swap:
+  - /dev/fake2p1
+  - device: /dev/fake2p2
+    label: swap
+zram:
+  size: ram/2
+  algorithm: zstd
+fstab_identifier: partuuid
+chroot:
+  - bootctl install
+  - label: network
+    cmds:
+      - "@quicknet eth0"
+"#;
+
+    let manifests = vec![
+        representative,
+        include_str!("./examples/uefi-root-on-lvm.yaml"),
+        include_str!("./examples/uefi-root-on-lvm-on-luks.yaml"),
+    ];
+
+    for manifest_yaml in manifests {
+        let manifest = parse(manifest_yaml).unwrap();
+        let yaml = manifest.to_yaml().unwrap();
+        let round_trip = parse(&yaml)
+            .unwrap_or_else(|err| panic!("bad serialized yaml {yaml}: {err}"));
+
+        assert_eq!(manifest, round_trip);
+    }
+
+    let yaml = parse(representative).unwrap().to_yaml().unwrap();
+    assert!(yaml.contains("table: mbr"));
+    assert!(yaml.contains("type: luks"));
+    assert!(yaml.contains("type: lvm"));
+    assert!(yaml.contains("fstab_identifier: partuuid"));
+}
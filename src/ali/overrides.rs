@@ -0,0 +1,146 @@
+use serde_yaml::Value;
+
+use super::Manifest;
+use crate::errors::AliError;
+
+/// Applies `PATH=VALUE` overrides to manifest, where `PATH` is
+/// a dotted path of manifest keys and sequence indexes,
+/// e.g. `hostname=srv1` or `disks.0.device=/dev/nvme0n1`.
+///
+/// `VALUE` is parsed as YAML, so `reboot_after=true` sets a boolean,
+/// and an empty `VALUE` unsets optional keys.
+///
+/// Paths are resolved against the canonical (non-alias) manifest keys,
+/// and every key in the path must already exist in the manifest.
+pub(super) fn apply(
+    manifest: &mut Manifest,
+    overrides: &[String],
+) -> Result<(), AliError> {
+    if overrides.is_empty() {
+        return Ok(());
+    }
+
+    let mut root = serde_yaml::to_value(&*manifest).map_err(|err| {
+        AliError::AliRsBug(format!("failed to serialize manifest: {err}"))
+    })?;
+
+    for ovr in overrides {
+        let Some((path, value)) = ovr.split_once('=') else {
+            return Err(AliError::BadArgs(format!(
+                "bad override {ovr}: expecting PATH=VALUE"
+            )));
+        };
+
+        let value: Value = serde_yaml::from_str(value).map_err(|err| {
+            AliError::BadArgs(format!("bad override value for {path}: {err}"))
+        })?;
+
+        *lookup(&mut root, path)? = value;
+    }
+
+    *manifest = serde_yaml::from_value(root).map_err(|err| {
+        AliError::BadArgs(format!("bad manifest after overrides: {err}"))
+    })?;
+
+    Ok(())
+}
+
+fn lookup<'a>(
+    root: &'a mut Value,
+    path: &str,
+) -> Result<&'a mut Value, AliError> {
+    let bad_path = |reason: String| {
+        AliError::BadArgs(format!("bad override path {path}: {reason}"))
+    };
+
+    if path.is_empty() {
+        return Err(bad_path("empty path".to_string()));
+    }
+
+    let mut current = root;
+    for key in path.split('.') {
+        current = match current {
+            Value::Mapping(map) => {
+                map.get_mut(key)
+                    .ok_or_else(|| bad_path(format!("no such key {key}")))?
+            }
+
+            Value::Sequence(seq) => {
+                let len = seq.len();
+                let i = key.parse::<usize>().map_err(|_| {
+                    bad_path(format!("{key} is not a sequence index"))
+                })?;
+
+                seq.get_mut(i).ok_or_else(|| {
+                    bad_path(format!("index {i} out of range (length {len})"))
+                })?
+            }
+
+            _ => {
+                return Err(bad_path(format!("cannot index {key} into value")))
+            }
+        };
+    }
+
+    Ok(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+hostname: foo
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda1
+  fstype: ext4
+"#;
+
+    #[test]
+    fn test_apply_overrides() {
+        let mut manifest = Manifest::from_yaml(MANIFEST).unwrap();
+        let overrides = vec![
+            "hostname=srv1".to_string(),
+            "timezone=Asia/Bangkok".to_string(),
+            "disks.0.device=/dev/nvme0n1".to_string(),
+            "rootfs.device=/dev/nvme0n1p1".to_string(),
+            "reboot_after=true".to_string(),
+        ];
+
+        apply(&mut manifest, &overrides).expect("failed to apply overrides");
+
+        assert_eq!(Some("srv1"), manifest.hostname.as_deref());
+        assert_eq!(Some("Asia/Bangkok"), manifest.timezone.as_deref());
+        assert_eq!("/dev/nvme0n1", manifest.disks.unwrap()[0].device);
+        assert_eq!("/dev/nvme0n1p1", manifest.rootfs.device);
+        assert_eq!(Some(true), manifest.reboot_after);
+
+        let mut manifest = Manifest::from_yaml(MANIFEST).unwrap();
+        apply(&mut manifest, &["hostname=".to_string()]).unwrap();
+        assert_eq!(None, manifest.hostname);
+
+        let should_err = vec![
+            "hostname",
+            "=foo",
+            "hostnme=foo",
+            "disks.1.device=/dev/sdb",
+            "disks.first.device=/dev/sdb",
+            "hostname.foo=bar",
+            "rootfs.no_such_key=bar",
+            "rootfs=foo",
+        ];
+
+        for ovr in should_err {
+            let mut manifest = Manifest::from_yaml(MANIFEST).unwrap();
+            if apply(&mut manifest, &[ovr.to_string()]).is_ok() {
+                panic!("unexpected ok result for override {ovr}");
+            }
+        }
+    }
+}
@@ -1,20 +1,70 @@
 use std::collections::{
     HashMap,
+    HashSet,
     LinkedList,
 };
 
-use crate::ali::ManifestDisk;
+use crate::ali::{
+    ManifestDisk,
+    PartitionTable,
+};
 use crate::errors::AliError;
 use crate::linux;
+use crate::linux::fdisk;
 use crate::types::blockdev::*;
 use crate::utils::fs::file_exists;
 
+/// Validates that all manifest disks exist on the system
+pub(crate) fn validate_exists(disks: &[ManifestDisk]) -> Result<(), AliError> {
+    for disk in disks {
+        if !file_exists(&disk.device) {
+            return Err(AliError::BadManifest(format!(
+                "no such disk device: {}",
+                disk.device
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates that disks with existing partition tables already have
+/// the manifest table type. Writing a GPT over an MBR (or vice versa)
+/// may leave remnants of the old table, so such disks must be wiped first.
+///
+/// `pt_type` returns the current table type of a disk, in blkid(8) terms.
+pub(crate) fn validate_tables<F>(
+    disks: &[ManifestDisk],
+    pt_type: F,
+) -> Result<(), AliError>
+where
+    F: Fn(&str) -> Result<Option<String>, AliError>,
+{
+    for disk in disks {
+        let Some(current) = pt_type(&disk.device)? else {
+            continue;
+        };
+
+        let wanted = linux::blkid::pt_type_name(&disk.table);
+        if current != wanted {
+            return Err(AliError::BadManifest(format!(
+                "disk {} already has a {current} partition table, but manifest wants {wanted}: wipe the disk first (e.g. wipefs -a {}), or use --overwrite",
+                disk.device, disk.device,
+            )));
+        }
+    }
+
+    Ok(())
+}
+
 pub(crate) fn collect_valids(
     disks: &[ManifestDisk],
     sys_fs_devs: &HashMap<String, BlockDevType>,
     sys_fs_ready_devs: &HashMap<String, BlockDevType>,
     valids: &mut BlockDevPaths,
 ) -> Result<(), AliError> {
+    validate_partition_labels(disks)?;
+
     for disk in disks {
         collect_valid(disk, sys_fs_devs, sys_fs_ready_devs, valids)?;
     }
@@ -28,13 +78,6 @@ fn collect_valid(
     sys_fs_ready_devs: &HashMap<String, BlockDevType>,
     valids: &mut BlockDevPaths,
 ) -> Result<(), AliError> {
-    if !file_exists(&disk.device) {
-        return Err(AliError::BadManifest(format!(
-            "no such disk device: {}",
-            disk.device
-        )));
-    }
-
     if let Some(fs) = sys_fs_devs.get(&disk.device) {
         return Err(AliError::BadManifest(format!(
             "disk {} already in use as {fs}",
@@ -55,6 +98,9 @@ fn collect_valid(
         }
     }
 
+    validate_partition_layout(disk)?;
+    validate_partition_uuids(disk)?;
+
     // Base disk
     let base = LinkedList::from([BlockDev {
         device: disk.device.clone(),
@@ -72,9 +118,12 @@ fn collect_valid(
         let partition_name =
             linux::partition_name(&disk.device, partition_number);
 
+        let extended = is_extended(disk, i + 1);
+
         // If multiple partitions are to be created on this disk,
-        // only the last partition could be unsized
-        if i != l - 1 && l != 1 && part.size.is_none() {
+        // only the last partition could be unsized, except the
+        // extended partition which may span the rest of the disk
+        if i != l - 1 && l != 1 && part.size.is_none() && !extended {
             return Err(AliError::BadManifest(format!(
                 "{msg}: unsized partition {partition_name} must be the last partition"
             )));
@@ -100,6 +149,11 @@ fn collect_valid(
             }
         }
 
+        // Extended partition cannot be used as a filesystem base
+        if extended {
+            continue;
+        }
+
         let mut partition = base.clone();
         partition.push_back(BlockDev {
             device: partition_name,
@@ -111,3 +165,353 @@ fn collect_valid(
 
     Ok(())
 }
+
+// MBR holds 4 primary partitions, or 3 primary partitions and
+// an extended partition with logical partitions up to fdisk's limit.
+// fdisk creates GPT with 128 partition entries by default.
+const MAX_PARTITIONS_MBR_PRIMARY: usize = 4;
+const MAX_PARTITIONS_MBR: usize = 60;
+const MAX_PARTITIONS_GPT: usize = 128;
+
+// The only MBR layout with an extended partition that ali-rs creates
+const MBR_EXTENDED_PARTITION_NUMBER: usize = 4;
+
+/// Returns whether partition #`number` on `disk` is the MBR extended
+/// partition, which only holds logical partitions and is not fs-ready
+pub(super) fn is_extended(disk: &ManifestDisk, number: usize) -> bool {
+    disk.table == PartitionTable::Mbr
+        && number == MBR_EXTENDED_PARTITION_NUMBER
+        && disk.partitions.len() > MAX_PARTITIONS_MBR_PRIMARY
+}
+
+/// Validates partition count against partition table limits.
+///
+/// MBR disks with more than 4 partitions must have partition #4
+/// as the extended partition, and partitions #5 onwards are created
+/// as logical partitions inside it. Extended partition type
+/// is not allowed anywhere else.
+fn validate_partition_layout(disk: &ManifestDisk) -> Result<(), AliError> {
+    let msg = "partition validation failed";
+    let count = disk.partitions.len();
+
+    let (table, max) = match disk.table {
+        PartitionTable::Mbr => ("MBR", MAX_PARTITIONS_MBR),
+        PartitionTable::Gpt => ("GPT", MAX_PARTITIONS_GPT),
+    };
+
+    if count > max {
+        return Err(AliError::BadManifest(format!(
+            "{msg}: disk {} has {count} partitions, but {table} supports at most {max}",
+            disk.device,
+        )));
+    }
+
+    if disk.table != PartitionTable::Mbr {
+        return Ok(());
+    }
+
+    for (i, part) in disk.partitions.iter().enumerate() {
+        let number = i + 1;
+        let extended_type = fdisk::is_extended_type(&part.part_type);
+
+        match (is_extended(disk, number), extended_type) {
+            (true, false) => {
+                return Err(AliError::BadManifest(format!(
+                    "{msg}: MBR disk {} has {count} partitions, so partition #{number} must be extended, got type {}",
+                    disk.device, part.part_type,
+                )));
+            }
+
+            (false, true) => {
+                return Err(AliError::BadManifest(format!(
+                    "{msg}: extended partition #{number} on MBR disk {} must be partition #{MBR_EXTENDED_PARTITION_NUMBER} followed by logical partitions",
+                    disk.device,
+                )));
+            }
+
+            _ => continue,
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates that partition GUIDs are only used on GPT disks,
+/// are well-formed, and are unique across the disk.
+fn validate_partition_uuids(disk: &ManifestDisk) -> Result<(), AliError> {
+    let msg = "partition uuid validation failed";
+    let mut uuids = HashSet::new();
+
+    for (i, part) in disk.partitions.iter().enumerate() {
+        let Some(ref uuid) = part.uuid else {
+            continue;
+        };
+
+        // MBR PARTUUIDs are derived from disk signature
+        if disk.table != PartitionTable::Gpt {
+            return Err(AliError::BadManifest(format!(
+                "{msg}: partition #{} on non-GPT disk {} has uuid {uuid}",
+                i + 1,
+                disk.device,
+            )));
+        }
+
+        if !is_guid(uuid) {
+            return Err(AliError::BadManifest(format!(
+                "{msg}: bad GUID {uuid} for partition #{} on {}",
+                i + 1,
+                disk.device,
+            )));
+        }
+
+        if !uuids.insert(uuid.to_lowercase()) {
+            return Err(AliError::BadManifest(format!(
+                "{msg}: duplicate GUID {uuid} on {}",
+                disk.device,
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates that partition labels are unique across all disks,
+/// since a repeated label (e.g. for fstab PARTLABEL= or
+/// /dev/disk/by-partlabel) cannot tell the partitions apart.
+fn validate_partition_labels(disks: &[ManifestDisk]) -> Result<(), AliError> {
+    let mut labels: HashMap<&str, &str> = HashMap::new();
+
+    for disk in disks {
+        for part in &disk.partitions {
+            let Some(used_on) =
+                labels.insert(part.label.as_str(), disk.device.as_str())
+            else {
+                continue;
+            };
+
+            return Err(AliError::BadManifest(format!(
+                "partition label validation failed: duplicate label {} on disk {}, already used on disk {used_on}",
+                part.label, disk.device,
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns if `s` is a GUID string, e.g. 5a4e6f3c-1b2d-4c8e-9f70-2d3b4a5c6d7e
+fn is_guid(s: &str) -> bool {
+    let groups: Vec<&str> = s.split('-').collect();
+    let lengths = [8, 4, 4, 4, 12];
+
+    groups.len() == lengths.len()
+        && groups.iter().zip(lengths).all(|(group, len)| {
+            group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ali::ManifestPartition;
+
+    fn partition(
+        label: &str,
+        part_type: &str,
+        uuid: Option<&str>,
+    ) -> ManifestPartition {
+        ManifestPartition {
+            label: label.into(),
+            size: Some("1G".into()),
+            part_type: part_type.into(),
+            uuid: uuid.map(String::from),
+        }
+    }
+
+    fn disk(table: PartitionTable, uuids: &[Option<&str>]) -> ManifestDisk {
+        ManifestDisk {
+            device: "/dev/fda".into(),
+            table,
+            partitions: uuids
+                .iter()
+                .map(|uuid| partition("part", "linux", *uuid))
+                .collect(),
+        }
+    }
+
+    fn disk_types(table: PartitionTable, types: &[&str]) -> ManifestDisk {
+        ManifestDisk {
+            device: "/dev/fda".into(),
+            table,
+            partitions: types
+                .iter()
+                .map(|part_type| partition("part", part_type, None))
+                .collect(),
+        }
+    }
+
+    fn disk_labels(device: &str, labels: &[&str]) -> ManifestDisk {
+        ManifestDisk {
+            device: device.into(),
+            table: PartitionTable::Gpt,
+            partitions: labels
+                .iter()
+                .map(|label| partition(label, "linux", None))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate_partition_layout() {
+        let should_ok = vec![
+            disk_types(PartitionTable::Mbr, &["linux"; 4]),
+            disk_types(
+                PartitionTable::Mbr,
+                &["uefi", "swap", "linux", "extended", "linux", "lvm"],
+            ),
+            disk_types(
+                PartitionTable::Mbr,
+                &["linux", "linux", "linux", "5", "linux"],
+            ),
+            disk_types(PartitionTable::Gpt, &["linux"; 128]),
+        ];
+
+        let should_err = vec![
+            disk_types(PartitionTable::Mbr, &["linux"; 5]),
+            disk_types(PartitionTable::Mbr, &["linux", "extended", "linux"]),
+            disk_types(
+                PartitionTable::Mbr,
+                &["linux", "linux", "linux", "extended"],
+            ),
+            disk_types(
+                PartitionTable::Mbr,
+                &["linux", "linux", "linux", "extended", "85", "linux"],
+            ),
+            disk_types(PartitionTable::Mbr, &["linux"; 61]),
+            disk_types(PartitionTable::Gpt, &["linux"; 129]),
+        ];
+
+        for d in should_ok {
+            if let Err(err) = validate_partition_layout(&d) {
+                panic!("unexpected error for {d:?}: {err}");
+            }
+        }
+
+        for d in should_err {
+            if validate_partition_layout(&d).is_ok() {
+                panic!("unexpected ok result for {d:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_tables() {
+        // Mocked blkid PTTYPE of /dev/fda
+        let no_table = |_: &str| Ok(None);
+        let mbr = |_: &str| Ok(Some("dos".to_string()));
+        let gpt = |_: &str| Ok(Some("gpt".to_string()));
+
+        let gpt_disk = [disk(PartitionTable::Gpt, &[None])];
+        let mbr_disk = [disk(PartitionTable::Mbr, &[None])];
+
+        validate_tables(&gpt_disk, no_table).expect("blank disk");
+        validate_tables(&gpt_disk, gpt).expect("same table type");
+        validate_tables(&mbr_disk, mbr).expect("same table type");
+
+        match validate_tables(&gpt_disk, mbr) {
+            Err(AliError::BadManifest(msg)) => {
+                assert!(msg.contains("/dev/fda"));
+                assert!(msg.contains("wipe"));
+            }
+            result => panic!("unexpected result for GPT on MBR: {result:?}"),
+        }
+
+        assert!(validate_tables(&mbr_disk, gpt).is_err());
+
+        // Detection errors are not swallowed
+        let failed = |_: &str| Err(AliError::NoSuchDevice("/dev/fda".into()));
+        assert!(validate_tables(&gpt_disk, failed).is_err());
+    }
+
+    #[test]
+    fn test_validate_partition_uuids() {
+        let uuid_1 = "5a4e6f3c-1b2d-4c8e-9f70-2d3b4a5c6d7e";
+        let uuid_2 = "0FC63DAF-8483-4772-8E79-3D69D8477DE4";
+
+        let should_ok = vec![
+            disk(PartitionTable::Gpt, &[None, None]),
+            disk(PartitionTable::Mbr, &[None]),
+            disk(PartitionTable::Gpt, &[Some(uuid_1), None, Some(uuid_2)]),
+        ];
+
+        let should_err = vec![
+            disk(PartitionTable::Mbr, &[Some(uuid_1)]),
+            disk(PartitionTable::Gpt, &[Some("not-a-guid")]),
+            disk(
+                PartitionTable::Gpt,
+                &[Some("5a4e6f3c1b2d4c8e9f702d3b4a5c6d7e")],
+            ),
+            disk(
+                PartitionTable::Gpt,
+                &[Some("5a4e6f3c-1b2d-4c8e-9f70-2d3b4a5c6d7g")],
+            ),
+            disk(
+                PartitionTable::Gpt,
+                &[Some(uuid_1), Some(&uuid_1.to_uppercase())],
+            ),
+        ];
+
+        for d in should_ok {
+            if let Err(err) = validate_partition_uuids(&d) {
+                panic!("unexpected error for {d:?}: {err}");
+            }
+        }
+
+        for d in should_err {
+            if validate_partition_uuids(&d).is_ok() {
+                panic!("unexpected ok result for {d:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_partition_labels() {
+        let should_ok = vec![
+            vec![disk_labels("/dev/fda", &["EFI", "DATA"])],
+            vec![
+                disk_labels("/dev/fda", &["EFI", "ROOT"]),
+                disk_labels("/dev/fdb", &["DATA"]),
+            ],
+        ];
+
+        // The last disk always has the duplicate label DATA
+        let should_err = vec![
+            vec![disk_labels("/dev/fda", &["DATA", "DATA"])],
+            vec![disk_labels("/dev/fdb", &["EFI", "DATA", "SWAP", "DATA"])],
+            vec![
+                disk_labels("/dev/fda", &["EFI", "DATA"]),
+                disk_labels("/dev/fdb", &["DATA"]),
+            ],
+        ];
+
+        for disks in should_ok {
+            if let Err(err) = validate_partition_labels(&disks) {
+                panic!("unexpected error for {disks:?}: {err}");
+            }
+        }
+
+        for disks in should_err {
+            let device = &disks.last().unwrap().device;
+
+            match validate_partition_labels(&disks) {
+                Err(AliError::BadManifest(msg)) => {
+                    assert!(msg.contains("DATA"));
+                    assert!(msg.contains(device.as_str()));
+                }
+                result => {
+                    panic!("unexpected result for {disks:?}: {result:?}")
+                }
+            }
+        }
+    }
+}
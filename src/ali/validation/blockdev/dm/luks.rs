@@ -1,6 +1,40 @@
+use std::ops::RangeInclusive;
+
 use super::*;
 use crate::ali::ManifestLuks;
 
+// Same limits as libcryptsetup argon2 PBKDF, memory in KiB
+const PBKDF_MEMORY: RangeInclusive<u32> = 32..=4 * 1024 * 1024;
+const PBKDF_PARALLEL: RangeInclusive<u32> = 1..=4;
+// Unlocking should not take more than a minute
+const PBKDF_TIME_MS: RangeInclusive<u32> = 1..=60_000;
+
+/// Validates that LUKS PBKDF options are within cryptsetup limits
+pub(super) fn validate_pbkdf(luks: &ManifestLuks) -> Result<(), AliError> {
+    let opts = [
+        ("pbkdf_time_ms", luks.pbkdf_time_ms, PBKDF_TIME_MS),
+        ("pbkdf_memory", luks.pbkdf_memory, PBKDF_MEMORY),
+        ("pbkdf_parallel", luks.pbkdf_parallel, PBKDF_PARALLEL),
+    ];
+
+    for (opt, value, range) in opts {
+        let Some(value) = value else {
+            continue;
+        };
+
+        if !range.contains(&value) {
+            return Err(AliError::BadManifest(format!(
+                "dm luks validation failed: luks {} {opt} {value} is out of range {}-{}",
+                luks.name,
+                range.start(),
+                range.end(),
+            )));
+        }
+    }
+
+    Ok(())
+}
+
 // Collects valid block device path(s) into valids
 #[inline]
 pub(super) fn collect_valid(
@@ -163,3 +197,49 @@ pub(super) fn collect_valid(
 
     Ok(())
 }
+
+#[test]
+fn test_validate_pbkdf() {
+    let luks = |time_ms, memory, parallel| {
+        ManifestLuks {
+            device: "/dev/sda2".into(),
+            name: "cryptroot".into(),
+            passphrase: None,
+            pbkdf_time_ms: time_ms,
+            pbkdf_memory: memory,
+            pbkdf_parallel: parallel,
+            header: None,
+            crypttab: None,
+        }
+    };
+
+    let should_ok = vec![
+        luks(None, None, None),
+        luks(Some(2000), None, None),
+        luks(None, Some(32), Some(1)),
+        luks(Some(60_000), Some(1048576), Some(4)),
+        luks(None, Some(4194304), None),
+    ];
+
+    let should_err = vec![
+        luks(Some(0), None, None),
+        luks(Some(60_001), None, None),
+        luks(None, Some(0), None),
+        luks(None, Some(31), None),
+        luks(None, Some(4194305), None),
+        luks(None, None, Some(0)),
+        luks(None, None, Some(5)),
+    ];
+
+    for luks in should_ok {
+        if let Err(err) = validate_pbkdf(&luks) {
+            panic!("unexpected error for {luks:?}: {err}");
+        }
+    }
+
+    for luks in should_err {
+        if validate_pbkdf(&luks).is_ok() {
+            panic!("unexpected ok result for {luks:?}");
+        }
+    }
+}
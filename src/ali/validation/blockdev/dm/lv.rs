@@ -3,6 +3,7 @@ use std::collections::HashSet;
 use super::*;
 use crate::ali::ManifestLvmLv;
 use crate::errors::AliError;
+use crate::linux::lvm;
 use crate::types::blockdev::*;
 
 const MSG: &str = "lvm lv validation failed";
@@ -44,7 +45,7 @@ pub(super) fn collect_valid(
 }
 
 // Only the last LV on each VG could be unsized
-// (uses 100% of the remaining space)
+// (uses 100% of the remaining space) or sized with %FREE
 #[inline]
 pub(super) fn validate_size(dms: &[ali::Dm]) -> Result<(), AliError> {
     // Collect VG -> LVs
@@ -59,7 +60,13 @@ pub(super) fn validate_size(dms: &[ali::Dm]) -> Result<(), AliError> {
             for lv in lvs {
                 // Check if size string is valid
                 if let Some(ref size) = lv.size {
-                    if let Err(err) = parse_human_bytes(size) {
+                    let result = match lvm::parse_lv_percentage(size) {
+                        Ok(Some(_)) => Ok(()),
+                        Ok(None) => parse_human_bytes(size).map(|_| ()),
+                        Err(err) => Err(err),
+                    };
+
+                    if let Err(err) = result {
                         return Err(AliError::BadManifest(format!(
                             "bad lv size {size}: {err}"
                         )));
@@ -87,11 +94,92 @@ pub(super) fn validate_size(dms: &[ali::Dm]) -> Result<(), AliError> {
         }
 
         for (i, lv) in lvs.into_iter().enumerate() {
-            if lv.size.is_none() && (i != l - 1) {
+            if i == l - 1 {
+                continue;
+            }
+
+            let Some(size) = lv.size else {
                 return Err(AliError::BadManifest(format!(
                     "lv {} on vg {vg} has None size",
                     lv.name
                 )));
+            };
+
+            if size.ends_with("%FREE") {
+                return Err(AliError::BadManifest(format!(
+                    "lv {} on vg {vg} has size {size}, but only the last lv could use %FREE",
+                    lv.name
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// LV names must be unique within each VG,
+// although LVs on different VGs may share names.
+#[inline]
+pub(super) fn validate_names(dms: &[ali::Dm]) -> Result<(), AliError> {
+    let mut lv_names = HashSet::new();
+
+    for dm in dms {
+        let ali::Dm::Lvm(lvm) = dm else {
+            continue;
+        };
+
+        for lv in lvm.lvs.iter().flatten() {
+            let (vg_name, lv_name) = vg_lv_name(lv);
+
+            if !lv_names.insert(lv_name) {
+                return Err(AliError::BadManifest(format!(
+                    "{MSG}: duplicate lv {} on vg {vg_name}",
+                    lv.name,
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// RAID LVs need enough PVs in their VGs for their RAID types.
+// VGs not declared in manifest are not checked.
+#[inline]
+pub(super) fn validate_raid(dms: &[ali::Dm]) -> Result<(), AliError> {
+    let vg_pvs: HashMap<&str, usize> = dms
+        .iter()
+        .filter_map(|dm| {
+            match dm {
+                ali::Dm::Lvm(lvm) => lvm.vgs.as_ref(),
+                _ => None,
+            }
+        })
+        .flatten()
+        .map(|vg| (vg.name.as_str(), vg.pvs.len()))
+        .collect();
+
+    for dm in dms {
+        let ali::Dm::Lvm(lvm) = dm else {
+            continue;
+        };
+
+        for lv in lvm.lvs.iter().flatten() {
+            let Some(min_pvs) = lvm::raid_min_pvs(lv)? else {
+                continue;
+            };
+
+            let vg = lv.vg.trim_start_matches("/dev/");
+            let Some(&pvs) = vg_pvs.get(vg) else {
+                continue;
+            };
+
+            if pvs < min_pvs {
+                return Err(AliError::BadManifest(format!(
+                    "{MSG}: lv {} with raid_type {} needs at least {min_pvs} pvs, but vg {vg} has {pvs}",
+                    lv.name,
+                    lv.raid_type.as_deref().unwrap_or_default(),
+                )));
             }
         }
     }
@@ -213,6 +301,9 @@ mod tests {
                         name: "1".into(),
                         vg: "foo".into(),
                         size: None,
+                        raid_type: None,
+                        mirrors: None,
+                        stripes: None,
                     }]),
                 })],
             },
@@ -228,11 +319,17 @@ mod tests {
                             name: "1".into(),
                             vg: "foo".into(),
                             size: Some("100G".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                         ManifestLvmLv {
                             name: "2".into(),
                             vg: "foo".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                     ]),
                 })],
@@ -249,6 +346,9 @@ mod tests {
                                 name: "1".into(),
                                 vg: "foo".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     }),
@@ -261,6 +361,9 @@ mod tests {
                                 name: "1".into(),
                                 vg: "bar".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     }),
@@ -273,23 +376,96 @@ mod tests {
                                 name: "1".into(),
                                 vg: "baz".into(),
                                 size: Some("100M".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             //
                             ManifestLvmLv {
                                 name: "2".into(),
                                 vg: "baz".into(),
                                 size: Some("150GB".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             //
                             ManifestLvmLv {
                                 name: "3".into(),
                                 vg: "baz".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     }),
                 ],
             },
+            TestValidateSize {
+                dms: vec![Dm::Lvm(ali::ManifestLvm {
+                    pvs: None,
+                    vgs: None,
+                    lvs: Some(vec![
+                        ManifestLvmLv {
+                            name: "1".into(),
+                            vg: "foo".into(),
+                            size: Some("50%VG".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                        ManifestLvmLv {
+                            name: "2".into(),
+                            vg: "foo".into(),
+                            size: Some("10G".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                        ManifestLvmLv {
+                            name: "3".into(),
+                            vg: "foo".into(),
+                            size: Some("100%FREE".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                    ]),
+                })],
+            },
+            TestValidateSize {
+                dms: vec![Dm::Lvm(ali::ManifestLvm {
+                    pvs: None,
+                    vgs: None,
+                    lvs: Some(vec![
+                        ManifestLvmLv {
+                            name: "1".into(),
+                            vg: "foo".into(),
+                            size: Some("20%PVS".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                        ManifestLvmLv {
+                            name: "2".into(),
+                            vg: "foo".into(),
+                            size: Some("30%VG".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                        ManifestLvmLv {
+                            name: "3".into(),
+                            vg: "foo".into(),
+                            size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                    ]),
+                })],
+            },
         ];
 
         let should_err = vec![
@@ -303,11 +479,17 @@ mod tests {
                             name: "1".into(),
                             vg: "foo".into(),
                             size: Some("100G".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                         ManifestLvmLv {
                             name: "1".into(),
                             vg: "foo".into(),
                             size: Some("badsize".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                     ]),
                 })],
@@ -322,11 +504,17 @@ mod tests {
                             name: "1".into(),
                             vg: "foo".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                         ManifestLvmLv {
                             name: "1".into(),
                             vg: "foo".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                     ]),
                 })],
@@ -341,11 +529,17 @@ mod tests {
                             name: "1".into(),
                             vg: "foo".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                         ManifestLvmLv {
                             name: "1".into(),
                             vg: "foo".into(),
                             size: Some("10G".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                     ]),
                 })],
@@ -362,6 +556,9 @@ mod tests {
                                 name: "1".into(),
                                 vg: "foo".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     }),
@@ -374,11 +571,17 @@ mod tests {
                                 name: "1".into(),
                                 vg: "bar".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "1".into(),
                                 vg: "bar".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     }),
@@ -391,23 +594,128 @@ mod tests {
                                 name: "1".into(),
                                 vg: "baz".into(),
                                 size: Some("100M".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             //
                             ManifestLvmLv {
                                 name: "2".into(),
                                 vg: "baz".into(),
                                 size: Some("150GB".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             //
                             ManifestLvmLv {
                                 name: "3".into(),
                                 vg: "baz".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     }),
                 ],
             },
+            TestValidateSize {
+                dms: vec![Dm::Lvm(ali::ManifestLvm {
+                    pvs: None,
+                    vgs: None,
+                    lvs: Some(vec![
+                        ManifestLvmLv {
+                            name: "1".into(),
+                            vg: "foo".into(),
+                            size: Some("100%FREE".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                        ManifestLvmLv {
+                            name: "2".into(),
+                            vg: "foo".into(),
+                            size: Some("10G".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                    ]),
+                })],
+            },
+            TestValidateSize {
+                dms: vec![Dm::Lvm(ali::ManifestLvm {
+                    pvs: None,
+                    vgs: None,
+                    lvs: Some(vec![
+                        ManifestLvmLv {
+                            name: "1".into(),
+                            vg: "foo".into(),
+                            size: Some("50%FREE".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                        ManifestLvmLv {
+                            name: "2".into(),
+                            vg: "foo".into(),
+                            size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                    ]),
+                })],
+            },
+            TestValidateSize {
+                dms: vec![Dm::Lvm(ali::ManifestLvm {
+                    pvs: None,
+                    vgs: None,
+                    lvs: Some(vec![
+                        ManifestLvmLv {
+                            name: "1".into(),
+                            vg: "foo".into(),
+                            size: Some("0%VG".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                        ManifestLvmLv {
+                            name: "2".into(),
+                            vg: "foo".into(),
+                            size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                    ]),
+                })],
+            },
+            TestValidateSize {
+                dms: vec![Dm::Lvm(ali::ManifestLvm {
+                    pvs: None,
+                    vgs: None,
+                    lvs: Some(vec![
+                        ManifestLvmLv {
+                            name: "1".into(),
+                            vg: "foo".into(),
+                            size: Some("10G".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                        ManifestLvmLv {
+                            name: "2".into(),
+                            vg: "foo".into(),
+                            size: Some("150%VG".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
+                        },
+                    ]),
+                })],
+            },
         ];
 
         for (_i, t) in should_ok.iter().enumerate() {
@@ -421,6 +729,102 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_validate_names() {
+        let lvm = |lvs: Vec<(&str, &str)>| {
+            Dm::Lvm(ali::ManifestLvm {
+                pvs: None,
+                vgs: None,
+                lvs: Some(
+                    lvs.into_iter()
+                        .map(|(name, vg)| {
+                            ManifestLvmLv {
+                                name: name.into(),
+                                vg: vg.into(),
+                                size: Some("1G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
+                            }
+                        })
+                        .collect(),
+                ),
+            })
+        };
+
+        let should_ok = vec![
+            vec![lvm(vec![("mylv", "myvg"), ("otherlv", "myvg")])],
+            vec![lvm(vec![("mylv", "myvg"), ("mylv", "othervg")])],
+            vec![lvm(vec![("mylv", "myvg")]), lvm(vec![("mylv", "othervg")])],
+        ];
+
+        let should_err = vec![
+            vec![lvm(vec![("mylv", "myvg"), ("mylv", "myvg")])],
+            vec![lvm(vec![("mylv", "myvg")]), lvm(vec![("mylv", "myvg")])],
+            vec![lvm(vec![("mylv", "myvg"), ("mylv", "/dev/myvg")])],
+        ];
+
+        for dms in should_ok {
+            if let Err(err) = validate_names(&dms) {
+                panic!("unexpected error for {dms:?}: {err}");
+            }
+        }
+
+        for dms in should_err {
+            if validate_names(&dms).is_ok() {
+                panic!("unexpected ok result for {dms:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_raid() {
+        use crate::ali::ManifestLvmVg;
+
+        let lvm = |pvs: &[&str], raid_type: &str, mirrors| {
+            Dm::Lvm(ali::ManifestLvm {
+                pvs: Some(pvs.iter().map(|pv| pv.to_string()).collect()),
+                vgs: Some(vec![ManifestLvmVg {
+                    name: "myvg".into(),
+                    pvs: pvs.iter().map(|pv| pv.to_string()).collect(),
+                }]),
+                lvs: Some(vec![ManifestLvmLv {
+                    name: "datalv".into(),
+                    vg: "myvg".into(),
+                    size: None,
+                    raid_type: Some(raid_type.into()),
+                    mirrors,
+                    stripes: None,
+                }]),
+            })
+        };
+
+        let should_ok = vec![
+            vec![lvm(&["/dev/fda1", "/dev/fdb1"], "raid1", Some(1))],
+            vec![lvm(&["/dev/fda1", "/dev/fdb1"], "raid1", None)],
+            vec![lvm(&["/dev/fda1", "/dev/fdb1", "/dev/fdc1"], "raid5", None)],
+        ];
+
+        let should_err = vec![
+            vec![lvm(&["/dev/fda1"], "raid1", Some(1))],
+            vec![lvm(&["/dev/fda1", "/dev/fdb1"], "raid1", Some(2))],
+            vec![lvm(&["/dev/fda1", "/dev/fdb1"], "raid5", None)],
+            vec![lvm(&["/dev/fda1", "/dev/fdb1"], "raid9", None)],
+        ];
+
+        for dms in should_ok {
+            if let Err(err) = validate_raid(&dms) {
+                panic!("unexpected error for {dms:?}: {err}");
+            }
+        }
+
+        for dms in should_err {
+            if validate_raid(&dms).is_ok() {
+                panic!("unexpected ok result for {dms:?}");
+            }
+        }
+    }
+
     #[test]
     fn test_collect_from_sys() {
         let should_ok = vec![
@@ -808,6 +1212,9 @@ mod tests {
                     name: "mylv".into(),
                     vg: "myvg".into(),
                     size: None,
+                    raid_type: None,
+                    mirrors: None,
+                    stripes: None,
                 },
                 sys_fs_devs: HashMap::from([
                     ("/dev/fda2".into(), BlockDevType::Fs("ext4".into())),
@@ -835,6 +1242,9 @@ mod tests {
                     name: "mylv".into(),
                     vg: "myvg".into(),
                     size: None,
+                    raid_type: None,
+                    mirrors: None,
+                    stripes: None,
                 },
                 sys_fs_devs: HashMap::from([
                     ("/dev/fda2".into(), BlockDevType::Fs("ext4".into())),
@@ -869,6 +1279,9 @@ mod tests {
                     name: "mylv".into(),
                     vg: "myvg".into(),
                     size: None,
+                    raid_type: None,
+                    mirrors: None,
+                    stripes: None,
                 },
                 sys_fs_devs: HashMap::from([
                     ("/dev/fda2".into(), BlockDevType::Fs("ext4".into())),
@@ -901,6 +1314,9 @@ mod tests {
                     name: "mylv".into(),
                     vg: "myvg".into(),
                     size: None,
+                    raid_type: None,
+                    mirrors: None,
+                    stripes: None,
                 },
                 sys_fs_devs: HashMap::from([
                     ("/dev/fda2".into(), BlockDevType::Fs("ext4".into())),
@@ -930,6 +1346,9 @@ mod tests {
                     name: "mylv".into(),
                     vg: "myvg".into(),
                     size: None,
+                    raid_type: None,
+                    mirrors: None,
+                    stripes: None,
                 },
                 sys_fs_devs: HashMap::from([
                     //
@@ -986,6 +1405,9 @@ mod tests {
                     name: "mylv".into(),
                     vg: "myvg".into(),
                     size: None,
+                    raid_type: None,
+                    mirrors: None,
+                    stripes: None,
                 },
                 sys_fs_devs: HashMap::from([
                     //
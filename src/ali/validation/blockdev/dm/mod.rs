@@ -5,6 +5,7 @@ mod vg;
 
 use std::collections::{
     HashMap,
+    HashSet,
     LinkedList,
 };
 
@@ -27,10 +28,22 @@ pub(super) fn collect_valids(
     // Only the last LV on each VG could be unsized (100%FREE)
     lv::validate_size(dms)?;
 
+    // LV names must be unique within a VG
+    lv::validate_names(dms)?;
+
+    // RAID LVs need enough PVs in their VGs
+    lv::validate_raid(dms)?;
+
+    // A device cannot be both LUKS base and LVM PV,
+    // regardless of the order in which they are declared
+    validate_bases(dms)?;
+
     // Collect all DMs into valids to be used later in filesystems validation
     for dm in dms {
         match dm {
             Dm::Luks(luks) => {
+                luks::validate_pbkdf(luks)?;
+
                 // Appends LUKS to a path in valids, if OK
                 luks::collect_valid(
                     luks,
@@ -78,6 +91,33 @@ pub(super) fn collect_valids(
     Ok(())
 }
 
+fn validate_bases(dms: &[Dm]) -> Result<(), AliError> {
+    let mut luks_bases = HashSet::new();
+    let mut pv_bases = HashSet::new();
+
+    for dm in dms {
+        match dm {
+            Dm::Luks(luks) => {
+                luks_bases.insert(&luks.device);
+            }
+            // VGs may name PVs not listed in pvs
+            Dm::Lvm(lvm) => {
+                pv_bases.extend(lvm.pvs.iter().flatten());
+                pv_bases
+                    .extend(lvm.vgs.iter().flatten().flat_map(|vg| &vg.pvs));
+            }
+        }
+    }
+
+    if let Some(dev) = luks_bases.intersection(&pv_bases).next() {
+        return Err(AliError::BadManifest(format!(
+            "device {dev} cannot be both a LUKS base and an LVM PV"
+        )));
+    }
+
+    Ok(())
+}
+
 #[inline(always)]
 fn is_luks_base(dev_type: &BlockDevType) -> bool {
     matches!(
@@ -109,3 +149,74 @@ fn is_vg_base(dev_type: &BlockDevType) -> bool {
 // fn is_lv_base(dev_type: &BlockDevType) -> bool {
 //     matches!(dev_type, BlockDevType::Dm(DmType::LvmVg))
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_bases() {
+        let dms = |yaml: &str| -> Vec<Dm> {
+            serde_yaml::from_str(yaml).expect("failed to parse dms")
+        };
+
+        let should_ok = vec![
+            dms(r#"
+- type: luks
+  device: /dev/sda2
+  name: cryptroot
+- type: lvm
+  pvs:
+    - /dev/mapper/cryptroot
+  vgs:
+    - name: myvg
+      pvs:
+        - /dev/mapper/cryptroot
+"#),
+            dms(r#"
+- type: luks
+  device: /dev/sda2
+  name: cryptroot
+- type: lvm
+  vgs:
+    - name: myvg
+      pvs:
+        - /dev/sda3
+"#),
+        ];
+
+        let should_err = vec![
+            dms(r#"
+- type: luks
+  device: /dev/sda2
+  name: cryptroot
+- type: lvm
+  pvs:
+    - /dev/sda2
+"#),
+            // PV only named by VG
+            dms(r#"
+- type: lvm
+  vgs:
+    - name: myvg
+      pvs:
+        - /dev/sda2
+- type: luks
+  device: /dev/sda2
+  name: cryptroot
+"#),
+        ];
+
+        for dms in should_ok {
+            if let Err(err) = validate_bases(&dms) {
+                panic!("unexpected error for {dms:?}: {err}");
+            }
+        }
+
+        for dms in should_err {
+            if validate_bases(&dms).is_ok() {
+                panic!("unexpected ok result for {dms:?}");
+            }
+        }
+    }
+}
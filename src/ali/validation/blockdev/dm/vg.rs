@@ -96,8 +96,20 @@ pub(super) fn collect_valid(
             }
         }
 
+        // pv_base is somewhere in manifest devices, but not as a free PV
+        let in_manifest =
+            valids.iter().flatten().any(|dev| dev.device == *pv_base);
+        if in_manifest {
+            return Err(AliError::BadManifest(format!(
+                "{msg}: vg {} pv {pv_base} is not a pv, or was already used by other devices",
+                vg.name,
+            )));
+        }
+
+        // Common mistake, e.g. a partition on a disk missing from manifest
         return Err(AliError::BadManifest(format!(
-            "{msg}: no pv device matching {pv_base} in manifest or in the system"
+            "{msg}: vg {} pv {pv_base} is not declared in manifest and does not exist in the system",
+            vg.name,
         )));
     }
 
@@ -118,6 +130,64 @@ mod tests {
         expected_valids: BlockDevPaths,
     }
 
+    #[test]
+    fn test_collect_valid_undeclared_pv() {
+        let vg = ManifestLvmVg {
+            name: "myvg".into(),
+            pvs: vec![
+                "./test_assets/mock_devs/sda2".into(),
+                "./test_assets/mock_devs/sdc1".into(),
+            ],
+        };
+
+        // Only sda and sdb were declared in manifest
+        let mut valids = BlockDevPaths::from([
+            LinkedList::from([
+                BlockDev {
+                    device: "./test_assets/mock_devs/sda".into(),
+                    device_type: TYPE_DISK,
+                },
+                BlockDev {
+                    device: "./test_assets/mock_devs/sda2".into(),
+                    device_type: TYPE_PART,
+                },
+                BlockDev {
+                    device: "./test_assets/mock_devs/sda2".into(),
+                    device_type: TYPE_PV,
+                },
+            ]),
+            LinkedList::from([
+                BlockDev {
+                    device: "./test_assets/mock_devs/sdb".into(),
+                    device_type: TYPE_DISK,
+                },
+                BlockDev {
+                    device: "./test_assets/mock_devs/sdb1".into(),
+                    device_type: TYPE_PART,
+                },
+                BlockDev {
+                    device: "./test_assets/mock_devs/sdb1".into(),
+                    device_type: TYPE_PV,
+                },
+            ]),
+        ]);
+
+        let result = collect_valid(
+            &vg,
+            &HashMap::new(),
+            &mut HashMap::new(),
+            &mut valids,
+        );
+
+        match result {
+            Err(AliError::BadManifest(msg)) => {
+                assert!(msg.contains("vg myvg pv ./test_assets/mock_devs/sdc1"));
+                assert!(msg.contains("not declared in manifest"));
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
     #[test]
     fn test_collect_valid() {
         let mut should_ok = vec![
@@ -1,7 +1,17 @@
 use std::collections::HashSet;
+use std::path::{
+    Component,
+    Path,
+};
 
-use crate::ali::ManifestFs;
+use crate::ali::{
+    Manifest,
+    ManifestFs,
+};
 use crate::errors::AliError;
+#[cfg(feature = "loop-image")]
+use crate::linux;
+use crate::linux::mkfs;
 
 pub(super) fn validate_rootfs(
     rootfs: &String,
@@ -25,6 +35,19 @@ pub(super) fn validate_rootfs(
     Ok(())
 }
 
+// Collects rootfs and filesystem devices which are
+// regular image files, which are fs-ready as loop devices
+#[cfg(feature = "loop-image")]
+pub(super) fn collect_images(manifest: &Manifest) -> HashSet<String> {
+    let filesystems = manifest.filesystems.iter().flatten();
+
+    std::iter::once(&manifest.rootfs.device)
+        .chain(filesystems.map(|fs| &fs.device))
+        .filter(|dev| linux::losetup::is_image(dev))
+        .cloned()
+        .collect()
+}
+
 // Collects filesystems into fs_devs,
 // and removing the base from fs_ready_devs as it goes through the list.
 pub(super) fn collect_fs_devs(
@@ -47,6 +70,31 @@ pub(super) fn collect_fs_devs(
         // Remove used up fs-ready device
         fs_ready_devs.remove(&fs.device);
 
+        // XFS external log and realtime devices consume fs-ready devices too
+        for (key, dev) in
+            [("log_device", &fs.log_device), ("rt_device", &fs.rt_device)]
+        {
+            let Some(dev) = dev else {
+                continue;
+            };
+
+            if fs.fs_type != "xfs" {
+                return Err(AliError::BadManifest(format!(
+                    "{MSG}: {key} {dev} for fs #{} is only supported on xfs, got {}",
+                    i + 1,
+                    fs.fs_type,
+                )));
+            }
+
+            if !fs_ready_devs.remove(dev) {
+                return Err(AliError::BadManifest(format!(
+                    "{MSG}: {key} {dev} for fs #{} ({}) is not fs-ready or is already used",
+                    i + 1,
+                    fs.device,
+                )));
+            }
+        }
+
         // Collect this fs to fs_dev to later validate mountpoints
         if fs_devs.insert(fs.device.clone()) {
             continue;
@@ -60,3 +108,437 @@ pub(super) fn collect_fs_devs(
 
     Ok(())
 }
+
+// btrfs quota can only be enabled on mounted btrfs filesystems
+pub(super) fn validate_quotas(manifest: &Manifest) -> Result<(), AliError> {
+    const MSG: &str = "btrfs quota validation failed";
+
+    let rootfs = &manifest.rootfs;
+    let filesystems = manifest.filesystems.iter().flatten();
+
+    let quotas =
+        std::iter::once((&rootfs.device, &rootfs.fs_type, rootfs.quota))
+            .chain(filesystems.map(|fs| (&fs.device, &fs.fs_type, fs.quota)))
+            .filter(|(_, _, quota)| quota.unwrap_or(false));
+
+    for (device, fs_type, _) in quotas {
+        if fs_type != "btrfs" {
+            return Err(AliError::BadManifest(format!(
+                "{MSG}: quota for {device} is only supported on btrfs, got {fs_type}"
+            )));
+        }
+
+        let mounted = device == &rootfs.device
+            || manifest
+                .mountpoints
+                .iter()
+                .flatten()
+                .any(|m| &m.device == device);
+
+        if !mounted {
+            return Err(AliError::BadManifest(format!(
+                "{MSG}: btrfs {device} has quota, but no mountpoint"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates that `tune2fs_args` is only used on ext2/3/4
+pub(super) fn validate_tune2fs(manifest: &Manifest) -> Result<(), AliError> {
+    const MSG: &str = "tune2fs validation failed";
+
+    let rootfs = &manifest.rootfs;
+    let filesystems = manifest.filesystems.iter().flatten();
+
+    let tunes = std::iter::once((
+        &rootfs.device,
+        &rootfs.fs_type,
+        &rootfs.tune2fs_args,
+    ))
+    .chain(filesystems.map(|fs| (&fs.device, &fs.fs_type, &fs.tune2fs_args)));
+
+    for (device, fs_type, args) in tunes {
+        let Some(args) = args else {
+            continue;
+        };
+
+        if !matches!(fs_type.as_str(), "ext2" | "ext3" | "ext4") {
+            return Err(AliError::BadManifest(format!(
+                "{MSG}: tune2fs_args for {device} is only supported on ext2/3/4, got {fs_type}"
+            )));
+        }
+
+        if args.is_empty() {
+            return Err(AliError::BadManifest(format!(
+                "{MSG}: empty tune2fs_args for {device}"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates that `mkfs_discard` is only used on supported fs types
+pub(super) fn validate_mkfs_discard(
+    manifest: &Manifest,
+) -> Result<(), AliError> {
+    let rootfs = &manifest.rootfs;
+    let filesystems = manifest.filesystems.iter().flatten();
+
+    let discards =
+        std::iter::once((&rootfs.device, &rootfs.fs_type, rootfs.mkfs_discard))
+            .chain(
+                filesystems
+                    .map(|fs| (&fs.device, &fs.fs_type, fs.mkfs_discard)),
+            );
+
+    for (device, fs_type, discard) in discards {
+        let Some(discard) = discard else {
+            continue;
+        };
+
+        if let Err(err) = mkfs::discard_args(fs_type, discard) {
+            return Err(AliError::BadManifest(format!(
+                "mkfs_discard validation failed for {device}: {err}"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates btrfs `subvolumes` and `default_subvol`, which
+/// must be one of the subvolumes created by ali-rs
+pub(super) fn validate_subvolumes(manifest: &Manifest) -> Result<(), AliError> {
+    const MSG: &str = "btrfs subvolume validation failed";
+
+    let rootfs: ManifestFs = manifest.rootfs.clone().into();
+    let filesystems =
+        std::iter::once(&rootfs).chain(manifest.filesystems.iter().flatten());
+
+    for fs in filesystems {
+        let device = &fs.device;
+        let subvols = fs.subvolumes.as_deref().unwrap_or_default();
+
+        if fs.subvolumes.is_none() && fs.default_subvol.is_none() {
+            continue;
+        }
+
+        if fs.fs_type != "btrfs" {
+            return Err(AliError::BadManifest(format!(
+                "{MSG}: subvolumes for {device} are only supported on btrfs, got {}",
+                fs.fs_type,
+            )));
+        }
+
+        let mut seen = HashSet::new();
+        for subvol in subvols {
+            let valid = !subvol.is_empty()
+                && Path::new(subvol)
+                    .components()
+                    .all(|c| matches!(c, Component::Normal(_)));
+
+            if !valid {
+                return Err(AliError::BadManifest(format!(
+                    "{MSG}: bad subvolume {subvol} for {device}, must be a relative path"
+                )));
+            }
+
+            // btrfs subvolume create(8) does not create parent dirs
+            let parent = Path::new(subvol)
+                .parent()
+                .and_then(Path::to_str)
+                .filter(|parent| !parent.is_empty());
+
+            if let Some(parent) = parent {
+                if !seen.contains(parent) {
+                    return Err(AliError::BadManifest(format!(
+                        "{MSG}: parent {parent} of subvolume {subvol} for {device} must be listed before it"
+                    )));
+                }
+            }
+
+            if !seen.insert(subvol.as_str()) {
+                return Err(AliError::BadManifest(format!(
+                    "{MSG}: duplicate subvolume {subvol} for {device}"
+                )));
+            }
+        }
+
+        if let Some(default) = &fs.default_subvol {
+            if !subvols.contains(default) {
+                return Err(AliError::BadManifest(format!(
+                    "{MSG}: default_subvol {default} for {device} is not one of its subvolumes"
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_validate_subvolumes() {
+    let should_ok = vec![
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+  subvolumes: ["@", "@home", "@snapshots", "@snapshots/root"]
+  default_subvol: "@"
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sda3
+    fstype: btrfs
+    subvols: [data]
+"#,
+    ];
+
+    let should_err = vec![
+        // Parent subvolume not listed
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+  subvolumes: ["@", "@snapshots/root"]
+"#,
+        // Parent subvolume listed after its child
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+  subvolumes: ["@snapshots/root", "@snapshots"]
+"#,
+        // Unknown default subvolume
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+  subvolumes: ["@", "@home"]
+  default_subvol: "@root"
+"#,
+        // Default subvolume without subvolumes
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+  default_subvol: "@"
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+  subvolumes: ["@"]
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+  subvolumes: ["/@"]
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+  subvolumes: ["@/../@home"]
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+filesystems:
+  - device: /dev/sda3
+    fstype: btrfs
+    subvolumes: [data, data]
+"#,
+    ];
+
+    for yaml in should_ok {
+        let manifest = Manifest::from_yaml(yaml).unwrap();
+        if let Err(err) = validate_subvolumes(&manifest) {
+            panic!("unexpected error: {err}");
+        }
+    }
+
+    for yaml in should_err {
+        let manifest = Manifest::from_yaml(yaml).unwrap();
+        if validate_subvolumes(&manifest).is_ok() {
+            panic!("unexpected ok result for manifest: {yaml}");
+        }
+    }
+}
+
+#[test]
+fn test_validate_mkfs_discard() {
+    let should_ok = vec![
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+  mkfs_discard: false
+filesystems:
+  - device: /dev/sda3
+    fstype: ext4
+    mkfs-discard: true
+  - device: /dev/sda1
+    fstype: vfat
+"#,
+    ];
+
+    let should_err = vec![
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sda1
+    fstype: vfat
+    mkfs_discard: false
+"#,
+    ];
+
+    for yaml in should_ok {
+        let manifest = Manifest::from_yaml(yaml).unwrap();
+        if let Err(err) = validate_mkfs_discard(&manifest) {
+            panic!("unexpected error: {err}");
+        }
+    }
+
+    for yaml in should_err {
+        let manifest = Manifest::from_yaml(yaml).unwrap();
+        if validate_mkfs_discard(&manifest).is_ok() {
+            panic!("unexpected ok result for manifest: {yaml}");
+        }
+    }
+}
+
+#[test]
+fn test_validate_tune2fs() {
+    let should_ok = vec![
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+  tune2fs_args: ["-m", "1"]
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+filesystems:
+  - device: /dev/sda3
+    fstype: ext3
+    tune2fs: ["-c", "30"]
+"#,
+    ];
+
+    let should_err = vec![
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+  tune2fs_args: ["-m", "1"]
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sda3
+    fstype: xfs
+    tune2fs_args: ["-c", "30"]
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+  tune2fs_args: []
+"#,
+    ];
+
+    for yaml in should_ok {
+        let manifest = Manifest::from_yaml(yaml).unwrap();
+        if let Err(err) = validate_tune2fs(&manifest) {
+            panic!("unexpected error: {err}");
+        }
+    }
+
+    for yaml in should_err {
+        let manifest = Manifest::from_yaml(yaml).unwrap();
+        if validate_tune2fs(&manifest).is_ok() {
+            panic!("unexpected ok result for manifest: {yaml}");
+        }
+    }
+}
+
+#[test]
+fn test_validate_quotas() {
+    let should_ok = vec![
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+  quota: true
+filesystems:
+  - device: /dev/sdb1
+    fstype: btrfs
+    quota: true
+  - device: /dev/sdb2
+    fstype: ext4
+    quota: false
+mountpoints:
+  - device: /dev/sdb1
+    dest: /home
+"#,
+    ];
+
+    let should_err = vec![
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+  quota: true
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+filesystems:
+  - device: /dev/sdb1
+    fstype: xfs
+    quota: true
+mountpoints:
+  - device: /dev/sdb1
+    dest: /data
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+filesystems:
+  - device: /dev/sdb1
+    fstype: btrfs
+    quota: true
+"#,
+    ];
+
+    for yaml in should_ok {
+        let manifest = Manifest::from_yaml(yaml).unwrap();
+        if let Err(err) = validate_quotas(&manifest) {
+            panic!("unexpected error: {err}");
+        }
+    }
+
+    for yaml in should_err {
+        let manifest = Manifest::from_yaml(yaml).unwrap();
+        if validate_quotas(&manifest).is_ok() {
+            panic!("unexpected ok result for manifest: {yaml}");
+        }
+    }
+}
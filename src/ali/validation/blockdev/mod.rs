@@ -2,9 +2,11 @@ mod disk;
 mod dm;
 mod fs;
 mod mount;
+mod offline;
 mod swap;
 mod sysfs;
 mod trace_blk;
+mod usage;
 
 use std::collections::{
     HashMap,
@@ -13,12 +15,18 @@ use std::collections::{
 
 use crate::ali::*;
 use crate::errors::AliError;
+use crate::linux;
 use crate::types::blockdev::*;
+use crate::types::report::ValidationReport;
 
 /// Validates manifest for `stage_mountpoints`
 /// See [`validate_blockdev`] for details.
 ///
-/// If `overwrite` is false, `validate` passes zeroed valued
+/// If `offline` is true, `validate` does not probe the system at all.
+/// Only references between manifest devices are validated, and any
+/// reference to a device not declared in the manifest is an error.
+///
+/// If `overwrite` is true, `validate` passes zeroed valued
 /// system state to `validate_blockdev`.
 ///
 /// Otherwise, it collects the current system state as hash maps
@@ -30,10 +38,17 @@ use crate::types::blockdev::*;
 pub(crate) fn validate(
     manifest: &Manifest,
     overwrite: bool,
-) -> Result<BlockDevPaths, AliError> {
+    offline: bool,
+) -> Result<ValidationReport, AliError> {
+    if offline {
+        offline::validate_references(manifest)?;
+    } else if let Some(disks) = &manifest.disks {
+        disk::validate_exists(disks)?;
+    }
+
     // Empty state maps will bypass the checks, allowing ali-rs to wipe any
     // existing system resources which appear in the manifest.
-    match overwrite {
+    match overwrite || offline {
         true => {
             validate_blockdev(
                 manifest,
@@ -44,6 +59,10 @@ pub(crate) fn validate(
         }
 
         false => {
+            if let Some(disks) = &manifest.disks {
+                disk::validate_tables(disks, linux::blkid::pt_type)?;
+            }
+
             // Get full blkid output
             let output_blkid = trace_blk::run_blkid("blkid")?;
 
@@ -55,7 +74,7 @@ pub(crate) fn validate(
 
             // Get all paths of existing LVM devices.
             // Unknown disks are not tracked - only LVM devices and their bases.
-            let sys_lvms = trace_blk::sys_lvms("lvs", "pvs");
+            let sys_lvms = trace_blk::sys_lvms("lvs", "pvs")?;
 
             validate_blockdev(
                 manifest,
@@ -69,7 +88,11 @@ pub(crate) fn validate(
 
 /// Validates manifest block storage.
 ///
-/// It first collects all valid system and manifest devices
+/// It first builds the device usage map, so that a device assigned
+/// conflicting roles (e.g. a partitioned disk also used as a PV)
+/// is reported before walking device paths.
+///
+/// It then collects all valid system and manifest devices
 /// into a list `valids`, returning error if found during collection.
 ///
 /// If all names are successfully collected into `valids`,
@@ -88,7 +111,10 @@ fn validate_blockdev(
     sys_fs_devs: &HashMap<String, BlockDevType>, /* Maps fs devs to their FS type (e.g. Btrfs) */
     mut sys_fs_ready_devs: HashMap<String, BlockDevType>, /* Maps fs-ready devs to their types (e.g. partition) */
     mut sys_lvms: HashMap<String, BlockDevPaths>, /* Maps pv path to all possible LV paths */
-) -> Result<BlockDevPaths, AliError> {
+) -> Result<ValidationReport, AliError> {
+    // Each device has exactly one role
+    usage::build_device_usage(manifest)?;
+
     // Valid block devices
     let valids = collect_valids(
         &manifest.disks,
@@ -102,6 +128,13 @@ fn validate_blockdev(
     let mut fs_ready_devs =
         collect_fs_ready_devs(&mut sys_fs_ready_devs, sys_lvms, &valids)?;
 
+    // Image files are attached as loop devices during apply
+    #[cfg(feature = "loop-image")]
+    fs_ready_devs.extend(fs::collect_images(manifest));
+
+    // Keep a copy for apply, since fs_ready_devs is consumed below
+    let all_fs_ready_devs = fs_ready_devs.clone();
+
     // Valid block devices used as filesystems
     let mut fs_devs =
         collect_fs_devs(manifest, sys_fs_devs, &mut fs_ready_devs)?;
@@ -114,6 +147,12 @@ fn validate_blockdev(
 
     fs_ready_devs.remove(&manifest.rootfs.device);
 
+    fs::validate_quotas(manifest)?;
+    fs::validate_tune2fs(manifest)?;
+    fs::validate_mkfs_discard(manifest)?;
+    fs::validate_subvolumes(manifest)?;
+    mount::validate_opts(manifest)?;
+
     if let Some(mountpoints) = &manifest.mountpoints {
         mount::validate_dups(mountpoints)?;
         mount::validate(mountpoints, &mut fs_devs)?;
@@ -123,7 +162,10 @@ fn validate_blockdev(
         swap::validate(swaps, &mut fs_ready_devs)?;
     }
 
-    Ok(valids)
+    Ok(ValidationReport {
+        block_devs: valids,
+        fs_ready_devs: all_fs_ready_devs,
+    })
 }
 
 fn collect_valids(
@@ -273,6 +315,7 @@ mod tests {
                             label: "ROOTFS".into(),
                             size: None,
                             part_type: "linux".into(),
+                            uuid: None,
                         },
                     ],
                 },
@@ -283,6 +326,11 @@ mod tests {
                     device: "./test_assets/mock_devs/sda1".into(),
                     name: "cryptroot".into(),
                     passphrase: None,
+                    pbkdf_time_ms: None,
+                    pbkdf_memory: None,
+                    pbkdf_parallel: None,
+                    header: None,
+                    crypttab: None,
                 }),
             ],
             sys_fs_devs: HashMap::new(),
@@ -369,16 +417,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -402,16 +468,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: None,
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -450,16 +534,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -497,6 +599,11 @@ mod tests {
                             device: "/dev/fake1p2".into(),
                             name:  "cryptroot".into(),
                             passphrase: None,
+                            pbkdf_time_ms: None,
+                            pbkdf_memory: None,
+                            pbkdf_parallel: None,
+                            header: None,
+                            crypttab: None,
                         }),
                     ]),
                     rootfs: ManifestRootFs{
@@ -504,16 +611,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/myvg/mylv".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -551,6 +676,11 @@ mod tests {
                             device: "/dev/fake1p2".into(),
                             name:  "cryptroot".into(),
                             passphrase: None,
+                            pbkdf_time_ms: None,
+                            pbkdf_memory: None,
+                            pbkdf_parallel: None,
+                            header: None,
+                            crypttab: None,
                         }),
                         Dm::Lvm(ManifestLvm {
                             pvs: None,
@@ -560,6 +690,9 @@ mod tests {
                                     name: "datalv".into(),
                                     vg: "myvg".into(),
                                     size: None,
+                                    raid_type: None,
+                                    mirrors: None,
+                                    stripes: None,
                                 },
                             ]),
                         })
@@ -569,16 +702,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/myvg/mylv".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -616,6 +767,11 @@ mod tests {
                             device: "/dev/fake1p2".into(),
                             name:  "cryptroot".into(),
                             passphrase: None,
+                            pbkdf_time_ms: None,
+                            pbkdf_memory: None,
+                            pbkdf_parallel: None,
+                            header: None,
+                            crypttab: None,
                         }),
                     ]),
                     rootfs: ManifestRootFs{
@@ -623,12 +779,30 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs{
                             device: "/dev/myvg/mylv".into(),
                             fs_type: "btrfs".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: Some(vec![
@@ -639,12 +813,22 @@ mod tests {
                         },
                     ]),
                     swap: None,
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -698,11 +882,21 @@ mod tests {
                             device: "/dev/myvg/mylv".into(),
                             name:  "cryptroot".into(),
                             passphrase: None,
+                            pbkdf_time_ms: None,
+                            pbkdf_memory: None,
+                            pbkdf_parallel: None,
+                            header: None,
+                            crypttab: None,
                         }),
                         Dm::Luks(ManifestLuks {
                             device: "/dev/fake1p2".into(),
                             name:  "cryptswap".into(),
                             passphrase: None,
+                            pbkdf_time_ms: None,
+                            pbkdf_memory: None,
+                            pbkdf_parallel: None,
+                            header: None,
+                            crypttab: None,
                         })
                     ]),
                     rootfs: ManifestRootFs {
@@ -710,16 +904,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/mapper/cryptswap".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -766,17 +978,30 @@ mod tests {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             }]),
                         }),
                         Dm::Luks(ManifestLuks {
                             device: "/dev/myvg/mylv".into(),
                             name:  "cryptroot".into(),
                             passphrase: None,
+                            pbkdf_time_ms: None,
+                            pbkdf_memory: None,
+                            pbkdf_parallel: None,
+                            header: None,
+                            crypttab: None,
                         }),
                         Dm::Luks(ManifestLuks {
                             device: "/dev/fake1p2".into(),
                             name:  "cryptswap".into(),
                             passphrase: None,
+                            pbkdf_time_ms: None,
+                            pbkdf_memory: None,
+                            pbkdf_parallel: None,
+                            header: None,
+                            crypttab: None,
                         })
                     ]),
                     rootfs: ManifestRootFs{
@@ -784,16 +1009,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/mapper/cryptswap".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -832,16 +1075,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -877,6 +1138,9 @@ mod tests {
                             name: "mylv".into(),
                             vg: "myvg".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         }]),
                     })]),
                     rootfs: ManifestRootFs{
@@ -884,16 +1148,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -920,6 +1202,9 @@ mod tests {
                             name: "mylv".into(),
                             vg: "myvg".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         }]),
                     })]),
                     rootfs: ManifestRootFs{
@@ -927,16 +1212,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -959,11 +1262,13 @@ mod tests {
                                 label: "PART_EFI".into(),
                                 size: Some("500M".into()),
                                 part_type: "ef".into(),
+                                uuid: None,
                             },
                             ManifestPartition {
                                 label: "PART_PV".into(),
                                 size: None,
                                 part_type: "8e".into(),
+                                uuid: None,
                             },
                         ],
                     }]),
@@ -977,6 +1282,9 @@ mod tests {
                             name: "mylv".into(),
                             vg: "myvg".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         }]),
                     })]),
                     rootfs: ManifestRootFs{
@@ -984,16 +1292,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -1018,11 +1344,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -1043,6 +1371,9 @@ mod tests {
                             name: "mylv".into(),
                             vg: "myvg".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         }]),
                     })]),
                     rootfs: ManifestRootFs{
@@ -1050,16 +1381,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts:None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -1084,11 +1433,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -1100,6 +1451,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ]
                         },
@@ -1122,6 +1474,9 @@ mod tests {
                             name: "mylv".into(),
                             vg: "myvg".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         }]),
                     })]),
                     rootfs: ManifestRootFs{
@@ -1129,16 +1484,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p1".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -1163,11 +1536,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -1179,6 +1554,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ]
                         },
@@ -1202,11 +1578,17 @@ mod tests {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: Some("8G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -1215,16 +1597,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/myvg/myswap".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -1249,11 +1649,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -1265,6 +1667,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ]
                         },
@@ -1288,16 +1691,25 @@ mod tests {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: Some("8G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: Some("10GB".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mydata".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -1306,16 +1718,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/myvg/myswap".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -1340,11 +1770,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -1356,6 +1788,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ]
                         },
@@ -1379,16 +1812,25 @@ mod tests {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: Some("8G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: Some("10GB".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mydata".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -1397,12 +1839,30 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs {
                             device: "/dev/fake1p2".into(),
                             fs_type: "xfs".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: Some(vec![
@@ -1413,12 +1873,22 @@ mod tests {
                         },
                     ]),
                     swap: Some(vec!["/dev/myvg/myswap".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -1443,11 +1913,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -1459,6 +1931,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ]
                         },
@@ -1482,16 +1955,25 @@ mod tests {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: Some("8G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: Some("10GB".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mydata".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -1500,17 +1982,45 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs {
                             device: "/dev/fake1p2".into(),
                             fs_type: "xfs".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                         ManifestFs {
                             device: "/dev/myvg/mydata".into(),
                             fs_type: "ext4".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: Some(vec![
@@ -1526,12 +2036,22 @@ mod tests {
                         },
                     ]),
                     swap: Some(vec!["/dev/myvg/myswap".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -1556,11 +2076,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -1572,6 +2094,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ],
                         },
@@ -1604,21 +2127,33 @@ mod tests {
                                 name: "swaplv".into(),
                                 vg: "sysvg".into(),
                                 size: Some("8G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "rootlv".into(),
                                 vg: "sysvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "data".into(),
                                 vg: "datavg".into(),
                                 size: Some("200GB".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mydata".into(),
                                 vg: "datavg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -1627,17 +2162,45 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs {
                             device: "/dev/datavg/data".into(),
                             fs_type: "ext4".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                         ManifestFs {
                             device: "/dev/datavg/mydata".into(),
                             fs_type: "xfs".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: Some(vec![
@@ -1653,12 +2216,22 @@ mod tests {
                         },
                     ]),
                     swap: Some(vec!["/dev/sysvg/swaplv".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -1689,11 +2262,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -1705,6 +2280,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ]
                         },
@@ -1729,11 +2305,17 @@ mod tests {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: Some("8G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             }
                         ]),
                     })]),
@@ -1742,16 +2324,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/myvg/myswap".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -1782,11 +2382,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -1797,6 +2399,7 @@ mod tests {
                                 label: "PART_PV2".into(),
                                 size: None,
                                 part_type: "8e".into(),
+                                uuid: None,
                             }],
                         },
                     ]),
@@ -1821,16 +2424,25 @@ mod tests {
                             name: "myswap".into(),
                             vg: "mynvmevg".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                         ManifestLvmLv {
                             name: "rootlv".into(),
                             vg: "mysatavg".into(),
                             size: Some("20G".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                         ManifestLvmLv {
                             name: "datalv".into(),
                             vg: "mysatavg".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                     ]),
                 })]),
@@ -1839,12 +2451,30 @@ mod tests {
                     fs_type: "btrfs".into(),
                     fs_opts: None,
                     mnt_opts: None,
+                    fstab_dump: None,
+                    fstab_pass: None,
+                    quota: None,
+                    tune2fs_args: None,
+                    mkfs_discard: None,
+                    mkfs_output: None,
+                    subvolumes: None,
+                    default_subvol: None,
                 },
                 filesystems: Some(vec![
                     ManifestFs {
                         device: "/dev/mysatavg/datalv".into(),
                         fs_type: "xfs".into(),
                         fs_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        log_device: None,
+                        rt_device: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                 ]),
                 mountpoints: Some(vec![
@@ -1857,14 +2487,99 @@ mod tests {
                 swap: Some(vec![
                     "/dev/mynvmevg/myswap".into(),
                 ]),
+                overlays: None,
+                zram: None,
                 pacstraps: None,
+                strict_signatures: None,
+                require_network: None,
+                pacman_cache: None,
                 chroot: None,
                 postinstall: None,
                 hostname: None,
                 timezone: None,
                 rootpasswd: None,
+                preload_modules: None,
+                reboot_after: None,
+                fstab_identifier: None,
+                fstab_pseudofs: None,
+                esp_min_size: None,
             },
-        }];
+        },
+
+            TestValidateBlockDev {
+                case: "XFS data with external log and realtime devices".into(),
+                context: None,
+                sys_fs_ready_devs: Some(HashMap::from([
+                    ("/dev/fake1p1".into(), TYPE_PART),
+                    ("/dev/fake1p2".into(), TYPE_PART),
+                    ("/dev/fake2p1".into(), TYPE_PART),
+                    ("/dev/fake2p2".into(), TYPE_PART),
+                ])),
+                sys_fs_devs: None,
+                sys_lvms: None,
+
+                manifest: Manifest {
+                    location: None,
+                    disks: None,
+                    device_mappers: None,
+                    rootfs: ManifestRootFs{
+                        device: "/dev/fake1p1".into(),
+                        fs_type: "btrfs".into(),
+                        fs_opts: None,
+                        mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
+                    },
+                    filesystems: Some(vec![
+                        ManifestFs {
+                            device: "/dev/fake1p2".into(),
+                            fs_type: "xfs".into(),
+                            fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: Some("/dev/fake2p1".into()),
+                            rt_device: Some("/dev/fake2p2".into()),
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
+                        },
+                    ]),
+                    mountpoints: Some(vec![
+                        ManifestMountpoint {
+                            device: "/dev/fake1p2".into(),
+                            dest: "/data".into(),
+                            mnt_opts: None,
+                        },
+                    ]),
+                    swap: None,
+                    overlays: None,
+                    zram: None,
+                    pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
+                    chroot: None,
+                    postinstall: None,
+                    hostname: None,
+                    timezone: None,
+                    rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
+                },
+            },
+        ];
 
         let should_err: Vec<TestValidateBlockDev> = vec![
             TestValidateBlockDev {
@@ -1883,16 +2598,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -1915,16 +2648,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -1949,16 +2700,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p3".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -1983,22 +2752,50 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs {
                             device: "/dev/fake1p1".into(),
                             fs_type: "ext4".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         }
                     ]),
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2023,24 +2820,52 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs {
                             device: "/dev/fake1p2".into(),
                             fs_type: "ext4".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: None,
                     swap: Some(vec![
                         "/dev/fake1p2".into(),
                     ]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2065,27 +2890,65 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs {
                             device: "/dev/fake1p2".into(),
                             fs_type: "ext4".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                         ManifestFs {
                             device: "/dev/fake1p2".into(),
                             fs_type: "btrfs".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         }
                     ]),
                     mountpoints: None,
                     swap: None,
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2122,6 +2985,11 @@ mod tests {
                             device: "/dev/fake1p2".into(),
                             name:  "cryptroot".into(),
                             passphrase: None,
+                            pbkdf_time_ms: None,
+                            pbkdf_memory: None,
+                            pbkdf_parallel: None,
+                            header: None,
+                            crypttab: None,
                         }),
                     ]),
                     rootfs: ManifestRootFs{
@@ -2129,16 +2997,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2191,6 +3077,11 @@ mod tests {
                             device: "/dev/myvg/mylv".into(),
                             name:  "cryptroot".into(),
                             passphrase: None,
+                            pbkdf_time_ms: None,
+                            pbkdf_memory: None,
+                            pbkdf_parallel: None,
+                            header: None,
+                            crypttab: None,
                         }),
                     ]),
                     rootfs: ManifestRootFs{
@@ -2198,16 +3089,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/myvg/mylv".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2245,6 +3154,11 @@ mod tests {
                             device: "/dev/fake1p2".into(),
                             name:  "cryptroot".into(),
                             passphrase: None,
+                            pbkdf_time_ms: None,
+                            pbkdf_memory: None,
+                            pbkdf_parallel: None,
+                            header: None,
+                            crypttab: None,
                         }),
                     ]),
                     rootfs: ManifestRootFs{
@@ -2252,22 +3166,50 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs{
                             device: "/dev/mapper/cryptroot".into(),
                             fs_type: "btrfs".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: None,
                     swap: None,
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2305,6 +3247,11 @@ mod tests {
                             device: "/dev/fake1p2".into(),
                             name:  "cryptroot".into(),
                             passphrase: None,
+                            pbkdf_time_ms: None,
+                            pbkdf_memory: None,
+                            pbkdf_parallel: None,
+                            header: None,
+                            crypttab: None,
                         }),
                     ]),
                     rootfs: ManifestRootFs{
@@ -2312,12 +3259,30 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs{
                             device: "/dev/myvg/mylv".into(),
                             fs_type: "btrfs".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: Some(vec![
@@ -2328,12 +3293,22 @@ mod tests {
                         },
                     ]),
                     swap: None,
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2357,11 +3332,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                     }]),
@@ -2380,6 +3357,9 @@ mod tests {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -2388,22 +3368,50 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs {
                             device: "/dev/myvg/mylv".into(),
                             fs_type: "btrfs".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2427,11 +3435,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                     }]),
@@ -2450,6 +3460,9 @@ mod tests {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -2458,6 +3471,14 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: Some(vec![
@@ -2468,12 +3489,22 @@ mod tests {
                         },
                     ]),
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2497,11 +3528,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                     }]),
@@ -2520,16 +3553,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2553,11 +3604,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: None,
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                     }]),
@@ -2574,11 +3627,17 @@ mod tests {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: Some("10G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -2587,16 +3646,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2620,11 +3697,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV".into(),
                                     size: Some("5.6T".into()),
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                     }]),
@@ -2641,11 +3720,17 @@ mod tests {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: Some("10G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -2654,16 +3739,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2687,11 +3790,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("5 gigabytes".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                     }]),
@@ -2708,11 +3813,17 @@ mod tests {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: Some("10G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -2721,16 +3832,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2754,11 +3883,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                     }]),
@@ -2775,11 +3906,17 @@ mod tests {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -2788,16 +3925,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2821,11 +3976,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                     }]),
@@ -2842,11 +3999,17 @@ mod tests {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: Some("5G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: Some("500.1G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -2855,16 +4018,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2888,11 +4069,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                     }]),
@@ -2909,11 +4092,17 @@ mod tests {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: Some("5 gigabytes".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -2922,16 +4111,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -2955,11 +4162,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                     }]),
@@ -2982,16 +4191,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -3015,11 +4242,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -3035,6 +4264,9 @@ mod tests {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -3043,12 +4275,30 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs {
                             device: "/dev/myvg/mylv".into(),
                             fs_type: "btrfs".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: Some(vec![
@@ -3059,12 +4309,22 @@ mod tests {
                         }
                     ]),
                     swap: Some(vec!["/dev/fake1p2".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -3088,11 +4348,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -3104,6 +4366,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ]
                         }]),
@@ -3125,6 +4388,9 @@ mod tests {
                             name: "mylv".into(),
                             vg: "myvg".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         }]),
                     })]),
                     rootfs: ManifestRootFs{
@@ -3132,16 +4398,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p2".into()]), // Was already used as manifest PV
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -3164,11 +4448,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -3180,6 +4466,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ]
                         },
@@ -3202,6 +4489,9 @@ mod tests {
                             name: "mylv".into(),
                             vg: "myvg".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         }]),
                     })]),
                     rootfs: ManifestRootFs{
@@ -3209,16 +4499,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/fake1p1".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -3245,11 +4553,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -3261,6 +4571,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ]
                         },
@@ -3285,11 +4596,17 @@ mod tests {
                             name: "myswap".into(),
                             vg: "myvg".into(),
                             size: Some("8G".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                         ManifestLvmLv {
                             name: "mylv".into(),
                             vg: "myvg".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         }]),
                     })]),
                     rootfs: ManifestRootFs{
@@ -3297,16 +4614,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/myvg/myswap".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -3340,11 +4675,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -3356,6 +4693,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ]
                         },
@@ -3380,11 +4718,17 @@ mod tests {
                             name: "myswap".into(),
                             vg: "myvg".into(),
                             size: Some("8G".into()),
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         },
                         ManifestLvmLv {
                             name: "mylv".into(),
                             vg: "myvg".into(),
                             size: None,
+                            raid_type: None,
+                            mirrors: None,
+                            stripes: None,
                         }]),
                     })]),
                     rootfs: ManifestRootFs{
@@ -3392,16 +4736,34 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: None,
                     mountpoints: None,
                     swap: Some(vec!["/dev/myvg/myswap".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -3426,11 +4788,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -3442,6 +4806,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ]
                         },
@@ -3465,16 +4830,25 @@ mod tests {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: Some("8G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: Some("10GB".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mydata".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -3483,12 +4857,30 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs {
                             device: "/dev/fake1p2".into(),
                             fs_type: "xfs".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: Some(vec![
@@ -3504,12 +4896,22 @@ mod tests {
                         },
                     ]),
                     swap: Some(vec!["/dev/myvg/myswap".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -3534,11 +4936,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -3550,6 +4954,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ]
                         },
@@ -3573,16 +4978,25 @@ mod tests {
                                 name: "myswap".into(),
                                 vg: "myvg".into(),
                                 size: Some("8G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mylv".into(),
                                 vg: "myvg".into(),
                                 size: Some("10GB".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mydata".into(),
                                 vg: "myvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -3591,12 +5005,30 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs {
                             device: "/dev/fake1p2".into(),
                             fs_type: "xfs".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: Some(vec![
@@ -3612,12 +5044,22 @@ mod tests {
                         },
                     ]),
                     swap: Some(vec!["/dev/myvg/myswap".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -3642,11 +5084,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -3658,6 +5102,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ],
                         },
@@ -3690,21 +5135,33 @@ mod tests {
                                 name: "swaplv".into(),
                                 vg: "sysvg".into(),
                                 size: Some("8G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "rootlv".into(),
                                 vg: "sysvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "data".into(),
                                 vg: "datavg".into(),
                                 size: Some("200GB".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mydata".into(),
                                 vg: "datavg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -3713,12 +5170,30 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs {
                             device: "/dev/datavg/data".into(),
                             fs_type: "ext4".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: Some(vec![
@@ -3734,12 +5209,22 @@ mod tests {
                         },
                     ]),
                     swap: Some(vec!["/dev/sysvg/swaplv".into()]),
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
 
@@ -3764,11 +5249,13 @@ mod tests {
                                     label: "PART_EFI".into(),
                                     size: Some("500M".into()),
                                     part_type: "ef".into(),
+                                    uuid: None,
                                 },
                                 ManifestPartition {
                                     label: "PART_PV1".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 },
                             ],
                         },
@@ -3780,6 +5267,7 @@ mod tests {
                                     label: "PART_PV2".into(),
                                     size: None,
                                     part_type: "8e".into(),
+                                    uuid: None,
                                 }
                             ],
                         },
@@ -3812,21 +5300,33 @@ mod tests {
                                 name: "swaplv".into(),
                                 vg: "sysvg".into(),
                                 size: Some("8G".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "rootlv".into(),
                                 vg: "sysvg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "data".into(),
                                 vg: "datavg".into(),
                                 size: Some("200GB".into()),
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                             ManifestLvmLv {
                                 name: "mydata".into(),
                                 vg: "datavg".into(),
                                 size: None,
+                                raid_type: None,
+                                mirrors: None,
+                                stripes: None,
                             },
                         ]),
                     })]),
@@ -3835,17 +5335,45 @@ mod tests {
                         fs_type: "btrfs".into(),
                         fs_opts: None,
                         mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
                     },
                     filesystems: Some(vec![
                         ManifestFs {
                             device: "/dev/datavg/data".into(),
                             fs_type: "ext4".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                         ManifestFs {
                             device: "/dev/datavg/mydata".into(),
                             fs_type: "xfs".into(),
                             fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: None,
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
                         },
                     ]),
                     mountpoints: Some(vec![
@@ -3861,12 +5389,385 @@ mod tests {
                         },
                     ]),
                     swap: Some(vec!["/dev/sysvg/swaplv".into()]),
+                    overlays: None,
+                    zram: None,
+                    pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
+                    chroot: None,
+                    postinstall: None,
+                    hostname: None,
+                    timezone: None,
+                    rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
+                },
+            },
+
+            TestValidateBlockDev {
+                case: "XFS log device is not fs-ready".into(),
+                context: None,
+                sys_fs_ready_devs: Some(HashMap::from([
+                    ("/dev/fake1p1".into(), TYPE_PART),
+                    ("/dev/fake1p2".into(), TYPE_PART),
+                ])),
+                sys_fs_devs: None,
+                sys_lvms: None,
+
+                manifest: Manifest {
+                    location: None,
+                    disks: None,
+                    device_mappers: None,
+                    rootfs: ManifestRootFs{
+                        device: "/dev/fake1p1".into(),
+                        fs_type: "btrfs".into(),
+                        fs_opts: None,
+                        mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
+                    },
+                    filesystems: Some(vec![
+                        ManifestFs {
+                            device: "/dev/fake1p2".into(),
+                            fs_type: "xfs".into(),
+                            fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: Some("/dev/fake2p1".into()),
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
+                        },
+                    ]),
+                    mountpoints: Some(vec![
+                        ManifestMountpoint {
+                            device: "/dev/fake1p2".into(),
+                            dest: "/data".into(),
+                            mnt_opts: None,
+                        },
+                    ]),
+                    swap: None,
+                    overlays: None,
+                    zram: None,
+                    pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
+                    chroot: None,
+                    postinstall: None,
+                    hostname: None,
+                    timezone: None,
+                    rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
+                },
+            },
+            TestValidateBlockDev {
+                case: "XFS log device is also rootfs".into(),
+                context: None,
+                sys_fs_ready_devs: Some(HashMap::from([
+                    ("/dev/fake1p1".into(), TYPE_PART),
+                    ("/dev/fake1p2".into(), TYPE_PART),
+                ])),
+                sys_fs_devs: None,
+                sys_lvms: None,
+
+                manifest: Manifest {
+                    location: None,
+                    disks: None,
+                    device_mappers: None,
+                    rootfs: ManifestRootFs{
+                        device: "/dev/fake1p1".into(),
+                        fs_type: "btrfs".into(),
+                        fs_opts: None,
+                        mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
+                    },
+                    filesystems: Some(vec![
+                        ManifestFs {
+                            device: "/dev/fake1p2".into(),
+                            fs_type: "xfs".into(),
+                            fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: Some("/dev/fake1p1".into()),
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
+                        },
+                    ]),
+                    mountpoints: Some(vec![
+                        ManifestMountpoint {
+                            device: "/dev/fake1p2".into(),
+                            dest: "/data".into(),
+                            mnt_opts: None,
+                        },
+                    ]),
+                    swap: None,
+                    overlays: None,
+                    zram: None,
+                    pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
+                    chroot: None,
+                    postinstall: None,
+                    hostname: None,
+                    timezone: None,
+                    rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
+                },
+            },
+            TestValidateBlockDev {
+                case: "External log device on non-xfs filesystem".into(),
+                context: None,
+                sys_fs_ready_devs: Some(HashMap::from([
+                    ("/dev/fake1p1".into(), TYPE_PART),
+                    ("/dev/fake1p2".into(), TYPE_PART),
+                    ("/dev/fake2p1".into(), TYPE_PART),
+                ])),
+                sys_fs_devs: None,
+                sys_lvms: None,
+
+                manifest: Manifest {
+                    location: None,
+                    disks: None,
+                    device_mappers: None,
+                    rootfs: ManifestRootFs{
+                        device: "/dev/fake1p1".into(),
+                        fs_type: "btrfs".into(),
+                        fs_opts: None,
+                        mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
+                    },
+                    filesystems: Some(vec![
+                        ManifestFs {
+                            device: "/dev/fake1p2".into(),
+                            fs_type: "ext4".into(),
+                            fs_opts: None,
+                            fstab_dump: None,
+                            fstab_pass: None,
+                            log_device: Some("/dev/fake2p1".into()),
+                            rt_device: None,
+                            quota: None,
+                            tune2fs_args: None,
+                            mkfs_discard: None,
+                            mkfs_output: None,
+                            subvolumes: None,
+                            default_subvol: None,
+                        },
+                    ]),
+                    mountpoints: Some(vec![
+                        ManifestMountpoint {
+                            device: "/dev/fake1p2".into(),
+                            dest: "/data".into(),
+                            mnt_opts: None,
+                        },
+                    ]),
+                    swap: None,
+                    overlays: None,
+                    zram: None,
+                    pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
+                    chroot: None,
+                    postinstall: None,
+                    hostname: None,
+                    timezone: None,
+                    rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
+                },
+            },
+            TestValidateBlockDev {
+                case: "Device is both LUKS base and LVM PV".into(),
+                context: None,
+                sys_fs_ready_devs: Some(HashMap::from([(
+                    "/dev/sda2".into(),
+                    TYPE_PART,
+                )])),
+                sys_fs_devs: None,
+                sys_lvms: None,
+
+                manifest: Manifest {
+                    location: None,
+                    disks: None,
+                    device_mappers: Some(vec![
+                        Dm::Luks(ManifestLuks {
+                            device: "/dev/sda2".into(),
+                            name: "cryptroot".into(),
+                            passphrase: None,
+                            pbkdf_time_ms: None,
+                            pbkdf_memory: None,
+                            pbkdf_parallel: None,
+                            header: None,
+                            crypttab: None,
+                        }),
+                        Dm::Lvm(ManifestLvm {
+                            pvs: Some(vec!["/dev/sda2".into()]),
+                            vgs: None,
+                            lvs: None,
+                        }),
+                    ]),
+                    rootfs: ManifestRootFs{
+                        device: "/dev/mapper/cryptroot".into(),
+                        fs_type: "btrfs".into(),
+                        fs_opts: None,
+                        mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
+                    },
+                    filesystems: None,
+                    mountpoints: None,
+                    swap: None,
+                    overlays: None,
+                    zram: None,
+                    pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
+                    chroot: None,
+                    postinstall: None,
+                    hostname: None,
+                    timezone: None,
+                    rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
+                },
+            },
+            TestValidateBlockDev {
+                case: "MBR disk with more than 4 partitions".into(),
+                context: Some("partition #4 must be extended to hold logical partitions".into()),
+                sys_fs_ready_devs: None,
+                sys_fs_devs: None,
+                sys_lvms: None,
+
+                manifest: Manifest {
+                    location: None,
+                    disks: Some(vec![
+                        ManifestDisk {
+                            device: "/dev/fake1".into(),
+                            table: PartitionTable::Mbr,
+                            partitions: vec![
+                            ManifestPartition {
+                                label: "p1".into(),
+                                size: Some("1G".into()),
+                                part_type: "linux".into(),
+                                uuid: None,
+                            },
+                            ManifestPartition {
+                                label: "p2".into(),
+                                size: Some("1G".into()),
+                                part_type: "linux".into(),
+                                uuid: None,
+                            },
+                            ManifestPartition {
+                                label: "p3".into(),
+                                size: Some("1G".into()),
+                                part_type: "linux".into(),
+                                uuid: None,
+                            },
+                            ManifestPartition {
+                                label: "p4".into(),
+                                size: Some("1G".into()),
+                                part_type: "linux".into(),
+                                uuid: None,
+                            },
+                            ManifestPartition {
+                                label: "p5".into(),
+                                size: Some("1G".into()),
+                                part_type: "linux".into(),
+                                uuid: None,
+                            },
+                            ],
+                        },
+                    ]),
+                    device_mappers: None,
+                    rootfs: ManifestRootFs{
+                        device: "/dev/fake1p1".into(),
+                        fs_type: "ext4".into(),
+                        fs_opts: None,
+                        mnt_opts: None,
+                        fstab_dump: None,
+                        fstab_pass: None,
+                        quota: None,
+                        tune2fs_args: None,
+                        mkfs_discard: None,
+                        mkfs_output: None,
+                        subvolumes: None,
+                        default_subvol: None,
+                    },
+                    filesystems: None,
+                    mountpoints: None,
+                    swap: None,
+                    overlays: None,
+                    zram: None,
                     pacstraps: None,
+                    strict_signatures: None,
+                    require_network: None,
+                    pacman_cache: None,
                     chroot: None,
                     postinstall: None,
                     hostname: None,
                     timezone: None,
                     rootpasswd: None,
+                    preload_modules: None,
+                    reboot_after: None,
+                    fstab_identifier: None,
+                    fstab_pseudofs: None,
+                    esp_min_size: None,
                 },
             },
         ];
@@ -3916,7 +5817,7 @@ mod tests {
                     eprintln!("\nCONTEXT: {ctx}\n");
                 }
 
-                let paths = result.unwrap();
+                let paths = result.unwrap().block_devs;
                 let paths_json = serde_json::to_string(&paths).unwrap();
 
                 eprintln!("Test structure: {test:?}");
@@ -1,10 +1,25 @@
 use std::collections::HashSet;
 
-use crate::ali::ManifestMountpoint;
+use crate::ali::{
+    Manifest,
+    ManifestMountpoint,
+};
 use crate::errors::AliError;
 
 const MSG: &str = "mountpoint validation failed";
 
+// Mount options that cancel each other out
+const OPTS_CONFLICTS: [(&str, &str); 8] = [
+    ("ro", "rw"),
+    ("sync", "async"),
+    ("exec", "noexec"),
+    ("suid", "nosuid"),
+    ("dev", "nodev"),
+    ("auto", "noauto"),
+    ("user", "nouser"),
+    ("atime", "noatime"),
+];
+
 pub(super) fn validate_dups(
     mountpoints: &[ManifestMountpoint],
 ) -> Result<(), AliError> {
@@ -48,3 +63,140 @@ pub(super) fn validate(
 
     Ok(())
 }
+
+/// Validates `mnt_opts` of rootfs and mountpoints as mount(8)
+/// option lists, i.e. comma-separated tokens without empty tokens
+/// or whitespace, and without conflicting options like `ro,rw`.
+pub(super) fn validate_opts(manifest: &Manifest) -> Result<(), AliError> {
+    let rootfs = std::iter::once(("/", &manifest.rootfs.mnt_opts));
+    let mountpoints = manifest
+        .mountpoints
+        .iter()
+        .flatten()
+        .map(|mnt| (mnt.dest.as_str(), &mnt.mnt_opts));
+
+    for (dest, opts) in rootfs.chain(mountpoints) {
+        let Some(opts) = opts else {
+            continue;
+        };
+
+        validate_opts_list(opts).map_err(|err| {
+            AliError::BadManifest(format!(
+                "{MSG}: bad mnt_opts {opts:?} for {dest}: {err}"
+            ))
+        })?;
+    }
+
+    Ok(())
+}
+
+fn validate_opts_list(opts: &str) -> Result<(), String> {
+    let mut seen = HashSet::new();
+
+    for opt in opts.split(',') {
+        if opt.is_empty() {
+            return Err("empty option".to_string());
+        }
+
+        if opt.contains(char::is_whitespace) {
+            return Err(format!("whitespace in option {opt:?}"));
+        }
+
+        if opt.starts_with('=') {
+            return Err(format!("missing option name in {opt:?}"));
+        }
+
+        seen.insert(opt);
+    }
+
+    for (a, b) in OPTS_CONFLICTS {
+        if seen.contains(a) && seen.contains(b) {
+            return Err(format!("conflicting options {a} and {b}"));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_validate_opts() {
+    let should_ok = vec![
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+  mnt_opts: noatime,compress=zstd
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+mountpoints:
+  - device: /dev/sda1
+    dest: /boot
+    mnt_opts: rw,nosuid,nodev,fmask=0022,dmask=0022
+"#,
+    ];
+
+    let should_err = vec![
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+  mnt_opts: rw,
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+  mnt_opts: ro,rw
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+mountpoints:
+  - device: /dev/sda1
+    dest: /boot
+    mnt_opts: noatime, nodev
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+mountpoints:
+  - device: /dev/sda1
+    dest: /boot
+    mnt_opts: defaults,,noatime
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+mountpoints:
+  - device: /dev/sda1
+    dest: /boot
+    mnt_opts: noexec,exec
+"#,
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+  mnt_opts: =zstd
+"#,
+    ];
+
+    for yaml in should_ok {
+        let manifest = Manifest::from_yaml(yaml).unwrap();
+        if let Err(err) = validate_opts(&manifest) {
+            panic!("unexpected error: {err}");
+        }
+    }
+
+    for yaml in should_err {
+        let manifest = Manifest::from_yaml(yaml).unwrap();
+        if validate_opts(&manifest).is_ok() {
+            panic!("unexpected ok result for manifest: {yaml}");
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashSet;
+
+use crate::ali::{
+    Dm,
+    Manifest,
+};
+use crate::errors::AliError;
+use crate::linux;
+use crate::types::blockdev::*;
+
+/// Validates that all devices referenced in manifest are also declared
+/// in manifest, i.e. created by ali-rs from `disks` and `device_mappers`.
+///
+/// In offline mode, the system state is not available, so references to
+/// existing system devices cannot be validated and are reported as errors.
+pub(super) fn validate_references(manifest: &Manifest) -> Result<(), AliError> {
+    let mut declared = HashSet::<String>::new();
+
+    for disk in manifest.disks.iter().flatten() {
+        declared.insert(disk.device.clone());
+
+        for i in 1..=disk.partitions.len() {
+            let Ok(partition_number) = u8::try_from(i) else {
+                return Err(AliError::BadManifest(format!(
+                    "disk {} has too many partitions: {}",
+                    disk.device,
+                    disk.partitions.len(),
+                )));
+            };
+
+            declared
+                .insert(linux::partition_name(&disk.device, partition_number));
+        }
+    }
+
+    for dm in manifest.device_mappers.iter().flatten() {
+        match dm {
+            Dm::Luks(luks) => {
+                require_declared(&declared, &luks.device, "luks base")?;
+                declared.insert(format!("/dev/mapper/{}", luks.name));
+            }
+
+            Dm::Lvm(lvm) => {
+                for pv in lvm.pvs.iter().flatten() {
+                    require_declared(&declared, pv, "lvm pv base")?;
+                }
+
+                for vg in lvm.vgs.iter().flatten() {
+                    for pv in &vg.pvs {
+                        require_declared(&declared, pv, "lvm vg pv")?;
+                    }
+
+                    declared.insert(BlockDev::from(vg).device);
+                }
+
+                for lv in lvm.lvs.iter().flatten() {
+                    let (vg_name, lv_name) = vg_lv_name(lv);
+                    require_declared(&declared, &vg_name, "lvm lv vg")?;
+                    declared.insert(lv_name);
+                }
+            }
+        }
+    }
+
+    require_declared(&declared, &manifest.rootfs.device, "rootfs")?;
+
+    let mut fs_devs = HashSet::from([manifest.rootfs.device.clone()]);
+    for fs in manifest.filesystems.iter().flatten() {
+        require_declared(&declared, &fs.device, "filesystem")?;
+
+        for dev in [&fs.log_device, &fs.rt_device].into_iter().flatten() {
+            require_declared(&declared, dev, "filesystem external section")?;
+        }
+
+        fs_devs.insert(fs.device.clone());
+    }
+
+    for mnt in manifest.mountpoints.iter().flatten() {
+        require_declared(&fs_devs, &mnt.device, "mountpoint filesystem")?;
+    }
+
+    for swap in manifest.swap.iter().flatten() {
+        require_declared(&declared, swap.device(), "swap")?;
+    }
+
+    Ok(())
+}
+
+fn require_declared(
+    declared: &HashSet<String>,
+    device: &str,
+    usage: &str,
+) -> Result<(), AliError> {
+    if declared.contains(device) {
+        return Ok(());
+    }
+
+    Err(AliError::Validation(format!(
+        "{usage} {device} is not declared in manifest: requires target system, cannot validate offline"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_references() {
+        let should_ok = vec![
+            r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: boot
+        size: 500M
+        type: uefi
+      - label: root
+        type: linux
+device_mappers:
+  - type: luks
+    device: /dev/sda2
+    name: cryptroot
+  - type: lvm
+    pvs:
+      - /dev/mapper/cryptroot
+    vgs:
+      - name: myvg
+        pvs:
+          - /dev/mapper/cryptroot
+    lvs:
+      - name: swaplv
+        vg: myvg
+        size: 8G
+      - name: rootlv
+        vg: myvg
+rootfs:
+  device: /dev/myvg/rootlv
+  fstype: btrfs
+filesystems:
+  - device: /dev/sda1
+    fstype: vfat
+mountpoints:
+  - device: /dev/sda1
+    dest: /boot
+swap:
+  - /dev/myvg/swaplv
+"#,
+        ];
+
+        let should_err = vec![
+            // rootfs on existing system partition
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+"#,
+            // LV on existing system VG
+            r#"
+device_mappers:
+  - type: lvm
+    lvs:
+      - name: rootlv
+        vg: sysvg
+rootfs:
+  device: /dev/sysvg/rootlv
+  fstype: ext4
+"#,
+            // Mounting existing system filesystem
+            r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda1
+  fstype: ext4
+mountpoints:
+  - device: /dev/sdb1
+    dest: /data
+"#,
+        ];
+
+        for yaml in should_ok {
+            let manifest = Manifest::from_yaml(yaml).unwrap();
+            // Disks do not exist on the test machine
+            if let Err(err) = super::super::validate(&manifest, false, true) {
+                panic!("unexpected error: {err}");
+            }
+        }
+
+        for yaml in should_err {
+            let manifest = Manifest::from_yaml(yaml).unwrap();
+            match validate_references(&manifest) {
+                Ok(_) => panic!("unexpected ok result for manifest: {yaml}"),
+                Err(err) => {
+                    assert!(err.to_string().contains("requires target system"))
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_references_partition_count() {
+        let partitions =
+            "      - label: part\n        type: linux\n".repeat(256);
+        let yaml = format!(
+            "disks:\n  - device: /dev/sda\n    table: gpt\n    partitions:\n{partitions}rootfs:\n  device: /dev/sda1\n  fstype: ext4\n"
+        );
+
+        let manifest = Manifest::from_yaml(&yaml).unwrap();
+        assert!(matches!(
+            validate_references(&manifest),
+            Err(AliError::BadManifest(_)),
+        ));
+    }
+}
@@ -1,14 +1,19 @@
 use std::collections::HashSet;
 
+use crate::ali::ManifestSwap;
 use crate::errors::AliError;
 
+const MSG: &str = "swap validation failed";
+
+// mkswap(8) labels are at most 16 bytes
+const LABEL_MAX_LEN: usize = 16;
+
 pub(super) fn validate(
-    swaps: &[String],
+    swaps: &[ManifestSwap],
     fs_ready_devs: &mut HashSet<String>,
 ) -> Result<(), AliError> {
-    const MSG: &str = "swap validation failed";
-
     for (i, swap) in swaps.iter().enumerate() {
+        let swap = swap.device();
         if !fs_ready_devs.contains(swap) {
             return Err(AliError::BadManifest(format!(
                 "{MSG}: device {swap} for swap #{} is not fs-ready",
@@ -19,5 +24,77 @@ pub(super) fn validate(
         fs_ready_devs.remove(swap);
     }
 
+    validate_labels(swaps)
+}
+
+/// Validates that swap labels are unique and usable in fstab(5)
+fn validate_labels(swaps: &[ManifestSwap]) -> Result<(), AliError> {
+    let mut labels = HashSet::new();
+
+    for swap in swaps {
+        let Some(label) = swap.label() else {
+            continue;
+        };
+
+        let device = swap.device();
+
+        if label.is_empty() || label.len() > LABEL_MAX_LEN {
+            return Err(AliError::BadManifest(format!(
+                "{MSG}: label {label:?} for swap {device} must be 1-{LABEL_MAX_LEN} bytes"
+            )));
+        }
+
+        if label.contains(char::is_whitespace) {
+            return Err(AliError::BadManifest(format!(
+                "{MSG}: label {label:?} for swap {device} contains whitespace"
+            )));
+        }
+
+        if !labels.insert(label) {
+            return Err(AliError::BadManifest(format!(
+                "{MSG}: duplicate swap label {label}"
+            )));
+        }
+    }
+
     Ok(())
 }
+
+#[test]
+fn test_validate_labels() {
+    let labeled = |device: &str, label: &str| {
+        ManifestSwap::Labeled {
+            device: device.into(),
+            label: label.into(),
+        }
+    };
+
+    let should_ok = vec![
+        vec![ManifestSwap::from("/dev/sda2")],
+        vec![
+            labeled("/dev/sda2", "swap"),
+            ManifestSwap::from("/dev/sda3"),
+        ],
+        vec![labeled("/dev/sda2", "swap1"), labeled("/dev/sda3", "swap2")],
+        vec![labeled("/dev/sda2", "0123456789abcdef")],
+    ];
+
+    let should_err = vec![
+        vec![labeled("/dev/sda2", "swap"), labeled("/dev/sda3", "swap")],
+        vec![labeled("/dev/sda2", "")],
+        vec![labeled("/dev/sda2", "0123456789abcdefg")],
+        vec![labeled("/dev/sda2", "my swap")],
+    ];
+
+    for swaps in should_ok {
+        if let Err(err) = validate_labels(&swaps) {
+            panic!("unexpected error for {swaps:?}: {err}");
+        }
+    }
+
+    for swaps in should_err {
+        if validate_labels(&swaps).is_ok() {
+            panic!("unexpected ok result for {swaps:?}");
+        }
+    }
+}
@@ -2,7 +2,6 @@ use std::collections::{
     HashMap,
     LinkedList,
 };
-use std::process::Command;
 
 use serde::{
     Deserialize,
@@ -10,7 +9,10 @@ use serde::{
 };
 use toml;
 
-use crate::utils::shell::CmdError;
+use crate::utils::shell::{
+    self,
+    CmdError,
+};
 
 use super::*;
 
@@ -31,16 +33,15 @@ struct EntryBlkid {
 }
 
 pub(super) fn run_blkid(cmd_blkid: &str) -> Result<String, AliError> {
-    let cmd = Command::new(cmd_blkid).output().map_err(|err| {
-        AliError::CmdFailed {
-            error: CmdError::ErrSpawn { error: err },
-            context: "blkid command failed".to_string(),
-        }
-    })?;
-
-    String::from_utf8(cmd.stdout).map_err(|err| {
-        AliError::AliRsBug(format!("blkid output not string: {err}"))
-    })
+    match shell::output(cmd_blkid, &[]) {
+        // blkid exits with 2 if no devices were identified
+        Err(AliError::CmdFailed {
+            error: CmdError::ErrRun { code: Some(2), .. },
+            ..
+        }) => Ok(String::new()),
+
+        result => result,
+    }
 }
 
 pub(super) fn sys_fs_ready(
@@ -123,6 +124,9 @@ pub(super) fn sys_fs(output_blkid: &str) -> HashMap<String, BlockDevType> {
     fs
 }
 
+// Printed by LVM commands if the kernel has no device-mapper
+const NO_DM: &str = "/dev/mapper/control";
+
 // Traces the LVM devices by listing all LVs and PVs,
 // returning a hash map with key mapped to LVM PV name (as a disk),
 // and values being paths from base -> pv -> vg -> lv.
@@ -134,10 +138,8 @@ pub(super) fn sys_fs(output_blkid: &str) -> HashMap<String, BlockDevType> {
 pub(super) fn sys_lvms(
     lvs_cmd: &str,
     pvs_cmd: &str,
-) -> HashMap<String, BlockDevPaths> {
-    let cmd_lvs = Command::new(lvs_cmd).output().expect("failed to run `lvs`");
-    let output_lvs =
-        String::from_utf8(cmd_lvs.stdout).expect("output is not utf-8");
+) -> Result<HashMap<String, BlockDevPaths>, AliError> {
+    let output_lvs = lvm_output(lvs_cmd)?;
     let lines_lvs: Vec<&str> = output_lvs.lines().skip(1).collect();
 
     // # Collect VG leading to LV
@@ -182,10 +184,7 @@ pub(super) fn sys_lvms(
         ]));
     }
 
-    let cmd_pvs = Command::new(pvs_cmd).output().expect("failed to run `pvs`");
-
-    let output_pvs =
-        String::from_utf8(cmd_pvs.stdout).expect("output is not utf-8");
+    let output_pvs = lvm_output(pvs_cmd)?;
     let lines_pvs: Vec<&str> = output_pvs.lines().skip(1).collect();
 
     let mut lvms = HashMap::new();
@@ -254,7 +253,31 @@ pub(super) fn sys_lvms(
         lvms.insert(pv_name.clone(), paths);
     }
 
-    lvms
+    Ok(lvms)
+}
+
+/// Runs LVM reporting command `cmd`. Systems without lvm2
+/// or device-mapper have no LVM, so that is empty output.
+fn lvm_output(cmd: &str) -> Result<String, AliError> {
+    match shell::output(cmd, &[]) {
+        Err(AliError::CmdFailed {
+            error: CmdError::ErrSpawn { error },
+            ..
+        }) if error.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+
+        Err(AliError::CmdFailed {
+            error:
+                CmdError::ErrRun {
+                    stderr: Some(stderr),
+                    ..
+                },
+            ..
+        }) if String::from_utf8_lossy(&stderr).contains(NO_DM) => {
+            Ok(String::new())
+        }
+
+        result => result,
+    }
 }
 
 #[test]
@@ -301,7 +324,8 @@ fn test_trace_existing_fs() {
 fn test_trace_existing_lvms() {
     // Hard-coded expected values from ./test_assets/mock_cmd/{lvs,pvs}
     let traced =
-        sys_lvms("./test_assets/mock_cmd/lvs", "./test_assets/mock_cmd/pvs");
+        sys_lvms("./test_assets/mock_cmd/lvs", "./test_assets/mock_cmd/pvs")
+            .expect("sys_lvms failed");
 
     // Hard-coded expected values
     let lists_vda1 = vec![
@@ -426,3 +450,19 @@ fn test_trace_existing_lvms() {
         println!();
     }
 }
+
+#[test]
+fn test_sys_lvms_no_lvm() {
+    let pvs = "./test_assets/mock_cmd/pvs";
+
+    let lvms = sys_lvms("./test_assets/mock_cmd/ali-rs-no-such-lvs", pvs)
+        .expect("unexpected error without lvm2");
+    assert!(lvms.values().all(|paths| paths.is_empty()));
+
+    let lvms = sys_lvms("./test_assets/mock_cmd/lvs-no-dm", pvs)
+        .expect("unexpected error without device-mapper");
+    assert!(lvms.values().all(|paths| paths.is_empty()));
+
+    // Other failures are still errors
+    assert!(sys_lvms("false", pvs).is_err());
+}
@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+
+use super::disk;
+use crate::ali::{
+    Dm,
+    Manifest,
+};
+use crate::errors::AliError;
+use crate::linux;
+
+const MSG: &str = "device usage validation failed";
+
+/// Role of a concrete device in manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum DeviceRole {
+    /// Manifest disk, to be partitioned
+    Disk,
+
+    /// Partition created on a manifest disk, not yet used by anything
+    Partition,
+
+    /// MBR extended partition, which only holds logical partitions
+    ExtendedPartition,
+
+    LvmPv,
+    LuksBase,
+    Filesystem,
+    Swap,
+}
+
+/// Maps each concrete device in manifest to its only role
+pub(super) type DeviceUsageMap = HashMap<String, DeviceRole>;
+
+impl std::fmt::Display for DeviceRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Disk => write!(f, "disk"),
+            Self::Partition => write!(f, "partition"),
+            Self::ExtendedPartition => write!(f, "extended partition"),
+            Self::LvmPv => write!(f, "lvm pv"),
+            Self::LuksBase => write!(f, "luks base"),
+            Self::Filesystem => write!(f, "filesystem"),
+            Self::Swap => write!(f, "swap"),
+        }
+    }
+}
+
+impl DeviceRole {
+    /// Returns whether the role is provided by a manifest disk,
+    /// as opposed to a role using a device
+    fn is_provided(&self) -> bool {
+        matches!(self, Self::Disk | Self::Partition | Self::ExtendedPartition)
+    }
+}
+
+/// Builds the usage map of all concrete devices in manifest, assigning
+/// each device exactly one role. Partitions created on manifest disks
+/// are free until used as e.g. a PV, while manifest disks and
+/// extended partitions cannot be used as anything else.
+///
+/// Any device assigned conflicting roles is an error.
+pub(super) fn build_device_usage(
+    manifest: &Manifest,
+) -> Result<DeviceUsageMap, AliError> {
+    let mut usage = DeviceUsageMap::new();
+
+    for d in manifest.disks.iter().flatten() {
+        assign(&mut usage, &d.device, DeviceRole::Disk)?;
+
+        for i in 1..=d.partitions.len() {
+            // Partition count is validated later with the disk layout
+            let Ok(partition_number) = u8::try_from(i) else {
+                break;
+            };
+
+            let role = match disk::is_extended(d, i) {
+                true => DeviceRole::ExtendedPartition,
+                false => DeviceRole::Partition,
+            };
+
+            let partition_name =
+                linux::partition_name(&d.device, partition_number);
+
+            assign(&mut usage, &partition_name, role)?;
+        }
+    }
+
+    for dm in manifest.device_mappers.iter().flatten() {
+        match dm {
+            Dm::Luks(luks) => {
+                assign(&mut usage, &luks.device, DeviceRole::LuksBase)?;
+            }
+
+            Dm::Lvm(lvm) => {
+                // VG PVs may also be listed in pvs
+                let mut pvs: Vec<&String> = Vec::new();
+                let vg_pvs = lvm.vgs.iter().flatten().flat_map(|vg| &vg.pvs);
+
+                for pv in lvm.pvs.iter().flatten().chain(vg_pvs) {
+                    if !pvs.contains(&pv) {
+                        pvs.push(pv);
+                    }
+                }
+
+                for pv in pvs {
+                    assign(&mut usage, pv, DeviceRole::LvmPv)?;
+                }
+            }
+        }
+    }
+
+    let filesystems =
+        manifest.filesystems.iter().flatten().map(|fs| &fs.device);
+    for device in std::iter::once(&manifest.rootfs.device).chain(filesystems) {
+        assign(&mut usage, device, DeviceRole::Filesystem)?;
+    }
+
+    for swap in manifest.swap.iter().flatten() {
+        assign(&mut usage, swap.device(), DeviceRole::Swap)?;
+    }
+
+    Ok(usage)
+}
+
+/// Assigns `role` to `device`, which may only replace a free partition
+fn assign(
+    usage: &mut DeviceUsageMap,
+    device: &str,
+    role: DeviceRole,
+) -> Result<(), AliError> {
+    let Some(&current) = usage.get(device) else {
+        usage.insert(device.to_string(), role);
+        return Ok(());
+    };
+
+    if current == DeviceRole::Partition && !role.is_provided() {
+        usage.insert(device.to_string(), role);
+        return Ok(());
+    }
+
+    let reason = match current {
+        _ if current == role => format!("is used as {role} more than once"),
+        DeviceRole::Disk => {
+            format!(
+                "is a manifest disk to be partitioned, and cannot also be used as {role}: use one of its partitions instead"
+            )
+        }
+        DeviceRole::ExtendedPartition => {
+            format!(
+                "is an MBR extended partition, and cannot be used as {role}: use a logical partition instead"
+            )
+        }
+        current => format!("is used as both {current} and {role}"),
+    };
+
+    Err(AliError::BadManifest(format!(
+        "{MSG}: device {device} {reason}"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_device_usage() {
+        let manifest = Manifest::from_yaml(
+            r#"
+disks:
+  - device: /dev/fake1
+    table: gpt
+    partitions:
+      - label: boot
+        size: 500M
+        type: ef
+      - label: root
+        type: linux
+dm:
+  - type: luks
+    device: /dev/fake1p2
+    name: cryptroot
+  - type: lvm
+    pvs:
+      - /dev/mapper/cryptroot
+    vgs:
+      - name: myvg
+        pvs:
+          - /dev/mapper/cryptroot
+    lvs:
+      - name: mylv
+        vg: myvg
+rootfs:
+  device: /dev/myvg/mylv
+  fstype: btrfs
+filesystems:
+  - device: /dev/fake1p1
+    fstype: vfat
+swap:
+  - /dev/fake2
+"#,
+        )
+        .expect("failed to parse manifest");
+
+        let usage = build_device_usage(&manifest).expect("unexpected error");
+        let expected = DeviceUsageMap::from([
+            ("/dev/fake1".to_string(), DeviceRole::Disk),
+            ("/dev/fake1p1".to_string(), DeviceRole::Filesystem),
+            ("/dev/fake1p2".to_string(), DeviceRole::LuksBase),
+            ("/dev/mapper/cryptroot".to_string(), DeviceRole::LvmPv),
+            ("/dev/myvg/mylv".to_string(), DeviceRole::Filesystem),
+            ("/dev/fake2".to_string(), DeviceRole::Swap),
+        ]);
+
+        assert_eq!(expected, usage);
+    }
+
+    #[test]
+    fn test_build_device_usage_conflicts() {
+        let should_err = vec![
+            (
+                "whole disk as pv",
+                r#"
+disks:
+  - device: /dev/fake1
+    table: gpt
+    partitions:
+      - label: root
+        type: linux
+dm:
+  - type: lvm
+    pvs:
+      - /dev/fake1
+rootfs:
+  device: /dev/fake1p1
+  fstype: ext4
+"#,
+                vec!["/dev/fake1 ", "manifest disk", "lvm pv"],
+            ),
+            (
+                "partition as luks base and rootfs",
+                r#"
+disks:
+  - device: /dev/fake1
+    table: gpt
+    partitions:
+      - label: root
+        type: linux
+dm:
+  - type: luks
+    device: /dev/fake1p1
+    name: cryptroot
+rootfs:
+  device: /dev/fake1p1
+  fstype: ext4
+"#,
+                vec!["/dev/fake1p1", "luks base and filesystem"],
+            ),
+            (
+                "partition as fs and swap",
+                r#"
+disks:
+  - device: /dev/fake1
+    table: gpt
+    partitions:
+      - label: root
+        type: linux
+      - label: swap
+        type: linux
+rootfs:
+  device: /dev/fake1p1
+  fstype: ext4
+swap:
+  - /dev/fake1p1
+"#,
+                vec!["/dev/fake1p1", "filesystem and swap"],
+            ),
+            (
+                "vg pv as fs",
+                r#"
+dm:
+  - type: lvm
+    vgs:
+      - name: myvg
+        pvs:
+          - /dev/fake1p1
+rootfs:
+  device: /dev/fake1p1
+  fstype: ext4
+"#,
+                vec!["/dev/fake1p1", "lvm pv and filesystem"],
+            ),
+            (
+                "rootfs also in filesystems",
+                r#"
+rootfs:
+  device: /dev/fake1p1
+  fstype: ext4
+filesystems:
+  - device: /dev/fake1p1
+    fstype: xfs
+"#,
+                vec!["/dev/fake1p1", "filesystem more than once"],
+            ),
+            (
+                "extended partition as fs",
+                r#"
+disks:
+  - device: /dev/fake1
+    table: mbr
+    partitions:
+      - label: p1
+        size: 1G
+        type: "83"
+      - label: p2
+        size: 1G
+        type: "83"
+      - label: p3
+        size: 1G
+        type: "83"
+      - label: extended
+        size: 4G
+        type: "5"
+      - label: p5
+        type: "83"
+rootfs:
+  device: /dev/fake1p4
+  fstype: ext4
+"#,
+                vec!["/dev/fake1p4", "extended partition"],
+            ),
+            (
+                "partition of another disk as disk",
+                r#"
+disks:
+  - device: /dev/fake1
+    table: gpt
+    partitions:
+      - label: root
+        type: linux
+  - device: /dev/fake1p1
+    table: gpt
+    partitions:
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/fake1p1p1
+  fstype: ext4
+"#,
+                vec!["/dev/fake1p1", "partition and disk"],
+            ),
+        ];
+
+        for (name, yaml, expected) in should_err {
+            let manifest =
+                Manifest::from_yaml(yaml).expect("failed to parse manifest");
+
+            match build_device_usage(&manifest) {
+                Err(AliError::BadManifest(msg)) => {
+                    for s in expected {
+                        assert!(
+                            msg.contains(s),
+                            "{name}: expected {s:?} in message {msg}"
+                        );
+                    }
+                }
+                Err(err) => panic!("{name}: unexpected error: {err}"),
+                Ok(usage) => panic!("{name}: unexpected ok result: {usage:?}"),
+            }
+        }
+    }
+}
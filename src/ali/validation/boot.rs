@@ -0,0 +1,515 @@
+use crate::ali::{
+    Manifest,
+    PartitionTable,
+};
+use crate::errors::AliError;
+use crate::linux;
+use crate::types::blockdev::parse_fdisk_bytes;
+use crate::types::warning::Warning;
+
+const MSG: &str = "boot partition validation failed";
+
+const MIB: usize = 1 << 20;
+const ESP_MIN_SIZE_DEFAULT: usize = 256 * MIB;
+const ESP_RECOMMENDED_SIZE: usize = 512 * MIB;
+
+// fdisk aliases, MBR type codes and GPT type GUIDs
+const PART_TYPES_ESP: [&str; 4] =
+    ["uefi", "ef", "ef00", "c12a7328-f81f-11d2-ba4b-00a0c93ec93b"];
+const PART_TYPES_XBOOTLDR: [&str; 4] = [
+    "xbootldr",
+    "ea",
+    "ea00",
+    "bc13c2ff-59e6-4262-a352-b275fd6f7172",
+];
+// GPT only, "4" is BIOS boot in fdisk GPT type list
+const PART_TYPES_BIOS_BOOT: [&str; 4] =
+    ["bios", "4", "ef02", "21686148-6449-6e6f-744e-656564454649"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum BootPartition {
+    Esp,
+    Xbootldr,
+    BiosBoot,
+}
+
+/// Returns whether `part_type` is an ESP or XBOOTLDR partition type
+pub(super) fn boot_partition(part_type: &str) -> Option<BootPartition> {
+    let part_type = part_type.to_lowercase();
+
+    if PART_TYPES_ESP.contains(&part_type.as_str()) {
+        return Some(BootPartition::Esp);
+    }
+
+    if PART_TYPES_XBOOTLDR.contains(&part_type.as_str()) {
+        return Some(BootPartition::Xbootldr);
+    }
+
+    if PART_TYPES_BIOS_BOOT.contains(&part_type.as_str()) {
+        return Some(BootPartition::BiosBoot);
+    }
+
+    None
+}
+
+/// Validates ESP, XBOOTLDR and BIOS boot partitions in manifest disks.
+///
+/// There can be at most 1 ESP and 1 XBOOTLDR. If an XBOOTLDR is present,
+/// it must be mounted at /boot, and the ESP must be mounted elsewhere
+/// (e.g. /efi), as expected by systemd-boot and bootctl(1).
+///
+/// If GRUB is to be installed without an ESP (i.e. BIOS boot),
+/// GPT disks in the manifest must have a BIOS boot partition for
+/// grub-install to embed its core image.
+///
+/// The ESP is also checked with [`validate_esp`].
+pub(super) fn validate(
+    manifest: &Manifest,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), AliError> {
+    let mut esp: Option<String> = None;
+    let mut esp_size: Option<&str> = None;
+    let mut xbootldr: Option<String> = None;
+    let mut has_gpt = false;
+    let mut has_bios_boot = false;
+
+    for disk in manifest.disks.iter().flatten() {
+        let is_gpt = disk.table == PartitionTable::Gpt;
+        has_gpt |= is_gpt;
+
+        for (i, part) in disk.partitions.iter().enumerate() {
+            let Some(boot_part) = boot_partition(&part.part_type) else {
+                continue;
+            };
+
+            let device = linux::partition_name(&disk.device, i as u8 + 1);
+            let found = match boot_part {
+                BootPartition::Esp => &mut esp,
+                BootPartition::Xbootldr => &mut xbootldr,
+                BootPartition::BiosBoot => {
+                    has_bios_boot |= is_gpt;
+                    continue;
+                }
+            };
+
+            if let Some(prev) = found {
+                return Err(AliError::BadManifest(format!(
+                    "{MSG}: multiple {boot_part} partitions: {prev} and {device}"
+                )));
+            }
+
+            if boot_part == BootPartition::Esp {
+                esp_size = part.size.as_deref();
+            }
+
+            *found = Some(device);
+        }
+    }
+
+    if let Some(esp) = &esp {
+        validate_esp(manifest, esp, esp_size, warnings)?;
+    }
+
+    let grub = manifest
+        .pacstraps
+        .as_ref()
+        .is_some_and(|pkgs| pkgs.contains("grub"));
+
+    if grub && esp.is_none() && has_gpt && !has_bios_boot {
+        return Err(AliError::BadManifest(format!(
+            "{MSG}: BIOS GRUB on GPT disk requires a BIOS boot partition (type ef02)"
+        )));
+    }
+
+    let Some(xbootldr) = xbootldr else {
+        return Ok(());
+    };
+
+    let mountpoint_of = |device: &str| {
+        manifest
+            .mountpoints
+            .iter()
+            .flatten()
+            .find(|m| m.device == device)
+            .map(|m| m.dest.trim_end_matches('/').to_string())
+    };
+
+    if let Some(dest) = mountpoint_of(&xbootldr) {
+        if dest != "/boot" {
+            return Err(AliError::BadManifest(format!(
+                "{MSG}: XBOOTLDR {xbootldr} must be mounted at /boot, got {dest}"
+            )));
+        }
+    }
+
+    if let Some(esp) = esp {
+        if let Some(dest) = mountpoint_of(&esp) {
+            if dest == "/boot" {
+                return Err(AliError::BadManifest(format!(
+                    "{MSG}: ESP {esp} cannot be mounted at /boot alongside XBOOTLDR {xbootldr}, use /efi"
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates ESP size and filesystem. Sizes are in fdisk(8) units,
+/// as used to create the partition.
+///
+/// Sized ESPs smaller than `esp_min_size` (default 256MiB) or the
+/// recommended 512MiB are reported as warnings, since modern kernels
+/// and initramfs images may not fit. If the ESP has an entry in
+/// manifest filesystems, it must be formatted as vfat.
+fn validate_esp(
+    manifest: &Manifest,
+    esp: &str,
+    size: Option<&str>,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), AliError> {
+    let min_size = match &manifest.esp_min_size {
+        Some(min) => parse_fdisk_bytes(min)?,
+        None => ESP_MIN_SIZE_DEFAULT,
+    };
+
+    if let Some(size) = size {
+        let bytes = parse_fdisk_bytes(size)?;
+
+        if bytes < min_size {
+            warnings.push(Warning::new(
+                super::WARN_CONTEXT,
+                format!(
+                    "ESP {esp} size {size} is smaller than minimum {}MiB",
+                    min_size / MIB
+                ),
+            ));
+        } else if bytes < ESP_RECOMMENDED_SIZE {
+            warnings.push(Warning::new(
+                super::WARN_CONTEXT,
+                format!(
+                    "ESP {esp} size {size} is smaller than recommended {}MiB",
+                    ESP_RECOMMENDED_SIZE / MIB
+                ),
+            ));
+        }
+    }
+
+    let fs_type = if manifest.rootfs.device == esp {
+        Some(&manifest.rootfs.fs_type)
+    } else {
+        manifest
+            .filesystems
+            .iter()
+            .flatten()
+            .find(|fs| fs.device == esp)
+            .map(|fs| &fs.fs_type)
+    };
+
+    match fs_type {
+        Some(fs_type) if fs_type != "vfat" => {
+            Err(AliError::BadManifest(format!(
+                "{MSG}: ESP {esp} must be formatted as vfat, got {fs_type}"
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+impl std::fmt::Display for BootPartition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Esp => write!(f, "ESP"),
+            Self::Xbootldr => write!(f, "XBOOTLDR"),
+            Self::BiosBoot => write!(f, "BIOS boot"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_boot_partitions() {
+        let should_ok = vec![
+            r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: esp
+        size: 500M
+        type: uefi
+      - label: xbootldr
+        size: 1G
+        type: bc13c2ff-59e6-4262-a352-b275fd6f7172
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda3
+  fstype: ext4
+filesystems:
+  - device: /dev/sda1
+    fstype: vfat
+  - device: /dev/sda2
+    fstype: vfat
+mountpoints:
+  - device: /dev/sda1
+    dest: /efi
+  - device: /dev/sda2
+    dest: /boot
+"#,
+            r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: biosboot
+        size: 1M
+        type: ef02
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+pacstraps:
+  - grub
+"#,
+            r#"
+disks:
+  - device: /dev/sda
+    table: mbr
+    partitions:
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda1
+  fstype: ext4
+pacstraps:
+  - grub
+"#,
+        ];
+
+        let should_err = vec![
+            r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: esp
+        size: 500M
+        type: uefi
+      - label: esp2
+        size: 500M
+        type: C12A7328-F81F-11D2-BA4B-00A0C93EC93B
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda3
+  fstype: ext4
+"#,
+            r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: esp
+        size: 500M
+        type: uefi
+      - label: xbootldr
+        size: 1G
+        type: xbootldr
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda3
+  fstype: ext4
+mountpoints:
+  - device: /dev/sda1
+    dest: /boot
+  - device: /dev/sda2
+    dest: /efi
+"#,
+            r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda1
+  fstype: ext4
+pacstraps:
+  - grub
+"#,
+        ];
+
+        for yaml in should_ok {
+            let manifest = Manifest::from_yaml(yaml).unwrap();
+            if let Err(err) = validate(&manifest, &mut vec![]) {
+                panic!("unexpected error: {err}");
+            }
+        }
+
+        for yaml in should_err {
+            let manifest = Manifest::from_yaml(yaml).unwrap();
+            if validate(&manifest, &mut vec![]).is_ok() {
+                panic!("unexpected ok result for manifest: {yaml}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_esp() {
+        // (manifest, expected warnings)
+        let should_ok = vec![
+            // fdisk 512M is 512MiB, the recommended size
+            (
+                r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: esp
+        size: 512M
+        type: uefi
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+"#,
+                0,
+            ),
+            (
+                r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: esp
+        size: 1G
+        type: uefi
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sda1
+    fstype: vfat
+"#,
+                0,
+            ),
+            (
+                r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: esp
+        size: 300M
+        type: uefi
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+"#,
+                1,
+            ),
+            (
+                r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: esp
+        size: 50M
+        type: ef00
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sda1
+    fstype: vfat
+"#,
+                1,
+            ),
+            (
+                r#"
+esp_min_size: 32M
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: esp
+        size: 1G
+        type: uefi
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+"#,
+                0,
+            ),
+        ];
+
+        let should_err = vec![
+            r#"
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: esp
+        size: 1G
+        type: uefi
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sda1
+    fstype: ext4
+"#,
+            r#"
+esp_min_size: foo
+disks:
+  - device: /dev/sda
+    table: gpt
+    partitions:
+      - label: esp
+        size: 1G
+        type: uefi
+      - label: root
+        type: linux
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+"#,
+        ];
+
+        for (yaml, expected) in should_ok {
+            let manifest = Manifest::from_yaml(yaml).unwrap();
+            let mut warnings = vec![];
+            if let Err(err) = validate(&manifest, &mut warnings) {
+                panic!("unexpected error: {err}");
+            }
+
+            assert_eq!(expected, warnings.len(), "{warnings:?}");
+        }
+
+        for yaml in should_err {
+            let manifest = Manifest::from_yaml(yaml).unwrap();
+            if validate(&manifest, &mut vec![]).is_ok() {
+                panic!("unexpected ok result for manifest: {yaml}");
+            }
+        }
+    }
+}
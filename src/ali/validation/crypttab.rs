@@ -0,0 +1,141 @@
+use crate::ali::{
+    Dm,
+    Manifest,
+};
+use crate::errors::AliError;
+use crate::types::warning::Warning;
+
+/// Validates detached LUKS headers against their crypttab(5) entries.
+///
+/// Header paths end up as crypttab option `header=<path>`,
+/// so they must be absolute and free of whitespace and commas.
+/// Header files on filesystems other than rootfs may not be
+/// mounted yet when crypttab is processed on boot.
+pub(super) fn validate(
+    manifest: &Manifest,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), AliError> {
+    for dm in manifest.device_mappers.iter().flatten() {
+        let Dm::Luks(luks) = dm else {
+            continue;
+        };
+
+        let Some(header) = &luks.header else {
+            continue;
+        };
+
+        if !header.starts_with('/')
+            || header.contains(|c: char| c.is_whitespace() || c == ',')
+        {
+            return Err(AliError::BadManifest(format!(
+                "bad header {header:?} for luks {}: expecting absolute path without whitespace or commas",
+                luks.name,
+            )));
+        }
+
+        if header == &luks.device {
+            return Err(AliError::BadManifest(format!(
+                "header for luks {} is its own device {header}",
+                luks.name,
+            )));
+        }
+
+        if !luks.crypttab.unwrap_or(false) || header.starts_with("/dev/") {
+            continue;
+        }
+
+        if let Some(mountpoint) = late_mountpoint(manifest, header) {
+            warnings.push(Warning::new(
+                super::WARN_CONTEXT,
+                format!(
+                    "header {header} for luks {} is on {mountpoint}, which may not be mounted when crypttab is processed on boot",
+                    luks.name,
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the deepest non-root manifest mountpoint containing `path`
+fn late_mountpoint<'a>(manifest: &'a Manifest, path: &str) -> Option<&'a str> {
+    manifest
+        .mountpoints
+        .iter()
+        .flatten()
+        .map(|m| m.dest.trim_end_matches('/'))
+        .filter(|dest| {
+            !dest.is_empty()
+                && path
+                    .strip_prefix(dest)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .max_by_key(|dest| dest.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_crypttab() {
+        let manifest = |header: &str| {
+            Manifest::from_yaml(&format!(
+                r#"
+device_mappers:
+  - type: luks
+    device: /dev/sdb1
+    name: cryptdata
+    header: {header}
+    crypttab: true
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sda1
+    fstype: vfat
+  - device: /dev/mapper/cryptdata
+    fstype: xfs
+mountpoints:
+  - device: /dev/sda1
+    dest: /boot
+  - device: /dev/mapper/cryptdata
+    dest: /data
+"#
+            ))
+            .expect("failed to parse manifest")
+        };
+
+        let should_ok = vec![
+            ("/dev/disk/by-id/usb-key-part1", false),
+            ("/etc/luks/data.header", false),
+            ("/bootstrap/data.header", false),
+            ("/boot/data.header", true),
+            ("/data/nested/data.header", true),
+        ];
+
+        let should_err = vec![
+            "luks/data.header",
+            "'/etc/luks/data header'",
+            "/etc/luks/a,b",
+            "/dev/sdb1",
+        ];
+
+        for (header, warn) in should_ok {
+            let mut warnings = Vec::new();
+            if let Err(err) = validate(&manifest(header), &mut warnings) {
+                panic!("unexpected error for {header}: {err}");
+            }
+
+            assert_eq!(warn, !warnings.is_empty(), "header {header}");
+        }
+
+        for header in should_err {
+            let mut warnings = Vec::new();
+            if validate(&manifest(header), &mut warnings).is_ok() {
+                panic!("unexpected ok result for {header}");
+            }
+        }
+    }
+}
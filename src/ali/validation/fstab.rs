@@ -0,0 +1,167 @@
+use crate::ali::Manifest;
+use crate::errors::AliError;
+use crate::types::warning::Warning;
+
+/// Validates fstab(5) dump and pass overrides in manifest.
+///
+/// Dump must be 0 or 1, and pass must be 0, 1 or 2. Filesystems with
+/// overrides must also be mounted, otherwise they never appear in fstab.
+pub(super) fn validate(
+    manifest: &Manifest,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), AliError> {
+    let rootfs = &manifest.rootfs;
+    validate_dump_pass(&rootfs.device, rootfs.fstab_dump, rootfs.fstab_pass)?;
+
+    match rootfs.fstab_pass {
+        Some(1) | None => {}
+        Some(pass) => {
+            warnings.push(Warning::new(
+                super::WARN_CONTEXT,
+                format!("rootfs fstab_pass is {pass}, expecting 1"),
+            ));
+        }
+    }
+
+    for fs in manifest.filesystems.iter().flatten() {
+        validate_dump_pass(&fs.device, fs.fstab_dump, fs.fstab_pass)?;
+
+        if fs.fstab_dump.is_none() && fs.fstab_pass.is_none() {
+            continue;
+        }
+
+        let mounted = manifest
+            .mountpoints
+            .iter()
+            .flatten()
+            .any(|m| m.device == fs.device);
+
+        if !mounted {
+            return Err(AliError::BadManifest(format!(
+                "filesystem on {} has fstab_dump or fstab_pass, but no mountpoint",
+                fs.device
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_dump_pass(
+    device: &str,
+    dump: Option<u8>,
+    pass: Option<u8>,
+) -> Result<(), AliError> {
+    if let Some(dump) = dump {
+        if dump > 1 {
+            return Err(AliError::BadManifest(format!(
+                "bad fstab_dump {dump} for {device}: must be 0 or 1"
+            )));
+        }
+    }
+
+    if let Some(pass) = pass {
+        if pass > 2 {
+            return Err(AliError::BadManifest(format!(
+                "bad fstab_pass {pass} for {device}: must be 0, 1 or 2"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_fstab() {
+        let should_ok = vec![
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+"#,
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+  fstab_dump: 1
+  fstab_pass: 0
+filesystems:
+  - device: /dev/sdb1
+    fstype: xfs
+    fstab_pass: 2
+mountpoints:
+  - device: /dev/sdb1
+    dest: /data
+"#,
+        ];
+
+        let should_err = vec![
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+  fstab_dump: 2
+"#,
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sdb1
+    fstype: xfs
+    fstab_pass: 3
+mountpoints:
+  - device: /dev/sdb1
+    dest: /data
+"#,
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sdb1
+    fstype: xfs
+    fstab_pass: 0
+"#,
+        ];
+
+        for yaml in should_ok {
+            let manifest = Manifest::from_yaml(yaml).unwrap();
+            if let Err(err) = validate(&manifest, &mut Vec::new()) {
+                panic!("unexpected error: {err}");
+            }
+        }
+
+        for yaml in should_err {
+            let manifest = Manifest::from_yaml(yaml).unwrap();
+            if validate(&manifest, &mut Vec::new()).is_ok() {
+                panic!("unexpected ok result for manifest: {yaml}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_fstab_warnings() {
+        let manifest = Manifest::from_yaml(
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+  fstab_pass: 2
+"#,
+        )
+        .unwrap();
+
+        let mut warnings = Vec::new();
+        if let Err(err) = validate(&manifest, &mut warnings) {
+            panic!("unexpected error: {err}");
+        }
+
+        assert_eq!(1, warnings.len());
+        assert!(warnings[0].message.contains("fstab_pass"));
+    }
+}
@@ -1,31 +1,160 @@
-use crate::ali::Manifest;
+use crate::ali::{
+    Manifest,
+    ManifestCmd,
+};
 use crate::errors::AliError;
 use crate::hooks;
 
 pub fn validate(manifest: &Manifest, mountpoint: &str) -> Result<(), AliError> {
-    if let Some(cmds) = &manifest.chroot {
-        validate_hooks(cmds, &hooks::Caller::ManifestChroot, mountpoint)?;
-    }
+    let chroot_cmds = manifest.chroot_cmds().map(|(_, cmd)| cmd);
+    validate_cmds(chroot_cmds, &hooks::Caller::ManifestChroot, mountpoint)?;
 
     if let Some(cmds) = &manifest.postinstall {
-        validate_hooks(cmds, &hooks::Caller::ManifestPostInstall, mountpoint)?;
+        validate_cmds(cmds, &hooks::Caller::ManifestPostInstall, mountpoint)?;
     }
 
     Ok(())
 }
 
-fn validate_hooks(
-    cmds: &Vec<String>,
+/// Validates that all commands are non-empty, and that hook commands
+/// parse and pass their hook's validation, before anything is installed.
+///
+/// Environment variable names must be valid shell identifiers,
+/// and hooks, which run inside ali-rs, cannot have environment.
+fn validate_cmds<'a>(
+    cmds: impl IntoIterator<Item = &'a ManifestCmd>,
     caller: &hooks::Caller,
     mountpoint: &str,
 ) -> Result<(), AliError> {
-    for cmd in cmds {
+    for (i, manifest_cmd) in cmds.into_iter().enumerate() {
+        let cmd = manifest_cmd.cmd();
+
+        if cmd.trim().is_empty() {
+            return Err(AliError::BadManifest(format!(
+                "empty command #{} in {caller}",
+                i + 1
+            )));
+        }
+
+        let env = manifest_cmd.env().into_iter().flatten();
+        if let Some((key, _)) = env.clone().find(|(key, _)| !is_identifier(key))
+        {
+            return Err(AliError::BadManifest(format!(
+                "bad env name {key:?} for command #{} in {caller}",
+                i + 1
+            )));
+        }
+
         if !hooks::is_hook(cmd) {
             continue;
         }
 
+        if env.count() > 0 {
+            return Err(AliError::BadManifest(format!(
+                "hook command #{} in {caller} cannot have env",
+                i + 1
+            )));
+        }
+
         hooks::validate_hook(cmd, caller, mountpoint)?;
     }
 
     Ok(())
 }
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[test]
+fn test_validate_cmds() {
+    let should_ok = vec![
+        vec!["echo hello"],
+        vec!["@replace-token foo bar /etc/foo.tpl /etc/foo"],
+        vec!["@wait-for-device /dev/null --timeout 1", "ls /"],
+    ];
+
+    let should_err = vec![
+        vec![""],
+        vec!["echo hello", "   "],
+        vec!["@replace-token foo"],
+        vec!["@replace-token '' bar /etc/foo.tpl"],
+        vec!["@no-such-hook foo"],
+    ];
+
+    for cmds in should_ok {
+        let cmds: Vec<ManifestCmd> =
+            cmds.into_iter().map(ManifestCmd::from).collect();
+        let result = validate_cmds(
+            &cmds,
+            &hooks::Caller::ManifestPostInstall,
+            "/alitarget",
+        );
+
+        if let Err(err) = result {
+            panic!("unexpected error for {cmds:?}: {err}");
+        }
+    }
+
+    for cmds in should_err {
+        let cmds: Vec<ManifestCmd> =
+            cmds.into_iter().map(ManifestCmd::from).collect();
+        let result = validate_cmds(
+            &cmds,
+            &hooks::Caller::ManifestPostInstall,
+            "/alitarget",
+        );
+
+        if result.is_ok() {
+            panic!("unexpected ok result for {cmds:?}");
+        }
+    }
+}
+
+#[test]
+fn test_validate_cmds_env() {
+    use std::collections::HashMap;
+
+    let with_env = |cmd: &str, key: &str| {
+        ManifestCmd::WithEnv {
+            cmd: cmd.to_string(),
+            env: HashMap::from([(key.to_string(), "bar".to_string())]),
+        }
+    };
+
+    let should_ok = vec![
+        with_env("echo $FOO", "FOO"),
+        with_env("echo $_foo_1", "_foo_1"),
+    ];
+
+    let should_err = vec![
+        with_env("echo foo", "1FOO"),
+        with_env("echo foo", "FOO-BAR"),
+        with_env("echo foo", "FOO BAR"),
+        with_env("echo foo", ""),
+        with_env("@replace-token foo bar /etc/foo.tpl /etc/foo", "FOO"),
+    ];
+
+    for cmd in should_ok {
+        let result =
+            validate_cmds([&cmd], &hooks::Caller::ManifestChroot, "/alitarget");
+
+        if let Err(err) = result {
+            panic!("unexpected error for {cmd:?}: {err}");
+        }
+    }
+
+    for cmd in should_err {
+        let result =
+            validate_cmds([&cmd], &hooks::Caller::ManifestChroot, "/alitarget");
+
+        if result.is_ok() {
+            panic!("unexpected ok result for {cmd:?}");
+        }
+    }
+}
@@ -1,72 +1,693 @@
 mod blockdev;
+mod boot;
+mod crypttab;
+mod fstab;
 mod hooks;
+mod mounted;
+mod network;
+mod overlay;
 
-use crate::ali::Manifest;
-use crate::constants::{
-    self,
-    defaults,
+use crate::ali::{
+    Dm,
+    Manifest,
 };
 use crate::errors::AliError;
+use crate::linux::is_on_disk;
 use crate::types::report::ValidationReport;
+use crate::types::warning::Warning;
 use crate::utils::fs::file_exists;
-use crate::utils::shell;
+use crate::utils::{
+    secret,
+    shell,
+};
+use crate::{
+    constants,
+    linux,
+};
+
+const WARN_CONTEXT: &str = "validation";
 
+const PROC_MOUNTS: &str = "/proc/mounts";
+
+/// Validates manifest against the current system.
+///
+/// If `offline` is true, only the manifest's internal coherence
+/// is validated, and the system is never probed.
+///
+/// Non-fatal issues are pushed to `warnings`.
 pub fn validate(
     manifest: &Manifest,
     install_location: &str,
     overwrite: bool,
+    offline: bool,
+    warnings: &mut Vec<Warning>,
 ) -> Result<ValidationReport, AliError> {
+    if let Some(location) = &manifest.location {
+        validate_location(location)?;
+    }
+
+    // Resolve LUKS passphrases now, instead of failing
+    // at luksFormat after disks were already wiped
+    if !offline {
+        validate_luks_passphrases(manifest)?;
+    }
+
     // Validate block devices in manifest
-    let block_devs = blockdev::validate(manifest, overwrite)?;
+    let report = blockdev::validate(manifest, overwrite, offline)?;
+
+    // Validate ESP and XBOOTLDR partitions
+    boot::validate(manifest, warnings)?;
+
+    // Validate fstab dump and pass overrides
+    fstab::validate(manifest, warnings)?;
+
+    // Validate detached LUKS headers and crypttab entries
+    crypttab::validate(manifest, warnings)?;
+
+    // Validate overlay dirs
+    overlay::validate(manifest)?;
+
+    // Validate ali-rs hooks
+    hooks::validate(manifest, install_location)?;
+
+    // Validate zram compression algorithm and size
+    if let Some(zram) = &manifest.zram {
+        linux::zram::validate(zram)?;
+    }
+
+    // Warn about fs types with unknown packages
+    validate_fs_types(manifest, warnings);
+
+    if offline {
+        if let Some(modules) = &manifest.preload_modules {
+            validate_module_names(modules)?;
+        }
+
+        return Ok(report);
+    }
 
     // Check all commands used by ALI before ch-root
-    for cmd in constants::REQUIRED_COMMANDS {
-        if !shell::in_path(cmd) {
-            return Err(AliError::Validation(format!(
-                "command {cmd} not in path"
+    validate_commands(&constants::REQUIRED_COMMANDS)?;
+
+    // Check kernel modules to be loaded on the live system
+    if let Some(modules) = &manifest.preload_modules {
+        validate_preload_modules(modules)?;
+    }
+
+    // Check mkfs.{fs_type} for rootfs and other filesystems
+    validate_mkfs(manifest, shell::in_path)?;
+
+    // Check local packages for pacstrap
+    if let Some(cache) = &manifest.pacman_cache {
+        validate_pacman_cache(cache)?;
+    }
+
+    // Check host pacman.conf SigLevel before disks are wiped
+    if manifest.strict_signatures.unwrap_or(false) {
+        linux::pacman::validate_strict_conf(linux::pacman::PACMAN_CONF)?;
+    }
+
+    validate_timezone(manifest, warnings)?;
+
+    Ok(report)
+}
+
+/// Validates manifest for config-only installs, where storage
+/// for the new system is already mounted at `install_location`.
+///
+/// Block devices are not validated at all. Instead, the install
+/// location and all manifest mountpoints must be mounted.
+///
+/// Non-fatal issues are pushed to `warnings`.
+pub fn validate_config_only(
+    manifest: &Manifest,
+    install_location: &str,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), AliError> {
+    if let Some(location) = &manifest.location {
+        validate_location(location)?;
+    }
+
+    let proc_mounts = std::fs::read_to_string(PROC_MOUNTS).map_err(|err| {
+        AliError::FileError(err, format!("failed to read {PROC_MOUNTS}"))
+    })?;
+
+    mounted::validate(manifest, install_location, &proc_mounts)?;
+
+    // Validate fstab dump and pass overrides
+    fstab::validate(manifest, warnings)?;
+
+    crypttab::validate(manifest, warnings)?;
+
+    overlay::validate(manifest)?;
+
+    // Validate ali-rs hooks
+    hooks::validate(manifest, install_location)?;
+
+    if let Some(zram) = &manifest.zram {
+        linux::zram::validate(zram)?;
+    }
+
+    validate_fs_types(manifest, warnings);
+
+    if let Some(cache) = &manifest.pacman_cache {
+        validate_pacman_cache(cache)?;
+    }
+
+    if manifest.strict_signatures.unwrap_or(false) {
+        linux::pacman::validate_strict_conf(linux::pacman::PACMAN_CONF)?;
+    }
+
+    validate_commands(&constants::REQUIRED_COMMANDS_CONFIG)?;
+    validate_timezone(manifest, warnings)
+}
+
+/// Validates that passphrase sources of all manifest LUKS devices
+/// resolve to non-empty passphrases, e.g. that `env:` variables are set
+/// and `file:` files are readable.
+fn validate_luks_passphrases(manifest: &Manifest) -> Result<(), AliError> {
+    for dm in manifest.device_mappers.iter().flatten() {
+        let Dm::Luks(luks) = dm else {
+            continue;
+        };
+
+        let Some(source) = &luks.passphrase else {
+            continue;
+        };
+
+        let passphrase = secret::resolve(source).map_err(|err| {
+            AliError::BadManifest(format!(
+                "bad passphrase for luks {}: {err}",
+                luks.name
+            ))
+        })?;
+
+        if passphrase.is_empty() {
+            return Err(AliError::BadManifest(format!(
+                "empty passphrase for luks {}",
+                luks.name
             )));
         }
     }
 
-    // Check mkfs for rootfs
-    let mkfs_rootfs = &format!("mkfs.{}", manifest.rootfs.fs_type);
-    if !shell::in_path(mkfs_rootfs) {
+    Ok(())
+}
+
+/// Validates that pacman cache dir `path` exists and has packages
+fn validate_pacman_cache(path: &str) -> Result<(), AliError> {
+    let entries = std::fs::read_dir(path).map_err(|err| {
+        AliError::BadManifest(format!(
+            "pacman_cache {path} is not a readable directory: {err}"
+        ))
+    })?;
+
+    let has_packages = entries
+        .flatten()
+        .any(|entry| entry.file_name().to_string_lossy().contains(".pkg.tar"));
+
+    if !has_packages {
         return Err(AliError::BadManifest(format!(
-            "no such program to create rootfs: {mkfs_rootfs}"
+            "pacman_cache {path} has no packages"
         )));
     }
 
-    // Check mkfs.{fs} for other FS
-    if let Some(filesystems) = &manifest.filesystems {
-        for fs in filesystems {
-            let mkfs_cmd = &format!("mkfs.{}", fs.fs_type);
-            if !shell::in_path(mkfs_cmd) {
-                let device = &fs.device;
+    Ok(())
+}
+
+/// Validates that pacstrap would be able to reach a pacman mirror,
+/// unless disabled with manifest `require_network`
+pub fn validate_network(manifest: &Manifest) -> Result<(), AliError> {
+    if !manifest.require_network.unwrap_or(true) {
+        return Ok(());
+    }
+
+    network::validate()
+}
+
+/// Validates that manifests to be applied in sequence do not
+/// share any block devices. `manifests` are pairs of manifest name
+/// (e.g. file path) and manifest.
+pub fn validate_distinct_devices(
+    manifests: &[(&str, &Manifest)],
+) -> Result<(), AliError> {
+    let devices: Vec<(&str, Vec<String>, Vec<String>)> = manifests
+        .iter()
+        .map(|(name, manifest)| {
+            let disks = manifest
+                .disks
+                .iter()
+                .flatten()
+                .map(|disk| disk.device.clone())
+                .collect();
+
+            (*name, disks, manifest.devices())
+        })
+        .collect();
+
+    for (i, (name_a, disks_a, devs_a)) in devices.iter().enumerate() {
+        for (name_b, disks_b, devs_b) in devices.iter().skip(i + 1) {
+            let used_a = disks_a.iter().chain(devs_a);
+            let used_b = || disks_b.iter().chain(devs_b);
 
-                return Err(AliError::BadManifest(format!(
-                    "no such program to create filesystem for device {device}: {mkfs_cmd}"
-                )));
+            for dev_a in used_a {
+                let conflict = used_b().find(|dev_b| {
+                    dev_a == *dev_b
+                        || (disks_a.contains(dev_a) && is_on_disk(dev_b, dev_a))
+                        || (disks_b.contains(dev_b) && is_on_disk(dev_a, dev_b))
+                });
+
+                if let Some(dev_b) = conflict {
+                    return Err(AliError::BadManifest(format!(
+                        "manifests {name_a} and {name_b} conflict on devices {dev_a} and {dev_b}"
+                    )));
+                }
             }
         }
     }
 
-    // Validate ali-rs hooks
-    hooks::validate(manifest, install_location)?;
+    Ok(())
+}
 
-    // Check timezone file in local installer
-    let zone_info = format!(
-        "/usr/share/zoneinfo/{}",
-        manifest
-            .timezone
-            .clone()
-            .unwrap_or(defaults::TIMEZONE.into())
-    );
+/// Validates that manifests to be applied in sequence install to
+/// different locations, since earlier installs are left mounted.
+/// `locations` are pairs of manifest name and install location.
+pub fn validate_distinct_locations(
+    locations: &[(&str, &str)],
+) -> Result<(), AliError> {
+    for (i, (name_a, location_a)) in locations.iter().enumerate() {
+        let location = location_a.trim_end_matches('/');
+        let conflict = locations.iter().skip(i + 1).find(|(_, location_b)| {
+            location_b.trim_end_matches('/') == location
+        });
+
+        if let Some((name_b, _)) = conflict {
+            return Err(AliError::BadManifest(format!(
+                "manifests {name_a} and {name_b} both install to {location_a}, set a different location in each manifest, without --location or ALI_LOC"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_commands(cmds: &[&str]) -> Result<(), AliError> {
+    for cmd in cmds {
+        if !shell::in_path(cmd) {
+            return Err(AliError::Validation(format!(
+                "command {cmd} not in path"
+            )));
+        }
+    }
 
+    Ok(())
+}
+
+// Check timezone file in local installer.
+// If timezone is not set, no timezone is linked and the system uses UTC.
+fn validate_timezone(
+    manifest: &Manifest,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), AliError> {
+    let Some(timezone) = &manifest.timezone else {
+        warnings.push(Warning::new(
+            WARN_CONTEXT,
+            "timezone not set, new system will default to UTC",
+        ));
+
+        return Ok(());
+    };
+
+    let zone_info = format!("/usr/share/zoneinfo/{timezone}");
     if !file_exists(&zone_info) {
         return Err(AliError::BadManifest(format!(
             "no zone info file {zone_info}"
         )));
     }
 
-    Ok(ValidationReport { block_devs })
+    Ok(())
+}
+
+/// Checks that `mkfs.{fs_type}` for every filesystem in manifest
+/// is available, according to `in_path`
+fn validate_mkfs(
+    manifest: &Manifest,
+    in_path: fn(&str) -> bool,
+) -> Result<(), AliError> {
+    let rootfs = (&manifest.rootfs.device, &manifest.rootfs.fs_type);
+    let filesystems = manifest
+        .filesystems
+        .iter()
+        .flatten()
+        .map(|fs| (&fs.device, &fs.fs_type));
+
+    for (device, fs_type) in std::iter::once(rootfs).chain(filesystems) {
+        let mkfs = format!("mkfs.{fs_type}");
+        if !in_path(&mkfs) {
+            return Err(AliError::BadManifest(format!(
+                "no program {mkfs} to create fs_type {fs_type} on device {device}, unsupported or misspelled fs_type?"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Warns about filesystems whose fs_type is unknown to ali-rs,
+/// since no package for their tools is added to pacstrap
+fn validate_fs_types(manifest: &Manifest, warnings: &mut Vec<Warning>) {
+    let rootfs = (&manifest.rootfs.device, &manifest.rootfs.fs_type);
+    let filesystems = manifest
+        .filesystems
+        .iter()
+        .flatten()
+        .map(|fs| (&fs.device, &fs.fs_type));
+
+    for (device, fs_type) in std::iter::once(rootfs).chain(filesystems) {
+        if !linux::mkfs::is_known_fs(fs_type) {
+            warnings.push(Warning::new(
+                WARN_CONTEXT,
+                format!(
+                    "unknown fs_type {fs_type} on device {device}, no package for its tools will be added to pacstrap"
+                ),
+            ));
+        }
+    }
+}
+
+fn validate_preload_modules(modules: &[String]) -> Result<(), AliError> {
+    if modules.is_empty() {
+        return Ok(());
+    }
+
+    if !shell::in_path("modprobe") {
+        return Err(AliError::Validation(
+            "command modprobe not in path, but preload_modules is set"
+                .to_string(),
+        ));
+    }
+
+    validate_module_names(modules)
+}
+
+fn validate_module_names(modules: &[String]) -> Result<(), AliError> {
+    for module in modules {
+        if module.is_empty()
+            || module.contains(char::is_whitespace)
+            || module.contains('/')
+        {
+            return Err(AliError::BadManifest(format!(
+                "bad kernel module name in preload_modules: \"{module}\""
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+// Install location is a mount base for the new system,
+// so it must be absolute and must not be the live root
+fn validate_location(location: &str) -> Result<(), AliError> {
+    if !location.starts_with('/') {
+        return Err(AliError::BadManifest(format!(
+            "install location {location} is not an absolute path"
+        )));
+    }
+
+    if location.trim_end_matches('/').is_empty() {
+        return Err(AliError::BadManifest(format!(
+            "install location cannot be root {location}"
+        )));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_validate_location() {
+    for location in ["/alitarget", "/mnt/", "/mnt/ali"] {
+        if let Err(err) = validate_location(location) {
+            panic!("unexpected error for {location}: {err}");
+        }
+    }
+
+    for location in ["/", "//", "", "mnt", "./alitarget"] {
+        if validate_location(location).is_ok() {
+            panic!("unexpected ok result for {location}");
+        }
+    }
+}
+
+#[test]
+fn test_validate_mkfs() {
+    let manifest = Manifest::from_yaml(
+        r#"
+rootfs:
+  device: /dev/fake1p2
+  fstype: btrfs
+filesystems:
+  - device: /dev/fake1p1
+    fstype: vfat
+  - device: /dev/fake1p3
+    fstype: ext44
+"#,
+    )
+    .expect("failed to parse manifest");
+
+    let in_path = |program: &str| program != "mkfs.ext44";
+
+    match validate_mkfs(&manifest, in_path) {
+        Err(AliError::BadManifest(msg)) => {
+            assert!(msg.contains("mkfs.ext44"));
+            assert!(msg.contains("/dev/fake1p3"));
+        }
+        Err(err) => panic!("unexpected error: {err}"),
+        Ok(_) => panic!("unexpected ok result"),
+    }
+
+    if let Err(err) = validate_mkfs(&manifest, |_| true) {
+        panic!("unexpected error: {err}");
+    }
+
+    let in_path = |program: &str| program != "mkfs.btrfs";
+    assert!(validate_mkfs(&manifest, in_path).is_err());
+}
+
+#[test]
+fn test_validate_fs_types() {
+    let manifest = Manifest::from_yaml(
+        r#"
+rootfs:
+  device: /dev/fake1p2
+  fstype: btrfs
+filesystems:
+  - device: /dev/fake1p1
+    fstype: vfat
+  - device: /dev/fake1p3
+    fstype: nilfs2
+  - device: /dev/fake1p4
+    fstype: reiser4
+"#,
+    )
+    .expect("failed to parse manifest");
+
+    let mut warnings = vec![];
+    validate_fs_types(&manifest, &mut warnings);
+
+    assert_eq!(1, warnings.len(), "unexpected warnings: {warnings:?}");
+    assert!(warnings[0].message.contains("reiser4"));
+    assert!(warnings[0].message.contains("/dev/fake1p4"));
+}
+
+#[test]
+fn test_validate_distinct_locations() {
+    let distinct = [("a.yaml", "/alitarget"), ("b.yaml", "/mnt/b")];
+    assert!(validate_distinct_locations(&distinct).is_ok());
+
+    let shared = [
+        ("a.yaml", "/alitarget"),
+        ("b.yaml", "/mnt/b"),
+        ("c.yaml", "/alitarget/"),
+    ];
+
+    match validate_distinct_locations(&shared) {
+        Err(AliError::BadManifest(msg)) => {
+            assert!(msg.contains("a.yaml"));
+            assert!(msg.contains("c.yaml"));
+        }
+        Err(err) => panic!("unexpected error: {err}"),
+        Ok(_) => panic!("unexpected ok result"),
+    }
+}
+
+#[test]
+fn test_validate_distinct_devices() {
+    let parse = |yaml: &str| {
+        Manifest::from_yaml(yaml).expect("failed to parse manifest")
+    };
+
+    let a = parse(
+        r#"
+disks:
+  - device: /dev/fake1
+    table: gpt
+    partitions:
+      - label: efi
+        size: 500M
+        type: ef
+      - label: root
+        type: 83
+rootfs:
+  device: /dev/fake1p2
+  fstype: ext4
+filesystems:
+  - device: /dev/fake1p1
+    fstype: vfat
+"#,
+    );
+
+    let b = parse(
+        r#"
+disks:
+  - device: /dev/fake2
+    table: gpt
+    partitions:
+      - label: data
+        type: 8e
+device_mappers:
+  - type: lvm
+    pvs:
+      - /dev/fake2p1
+    vgs:
+      - name: fakevg
+        pvs:
+          - /dev/fake2p1
+    lvs:
+      - name: rootlv
+        vg: fakevg
+rootfs:
+  device: /dev/fakevg/rootlv
+  fstype: btrfs
+"#,
+    );
+
+    // Same disk, different partitions
+    let c = parse(
+        r#"
+rootfs:
+  device: /dev/fake1p3
+  fstype: xfs
+"#,
+    );
+
+    // Same LV as b
+    let d = parse(
+        r#"
+rootfs:
+  device: /dev/fakevg/rootlv
+  fstype: ext4
+"#,
+    );
+
+    let should_ok = vec![
+        vec![("a.yaml", &a), ("b.yaml", &b)],
+        vec![("b.yaml", &b), ("c.yaml", &c)],
+        vec![("a.yaml", &a)],
+    ];
+
+    let should_err = vec![
+        vec![("a.yaml", &a), ("c.yaml", &c)],
+        vec![("c.yaml", &c), ("a.yaml", &a)],
+        vec![("a.yaml", &a), ("b.yaml", &b), ("d.yaml", &d)],
+        vec![("a.yaml", &a), ("a.yaml", &a)],
+    ];
+
+    for manifests in should_ok {
+        if let Err(err) = validate_distinct_devices(&manifests) {
+            panic!("unexpected error: {err}");
+        }
+    }
+
+    for manifests in should_err {
+        match validate_distinct_devices(&manifests) {
+            Err(AliError::BadManifest(msg)) => {
+                assert!(msg.contains("conflict"));
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_validate_luks_passphrases() {
+    let manifest = |passphrase: &str| {
+        Manifest::from_yaml(&format!(
+            r#"
+disks:
+  - device: /dev/fake1
+    table: gpt
+    partitions:
+      - label: efi
+        size: 500M
+        type: ef
+      - label: root
+        type: 83
+dm:
+  - type: luks
+    device: /dev/fake1p2
+    name: cryptroot
+    passphrase: {passphrase}
+rootfs:
+  device: /dev/mapper/cryptroot
+  fstype: ext4
+"#
+        ))
+        .expect("failed to parse manifest")
+    };
+
+    let should_ok = vec!["literal", "env:PATH"];
+    let should_err = vec![
+        "env:ALI_RS_TEST_MISSING",
+        "file:/ali-rs/test/missing/keyfile",
+    ];
+
+    for passphrase in should_ok {
+        validate_luks_passphrases(&manifest(passphrase)).unwrap_or_else(
+            |err| panic!("unexpected error for {passphrase}: {err}"),
+        );
+    }
+
+    for passphrase in should_err {
+        let result = validate_luks_passphrases(&manifest(passphrase));
+        assert!(result.is_err(), "unexpected ok result for {passphrase}");
+    }
+
+    // Passphrases are resolved by validate before any system probes
+    let manifest = manifest("env:ALI_RS_TEST_MISSING");
+    match validate(&manifest, "/alitarget", true, false, &mut Vec::new()) {
+        Err(AliError::BadManifest(msg)) => {
+            assert!(msg.contains("passphrase"), "unexpected error: {msg}");
+        }
+        Err(err) => panic!("unexpected error: {err}"),
+        Ok(_) => panic!("unexpected ok result for missing passphrase"),
+    }
+
+    // Offline validation never reads passphrase sources
+    validate(&manifest, "/alitarget", true, true, &mut Vec::new())
+        .unwrap_or_else(|err| panic!("unexpected offline error: {err}"));
+}
+
+#[test]
+fn test_validate_pacman_cache() {
+    let dir = std::env::temp_dir().join("ali-rs-test-pacman-cache");
+    _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.to_str().unwrap();
+    assert!(validate_pacman_cache(path).is_err(), "empty cache");
+    assert!(validate_pacman_cache(&format!("{path}/missing")).is_err());
+
+    std::fs::write(dir.join("base-3-2-any.pkg.tar.zst"), "").unwrap();
+    validate_pacman_cache(path).expect("unexpected error");
+
+    std::fs::remove_dir_all(&dir).unwrap();
 }
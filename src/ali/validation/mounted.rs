@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+
+use crate::ali::Manifest;
+use crate::errors::AliError;
+use crate::linux;
+
+const MSG: &str = "config-only validation failed";
+
+/// Validates that `install_location` and all manifest mountpoints
+/// under it are already mounted, according to `proc_mounts`
+/// (content of /proc/mounts).
+pub(super) fn validate(
+    manifest: &Manifest,
+    install_location: &str,
+    proc_mounts: &str,
+) -> Result<(), AliError> {
+    let mounted = parse_mountpoints(proc_mounts);
+    let root = install_location.trim_end_matches('/');
+
+    if !mounted.contains(root) {
+        return Err(AliError::Validation(format!(
+            "{MSG}: rootfs {} is not mounted at {install_location}",
+            manifest.rootfs.device,
+        )));
+    }
+
+    for mnt in manifest.mountpoints.iter().flatten() {
+        let dest = linux::mount::prepend_base(root, &mnt.dest);
+        if !mounted.contains(dest.trim_end_matches('/')) {
+            return Err(AliError::Validation(format!(
+                "{MSG}: {} is not mounted at {dest}",
+                mnt.device,
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns mountpoints (2nd field) in /proc/mounts,
+/// with octal escapes such as `\040` (space) decoded
+fn parse_mountpoints(proc_mounts: &str) -> HashSet<String> {
+    proc_mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(unescape_octal)
+        .map(|mountpoint| {
+            match mountpoint.trim_end_matches('/') {
+                "" => "/".to_string(),
+                trimmed => trimmed.to_string(),
+            }
+        })
+        .collect()
+}
+
+fn unescape_octal(s: &str) -> String {
+    let mut unescaped = String::new();
+    let mut rest = s;
+
+    while let Some(i) = rest.find('\\') {
+        unescaped.push_str(&rest[..i]);
+
+        let code = rest.get(i + 1..i + 4);
+        match code.and_then(|code| u8::from_str_radix(code, 8).ok()) {
+            Some(byte) => {
+                unescaped.push(byte as char);
+                rest = &rest[i + 4..];
+            }
+            None => {
+                unescaped.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[test]
+fn test_validate_mounted() {
+    let manifest = Manifest::from_yaml(
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sda1
+    fstype: vfat
+  - device: /dev/sda3
+    fstype: ext4
+mountpoints:
+  - device: /dev/sda1
+    dest: /boot
+  - device: /dev/sda3
+    dest: /my data
+"#,
+    )
+    .unwrap();
+
+    let proc_mounts_ok = r#"proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
+/dev/sdb1 / ext4 rw,relatime 0 0
+/dev/sda2 /alitarget ext4 rw,relatime 0 0
+/dev/sda1 /alitarget/boot vfat rw,relatime 0 0
+/dev/sda3 /alitarget/my\040data ext4 rw,relatime 0 0
+"#;
+
+    let should_err = vec![
+        // Root not mounted
+        r#"/dev/sdb1 / ext4 rw,relatime 0 0
+"#,
+        // /boot not mounted
+        r#"/dev/sdb1 / ext4 rw,relatime 0 0
+/dev/sda2 /alitarget ext4 rw,relatime 0 0
+/dev/sda3 /alitarget/my\040data ext4 rw,relatime 0 0
+"#,
+        // /boot mounted outside of install location
+        r#"/dev/sda2 /alitarget ext4 rw,relatime 0 0
+/dev/sda1 /boot vfat rw,relatime 0 0
+/dev/sda3 /alitarget/my\040data ext4 rw,relatime 0 0
+"#,
+    ];
+
+    for location in ["/alitarget", "/alitarget/"] {
+        if let Err(err) = validate(&manifest, location, proc_mounts_ok) {
+            panic!("unexpected error for location {location}: {err}");
+        }
+    }
+
+    for proc_mounts in should_err {
+        if validate(&manifest, "/alitarget", proc_mounts).is_ok() {
+            panic!("unexpected ok result for mounts: {proc_mounts}");
+        }
+    }
+}
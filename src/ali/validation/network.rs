@@ -0,0 +1,158 @@
+use std::net::{
+    TcpStream,
+    ToSocketAddrs,
+};
+use std::time::Duration;
+
+use crate::errors::AliError;
+
+const MIRRORLIST: &str = "/etc/pacman.d/mirrorlist";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Validates that the first mirror in the live system's mirrorlist
+/// accepts TCP connections, so that pacstrap would not fail
+/// after disks were already prepared
+pub(super) fn validate() -> Result<(), AliError> {
+    let mirrorlist = std::fs::read_to_string(MIRRORLIST).map_err(|err| {
+        AliError::FileError(err, format!("failed to read {MIRRORLIST}"))
+    })?;
+
+    let (host, port) = mirror_host(&mirrorlist).ok_or_else(|| {
+        AliError::Validation(format!("no mirror Server found in {MIRRORLIST}"))
+    })?;
+
+    connect(&host, port, CONNECT_TIMEOUT)
+}
+
+/// Returns host and port of the first `Server` in pacman `mirrorlist`.
+/// Port defaults to 443 for https and 80 for http.
+fn mirror_host(mirrorlist: &str) -> Option<(String, u16)> {
+    let server = mirrorlist.lines().find_map(|line| {
+        let (key, value) = line.trim().split_once('=')?;
+        match key.trim() {
+            "Server" => Some(value.trim()),
+            _ => None,
+        }
+    })?;
+
+    let (scheme, rest) = server.split_once("://")?;
+    let default_port = match scheme {
+        "https" => 443,
+        "http" => 80,
+        _ => return None,
+    };
+
+    let authority = rest.split('/').next()?;
+
+    // IPv6 address like [::1]:8080
+    if let Some(rest) = authority.strip_prefix('[') {
+        let (host, port) = rest.split_once(']')?;
+        let port = match port.strip_prefix(':') {
+            Some(port) => port.parse().ok()?,
+            None => default_port,
+        };
+
+        return Some((host.to_string(), port));
+    }
+
+    match authority.split_once(':') {
+        Some((host, port)) => Some((host.to_string(), port.parse().ok()?)),
+        None if authority.is_empty() => None,
+        None => Some((authority.to_string(), default_port)),
+    }
+}
+
+/// Resolves `host` and connects to any of its addresses on `port`
+fn connect(host: &str, port: u16, timeout: Duration) -> Result<(), AliError> {
+    let no_network = |reason: String| {
+        AliError::Validation(format!(
+            "network check failed: {reason}. pacstrap needs network access, check the live system's network, or set require_network: false"
+        ))
+    };
+
+    let addrs = (host, port).to_socket_addrs().map_err(|err| {
+        no_network(format!("failed to resolve {host}: {err}"))
+    })?;
+
+    let mut last_err = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => return Ok(()),
+            Err(err) => last_err = Some(err),
+        }
+    }
+
+    Err(no_network(match last_err {
+        Some(err) => format!("failed to connect to {host}:{port}: {err}"),
+        None => format!("no address for {host}"),
+    }))
+}
+
+#[test]
+fn test_mirror_host() {
+    let mirrorlist = r#"
+## Worldwide
+#Server = https://commented.example.org/$repo/os/$arch
+
+## Thailand
+Server = https://mirror.example.ac.th/archlinux/$repo/os/$arch
+Server = http://second.example.org/$repo/os/$arch
+"#;
+
+    assert_eq!(
+        Some(("mirror.example.ac.th".to_string(), 443)),
+        mirror_host(mirrorlist),
+    );
+
+    let should_ok = vec![
+        (
+            "Server = http://mirror.example.org/$repo",
+            ("mirror.example.org", 80),
+        ),
+        (
+            "Server=https://mirror.example.org:8443/",
+            ("mirror.example.org", 8443),
+        ),
+        ("Server = http://[::1]:8080/$repo", ("::1", 8080)),
+        ("Server = https://[fe80::1]/$repo", ("fe80::1", 443)),
+    ];
+
+    let should_err = vec![
+        "",
+        "#Server = https://mirror.example.org/$repo",
+        "Server = ftp://mirror.example.org/$repo",
+        "Server = https:///$repo",
+        "Server = https://mirror.example.org:port/$repo",
+    ];
+
+    for (mirrorlist, (host, port)) in should_ok {
+        assert_eq!(
+            Some((host.to_string(), port)),
+            mirror_host(mirrorlist),
+            "unexpected result for {mirrorlist}",
+        );
+    }
+
+    for mirrorlist in should_err {
+        assert_eq!(
+            None,
+            mirror_host(mirrorlist),
+            "unexpected host for {mirrorlist}"
+        );
+    }
+}
+
+#[test]
+fn test_connect() {
+    let timeout = Duration::from_secs(1);
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")
+        .expect("failed to bind local listener");
+
+    let port = listener.local_addr().unwrap().port();
+    connect("127.0.0.1", port, timeout).expect("failed to connect to listener");
+
+    // Port is closed once the listener is dropped
+    drop(listener);
+    assert!(connect("127.0.0.1", port, timeout).is_err());
+}
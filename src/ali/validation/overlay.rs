@@ -0,0 +1,247 @@
+use std::collections::HashSet;
+use std::path::{
+    Component,
+    Path,
+};
+
+use crate::ali::{
+    Manifest,
+    ManifestOverlay,
+};
+use crate::errors::AliError;
+
+/// Validates overlay mounts in manifest.
+///
+/// All overlay dirs must be absolute, normalized paths inside the new
+/// system, and dirs of an overlay must be distinct. overlayfs(5) also
+/// requires upperdir and workdir to be separate subtrees on the same
+/// filesystem, which is checked against manifest mountpoints.
+pub(super) fn validate(manifest: &Manifest) -> Result<(), AliError> {
+    let mut mnts = HashSet::new();
+
+    for overlay in manifest.overlays.iter().flatten() {
+        validate_overlay(overlay)?;
+
+        if !mnts.insert(overlay.mnt.as_str()) {
+            return Err(AliError::BadManifest(format!(
+                "duplicate overlay mountpoint {}",
+                overlay.mnt
+            )));
+        }
+
+        let upper_mnt = backing_mountpoint(manifest, &overlay.upper_dir);
+        let work_mnt = backing_mountpoint(manifest, &overlay.work_dir);
+
+        if upper_mnt != work_mnt {
+            return Err(AliError::BadManifest(format!(
+                "overlay on {}: upperdir {} (on {upper_mnt}) and workdir {} (on {work_mnt}) must be on the same filesystem",
+                overlay.mnt, overlay.upper_dir, overlay.work_dir,
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_overlay(overlay: &ManifestOverlay) -> Result<(), AliError> {
+    let dirs: Vec<(&str, &str)> = overlay
+        .lower_dirs()
+        .map(|dir| ("lowerdir", dir))
+        .chain([
+            ("upperdir", overlay.upper_dir.as_str()),
+            ("workdir", overlay.work_dir.as_str()),
+            ("mnt", overlay.mnt.as_str()),
+        ])
+        .collect();
+
+    for (key, dir) in &dirs {
+        if !is_within_tree(dir) {
+            return Err(AliError::BadManifest(format!(
+                "overlay on {}: {key} {dir} is not an absolute path inside the new system",
+                overlay.mnt
+            )));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for (key, dir) in &dirs {
+        if !seen.insert(*dir) {
+            return Err(AliError::BadManifest(format!(
+                "overlay on {}: {key} {dir} is used more than once",
+                overlay.mnt
+            )));
+        }
+    }
+
+    let (upper, work) =
+        (Path::new(&overlay.upper_dir), Path::new(&overlay.work_dir));
+    if upper.starts_with(work) || work.starts_with(upper) {
+        return Err(AliError::BadManifest(format!(
+            "overlay on {}: upperdir {} and workdir {} must not be nested",
+            overlay.mnt, overlay.upper_dir, overlay.work_dir,
+        )));
+    }
+
+    Ok(())
+}
+
+// Only absolute paths without `.` or `..` components,
+// and other than `/` itself, stay inside the new system
+fn is_within_tree(dir: &str) -> bool {
+    let path = Path::new(dir);
+    let mut components = path.components();
+
+    if components.next() != Some(Component::RootDir) {
+        return false;
+    }
+
+    let mut normal = false;
+    for component in components {
+        match component {
+            Component::Normal(_) => normal = true,
+            _ => return false,
+        }
+    }
+
+    normal
+}
+
+// Returns the manifest mountpoint whose filesystem holds `dir`
+fn backing_mountpoint<'a>(manifest: &'a Manifest, dir: &str) -> &'a str {
+    manifest
+        .mountpoints
+        .iter()
+        .flatten()
+        .map(|mnt| mnt.dest.as_str())
+        .filter(|dest| Path::new(dir).starts_with(dest))
+        .max_by_key(|dest| dest.len())
+        .unwrap_or("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_overlays() {
+        let should_ok = vec![
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+overlays:
+  - lowerdir: /usr/lib/os:/opt/base
+    upperdir: /var/overlay/upper
+    workdir: /var/overlay/work
+    mnt: /srv
+"#,
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+mountpoints:
+  - device: /dev/sda3
+    dest: /var
+overlays:
+  - lowerdir: /opt/base
+    upperdir: /var/upper
+    workdir: /var/work
+    mnt: /opt/merged
+"#,
+        ];
+
+        let should_err = vec![
+            // Relative dir
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+overlays:
+  - lowerdir: opt/base
+    upperdir: /var/upper
+    workdir: /var/work
+    mnt: /srv
+"#,
+            // Dir escapes the new system
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+overlays:
+  - lowerdir: /opt/base
+    upperdir: /var/../../upper
+    workdir: /var/work
+    mnt: /srv
+"#,
+            // Same lower and upper dirs
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+overlays:
+  - lowerdir: /opt/base
+    upperdir: /opt/base
+    workdir: /var/work
+    mnt: /srv
+"#,
+            // Nested upper and work dirs
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+overlays:
+  - lowerdir: /opt/base
+    upperdir: /var/upper
+    workdir: /var/upper/work
+    mnt: /srv
+"#,
+            // Upper and work dirs on different filesystems
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+mountpoints:
+  - device: /dev/sda3
+    dest: /var
+overlays:
+  - lowerdir: /opt/base
+    upperdir: /var/upper
+    workdir: /work
+    mnt: /srv
+"#,
+            // Duplicate overlay mountpoints
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: btrfs
+overlays:
+  - lowerdir: /opt/a
+    upperdir: /var/a/upper
+    workdir: /var/a/work
+    mnt: /srv
+  - lowerdir: /opt/b
+    upperdir: /var/b/upper
+    workdir: /var/b/work
+    mnt: /srv
+"#,
+        ];
+
+        for yaml in should_ok {
+            let manifest =
+                Manifest::from_yaml(yaml).expect("failed to parse manifest");
+
+            if let Err(err) = validate(&manifest) {
+                panic!("unexpected error: {err}\n{yaml}");
+            }
+        }
+
+        for yaml in should_err {
+            let manifest =
+                Manifest::from_yaml(yaml).expect("failed to parse manifest");
+
+            if validate(&manifest).is_ok() {
+                panic!("unexpected ok result\n{yaml}");
+            }
+        }
+    }
+}
@@ -26,12 +26,21 @@ pub struct Cli {
         value_parser = validate_filename,
     )]
     pub manifest: String,
+
+    /// Install location of the new system, overriding
+    /// ALI_LOC environment variable and manifest `location`
+    #[arg(global = true, long = "location", alias = "install-root")]
+    pub location: Option<String>,
+
+    /// Fail validation if it produced any warnings
+    #[arg(global = true, long = "strict", default_value_t = false)]
+    pub strict: bool,
 }
 
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     /// Validates manifest
-    Validate,
+    Validate(ArgsValidate),
 
     /// Applies all stages in the manifest to create a new system
     Apply(ArgsApply),
@@ -40,8 +49,21 @@ pub enum Commands {
     Hooks(ArgsHooks),
 }
 
-#[derive(Debug, Args)]
+#[derive(Debug, Default, Args)]
+pub struct ArgsValidate {
+    /// Do not probe the system, and only validate the manifest itself.
+    /// References to existing system devices are reported as errors
+    #[arg(long = "offline", default_value_t = false)]
+    pub offline: bool,
+}
+
+#[derive(Debug, Default, Args)]
 pub struct ArgsApply {
+    /// Manifest files to validate and then apply in order,
+    /// overriding `--file`. Manifests must not share block devices
+    #[arg(value_name = "MANIFEST", value_parser = validate_filename)]
+    pub manifests: Vec<String>,
+
     /// Do not validate manifest entries
     #[arg(long = "no-validate")]
     pub no_validate: bool,
@@ -52,6 +74,11 @@ pub struct ArgsApply {
     #[arg(short = 'o', long = "overwrite", default_value_t = false)]
     pub overwrite: bool,
 
+    /// Override manifest values with PATH=VALUE before validation,
+    /// e.g. `--set hostname=srv1 --set disks.0.device=/dev/nvme0n1`
+    #[arg(long = "set", alias = "manifest-var", value_name = "PATH=VALUE")]
+    pub overrides: Vec<String>,
+
     /// Explicit stages to run
     #[arg(short = 's', long = "stages", num_args(0..))]
     pub stages: Option<Vec<stage::Stage>>,
@@ -60,10 +87,59 @@ pub struct ArgsApply {
     #[arg(long = "skip", num_args(0..))]
     pub skip_stages: Vec<stage::Stage>,
 
+    /// Only prepare and mount storage, skipping pacstrap and all
+    /// later stages. The new root is left mounted
+    #[arg(long = "no-pacstrap", conflicts_with_all = ["stages", "reboot"])]
+    pub no_pacstrap: bool,
+
+    /// Write shell script equivalent of the install to PATH.
+    /// With `-n`, the script is written without applying the manifest
+    #[arg(long = "emit-script", value_name = "PATH")]
+    pub emit_script: Option<String>,
+
+    /// On failure, write JSON report with actions performed,
+    /// the failed action and the error to PATH
+    #[arg(long = "report-on-failure", value_name = "PATH")]
+    pub report_on_failure: Option<String>,
+
+    /// Only configure an already mounted new system, skipping
+    /// stage mountpoints. Validation checks that the install location
+    /// and manifest mountpoints are mounted, instead of block devices
+    #[arg(long = "config-only", conflicts_with_all = ["stages", "no_pacstrap"])]
+    pub config_only: bool,
+
+    /// Run executable SCRIPT after validation, with the manifest as
+    /// JSON on its stdin. Non-zero exit status rejects the manifest
+    #[arg(long = "policy", value_name = "SCRIPT")]
+    pub policy: Option<String>,
+
+    /// Apply even if manifest block devices back the running
+    /// system's root filesystem, destroying the running system
+    #[arg(long = "i-know-what-im-doing", default_value_t = false)]
+    pub i_know_what_im_doing: bool,
+
+    /// Write each action to PATH as a line of JSON as soon as it
+    /// is performed, e.g. a FIFO or /dev/fd/3
+    #[arg(long = "stream-json", value_name = "PATH")]
+    pub stream_json: Option<String>,
+
+    /// On SIGINT, unmount the install location and close
+    /// device mappers opened by stages already applied
+    #[arg(long = "teardown-on-interrupt", default_value_t = false)]
+    pub teardown_on_interrupt: bool,
+
     /// Dry-run, ali-rs will not commit any changes to disks,
     /// and will just print steps to be performed
     #[arg(global = true, short = 'n', default_value_t = false)]
     pub dry_run: bool,
+
+    /// Unmount and reboot after a fully successful install
+    #[arg(long = "reboot", default_value_t = false)]
+    pub reboot: bool,
+
+    /// Reboot without asking for confirmation
+    #[arg(short = 'y', long = "yes", default_value_t = false)]
+    pub yes: bool,
 }
 
 #[derive(Debug, Args)]
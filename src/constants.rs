@@ -1,12 +1,10 @@
 pub mod defaults {
 
-    pub const TIMEZONE: &str = "America/Los_Angeles";
     pub const INSTALL_LOCATION: &str = "/alitarget";
-    pub const HOSTNAME: &str = "arch-ali";
     pub const LOCALE_GEN: &str = "en_US.UTF-8 UTF-8";
     pub const LOCALE_CONF: &str = "LANG=en_US.UTF-8";
 
-    const ROOT_PASSWD: &str = "archalirs";
+    pub const ROOT_PASSWD: &str = "archalirs";
 
     pub fn hashed_password() -> String {
         let h = pwhash::bcrypt::hash(ROOT_PASSWD)
@@ -30,7 +28,7 @@ pub mod defaults {
 pub const ENV_ALI_LOC: &str = "ALI_LOC";
 
 // Use programs instead of bindings to avoid API dependencies
-pub const REQUIRED_COMMANDS: [&str; 15] = [
+pub const REQUIRED_COMMANDS: [&str; 17] = [
     "arch-chroot",
     "fdisk",
     "blkid",
@@ -41,9 +39,15 @@ pub const REQUIRED_COMMANDS: [&str; 15] = [
     "pvcreate",
     "vgcreate",
     "lvcreate",
+    "vgscan",
+    "udevadm",
     "genfstab",
     "echo",
     "printf",
     "openssl",
     "chpasswd",
 ];
+
+// Commands used by stages after storage is mounted
+pub const REQUIRED_COMMANDS_CONFIG: [&str; 4] =
+    ["arch-chroot", "pacstrap", "genfstab", "chpasswd"];
@@ -33,6 +33,20 @@ pub enum AliError {
         actions_performed: Vec<action::Action>,
     },
 
+    /// ManifestError represents an error from one of
+    /// several manifests applied in sequence.
+    ///
+    /// Manifests applied before the failed one are not undone.
+    #[error("manifest {manifest} failed: {error}")]
+    ManifestError {
+        manifest: String,
+        error: Box<AliError>,
+        manifests_applied: Vec<String>,
+    },
+
+    #[error("interrupted: {0}")]
+    Interrupted(String),
+
     #[error("no such file {1}: {0}")]
     NoSuchFile(std::io::Error, String),
 
@@ -95,6 +109,17 @@ impl AliError {
                     "actionsPerformed": actions_performed,
                 })
             }
+            Self::ManifestError {
+                manifest,
+                error,
+                manifests_applied,
+            } => {
+                json!({
+                    "manifest": manifest,
+                    "error": error.to_json_string(),
+                    "manifestsApplied": manifests_applied,
+                })
+            }
             _ => {
                 json!({
                     "error": self.to_string(),
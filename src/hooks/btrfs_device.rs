@@ -0,0 +1,336 @@
+use std::path::Path;
+
+use serde_json::json;
+
+use super::{
+    extract_key_and_parts,
+    wrap_bad_hook_cmd,
+    ActionHook,
+    Caller,
+    Hook,
+    ModeHook,
+    ParseError,
+    KEY_BTRFS_ADD_DEVICE,
+};
+use crate::errors::AliError;
+use crate::linux;
+use crate::utils::shell;
+
+const USAGE: &str = "<MOUNTPOINT> <DEVICE> [--balance]";
+
+#[derive(Debug, Clone, PartialEq)]
+struct HookBtrfsAddDevice {
+    mountpoint: String,
+    device: String,
+    balance: bool,
+}
+
+pub(super) fn parse(k: &str, cmd: &str) -> Result<Box<dyn Hook>, ParseError> {
+    match k {
+        KEY_BTRFS_ADD_DEVICE => {
+            match HookBtrfsAddDevice::try_from(cmd) {
+                Err(err) => Err(wrap_bad_hook_cmd(err, USAGE)),
+                Ok(hook) => Ok(Box::new(hook)),
+            }
+        }
+
+        key => panic!("unexpected key {key}"),
+    }
+}
+
+impl TryFrom<&str> for HookBtrfsAddDevice {
+    type Error = AliError;
+
+    fn try_from(cmd: &str) -> Result<Self, Self::Error> {
+        let (hook_key, parts) = extract_key_and_parts(cmd)?;
+        if hook_key != KEY_BTRFS_ADD_DEVICE {
+            panic!("unexpected key {hook_key}");
+        }
+
+        let mut balance = false;
+        let mut positionals = Vec::new();
+
+        for arg in parts.into_iter().skip(1) {
+            if arg == "--balance" {
+                balance = true;
+                continue;
+            }
+
+            positionals.push(arg);
+        }
+
+        let [mountpoint, device]: [String; 2] =
+            positionals.try_into().map_err(|args: Vec<String>| {
+                AliError::BadHookCmd(format!(
+                    "{hook_key}: expecting 2 arguments, got {}",
+                    args.len()
+                ))
+            })?;
+
+        Ok(Self {
+            mountpoint,
+            device,
+            balance,
+        })
+    }
+}
+
+impl Hook for HookBtrfsAddDevice {
+    fn base_key(&self) -> &'static str {
+        KEY_BTRFS_ADD_DEVICE
+    }
+
+    /// `@btrfs-add-device <MOUNTPOINT> <DEVICE> [--balance]`
+    ///
+    /// Examples:
+    ///
+    /// 1. Add /dev/sdb to btrfs mounted at /home
+    ///
+    /// ```txt
+    /// @btrfs-add-device /home /dev/sdb
+    /// ```
+    ///
+    /// 2. Add /dev/sdb to btrfs mounted at / and rebalance
+    ///
+    /// ```txt
+    /// @btrfs-add-device / /dev/sdb --balance
+    /// ```
+    fn usage(&self) -> &'static str {
+        USAGE
+    }
+
+    fn mode(&self) -> ModeHook {
+        ModeHook::Normal
+    }
+
+    fn should_chroot(&self) -> bool {
+        true
+    }
+
+    fn prefer_caller(&self, caller: &Caller) -> bool {
+        matches!(caller, Caller::ManifestChroot | Caller::Cli)
+    }
+
+    fn abort_if_no_mount(&self) -> bool {
+        true
+    }
+
+    fn validate(&self) -> Result<(), AliError> {
+        if !self.mountpoint.starts_with('/') {
+            return Err(AliError::BadHookCmd(format!(
+                "{}: mountpoint {} is not absolute",
+                self.hook_key(),
+                self.mountpoint,
+            )));
+        }
+
+        if !self.device.starts_with("/dev/") {
+            return Err(AliError::BadHookCmd(format!(
+                "{}: device {} is not a device path",
+                self.hook_key(),
+                self.device,
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn run_hook(
+        &self,
+        _caller: &Caller,
+        root_location: &str,
+    ) -> Result<ActionHook, AliError> {
+        if !Path::new(&self.device).exists() {
+            return Err(
+                self.hook_error(&format!("no such device {}", self.device))
+            );
+        }
+
+        // Filesystem mounted on the host under root_location
+        let mountpoint = match self.mountpoint.as_str() {
+            "/" => root_location.to_string(),
+            _ => linux::mount::prepend_base(root_location, &self.mountpoint),
+        };
+
+        let show = shell::output("btrfs", &["filesystem", "show", &mountpoint])
+            .map_err(|err| {
+                self.hook_error(&format!(
+                    "{} is not a mounted btrfs: {err}",
+                    self.mountpoint
+                ))
+            })?;
+
+        if btrfs_devices(&show).contains(&self.device.as_str()) {
+            return Err(self.hook_error(&format!(
+                "device {} is already part of btrfs at {}",
+                self.device, self.mountpoint,
+            )));
+        }
+
+        // blkid exits with non-zero status if no signature was found
+        if let Ok(signature) = shell::output("blkid", &[&self.device]) {
+            return Err(self.hook_error(&format!(
+                "device {} is not fs-ready: {signature}",
+                self.device,
+            )));
+        }
+
+        for cmd in cmds_btrfs_add_device(self) {
+            shell::arch_chroot(root_location, &cmd)?;
+        }
+
+        Ok(ActionHook::BtrfsAddDevice(
+            json!({
+                "mountpoint": self.mountpoint,
+                "device": self.device,
+                "balance": self.balance,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+fn cmds_btrfs_add_device(hook: &HookBtrfsAddDevice) -> Vec<String> {
+    let mut cmds = vec![format!(
+        "btrfs device add {} {}",
+        hook.device, hook.mountpoint
+    )];
+
+    if hook.balance {
+        cmds.push(format!(
+            "btrfs balance start --full-balance {}",
+            hook.mountpoint
+        ));
+    }
+
+    cmds
+}
+
+/// Returns device paths from `btrfs filesystem show` output, e.g.
+/// `devid    1 size 20.00GiB used 2.02GiB path /dev/sda2`
+fn btrfs_devices(output: &str) -> Vec<&str> {
+    output
+        .lines()
+        .filter(|line| line.trim_start().starts_with("devid"))
+        .filter_map(|line| line.split_once(" path "))
+        .map(|(_, path)| path.trim())
+        .collect()
+}
+
+#[test]
+fn test_parse_btrfs_add_device() {
+    let should_pass = vec![
+        (
+            "@btrfs-add-device /home /dev/sdb",
+            HookBtrfsAddDevice {
+                mountpoint: "/home".into(),
+                device: "/dev/sdb".into(),
+                balance: false,
+            },
+        ),
+        (
+            "@btrfs-add-device / /dev/sdb1 --balance",
+            HookBtrfsAddDevice {
+                mountpoint: "/".into(),
+                device: "/dev/sdb1".into(),
+                balance: true,
+            },
+        ),
+        (
+            "@btrfs-add-device --balance /data /dev/nvme1n1",
+            HookBtrfsAddDevice {
+                mountpoint: "/data".into(),
+                device: "/dev/nvme1n1".into(),
+                balance: true,
+            },
+        ),
+    ];
+
+    let should_err = vec![
+        "@btrfs-add-device",
+        "@btrfs-add-device /home",
+        "@btrfs-add-device /home /dev/sdb /dev/sdc",
+    ];
+
+    for (cmd, expected) in should_pass {
+        let hook_result = HookBtrfsAddDevice::try_from(cmd);
+        if let Err(ref err) = hook_result {
+            eprintln!("unexpected error result from {cmd}: {err}");
+        }
+
+        assert_eq!(expected, hook_result.unwrap());
+    }
+
+    for cmd in should_err {
+        if let Ok(hook) = HookBtrfsAddDevice::try_from(cmd) {
+            panic!("unexpected ok result from bad arg {cmd}: {hook:?}");
+        }
+    }
+}
+
+#[test]
+fn test_validate_btrfs_add_device() {
+    let should_ok = vec![
+        "@btrfs-add-device / /dev/sdb",
+        "@btrfs-add-device /home /dev/mapper/cryptdata",
+    ];
+
+    let should_err = vec![
+        // Relative mountpoint
+        "@btrfs-add-device home /dev/sdb",
+        // Not a device path
+        "@btrfs-add-device /home sdb",
+        // Swapped arguments
+        "@btrfs-add-device /dev/sdb home",
+    ];
+
+    for cmd in should_ok {
+        let hook = HookBtrfsAddDevice::try_from(cmd).unwrap();
+        if let Err(err) = hook.validate() {
+            panic!("unexpected validation error for {cmd}: {err}");
+        }
+    }
+
+    for cmd in should_err {
+        let hook = HookBtrfsAddDevice::try_from(cmd).unwrap();
+        assert!(hook.validate().is_err(), "bad hook {cmd} validated");
+    }
+}
+
+#[test]
+fn test_cmds_btrfs_add_device() {
+    let hook = HookBtrfsAddDevice::try_from("@btrfs-add-device /home /dev/sdb")
+        .unwrap();
+
+    assert_eq!(
+        vec!["btrfs device add /dev/sdb /home"],
+        cmds_btrfs_add_device(&hook),
+    );
+
+    let hook = HookBtrfsAddDevice::try_from(
+        "@btrfs-add-device /home /dev/sdb --balance",
+    )
+    .unwrap();
+
+    assert_eq!(
+        vec![
+            "btrfs device add /dev/sdb /home",
+            "btrfs balance start --full-balance /home",
+        ],
+        cmds_btrfs_add_device(&hook),
+    );
+}
+
+#[test]
+fn test_btrfs_devices() {
+    let output = r#"Label: 'root'  uuid: 1b8b2a4c-7c2e-4c43-9a4f-6e0c1d2e3f40
+	Total devices 2 FS bytes used 1.50GiB
+	devid    1 size 20.00GiB used 2.02GiB path /dev/sda2
+	devid    2 size 10.00GiB used 0.00B path /dev/mapper/cryptdata
+"#;
+
+    assert_eq!(
+        vec!["/dev/sda2", "/dev/mapper/cryptdata"],
+        btrfs_devices(output),
+    );
+}
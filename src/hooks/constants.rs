@@ -13,6 +13,15 @@ pub mod hook_keys {
     pub const KEY_REPLACE_TOKEN_PRINT: &str = "@replace-token-print";
     pub const KEY_DOWNLOAD: &str = "@download";
     pub const KEY_DOWNLOAD_PRINT: &str = "@download-print";
+    pub const KEY_WAIT_FOR_DEVICE: &str = "@wait-for-device";
+    pub const KEY_CRYPTENROLL_TPM: &str = "@cryptenroll-tpm";
+    pub const KEY_PACMAN_INSTALL: &str = "@pacman-install";
+    pub const KEY_BTRFS_ADD_DEVICE: &str = "@btrfs-add-device";
+    pub const KEY_CHMOD: &str = "@chmod";
+    pub const KEY_CHOWN: &str = "@chown";
+    pub const KEY_GETTY_AUTOLOGIN: &str = "@getty-autologin";
+    pub const KEY_NTP: &str = "@ntp";
+    pub const KEY_MOTD: &str = "@motd";
 }
 
 pub mod quicknet {
@@ -20,6 +29,8 @@ pub mod quicknet {
 
     pub const TOKEN_DNS: &str = "{{ dns_upstream }}";
 
+    pub const NETWORKD_DIR: &str = "/etc/systemd/network";
+
     pub const FILENAME_TPL: &str = "00-dhcp_{{ inf }}-quicknet.conf";
 
     pub const NETWORKD_DHCP: &str = r#"# Installed by ali-rs hook @quicknet
@@ -42,6 +53,51 @@ DNS={{ dns_upstream }}
     }
 }
 
+pub mod getty {
+    pub const TOKEN_USER: &str = "{{ user }}";
+
+    pub const SYSTEMD_SYSTEM_DIR: &str = "/etc/systemd/system";
+
+    pub const DEFAULT_TTY: &str = "tty1";
+
+    pub const OVERRIDE_CONF: &str = r#"# Installed by ali-rs hook @getty-autologin
+[Service]
+ExecStart=
+ExecStart=-/sbin/agetty -o '-p -f -- \\u' --noclear --autologin {{ user }} %I $TERM
+"#;
+
+    #[test]
+    fn test_tokens() {
+        assert!(OVERRIDE_CONF.contains(TOKEN_USER));
+    }
+}
+
+pub mod ntp {
+    pub const TOKEN_SERVERS: &str = "{{ servers }}";
+
+    pub const TIMESYNCD_CONF_DIR: &str = "/etc/systemd/timesyncd.conf.d";
+
+    pub const FILENAME: &str = "ali-rs-ntp.conf";
+
+    pub const TIMESYNCD_SERVICE: &str = "systemd-timesyncd.service";
+
+    pub const TIMESYNCD_CONF: &str = r#"# Installed by ali-rs hook @ntp
+[Time]
+NTP={{ servers }}
+"#;
+
+    #[test]
+    fn test_tokens() {
+        assert!(TIMESYNCD_CONF.contains(TOKEN_SERVERS));
+    }
+}
+
+pub mod motd {
+    pub const MOTD: &str = "/etc/motd";
+
+    pub const ISSUE: &str = "/etc/issue";
+}
+
 pub mod mkinitcpio {
     pub const MKINITCPIO_PRESET_LVM_ROOT: &str =
         "base udev autodetect modconf kms keyboard keymap consolefont block lvm2 filesystems fsck";
@@ -0,0 +1,271 @@
+use serde_json::json;
+
+use super::{
+    extract_key_and_parts,
+    wrap_bad_hook_cmd,
+    ActionHook,
+    Caller,
+    Hook,
+    ModeHook,
+    ParseError,
+    KEY_CRYPTENROLL_TPM,
+};
+use crate::errors::AliError;
+use crate::utils::shell;
+
+const USAGE: &str = "<LUKS_DEVICE> [--pcrs <PCRS>]";
+
+// Same as systemd-cryptenroll(1) default, i.e. Secure Boot state
+const DEFAULT_PCRS: &str = "7";
+
+// TPM2 devices have 24 PCRs
+const MAX_PCR: u8 = 23;
+
+#[derive(Debug, Clone, PartialEq)]
+struct HookCryptEnrollTpm {
+    device: String,
+    pcrs: String,
+}
+
+pub(super) fn parse(k: &str, cmd: &str) -> Result<Box<dyn Hook>, ParseError> {
+    match k {
+        KEY_CRYPTENROLL_TPM => {
+            match HookCryptEnrollTpm::try_from(cmd) {
+                Err(err) => Err(wrap_bad_hook_cmd(err, USAGE)),
+                Ok(hook) => Ok(Box::new(hook)),
+            }
+        }
+
+        key => panic!("unexpected key {key}"),
+    }
+}
+
+impl TryFrom<&str> for HookCryptEnrollTpm {
+    type Error = AliError;
+
+    fn try_from(cmd: &str) -> Result<Self, Self::Error> {
+        let (hook_key, parts) = extract_key_and_parts(cmd)?;
+        if hook_key != KEY_CRYPTENROLL_TPM {
+            panic!("unexpected key {hook_key}");
+        }
+
+        let mut device = None;
+        let mut pcrs = DEFAULT_PCRS.to_string();
+
+        let mut args = parts.iter().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--pcrs" {
+                let Some(value) = args.next() else {
+                    return Err(AliError::BadHookCmd(format!(
+                        "{hook_key}: missing value for --pcrs"
+                    )));
+                };
+
+                pcrs = value.clone();
+                continue;
+            }
+
+            if device.is_some() {
+                return Err(AliError::BadHookCmd(format!(
+                    "{hook_key}: unexpected argument {arg}"
+                )));
+            }
+
+            device = Some(arg.clone());
+        }
+
+        let Some(device) = device else {
+            return Err(AliError::BadHookCmd(format!(
+                "{hook_key}: missing LUKS device path"
+            )));
+        };
+
+        Ok(Self { device, pcrs })
+    }
+}
+
+impl Hook for HookCryptEnrollTpm {
+    fn base_key(&self) -> &'static str {
+        KEY_CRYPTENROLL_TPM
+    }
+
+    /// `@cryptenroll-tpm <LUKS_DEVICE> [--pcrs <PCRS>]`
+    ///
+    /// Examples:
+    ///
+    /// 1. Enroll TPM2 token bound to PCR 7 (default) on /dev/sda2
+    ///
+    /// ```txt
+    /// @cryptenroll-tpm /dev/sda2
+    /// ```
+    ///
+    /// 2. Enroll TPM2 token bound to PCRs 0 and 7
+    ///
+    /// ```txt
+    /// @cryptenroll-tpm /dev/sda2 --pcrs 0+7
+    /// ```
+    fn usage(&self) -> &'static str {
+        USAGE
+    }
+
+    fn mode(&self) -> ModeHook {
+        ModeHook::Normal
+    }
+
+    fn should_chroot(&self) -> bool {
+        true
+    }
+
+    fn prefer_caller(&self, caller: &Caller) -> bool {
+        matches!(caller, Caller::ManifestChroot | Caller::Cli)
+    }
+
+    fn abort_if_no_mount(&self) -> bool {
+        true
+    }
+
+    fn validate(&self) -> Result<(), AliError> {
+        if !self.device.starts_with("/dev/") {
+            return Err(AliError::BadHookCmd(format!(
+                "{}: LUKS device {} is not a device path",
+                self.hook_key(),
+                self.device,
+            )));
+        }
+
+        validate_pcrs(&self.pcrs).map_err(|err| {
+            AliError::BadHookCmd(format!("{}: {err}", self.hook_key()))
+        })
+    }
+
+    fn run_hook(
+        &self,
+        _caller: &Caller,
+        root_location: &str,
+    ) -> Result<ActionHook, AliError> {
+        shell::exec("cryptsetup", &["isLuks", &self.device]).map_err(|_| {
+            self.hook_error(&format!("{} is not a LUKS device", self.device))
+        })?;
+
+        shell::arch_chroot(root_location, &cmd_cryptenroll(self))?;
+
+        Ok(ActionHook::CryptEnrollTpm(
+            json!({
+                "device": self.device,
+                "pcrs": self.pcrs,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+fn cmd_cryptenroll(hook: &HookCryptEnrollTpm) -> String {
+    format!(
+        "systemd-cryptenroll --tpm2-device=auto --tpm2-pcrs={} {}",
+        hook.pcrs, hook.device,
+    )
+}
+
+/// Validates `+`-separated PCR indexes, e.g. `0+7`
+fn validate_pcrs(pcrs: &str) -> Result<(), String> {
+    for pcr in pcrs.split('+') {
+        match pcr.parse::<u8>() {
+            Ok(index) if index <= MAX_PCR => continue,
+            _ => return Err(format!("bad PCR {pcr:?} in {pcrs}")),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_cryptenroll_tpm() {
+    let should_pass = vec![
+        (
+            "@cryptenroll-tpm /dev/sda2",
+            HookCryptEnrollTpm {
+                device: "/dev/sda2".into(),
+                pcrs: DEFAULT_PCRS.into(),
+            },
+        ),
+        (
+            "@cryptenroll-tpm /dev/sda2 --pcrs 0+7",
+            HookCryptEnrollTpm {
+                device: "/dev/sda2".into(),
+                pcrs: "0+7".into(),
+            },
+        ),
+        (
+            "@cryptenroll-tpm --pcrs 23 /dev/nvme0n1p2",
+            HookCryptEnrollTpm {
+                device: "/dev/nvme0n1p2".into(),
+                pcrs: "23".into(),
+            },
+        ),
+    ];
+
+    let should_err = vec![
+        "@cryptenroll-tpm",
+        "@cryptenroll-tpm --pcrs 7",
+        "@cryptenroll-tpm /dev/sda2 --pcrs",
+        "@cryptenroll-tpm /dev/sda2 /dev/sdb2",
+    ];
+
+    for (cmd, expected) in should_pass {
+        let hook_result = HookCryptEnrollTpm::try_from(cmd);
+        if let Err(ref err) = hook_result {
+            eprintln!("unexpected error result from {cmd}: {err}");
+        }
+
+        assert_eq!(expected, hook_result.unwrap());
+    }
+
+    for cmd in should_err {
+        if let Ok(hook) = HookCryptEnrollTpm::try_from(cmd) {
+            panic!("unexpected ok result from bad arg {cmd}: {hook:?}");
+        }
+    }
+}
+
+#[test]
+fn test_validate_cryptenroll_tpm() {
+    let should_ok = vec![
+        "@cryptenroll-tpm /dev/sda2",
+        "@cryptenroll-tpm /dev/mapper/foo --pcrs 0+7+23",
+    ];
+
+    let should_err = vec![
+        // Not device paths
+        "@cryptenroll-tpm sda2",
+        "@cryptenroll-tpm ./sda2",
+        // Bad PCRs
+        "@cryptenroll-tpm /dev/sda2 --pcrs 24",
+        "@cryptenroll-tpm /dev/sda2 --pcrs 0+",
+        "@cryptenroll-tpm /dev/sda2 --pcrs 0,7",
+        "@cryptenroll-tpm /dev/sda2 --pcrs -1",
+    ];
+
+    for cmd in should_ok {
+        let hook = HookCryptEnrollTpm::try_from(cmd).unwrap();
+        if let Err(err) = hook.validate() {
+            panic!("unexpected validation error for {cmd}: {err}");
+        }
+    }
+
+    for cmd in should_err {
+        let hook = HookCryptEnrollTpm::try_from(cmd).unwrap();
+        assert!(hook.validate().is_err(), "bad hook {cmd} validated");
+    }
+}
+
+#[test]
+fn test_cmd_cryptenroll() {
+    let hook =
+        HookCryptEnrollTpm::try_from("@cryptenroll-tpm /dev/sda2 --pcrs 0+7")
+            .unwrap();
+
+    assert_eq!(
+        "systemd-cryptenroll --tpm2-device=auto --tpm2-pcrs=0+7 /dev/sda2",
+        cmd_cryptenroll(&hook),
+    );
+}
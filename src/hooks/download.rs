@@ -9,6 +9,7 @@ use super::{
     KEY_DOWNLOAD_PRINT,
 };
 use crate::errors::AliError;
+use crate::utils::fs::join_root;
 
 const USAGE: &str = "<url> <outfile>";
 
@@ -82,19 +83,19 @@ impl Hook for HookDownload {
         false
     }
 
-    // @TODO: Use param caller and root_location
     fn run_hook(
         &self,
         _caller: &super::Caller,
-        _root_location: &str,
+        root_location: &str,
     ) -> Result<super::ActionHook, AliError> {
         let downloader = download::Downloader::new_from_url(&self.url)?;
         let bytes = downloader.get_bytes()?;
 
-        if let Err(err) = std::fs::write(&self.outfile, bytes) {
+        let outfile = join_root(root_location, &self.outfile);
+        if let Err(err) = std::fs::write(&outfile, bytes) {
             return Err(AliError::FileError(
                 err,
-                format!("failed to write downloaded file to {}", self.outfile),
+                format!("failed to write downloaded file to {outfile}"),
             ));
         }
 
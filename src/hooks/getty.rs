@@ -0,0 +1,288 @@
+use serde_json::json;
+
+use super::constants::getty::{
+    DEFAULT_TTY,
+    OVERRIDE_CONF,
+    SYSTEMD_SYSTEM_DIR,
+    TOKEN_USER,
+};
+use super::{
+    extract_key_and_parts,
+    permissions,
+    wrap_bad_hook_cmd,
+    ActionHook,
+    Caller,
+    Hook,
+    ModeHook,
+    ParseError,
+    KEY_GETTY_AUTOLOGIN,
+};
+use crate::errors::AliError;
+use crate::utils::fs::join_root;
+use crate::utils::shell;
+
+const USAGE: &str = "<USER> [--tty <TTY>]";
+
+#[derive(Debug, Clone, PartialEq)]
+struct HookGettyAutologin {
+    user: String,
+    tty: String,
+}
+
+pub(super) fn parse(k: &str, cmd: &str) -> Result<Box<dyn Hook>, ParseError> {
+    match k {
+        KEY_GETTY_AUTOLOGIN => {
+            match HookGettyAutologin::try_from(cmd) {
+                Err(err) => Err(wrap_bad_hook_cmd(err, USAGE)),
+                Ok(hook) => Ok(Box::new(hook)),
+            }
+        }
+
+        key => panic!("unexpected key {key}"),
+    }
+}
+
+impl TryFrom<&str> for HookGettyAutologin {
+    type Error = AliError;
+
+    fn try_from(cmd: &str) -> Result<Self, Self::Error> {
+        let (hook_key, parts) = extract_key_and_parts(cmd)?;
+        if hook_key != KEY_GETTY_AUTOLOGIN {
+            panic!("unexpected key {hook_key}");
+        }
+
+        let mut user = None;
+        let mut tty = DEFAULT_TTY.to_string();
+
+        let mut args = parts.iter().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--tty" {
+                let Some(value) = args.next() else {
+                    return Err(AliError::BadHookCmd(format!(
+                        "{hook_key}: missing value for --tty"
+                    )));
+                };
+
+                tty = value.clone();
+                continue;
+            }
+
+            if user.is_some() {
+                return Err(AliError::BadHookCmd(format!(
+                    "{hook_key}: unexpected argument {arg}"
+                )));
+            }
+
+            user = Some(arg.clone());
+        }
+
+        let Some(user) = user else {
+            return Err(AliError::BadHookCmd(format!(
+                "{hook_key}: missing user"
+            )));
+        };
+
+        Ok(Self { user, tty })
+    }
+}
+
+impl Hook for HookGettyAutologin {
+    fn base_key(&self) -> &'static str {
+        KEY_GETTY_AUTOLOGIN
+    }
+
+    /// `@getty-autologin <USER> [--tty <TTY>]`
+    ///
+    /// Examples:
+    ///
+    /// 1. Autologin user kiosk on tty1 (default)
+    ///
+    /// ```txt
+    /// @getty-autologin kiosk
+    /// ```
+    ///
+    /// 2. Autologin root on tty2
+    ///
+    /// ```txt
+    /// @getty-autologin root --tty tty2
+    /// ```
+    fn usage(&self) -> &'static str {
+        USAGE
+    }
+
+    fn mode(&self) -> ModeHook {
+        ModeHook::Normal
+    }
+
+    fn should_chroot(&self) -> bool {
+        true
+    }
+
+    fn prefer_caller(&self, caller: &Caller) -> bool {
+        matches!(caller, Caller::ManifestChroot | Caller::Cli)
+    }
+
+    fn abort_if_no_mount(&self) -> bool {
+        true
+    }
+
+    fn validate(&self) -> Result<(), AliError> {
+        let numeric = self.user.chars().all(|c| c.is_ascii_digit());
+        if numeric || !permissions::is_valid_name(&self.user) {
+            return Err(AliError::BadHookCmd(format!(
+                "{}: bad user name {:?}",
+                self.hook_key(),
+                self.user,
+            )));
+        }
+
+        if !is_valid_tty(&self.tty) {
+            return Err(AliError::BadHookCmd(format!(
+                "{}: bad tty name {:?}",
+                self.hook_key(),
+                self.tty,
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn run_hook(
+        &self,
+        _caller: &Caller,
+        root_location: &str,
+    ) -> Result<ActionHook, AliError> {
+        let dropin_dir = join_root(
+            root_location,
+            &dropin_dir(SYSTEMD_SYSTEM_DIR, &self.tty),
+        );
+        let filename = join_root(&dropin_dir, "override.conf");
+
+        shell::exec("mkdir", &["-p", &dropin_dir])?;
+
+        std::fs::write(&filename, override_conf(&self.user)).map_err(
+            |err| {
+                AliError::FileError(
+                    err,
+                    format!("{}: writing file {filename}", self.hook_key()),
+                )
+            },
+        )?;
+
+        Ok(ActionHook::GettyAutologin(
+            json!({
+                "user": self.user,
+                "tty": self.tty,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+/// Returns drop-in directory for getty@`tty`.service under `systemd_dir`
+fn dropin_dir(systemd_dir: &str, tty: &str) -> String {
+    format!("{systemd_dir}/getty@{tty}.service.d")
+}
+
+fn override_conf(user: &str) -> String {
+    OVERRIDE_CONF.replace(TOKEN_USER, user)
+}
+
+/// Validates tty names like `tty1` or `ttyS0`
+fn is_valid_tty(tty: &str) -> bool {
+    let Some(rest) = tty.strip_prefix("tty") else {
+        return false;
+    };
+
+    let number = rest.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+
+    !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+}
+
+#[test]
+fn test_parse_getty_autologin() {
+    let should_pass = vec![
+        (
+            "@getty-autologin kiosk",
+            HookGettyAutologin {
+                user: "kiosk".into(),
+                tty: "tty1".into(),
+            },
+        ),
+        (
+            "@getty-autologin root --tty tty2",
+            HookGettyAutologin {
+                user: "root".into(),
+                tty: "tty2".into(),
+            },
+        ),
+        (
+            "@getty-autologin --tty ttyS0 _svc-user",
+            HookGettyAutologin {
+                user: "_svc-user".into(),
+                tty: "ttyS0".into(),
+            },
+        ),
+    ];
+
+    let should_err = vec![
+        "@getty-autologin",
+        "@getty-autologin kiosk --tty",
+        "@getty-autologin kiosk root",
+    ];
+
+    for (cmd, expected) in should_pass {
+        let hook_result = HookGettyAutologin::try_from(cmd);
+        if let Err(ref err) = hook_result {
+            eprintln!("unexpected error result from {cmd}: {err}");
+        }
+
+        assert_eq!(expected, hook_result.unwrap());
+    }
+
+    for cmd in should_err {
+        if let Ok(hook) = HookGettyAutologin::try_from(cmd) {
+            panic!("unexpected ok result from bad arg {cmd}: {hook:?}");
+        }
+    }
+}
+
+#[test]
+fn test_validate_getty_autologin() {
+    let should_err = vec![
+        // Bad user names, numeric UIDs are not allowed in agetty -a
+        "@getty-autologin Kiosk",
+        "@getty-autologin 1000",
+        // Bad ttys
+        "@getty-autologin kiosk --tty 1",
+        "@getty-autologin kiosk --tty tty",
+        "@getty-autologin kiosk --tty ttyS",
+        "@getty-autologin kiosk --tty tty1/../..",
+        "@getty-autologin kiosk --tty pts0",
+    ];
+
+    let hook =
+        HookGettyAutologin::try_from("@getty-autologin kiosk --tty ttyS0")
+            .unwrap();
+    assert!(hook.validate().is_ok());
+
+    for cmd in should_err {
+        let hook = HookGettyAutologin::try_from(cmd).unwrap();
+        assert!(hook.validate().is_err(), "bad hook {cmd} validated");
+    }
+}
+
+#[test]
+fn test_override_conf() {
+    let expected = r#"# Installed by ali-rs hook @getty-autologin
+[Service]
+ExecStart=
+ExecStart=-/sbin/agetty -o '-p -f -- \\u' --noclear --autologin kiosk %I $TERM
+"#;
+
+    assert_eq!(expected, override_conf("kiosk"));
+    assert_eq!(
+        "/etc/systemd/system/getty@tty2.service.d",
+        dropin_dir(SYSTEMD_SYSTEM_DIR, "tty2"),
+    );
+}
@@ -15,6 +15,7 @@ use super::{
     KEY_MKINITCPIO_PRINT,
 };
 use crate::errors::AliError;
+use crate::utils::fs::join_root;
 
 const USAGE: &str =
     "[boot_hook=<BOOT_HOOK_PRESET>] [hooks=<HOOKS>] [binaries=BINARIES]";
@@ -192,7 +193,7 @@ fn apply_mkinitcpio(
         return Ok(ActionHook::Mkinitcpio(s));
     }
 
-    let _mkinitcpio_conf = format!("/{root_location}/mkinitcpio.conf");
+    let _mkinitcpio_conf = join_root(root_location, "/etc/mkinitcpio.conf");
 
     Err(AliError::NotImplemented(
         format!("{hook_key}: write files",),
@@ -1,10 +1,18 @@
+mod btrfs_device;
 mod constants;
+mod cryptenroll;
 mod download;
+mod getty;
 mod mkinitcpio;
+mod motd;
+mod ntp;
+mod pacman_install;
+mod permissions;
 mod quicknet;
 mod replace_token;
 mod uncomment;
 mod utils;
+mod wait_device;
 mod wrappers;
 
 pub use self::constants::hook_keys::*;
@@ -28,6 +36,15 @@ pub enum ActionHook {
     Uncomment(String),
     Mkinitcpio(String),
     Download(String),
+    WaitForDevice(String),
+    CryptEnrollTpm(String),
+    PacmanInstall(String),
+    BtrfsAddDevice(String),
+    Chmod(String),
+    Chown(String),
+    GettyAutologin(String),
+    Ntp(String),
+    Motd(String),
 }
 
 /// Entrypoint for hooks.
@@ -101,6 +118,12 @@ trait Hook {
     /// (i.e. root_location or mountpoint == /)
     fn abort_if_no_mount(&self) -> bool;
 
+    /// (Default) Validates parsed hook arguments without side effects.
+    /// Hooks override this for checks beyond what the parser enforces.
+    fn validate(&self) -> Result<(), AliError> {
+        Ok(())
+    }
+
     /// Executes hook once parsed
     fn run_hook(
         &self,
@@ -109,13 +132,19 @@ trait Hook {
     ) -> Result<ActionHook, AliError>;
 }
 
+/// Parses, validates and runs hook `cmd`.
+///
+/// Hooks are always run by ali-rs outside of chroot, regardless of
+/// `caller`, so paths in the new system are resolved by hooks
+/// against `root_location` with [`join_root`](crate::utils::fs::join_root).
 pub fn apply_hook(
     cmd: &str,
     caller: Caller,
     root_location: &str,
 ) -> Result<ActionHook, AliError> {
-    let h = parse_validate_caller(cmd, &caller, root_location)?;
-    h.run_hook(&caller, root_location)
+    let root_location = resolve_root(root_location);
+    let h = parse_validate_caller(cmd, &caller, &root_location)?;
+    h.run_hook(&caller, &root_location)
 }
 
 /// Validates if hook_cmd is valid for its caller and mountpoint
@@ -124,11 +153,19 @@ pub fn validate_hook(
     caller: &Caller,
     root_location: &str,
 ) -> Result<(), AliError> {
-    _ = parse_validate_caller(cmd, caller, root_location)?;
+    _ = parse_validate_caller(cmd, caller, &resolve_root(root_location))?;
 
     Ok(())
 }
 
+/// Returns `root_location` without trailing slashes, or `/`
+fn resolve_root(root_location: &str) -> String {
+    match root_location.trim_end_matches('/') {
+        "" => "/".to_string(),
+        root => root.to_string(),
+    }
+}
+
 pub fn is_hook(cmd: &str) -> bool {
     cmd.starts_with('@')
 }
@@ -192,6 +229,22 @@ fn parse_hook(k: &str, cmd: &str) -> Result<Box<dyn Hook>, ParseError> {
 
         KEY_DOWNLOAD | KEY_DOWNLOAD_PRINT => download::parse(k, cmd),
 
+        KEY_WAIT_FOR_DEVICE => wait_device::parse(k, cmd),
+
+        KEY_CRYPTENROLL_TPM => cryptenroll::parse(k, cmd),
+
+        KEY_PACMAN_INSTALL => pacman_install::parse(k, cmd),
+
+        KEY_BTRFS_ADD_DEVICE => btrfs_device::parse(k, cmd),
+
+        KEY_CHMOD | KEY_CHOWN => permissions::parse(k, cmd),
+
+        KEY_GETTY_AUTOLOGIN => getty::parse(k, cmd),
+
+        KEY_NTP => ntp::parse(k, cmd),
+
+        KEY_MOTD => motd::parse(k, cmd),
+
         KEY_UNCOMMENT
         | KEY_UNCOMMENT_PRINT
         | KEY_UNCOMMENT_ALL
@@ -222,6 +275,8 @@ fn parse_validate_caller(
     }
 
     let hook = result.unwrap();
+    hook.validate()?;
+
     if hook.should_chroot() {
         handle_no_mountpoint(hook.as_ref(), caller, root_location)?;
     }
@@ -0,0 +1,314 @@
+use serde_json::json;
+
+use super::constants::motd::{
+    ISSUE,
+    MOTD,
+};
+use super::utils::download;
+use super::{
+    extract_key_and_parts_shlex,
+    wrap_bad_hook_cmd,
+    ActionHook,
+    Caller,
+    Hook,
+    ModeHook,
+    ParseError,
+    KEY_MOTD,
+};
+use crate::errors::AliError;
+use crate::utils::fs::join_root;
+
+const USAGE: &str = "[--issue] <TEXT> | [--issue] --file <PATH|URL>";
+
+#[derive(Debug, Clone, PartialEq)]
+struct HookMotd {
+    source: MotdSource,
+    issue: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum MotdSource {
+    /// Inline banner text
+    Text(String),
+
+    /// Path on the live system, or remote URL, to read banner from
+    File(String),
+}
+
+pub(super) fn parse(k: &str, cmd: &str) -> Result<Box<dyn Hook>, ParseError> {
+    match k {
+        KEY_MOTD => {
+            match HookMotd::try_from(cmd) {
+                Err(err) => Err(wrap_bad_hook_cmd(err, USAGE)),
+                Ok(hook) => Ok(Box::new(hook)),
+            }
+        }
+
+        key => panic!("unexpected key {key}"),
+    }
+}
+
+impl TryFrom<&str> for HookMotd {
+    type Error = AliError;
+
+    fn try_from(cmd: &str) -> Result<Self, Self::Error> {
+        let (hook_key, parts) = extract_key_and_parts_shlex(cmd)?;
+        if hook_key != KEY_MOTD {
+            panic!("unexpected key {hook_key}");
+        }
+
+        let mut issue = false;
+        let mut file = None;
+        let mut words = Vec::new();
+
+        let mut args = parts.iter().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--issue" => issue = true,
+                "--file" => {
+                    let Some(path) = args.next() else {
+                        return Err(AliError::BadHookCmd(format!(
+                            "{hook_key}: missing value for --file"
+                        )));
+                    };
+
+                    if file.replace(path.clone()).is_some() {
+                        return Err(AliError::BadHookCmd(format!(
+                            "{hook_key}: duplicate --file"
+                        )));
+                    }
+                }
+                word => words.push(word),
+            }
+        }
+
+        let source = match (file, words.is_empty()) {
+            (Some(file), true) => MotdSource::File(file),
+            (None, false) => MotdSource::Text(words.join(" ")),
+            (Some(_), false) => {
+                return Err(AliError::BadHookCmd(format!(
+                    "{hook_key}: got both text and --file"
+                )));
+            }
+            (None, true) => {
+                return Err(AliError::BadHookCmd(format!(
+                    "{hook_key}: expecting text or --file"
+                )));
+            }
+        };
+
+        Ok(Self { source, issue })
+    }
+}
+
+impl Hook for HookMotd {
+    fn base_key(&self) -> &'static str {
+        KEY_MOTD
+    }
+
+    /// `@motd [--issue] <TEXT>` or `@motd [--issue] --file <PATH|URL>`
+    ///
+    /// Examples:
+    ///
+    /// 1. Write inline text to /etc/motd
+    ///
+    /// ```txt
+    /// @motd "Welcome to foo, managed by ali-rs"
+    /// ```
+    ///
+    /// 2. Write banner downloaded from URL to /etc/motd and /etc/issue
+    ///
+    /// ```txt
+    /// @motd --issue --file https://example.com/banner.txt
+    /// ```
+    fn usage(&self) -> &'static str {
+        USAGE
+    }
+
+    fn mode(&self) -> ModeHook {
+        ModeHook::Normal
+    }
+
+    fn should_chroot(&self) -> bool {
+        false
+    }
+
+    fn prefer_caller(&self, _caller: &Caller) -> bool {
+        true
+    }
+
+    fn abort_if_no_mount(&self) -> bool {
+        true
+    }
+
+    fn validate(&self) -> Result<(), AliError> {
+        match &self.source {
+            MotdSource::Text(text) if text.trim().is_empty() => {
+                Err(self.hook_error("empty text"))
+            }
+            MotdSource::File(file) if file.trim().is_empty() => {
+                Err(self.hook_error("empty file path"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn run_hook(
+        &self,
+        _caller: &Caller,
+        root_location: &str,
+    ) -> Result<ActionHook, AliError> {
+        let banner = self.banner()?;
+
+        let mut targets = vec![MOTD];
+        if self.issue {
+            targets.push(ISSUE);
+        }
+
+        // Targets are fixed paths, joined under root_location
+        for target in &targets {
+            let filename = join_root(root_location, target);
+
+            std::fs::write(&filename, &banner).map_err(|err| {
+                AliError::FileError(
+                    err,
+                    format!("{}: writing file {filename}", self.hook_key()),
+                )
+            })?;
+        }
+
+        let source = match &self.source {
+            MotdSource::Text(_) => "text".to_string(),
+            MotdSource::File(file) => file.clone(),
+        };
+
+        Ok(ActionHook::Motd(
+            json!({
+                "source": source,
+                "files": targets,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+impl HookMotd {
+    /// Returns banner content, always ending with a newline
+    fn banner(&self) -> Result<String, AliError> {
+        let banner = match &self.source {
+            MotdSource::Text(text) => text.clone(),
+            MotdSource::File(file) => {
+                match download::Downloader::new_from_url(file) {
+                    Ok(downloader) => downloader.get_string()?,
+                    Err(_) => {
+                        std::fs::read_to_string(file).map_err(|err| {
+                            AliError::FileError(
+                                err,
+                                format!("{}: reading {file}", self.hook_key()),
+                            )
+                        })?
+                    }
+                }
+            }
+        };
+
+        match banner.ends_with('\n') {
+            true => Ok(banner),
+            false => Ok(format!("{banner}\n")),
+        }
+    }
+}
+
+#[test]
+fn test_parse_motd() {
+    let should_pass = vec![
+        (
+            "@motd Welcome",
+            HookMotd {
+                source: MotdSource::Text("Welcome".into()),
+                issue: false,
+            },
+        ),
+        (
+            "@motd 'Welcome to foo, managed by ali-rs'",
+            HookMotd {
+                source: MotdSource::Text(
+                    "Welcome to foo, managed by ali-rs".into(),
+                ),
+                issue: false,
+            },
+        ),
+        (
+            "@motd --issue Authorized users only",
+            HookMotd {
+                source: MotdSource::Text("Authorized users only".into()),
+                issue: true,
+            },
+        ),
+        (
+            "@motd --file https://example.com/banner.txt --issue",
+            HookMotd {
+                source: MotdSource::File(
+                    "https://example.com/banner.txt".into(),
+                ),
+                issue: true,
+            },
+        ),
+    ];
+
+    let should_err = vec![
+        "@motd",
+        "@motd --issue",
+        "@motd --file",
+        "@motd --file /root/a.txt --file /root/b.txt",
+        "@motd --file /root/banner.txt Welcome",
+        "@motd 'unterminated",
+    ];
+
+    for (cmd, expected) in should_pass {
+        let hook_result = HookMotd::try_from(cmd);
+        if let Err(ref err) = hook_result {
+            eprintln!("unexpected error result from {cmd}: {err}");
+        }
+
+        assert_eq!(expected, hook_result.unwrap());
+    }
+
+    for cmd in should_err {
+        if let Ok(hook) = HookMotd::try_from(cmd) {
+            panic!("unexpected ok result from bad arg {cmd}: {hook:?}");
+        }
+    }
+
+    // Blank banners are only rejected by validate
+    let hook = HookMotd::try_from("@motd ' '").unwrap();
+    assert!(hook.validate().is_err());
+}
+
+#[test]
+fn test_motd_banner() {
+    let dir = std::env::temp_dir().join("ali-rs-test-motd");
+    let banner_file = dir.join("banner.txt");
+
+    std::fs::create_dir_all(dir.join("etc")).expect("failed to mkdir");
+    std::fs::write(&banner_file, "Hello from file").unwrap();
+
+    // Banners always end with a newline
+    let hook = HookMotd::try_from("@motd Welcome").unwrap();
+    assert_eq!("Welcome\n", hook.banner().unwrap());
+
+    let cmd = format!("@motd --issue --file {}", banner_file.display());
+    let hook = HookMotd::try_from(cmd.as_str()).expect("bad cmd");
+
+    let root = dir.to_str().unwrap();
+    hook.run_hook(&Caller::Cli, root)
+        .expect("failed to run hook");
+
+    let motd = std::fs::read_to_string(dir.join("etc/motd")).unwrap();
+    let issue = std::fs::read_to_string(dir.join("etc/issue")).unwrap();
+
+    std::fs::remove_dir_all(&dir).expect("failed to clean up");
+
+    assert_eq!("Hello from file\n", motd);
+    assert_eq!(motd, issue);
+}
@@ -0,0 +1,320 @@
+use std::net::IpAddr;
+
+use serde_json::json;
+
+use super::constants::ntp::{
+    FILENAME,
+    TIMESYNCD_CONF,
+    TIMESYNCD_CONF_DIR,
+    TIMESYNCD_SERVICE,
+    TOKEN_SERVERS,
+};
+use super::{
+    extract_key_and_parts,
+    wrap_bad_hook_cmd,
+    ActionHook,
+    Caller,
+    Hook,
+    ModeHook,
+    ParseError,
+    KEY_NTP,
+};
+use crate::errors::AliError;
+use crate::utils::fs::join_root;
+use crate::utils::shell;
+
+const USAGE: &str = "[--server <HOST>...] [--enable]";
+
+#[derive(Debug, Clone, PartialEq)]
+struct HookNtp {
+    servers: Vec<String>,
+    enable: bool,
+}
+
+pub(super) fn parse(k: &str, cmd: &str) -> Result<Box<dyn Hook>, ParseError> {
+    match k {
+        KEY_NTP => {
+            match HookNtp::try_from(cmd) {
+                Err(err) => Err(wrap_bad_hook_cmd(err, USAGE)),
+                Ok(hook) => Ok(Box::new(hook)),
+            }
+        }
+
+        key => panic!("unexpected key {key}"),
+    }
+}
+
+impl TryFrom<&str> for HookNtp {
+    type Error = AliError;
+
+    fn try_from(cmd: &str) -> Result<Self, Self::Error> {
+        let (hook_key, parts) = extract_key_and_parts(cmd)?;
+        if hook_key != KEY_NTP {
+            panic!("unexpected key {hook_key}");
+        }
+
+        let mut servers = Vec::new();
+        let mut enable = false;
+
+        let mut args = parts.iter().skip(1).peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--enable" => enable = true,
+
+                // Servers are taken until the next flag
+                "--server" => {
+                    let count = servers.len();
+                    while let Some(server) =
+                        args.next_if(|arg| !arg.starts_with("--"))
+                    {
+                        servers.push(server.clone());
+                    }
+
+                    if servers.len() == count {
+                        return Err(AliError::BadHookCmd(format!(
+                            "{hook_key}: missing value for --server"
+                        )));
+                    }
+                }
+
+                arg => {
+                    return Err(AliError::BadHookCmd(format!(
+                        "{hook_key}: unexpected argument {arg}"
+                    )));
+                }
+            }
+        }
+
+        if servers.is_empty() && !enable {
+            return Err(AliError::BadHookCmd(format!(
+                "{hook_key}: expecting --server or --enable"
+            )));
+        }
+
+        Ok(Self { servers, enable })
+    }
+}
+
+impl Hook for HookNtp {
+    fn base_key(&self) -> &'static str {
+        KEY_NTP
+    }
+
+    /// `@ntp [--server <HOST>...] [--enable]`
+    ///
+    /// Examples:
+    ///
+    /// 1. Use 2 NTP servers, and enable systemd-timesyncd
+    ///
+    /// ```txt
+    /// @ntp --server 0.arch.pool.ntp.org 1.arch.pool.ntp.org --enable
+    /// ```
+    ///
+    /// 2. Enable systemd-timesyncd with its default servers
+    ///
+    /// ```txt
+    /// @ntp --enable
+    /// ```
+    fn usage(&self) -> &'static str {
+        USAGE
+    }
+
+    fn mode(&self) -> ModeHook {
+        ModeHook::Normal
+    }
+
+    // Writing config is done outside of chroot,
+    // but enabling the service is not
+    fn should_chroot(&self) -> bool {
+        self.enable
+    }
+
+    fn prefer_caller(&self, caller: &Caller) -> bool {
+        matches!(caller, Caller::ManifestChroot | Caller::Cli)
+    }
+
+    fn abort_if_no_mount(&self) -> bool {
+        true
+    }
+
+    fn validate(&self) -> Result<(), AliError> {
+        for server in &self.servers {
+            if !is_valid_server(server) {
+                return Err(AliError::BadHookCmd(format!(
+                    "{}: bad ntp server {server:?}",
+                    self.hook_key(),
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_hook(
+        &self,
+        _caller: &Caller,
+        root_location: &str,
+    ) -> Result<ActionHook, AliError> {
+        if !self.servers.is_empty() {
+            let conf_dir = join_root(root_location, TIMESYNCD_CONF_DIR);
+            let filename = join_root(&conf_dir, FILENAME);
+
+            shell::exec("mkdir", &["-p", &conf_dir])?;
+
+            std::fs::write(&filename, timesyncd_conf(&self.servers)).map_err(
+                |err| {
+                    AliError::FileError(
+                        err,
+                        format!("{}: writing file {filename}", self.hook_key()),
+                    )
+                },
+            )?;
+        }
+
+        if self.enable {
+            shell::arch_chroot(
+                root_location,
+                &format!("systemctl enable {TIMESYNCD_SERVICE}"),
+            )?;
+        }
+
+        Ok(ActionHook::Ntp(
+            json!({
+                "servers": self.servers,
+                "enable": self.enable,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+fn timesyncd_conf(servers: &[String]) -> String {
+    TIMESYNCD_CONF.replace(TOKEN_SERVERS, &servers.join(" "))
+}
+
+/// Validates NTP server, which is either an IP address,
+/// or a hostname made of dot-separated RFC 1123 labels
+fn is_valid_server(server: &str) -> bool {
+    if server.parse::<IpAddr>().is_ok() {
+        return true;
+    }
+
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+
+    let hostname = server.strip_suffix('.').unwrap_or(server);
+
+    hostname.len() <= 253 && hostname.split('.').all(valid_label)
+}
+
+#[test]
+fn test_parse_ntp() {
+    let should_pass = vec![
+        (
+            "@ntp --server pool.ntp.org",
+            HookNtp {
+                servers: vec!["pool.ntp.org".into()],
+                enable: false,
+            },
+        ),
+        (
+            "@ntp --server 0.arch.pool.ntp.org 1.arch.pool.ntp.org",
+            HookNtp {
+                servers: vec![
+                    "0.arch.pool.ntp.org".into(),
+                    "1.arch.pool.ntp.org".into(),
+                ],
+                enable: false,
+            },
+        ),
+        (
+            "@ntp --server time.example.com --enable --server 10.0.0.1",
+            HookNtp {
+                servers: vec!["time.example.com".into(), "10.0.0.1".into()],
+                enable: true,
+            },
+        ),
+        (
+            "@ntp --enable",
+            HookNtp {
+                servers: vec![],
+                enable: true,
+            },
+        ),
+    ];
+
+    let should_err = vec![
+        "@ntp",
+        "@ntp --server",
+        "@ntp --server --enable",
+        "@ntp pool.ntp.org",
+        "@ntp --server pool.ntp.org --disable",
+    ];
+
+    for (cmd, expected) in should_pass {
+        let hook_result = HookNtp::try_from(cmd);
+        if let Err(ref err) = hook_result {
+            eprintln!("unexpected error result from {cmd}: {err}");
+        }
+
+        let hook = hook_result.unwrap();
+
+        // Only enabling timesyncd needs the chroot
+        assert_eq!(expected.enable, hook.should_chroot());
+        assert_eq!(expected, hook);
+    }
+
+    for cmd in should_err {
+        if let Ok(hook) = HookNtp::try_from(cmd) {
+            panic!("unexpected ok result from bad arg {cmd}: {hook:?}");
+        }
+    }
+}
+
+#[test]
+fn test_is_valid_server() {
+    let should_ok = vec![
+        "pool.ntp.org",
+        "ntp1",
+        "10.0.0.1",
+        "fd00::123",
+        "time-1.example.com",
+    ];
+
+    let should_err = vec![
+        "",
+        "-pool.ntp.org",
+        "pool..ntp.org",
+        "pool_ntp.org",
+        "pool.ntp.org;reboot",
+        "../../etc",
+    ];
+
+    for server in should_ok {
+        assert!(is_valid_server(server), "good server {server} rejected");
+    }
+
+    for server in should_err {
+        assert!(!is_valid_server(server), "bad server {server} accepted");
+    }
+}
+
+#[test]
+fn test_timesyncd_conf() {
+    let expected = r#"# Installed by ali-rs hook @ntp
+[Time]
+NTP=0.arch.pool.ntp.org 1.arch.pool.ntp.org
+"#;
+
+    let servers = vec![
+        "0.arch.pool.ntp.org".to_string(),
+        "1.arch.pool.ntp.org".to_string(),
+    ];
+
+    assert_eq!(expected, timesyncd_conf(&servers));
+}
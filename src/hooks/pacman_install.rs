@@ -0,0 +1,208 @@
+use serde_json::json;
+
+use super::{
+    extract_key_and_parts,
+    wrap_bad_hook_cmd,
+    ActionHook,
+    Caller,
+    Hook,
+    ModeHook,
+    ParseError,
+    KEY_PACMAN_INSTALL,
+};
+use crate::errors::AliError;
+use crate::utils::shell;
+
+const USAGE: &str = "<PACKAGE>...";
+
+#[derive(Debug, Clone, PartialEq)]
+struct HookPacmanInstall {
+    packages: Vec<String>,
+}
+
+pub(super) fn parse(k: &str, cmd: &str) -> Result<Box<dyn Hook>, ParseError> {
+    match k {
+        KEY_PACMAN_INSTALL => {
+            match HookPacmanInstall::try_from(cmd) {
+                Err(err) => Err(wrap_bad_hook_cmd(err, USAGE)),
+                Ok(hook) => Ok(Box::new(hook)),
+            }
+        }
+
+        key => panic!("unexpected key {key}"),
+    }
+}
+
+impl TryFrom<&str> for HookPacmanInstall {
+    type Error = AliError;
+
+    fn try_from(cmd: &str) -> Result<Self, Self::Error> {
+        let (hook_key, parts) = extract_key_and_parts(cmd)?;
+        if hook_key != KEY_PACMAN_INSTALL {
+            panic!("unexpected key {hook_key}");
+        }
+
+        let packages: Vec<String> = parts.into_iter().skip(1).collect();
+        if packages.is_empty() {
+            return Err(AliError::BadHookCmd(format!(
+                "{hook_key}: missing package names"
+            )));
+        }
+
+        Ok(Self { packages })
+    }
+}
+
+impl Hook for HookPacmanInstall {
+    fn base_key(&self) -> &'static str {
+        KEY_PACMAN_INSTALL
+    }
+
+    /// `@pacman-install <PACKAGE>...`
+    ///
+    /// Examples:
+    ///
+    /// 1. Install neovim
+    ///
+    /// ```txt
+    /// @pacman-install neovim
+    /// ```
+    ///
+    /// 2. Install multiple packages in 1 transaction
+    ///
+    /// ```txt
+    /// @pacman-install networkmanager openssh sudo
+    /// ```
+    fn usage(&self) -> &'static str {
+        USAGE
+    }
+
+    fn mode(&self) -> ModeHook {
+        ModeHook::Normal
+    }
+
+    fn should_chroot(&self) -> bool {
+        true
+    }
+
+    fn prefer_caller(&self, caller: &Caller) -> bool {
+        matches!(caller, Caller::ManifestChroot | Caller::Cli)
+    }
+
+    fn abort_if_no_mount(&self) -> bool {
+        true
+    }
+
+    fn validate(&self) -> Result<(), AliError> {
+        for package in &self.packages {
+            if !is_package_name(package) {
+                return Err(AliError::BadHookCmd(format!(
+                    "{}: bad package name {package}",
+                    self.hook_key(),
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_hook(
+        &self,
+        _caller: &Caller,
+        root_location: &str,
+    ) -> Result<ActionHook, AliError> {
+        shell::arch_chroot(root_location, &cmd_pacman_install(&self.packages))?;
+
+        Ok(ActionHook::PacmanInstall(
+            json!({
+                "packages": self.packages,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+fn cmd_pacman_install(packages: &[String]) -> String {
+    format!("pacman -S --noconfirm {}", packages.join(" "))
+}
+
+/// Package names may only contain lowercase alphanumerics and `@._+-`,
+/// and may not start with `-` or `.` (see PKGBUILD(5) `pkgname`).
+/// Repository-qualified names, e.g. `extra/neovim`, are also allowed.
+fn is_package_name(name: &str) -> bool {
+    let name = match name.split_once('/') {
+        Some((repo, pkg)) if is_package_name(repo) => pkg,
+        Some(_) => return false,
+        None => name,
+    };
+
+    let valid_char = |c: char| {
+        c.is_ascii_lowercase()
+            || c.is_ascii_digit()
+            || matches!(c, '@' | '.' | '_' | '+' | '-')
+    };
+
+    !name.is_empty()
+        && !name.starts_with(['-', '.'])
+        && name.chars().all(valid_char)
+}
+
+#[test]
+fn test_parse_pacman_install() {
+    let should_pass = vec![
+        ("@pacman-install neovim", vec!["neovim"]),
+        (
+            "@pacman-install networkmanager openssh sudo",
+            vec!["networkmanager", "openssh", "sudo"],
+        ),
+    ];
+
+    for (cmd, expected) in should_pass {
+        let hook_result = HookPacmanInstall::try_from(cmd);
+        if let Err(ref err) = hook_result {
+            eprintln!("unexpected error result from {cmd}: {err}");
+        }
+
+        assert_eq!(expected, hook_result.unwrap().packages);
+    }
+
+    assert!(HookPacmanInstall::try_from("@pacman-install").is_err());
+}
+
+#[test]
+fn test_is_package_name() {
+    let should_ok = vec!["gtk3", "libc++", "python-pip", "extra/neovim"];
+
+    let should_err = vec![
+        "Neovim",
+        "-Syu",
+        ".hidden",
+        "neovim;rm",
+        "foo$(id)",
+        "extra/",
+        "a/b/c",
+    ];
+
+    for name in should_ok {
+        assert!(is_package_name(name), "good package name {name} rejected");
+    }
+
+    for name in should_err {
+        assert!(!is_package_name(name), "bad package name {name} accepted");
+    }
+
+    // validate checks every package
+    let hook =
+        HookPacmanInstall::try_from("@pacman-install sudo -Syu").unwrap();
+    assert!(hook.validate().is_err());
+}
+
+#[test]
+fn test_cmd_pacman_install() {
+    let packages = vec!["openssh".to_string(), "sudo".to_string()];
+
+    assert_eq!(
+        "pacman -S --noconfirm openssh sudo",
+        cmd_pacman_install(&packages),
+    );
+}
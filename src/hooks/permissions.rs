@@ -0,0 +1,617 @@
+use std::path::{
+    Component,
+    Path,
+    PathBuf,
+};
+
+use serde_json::json;
+
+use super::{
+    extract_key_and_parts,
+    wrap_bad_hook_cmd,
+    ActionHook,
+    Caller,
+    Hook,
+    ModeHook,
+    ParseError,
+    KEY_CHMOD,
+    KEY_CHOWN,
+};
+use crate::errors::AliError;
+use crate::utils::fs::join_root;
+use crate::utils::shell;
+
+const USAGE_CHMOD: &str = "<MODE> <PATH> [--recursive]";
+const USAGE_CHOWN: &str = "<OWNER>[:<GROUP>] <PATH> [--recursive]";
+
+const PASSWD: &str = "/etc/passwd";
+const GROUP: &str = "/etc/group";
+
+#[derive(Debug, Clone, PartialEq)]
+struct HookChmod {
+    mode: String,
+    path: String,
+    recursive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct HookChown {
+    owner: String,
+    group: Option<String>,
+    path: String,
+    recursive: bool,
+}
+
+pub(super) fn parse(k: &str, cmd: &str) -> Result<Box<dyn Hook>, ParseError> {
+    match k {
+        KEY_CHMOD => {
+            match HookChmod::try_from(cmd) {
+                Err(err) => Err(wrap_bad_hook_cmd(err, USAGE_CHMOD)),
+                Ok(hook) => Ok(Box::new(hook)),
+            }
+        }
+
+        KEY_CHOWN => {
+            match HookChown::try_from(cmd) {
+                Err(err) => Err(wrap_bad_hook_cmd(err, USAGE_CHOWN)),
+                Ok(hook) => Ok(Box::new(hook)),
+            }
+        }
+
+        key => panic!("unexpected key {key}"),
+    }
+}
+
+/// Parses `<ARG> <PATH> [--recursive]` shared by both hooks
+fn parse_args(
+    expected_key: &str,
+    cmd: &str,
+) -> Result<(String, String, bool), AliError> {
+    let (hook_key, parts) = extract_key_and_parts(cmd)?;
+    if hook_key != expected_key {
+        panic!("unexpected key {hook_key}");
+    }
+
+    let mut recursive = false;
+    let mut positionals = Vec::new();
+
+    for arg in parts.into_iter().skip(1) {
+        if arg == "--recursive" || arg == "-R" {
+            recursive = true;
+            continue;
+        }
+
+        positionals.push(arg);
+    }
+
+    let [arg, path]: [String; 2] =
+        positionals.try_into().map_err(|args: Vec<String>| {
+            AliError::BadHookCmd(format!(
+                "{hook_key}: expecting 2 arguments, got {}",
+                args.len()
+            ))
+        })?;
+
+    Ok((arg, path, recursive))
+}
+
+impl TryFrom<&str> for HookChmod {
+    type Error = AliError;
+
+    fn try_from(cmd: &str) -> Result<Self, Self::Error> {
+        let (mode, path, recursive) = parse_args(KEY_CHMOD, cmd)?;
+
+        Ok(Self {
+            mode,
+            path,
+            recursive,
+        })
+    }
+}
+
+impl TryFrom<&str> for HookChown {
+    type Error = AliError;
+
+    fn try_from(cmd: &str) -> Result<Self, Self::Error> {
+        let (owner, path, recursive) = parse_args(KEY_CHOWN, cmd)?;
+
+        let (owner, group) = match owner.split_once(':') {
+            Some((owner, group)) => {
+                (owner.to_string(), Some(group.to_string()))
+            }
+            None => (owner, None),
+        };
+
+        Ok(Self {
+            owner,
+            group,
+            path,
+            recursive,
+        })
+    }
+}
+
+impl Hook for HookChmod {
+    fn base_key(&self) -> &'static str {
+        KEY_CHMOD
+    }
+
+    /// `@chmod <MODE> <PATH> [--recursive]`
+    ///
+    /// Examples:
+    ///
+    /// 1. Make /srv group-writable
+    ///
+    /// ```txt
+    /// @chmod g+w /srv
+    /// ```
+    ///
+    /// 2. Recursively set mode 0750 on /home/foo
+    ///
+    /// ```txt
+    /// @chmod 0750 /home/foo --recursive
+    /// ```
+    fn usage(&self) -> &'static str {
+        USAGE_CHMOD
+    }
+
+    fn mode(&self) -> ModeHook {
+        ModeHook::Normal
+    }
+
+    fn should_chroot(&self) -> bool {
+        true
+    }
+
+    fn prefer_caller(&self, caller: &Caller) -> bool {
+        matches!(caller, Caller::ManifestPostInstall | Caller::Cli)
+    }
+
+    fn abort_if_no_mount(&self) -> bool {
+        false
+    }
+
+    fn validate(&self) -> Result<(), AliError> {
+        if !is_valid_mode(&self.mode) {
+            return Err(AliError::BadHookCmd(format!(
+                "{}: bad mode {}",
+                self.hook_key(),
+                self.mode,
+            )));
+        }
+
+        validate_path(&self.path).map_err(|err| {
+            AliError::BadHookCmd(format!("{}: {err}", self.hook_key()))
+        })
+    }
+
+    fn run_hook(
+        &self,
+        _caller: &Caller,
+        root_location: &str,
+    ) -> Result<ActionHook, AliError> {
+        let target = target_path(root_location, &self.path)
+            .map_err(|err| self.hook_error(&err))?;
+
+        let target = target.to_string_lossy();
+        let args = cmd_args(&self.mode, &target, self.recursive);
+        shell::exec(
+            "chmod",
+            &args.iter().map(|s| s.as_str()).collect::<Vec<_>>(),
+        )?;
+
+        Ok(ActionHook::Chmod(
+            json!({
+                "mode": self.mode,
+                "path": self.path,
+                "recursive": self.recursive,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+impl Hook for HookChown {
+    fn base_key(&self) -> &'static str {
+        KEY_CHOWN
+    }
+
+    /// `@chown <OWNER>[:<GROUP>] <PATH> [--recursive]`
+    ///
+    /// Owner and group names are looked up in the new system's
+    /// /etc/passwd and /etc/group, not the live system's.
+    ///
+    /// Examples:
+    ///
+    /// 1. Change owner of /srv/www to user http and group http
+    ///
+    /// ```txt
+    /// @chown http:http /srv/www --recursive
+    /// ```
+    ///
+    /// 2. Change owner of /opt/foo to UID 1000
+    ///
+    /// ```txt
+    /// @chown 1000 /opt/foo
+    /// ```
+    fn usage(&self) -> &'static str {
+        USAGE_CHOWN
+    }
+
+    fn mode(&self) -> ModeHook {
+        ModeHook::Normal
+    }
+
+    fn should_chroot(&self) -> bool {
+        true
+    }
+
+    fn prefer_caller(&self, caller: &Caller) -> bool {
+        matches!(caller, Caller::ManifestPostInstall | Caller::Cli)
+    }
+
+    fn abort_if_no_mount(&self) -> bool {
+        false
+    }
+
+    fn validate(&self) -> Result<(), AliError> {
+        let names = std::iter::once(&self.owner).chain(self.group.as_ref());
+
+        for name in names {
+            if !is_valid_name(name) {
+                return Err(AliError::BadHookCmd(format!(
+                    "{}: bad user or group name {name:?}",
+                    self.hook_key(),
+                )));
+            }
+        }
+
+        validate_path(&self.path).map_err(|err| {
+            AliError::BadHookCmd(format!("{}: {err}", self.hook_key()))
+        })
+    }
+
+    fn run_hook(
+        &self,
+        _caller: &Caller,
+        root_location: &str,
+    ) -> Result<ActionHook, AliError> {
+        let target = target_path(root_location, &self.path)
+            .map_err(|err| self.hook_error(&err))?;
+
+        let uid = self.resolve(root_location, PASSWD, &self.owner)?;
+        let owner = match &self.group {
+            Some(group) => {
+                let gid = self.resolve(root_location, GROUP, group)?;
+                format!("{uid}:{gid}")
+            }
+            None => uid.to_string(),
+        };
+
+        let target = target.to_string_lossy();
+        let args = cmd_args(&owner, &target, self.recursive);
+        shell::exec(
+            "chown",
+            &args.iter().map(|s| s.as_str()).collect::<Vec<_>>(),
+        )?;
+
+        Ok(ActionHook::Chown(
+            json!({
+                "owner": self.owner,
+                "group": self.group,
+                "uid_gid": owner,
+                "path": self.path,
+                "recursive": self.recursive,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+impl HookChown {
+    /// Resolves `name` to numeric ID with `db` (passwd or group)
+    /// inside `root_location`. Numeric names are used as-is.
+    fn resolve(
+        &self,
+        root_location: &str,
+        db: &str,
+        name: &str,
+    ) -> Result<u32, AliError> {
+        if let Ok(id) = name.parse::<u32>() {
+            return Ok(id);
+        }
+
+        let db_path = join_root(root_location, db);
+        let content = std::fs::read_to_string(&db_path).map_err(|err| {
+            AliError::FileError(
+                err,
+                format!("{}: read {db_path}", self.hook_key()),
+            )
+        })?;
+
+        lookup_id(&content, name).ok_or_else(|| {
+            self.hook_error(&format!("no such name {name} in {db_path}"))
+        })
+    }
+}
+
+fn cmd_args(arg: &str, target: &str, recursive: bool) -> Vec<String> {
+    let mut args = Vec::new();
+    if recursive {
+        args.push("-R".to_string());
+    }
+
+    args.push(arg.to_string());
+    args.push(target.to_string());
+
+    args
+}
+
+/// Returns ID (3rd field) of entry `name` in passwd(5) or group(5) content
+fn lookup_id(content: &str, name: &str) -> Option<u32> {
+    content
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.first() == Some(&name))
+        .and_then(|fields| fields.get(2)?.parse().ok())
+}
+
+/// Validates octal (e.g. `0755`) or symbolic (e.g. `u+x,go-w`) modes
+fn is_valid_mode(mode: &str) -> bool {
+    if mode.chars().all(|c| c.is_ascii_digit()) {
+        return (3..=4).contains(&mode.len()) && mode.chars().all(|c| c < '8');
+    }
+
+    mode.split(',').all(|clause| {
+        let perms = clause.trim_start_matches(['u', 'g', 'o', 'a']);
+        if perms.is_empty() {
+            return false;
+        }
+
+        // Each operator is followed by permissions or a single who
+        perms.starts_with(['+', '-', '='])
+            && perms.split(['+', '-', '=']).skip(1).all(|p| {
+                p.chars().all(|c| "rwxXst".contains(c))
+                    || matches!(p, "u" | "g" | "o")
+            })
+    })
+}
+
+/// Validates user or group names, or numeric IDs
+pub(super) fn is_valid_name(name: &str) -> bool {
+    let Some(first) = name.chars().next() else {
+        return false;
+    };
+
+    if name.chars().all(|c| c.is_ascii_digit()) {
+        return name.parse::<u32>().is_ok();
+    }
+
+    (first.is_ascii_lowercase() || first == '_')
+        && name.trim_end_matches('$').chars().all(|c| {
+            c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-'
+        })
+}
+
+/// Validates that `path` is absolute and cannot escape root with `..`
+fn validate_path(path: &str) -> Result<(), String> {
+    let path = Path::new(path);
+    if !path.is_absolute() {
+        return Err(format!("path {} is not absolute", path.display()));
+    }
+
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Err(format!("path {} contains ..", path.display()));
+    }
+
+    Ok(())
+}
+
+/// Returns canonicalized `path` under `root_location`.
+///
+/// Errors if the path does not exist, or if it resolves
+/// outside of `root_location`, e.g. via absolute symlinks.
+fn target_path(root_location: &str, path: &str) -> Result<PathBuf, String> {
+    let root = Path::new(root_location)
+        .canonicalize()
+        .map_err(|err| format!("bad root {root_location}: {err}"))?;
+
+    let target = join_root(root_location, path);
+    let resolved = Path::new(&target)
+        .canonicalize()
+        .map_err(|err| format!("bad path {target}: {err}"))?;
+
+    if !resolved.starts_with(&root) {
+        return Err(format!(
+            "path {path} resolves to {} outside of {root_location}",
+            resolved.display(),
+        ));
+    }
+
+    Ok(resolved)
+}
+
+#[test]
+fn test_parse_chmod() {
+    let should_pass = vec![
+        (
+            "@chmod g+w /srv",
+            HookChmod {
+                mode: "g+w".into(),
+                path: "/srv".into(),
+                recursive: false,
+            },
+        ),
+        (
+            "@chmod 0750 /home/foo --recursive",
+            HookChmod {
+                mode: "0750".into(),
+                path: "/home/foo".into(),
+                recursive: true,
+            },
+        ),
+        (
+            "@chmod -R u=rwx,go=rx /opt",
+            HookChmod {
+                mode: "u=rwx,go=rx".into(),
+                path: "/opt".into(),
+                recursive: true,
+            },
+        ),
+    ];
+
+    let should_err = vec!["@chmod", "@chmod 755", "@chmod 755 /srv /opt"];
+
+    for (cmd, expected) in should_pass {
+        let hook_result = HookChmod::try_from(cmd);
+        if let Err(ref err) = hook_result {
+            eprintln!("unexpected error result from {cmd}: {err}");
+        }
+
+        assert_eq!(expected, hook_result.unwrap());
+    }
+
+    for cmd in should_err {
+        if let Ok(hook) = HookChmod::try_from(cmd) {
+            panic!("unexpected ok result from bad arg {cmd}: {hook:?}");
+        }
+    }
+}
+
+#[test]
+fn test_parse_chown() {
+    let should_pass = vec![
+        (
+            "@chown http:http /srv/www --recursive",
+            HookChown {
+                owner: "http".into(),
+                group: Some("http".into()),
+                path: "/srv/www".into(),
+                recursive: true,
+            },
+        ),
+        (
+            "@chown 1000 /opt/foo",
+            HookChown {
+                owner: "1000".into(),
+                group: None,
+                path: "/opt/foo".into(),
+                recursive: false,
+            },
+        ),
+        (
+            "@chown -R _foo-bar:1000 /srv",
+            HookChown {
+                owner: "_foo-bar".into(),
+                group: Some("1000".into()),
+                path: "/srv".into(),
+                recursive: true,
+            },
+        ),
+    ];
+
+    let should_err = vec!["@chown", "@chown foo", "@chown foo /srv /opt"];
+
+    for (cmd, expected) in should_pass {
+        let hook_result = HookChown::try_from(cmd);
+        if let Err(ref err) = hook_result {
+            eprintln!("unexpected error result from {cmd}: {err}");
+        }
+
+        assert_eq!(expected, hook_result.unwrap());
+    }
+
+    for cmd in should_err {
+        if let Ok(hook) = HookChown::try_from(cmd) {
+            panic!("unexpected ok result from bad arg {cmd}: {hook:?}");
+        }
+    }
+
+    // Empty owner or group, and extra separators are kept for validate
+    let should_err = vec![
+        "@chown :foo /srv",
+        "@chown foo: /srv",
+        "@chown foo:bar:baz /srv",
+        "@chown foo /srv/../../etc",
+    ];
+
+    for cmd in should_err {
+        let hook = HookChown::try_from(cmd).unwrap();
+        assert!(hook.validate().is_err(), "bad hook {cmd} validated");
+    }
+}
+
+#[test]
+fn test_is_valid_mode() {
+    let should_ok = vec!["755", "0750", "g+w", "u=rwx,go=rx", "a+X", "u+s,g=u"];
+    let should_err = vec!["789", "75", "07555", "g+q", "g", "u+w,", "rwx"];
+
+    for mode in should_ok {
+        assert!(is_valid_mode(mode), "good mode {mode} rejected");
+    }
+
+    for mode in should_err {
+        assert!(!is_valid_mode(mode), "bad mode {mode} accepted");
+    }
+}
+
+#[test]
+fn test_is_valid_name() {
+    let should_ok = vec!["http", "_foo-bar", "foo1", "machine$", "0", "1000"];
+    let should_err = vec!["", "Foo", "1foo", "foo bar", "99999999999"];
+
+    for name in should_ok {
+        assert!(is_valid_name(name), "good name {name} rejected");
+    }
+
+    for name in should_err {
+        assert!(!is_valid_name(name), "bad name {name} accepted");
+    }
+}
+
+#[test]
+fn test_validate_path() {
+    assert!(validate_path("/srv/www").is_ok());
+    assert!(validate_path("srv").is_err());
+    assert!(validate_path("/srv/../etc").is_err());
+    assert!(validate_path("/../etc").is_err());
+}
+
+#[test]
+fn test_cmd_args() {
+    assert_eq!(vec!["g+w", "/mnt/srv"], cmd_args("g+w", "/mnt/srv", false));
+    assert_eq!(
+        vec!["-R", "33:33", "/mnt/srv"],
+        cmd_args("33:33", "/mnt/srv", true),
+    );
+}
+
+#[test]
+fn test_lookup_id() {
+    let passwd = r#"root:x:0:0::/root:/usr/bin/bash
+http:x:33:33::/srv/http:/usr/bin/nologin
+foo:x:1000:1000::/home/foo:/usr/bin/bash
+"#;
+
+    assert_eq!(Some(0), lookup_id(passwd, "root"));
+    assert_eq!(Some(33), lookup_id(passwd, "http"));
+    assert_eq!(Some(1000), lookup_id(passwd, "foo"));
+    assert_eq!(None, lookup_id(passwd, "fo"));
+    assert_eq!(None, lookup_id(passwd, "bar"));
+}
+
+#[test]
+fn test_target_path() {
+    let root = std::env::temp_dir().join("ali-rs-test-permissions");
+    _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("srv")).unwrap();
+    std::os::unix::fs::symlink("/etc", root.join("escape")).unwrap();
+
+    let root_location = root.to_str().unwrap();
+
+    assert!(target_path(root_location, "/srv").is_ok());
+    assert!(target_path(root_location, "/no-such-path").is_err());
+    assert!(target_path(root_location, "/escape").is_err());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
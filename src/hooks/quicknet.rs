@@ -13,6 +13,7 @@ use super::{
     KEY_QUICKNET_PRINT,
 };
 use crate::errors::AliError;
+use crate::utils::fs::join_root;
 use crate::utils::shell;
 
 const USAGE: &str = "interface [dns <DNS_STREAM>]";
@@ -177,8 +178,9 @@ fn apply_quicknet(
     root_location: &str,
 ) -> Result<ActionHook, AliError> {
     // Formats filename and string output
+    let networkd_dir = join_root(root_location, NETWORKD_DIR);
     let filename = FILENAME_TPL.replace(TOKEN_INTERFACE, &qn.interface);
-    let filename = format!("{root_location}/{filename}");
+    let filename = join_root(&networkd_dir, &filename);
     let conf_str = qn.encode_to_string();
 
     match mode_hook {
@@ -186,9 +188,7 @@ fn apply_quicknet(
             println!("{conf_str}");
         }
         ModeHook::Normal => {
-            // Extends to include systemd path
-            let root_location = format!("{root_location}/etc/systemd/network");
-            shell::exec("mkdir", &["-p", &root_location])?;
+            shell::exec("mkdir", &["-p", &networkd_dir])?;
 
             std::fs::write(&filename, conf_str).map_err(|err| {
                 AliError::FileError(
@@ -332,3 +332,26 @@ DNS=8.8.8.8
         assert_eq!(expected, s);
     }
 }
+
+#[test]
+fn test_quicknet_target_path() {
+    let root = std::env::temp_dir().join("ali-rs-test-quicknet-root");
+    let expected = root.join("etc/systemd/network/00-dhcp_eth0-quicknet.conf");
+    std::fs::create_dir_all(&root).unwrap();
+
+    let root_location = root.to_str().unwrap();
+    let callers = [
+        (Caller::ManifestChroot, root_location.to_string()),
+        (Caller::Cli, format!("{root_location}/")),
+    ];
+
+    for (caller, root_location) in callers {
+        super::apply_hook("@quicknet eth0", caller, &root_location)
+            .expect("failed to apply hook");
+
+        assert!(expected.exists(), "{expected:?} does not exist");
+        std::fs::remove_file(&expected).unwrap();
+    }
+
+    std::fs::remove_dir_all(root).unwrap();
+}
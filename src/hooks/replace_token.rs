@@ -13,6 +13,7 @@ use super::{
     KEY_REPLACE_TOKEN_PRINT,
 };
 use crate::errors::AliError;
+use crate::utils::fs::join_root;
 
 const USAGE: &str = "<TOKEN> <VALUE> <TEMPLATE> [OUTPUT]";
 
@@ -62,6 +63,18 @@ impl Hook for HookReplaceToken {
         false
     }
 
+    fn validate(&self) -> Result<(), AliError> {
+        if self.rp.token.trim().is_empty() {
+            return Err(self.hook_error("empty token"));
+        }
+
+        if self.template.trim().is_empty() || self.output.trim().is_empty() {
+            return Err(self.hook_error("empty template or output path"));
+        }
+
+        Ok(())
+    }
+
     fn run_hook(
         &self,
         _caller: &Caller,
@@ -172,10 +185,7 @@ fn apply_replace_token(
         }
 
         ModeHook::Normal => {
-            let output_location = match root_location {
-                "/" => output.to_string(),
-                _ => format!("/{root_location}/{output}"),
-            };
+            let output_location = join_root(root_location, output);
 
             std::fs::write(output_location, replaced).map_err(|err| {
                 AliError::HookError(format!(
@@ -14,6 +14,7 @@ use super::{
     KEY_UNCOMMENT_PRINT,
 };
 use crate::errors::AliError;
+use crate::utils::fs::join_root;
 
 const USAGE: &str = "<PATTERN> [marker <COMMENT_MARKER=\"#\">] FILE";
 
@@ -176,19 +177,11 @@ fn apply_uncomment(
     mode_hook: &ModeHook,
     mode: &Mode,
     uc: &Uncomment,
-    caller: &Caller,
+    _caller: &Caller,
     root_location: &str,
 ) -> Result<ActionHook, AliError> {
     // Outfile, and maybe infile too if uc.source is not remote URL
-    let target_file = match caller {
-        Caller::ManifestPostInstall => {
-            format!("{root_location}/{}", uc.source)
-        }
-        Caller::Cli => {
-            format!("{root_location}/{}", uc.source)
-        }
-        _ => uc.source.clone(),
-    };
+    let target_file = join_root(root_location, &uc.source);
 
     // Get original from remote location if source is remote URL
     let original = if let Ok(downloader) =
@@ -398,3 +391,32 @@ PubkeyAuthentication no"#;
         assert_eq!(expected, uncommented_all);
     }
 }
+
+#[test]
+fn test_uncomment_target_path() {
+    let root = std::env::temp_dir().join("ali-rs-test-uncomment-root");
+    let target = root.join("etc/foo.conf");
+    std::fs::create_dir_all(root.join("etc")).unwrap();
+
+    let root_location = root.to_str().unwrap();
+    let callers = [
+        (Caller::ManifestChroot, root_location.to_string()),
+        (Caller::ManifestPostInstall, root_location.to_string()),
+        (Caller::Cli, format!("{root_location}/")),
+    ];
+
+    for (caller, root_location) in callers {
+        std::fs::write(&target, "#Foo yes\n").unwrap();
+
+        super::apply_hook(
+            "@uncomment Foo /etc/foo.conf",
+            caller,
+            &root_location,
+        )
+        .expect("failed to apply hook");
+
+        assert_eq!("Foo yes\n", std::fs::read_to_string(&target).unwrap());
+    }
+
+    std::fs::remove_dir_all(root).unwrap();
+}
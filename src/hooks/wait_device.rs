@@ -0,0 +1,257 @@
+use std::path::Path;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use serde_json::json;
+
+use super::{
+    extract_key_and_parts,
+    wrap_bad_hook_cmd,
+    ActionHook,
+    Caller,
+    Hook,
+    ModeHook,
+    ParseError,
+    KEY_WAIT_FOR_DEVICE,
+};
+use crate::errors::AliError;
+
+const USAGE: &str = "<DEVICE> [--timeout <SECONDS>]";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq)]
+struct HookWaitForDevice {
+    device: String,
+    timeout_secs: u64,
+}
+
+pub(super) fn parse(k: &str, cmd: &str) -> Result<Box<dyn Hook>, ParseError> {
+    match k {
+        KEY_WAIT_FOR_DEVICE => {
+            match HookWaitForDevice::try_from(cmd) {
+                Err(err) => Err(wrap_bad_hook_cmd(err, USAGE)),
+                Ok(hook) => Ok(Box::new(hook)),
+            }
+        }
+
+        key => panic!("unexpected key {key}"),
+    }
+}
+
+impl TryFrom<&str> for HookWaitForDevice {
+    type Error = AliError;
+
+    fn try_from(cmd: &str) -> Result<Self, Self::Error> {
+        let (hook_key, parts) = extract_key_and_parts(cmd)?;
+        if hook_key != KEY_WAIT_FOR_DEVICE {
+            panic!("unexpected key {hook_key}");
+        }
+
+        let mut device = None;
+        let mut timeout_secs = DEFAULT_TIMEOUT_SECS;
+
+        let mut args = parts.iter().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--timeout" {
+                let Some(secs) = args.next() else {
+                    return Err(AliError::BadHookCmd(format!(
+                        "{hook_key}: missing value for --timeout"
+                    )));
+                };
+
+                timeout_secs = match secs.parse::<u64>() {
+                    Ok(0) | Err(_) => {
+                        return Err(AliError::BadHookCmd(format!(
+                            "{hook_key}: bad timeout {secs}"
+                        )));
+                    }
+                    Ok(secs) => secs,
+                };
+
+                continue;
+            }
+
+            if device.is_some() {
+                return Err(AliError::BadHookCmd(format!(
+                    "{hook_key}: unexpected argument {arg}"
+                )));
+            }
+
+            device = Some(arg.clone());
+        }
+
+        let Some(device) = device else {
+            return Err(AliError::BadHookCmd(format!(
+                "{hook_key}: missing device path"
+            )));
+        };
+
+        if !device.starts_with('/') {
+            return Err(AliError::BadHookCmd(format!(
+                "{hook_key}: device path {device} is not absolute"
+            )));
+        }
+
+        Ok(Self {
+            device,
+            timeout_secs,
+        })
+    }
+}
+
+impl Hook for HookWaitForDevice {
+    fn base_key(&self) -> &'static str {
+        KEY_WAIT_FOR_DEVICE
+    }
+
+    /// `@wait-for-device <DEVICE> [--timeout <SECONDS>]`
+    ///
+    /// Examples:
+    ///
+    /// 1. Wait up to 30 seconds (default) for /dev/sdb
+    ///
+    /// ```txt
+    /// @wait-for-device /dev/sdb
+    /// ```
+    ///
+    /// 2. Wait up to 2 minutes for a network block device
+    ///
+    /// ```txt
+    /// @wait-for-device /dev/nbd0 --timeout 120
+    /// ```
+    fn usage(&self) -> &'static str {
+        USAGE
+    }
+
+    fn mode(&self) -> ModeHook {
+        ModeHook::Normal
+    }
+
+    fn should_chroot(&self) -> bool {
+        false
+    }
+
+    fn prefer_caller(&self, caller: &Caller) -> bool {
+        matches!(caller, Caller::ManifestPostInstall | Caller::Cli)
+    }
+
+    fn abort_if_no_mount(&self) -> bool {
+        false
+    }
+
+    fn run_hook(
+        &self,
+        _caller: &Caller,
+        _root_location: &str,
+    ) -> Result<ActionHook, AliError> {
+        wait_for_device(&self.device, Duration::from_secs(self.timeout_secs))
+            .map_err(|err| self.hook_error(&err))?;
+
+        Ok(ActionHook::WaitForDevice(
+            json!({
+                "device": self.device,
+                "timeout": self.timeout_secs,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+/// Polls for `device` node until it exists or `timeout` has passed
+fn wait_for_device(device: &str, timeout: Duration) -> Result<(), String> {
+    let start = Instant::now();
+
+    loop {
+        if Path::new(device).exists() {
+            return Ok(());
+        }
+
+        let elapsed = start.elapsed();
+        if elapsed >= timeout {
+            return Err(format!(
+                "timed out after {}s waiting for device {device}",
+                timeout.as_secs()
+            ));
+        }
+
+        std::thread::sleep(POLL_INTERVAL.min(timeout - elapsed));
+    }
+}
+
+#[test]
+fn test_parse_wait_for_device() {
+    let should_pass = vec![
+        (
+            "@wait-for-device /dev/sdb",
+            HookWaitForDevice {
+                device: "/dev/sdb".into(),
+                timeout_secs: DEFAULT_TIMEOUT_SECS,
+            },
+        ),
+        (
+            "@wait-for-device /dev/nbd0 --timeout 120",
+            HookWaitForDevice {
+                device: "/dev/nbd0".into(),
+                timeout_secs: 120,
+            },
+        ),
+        (
+            "@wait-for-device --timeout 5 /dev/sdc",
+            HookWaitForDevice {
+                device: "/dev/sdc".into(),
+                timeout_secs: 5,
+            },
+        ),
+    ];
+
+    let should_err = vec![
+        "@wait-for-device",
+        "@wait-for-device sdb",
+        "@wait-for-device /dev/sdb --timeout",
+        "@wait-for-device /dev/sdb --timeout 0",
+        "@wait-for-device /dev/sdb --timeout -1",
+        "@wait-for-device /dev/sdb --timeout foo",
+        "@wait-for-device /dev/sdb /dev/sdc",
+    ];
+
+    for (cmd, expected) in should_pass {
+        let hook_result = HookWaitForDevice::try_from(cmd);
+        if let Err(ref err) = hook_result {
+            eprintln!("unexpected error result from {cmd}: {err}");
+        }
+
+        assert_eq!(expected, hook_result.unwrap());
+    }
+
+    for cmd in should_err {
+        if let Ok(hook) = HookWaitForDevice::try_from(cmd) {
+            panic!("unexpected ok result from bad arg {cmd}: {hook:?}");
+        }
+    }
+}
+
+#[test]
+fn test_wait_for_device() {
+    let hook = HookWaitForDevice::try_from(
+        "@wait-for-device /dev/ali-rs-no-such-device --timeout 1",
+    )
+    .unwrap();
+
+    let start = Instant::now();
+    if let Ok(action) = hook.run_hook(&Caller::Cli, "/") {
+        panic!("unexpected ok result for nonexistent device: {action:?}");
+    }
+    assert!(start.elapsed() >= Duration::from_secs(1));
+
+    let hook =
+        HookWaitForDevice::try_from("@wait-for-device /dev/null --timeout 1")
+            .unwrap();
+
+    if let Err(err) = hook.run_hook(&Caller::Cli, "/") {
+        panic!("unexpected error for existing device: {err}");
+    }
+}
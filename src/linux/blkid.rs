@@ -0,0 +1,60 @@
+use crate::ali::PartitionTable;
+use crate::errors::AliError;
+use crate::utils::shell::{
+    self,
+    CmdError,
+};
+
+/// Returns type of the partition table already on `device`
+/// as reported by blkid(8), e.g. `gpt` or `dos`,
+/// or `None` if `device` has no partition table
+pub fn pt_type(device: &str) -> Result<Option<String>, AliError> {
+    let (cmd, args) = cmd_pt_type(device);
+
+    match shell::output(cmd, &args) {
+        Ok(pt_type) if pt_type.is_empty() => Ok(None),
+        Ok(pt_type) => Ok(Some(pt_type)),
+
+        // blkid exits with 2 if nothing was found on device
+        Err(AliError::CmdFailed {
+            error: CmdError::ErrRun { code: Some(2), .. },
+            ..
+        }) => Ok(None),
+
+        Err(err) => Err(err),
+    }
+}
+
+pub fn cmd_pt_type(device: &str) -> (&'static str, Vec<&str>) {
+    ("blkid", vec!["-p", "-o", "value", "-s", "PTTYPE", device])
+}
+
+/// Returns filesystem UUID on `device` as reported by blkid(8),
+/// or `None` if blkid found none
+pub fn fs_uuid(device: &str) -> Result<Option<String>, AliError> {
+    let (cmd, args) = cmd_fs_uuid(device);
+
+    match shell::output(cmd, &args) {
+        Ok(uuid) if uuid.is_empty() => Ok(None),
+        Ok(uuid) => Ok(Some(uuid)),
+
+        Err(AliError::CmdFailed {
+            error: CmdError::ErrRun { code: Some(2), .. },
+            ..
+        }) => Ok(None),
+
+        Err(err) => Err(err),
+    }
+}
+
+pub fn cmd_fs_uuid(device: &str) -> (&'static str, Vec<&str>) {
+    ("blkid", vec!["-p", "-o", "value", "-s", "UUID", device])
+}
+
+/// Returns blkid(8) PTTYPE value for `table`
+pub fn pt_type_name(table: &PartitionTable) -> &'static str {
+    match table {
+        PartitionTable::Gpt => "gpt",
+        PartitionTable::Mbr => "dos",
+    }
+}
@@ -0,0 +1,120 @@
+use crate::errors::AliError;
+use crate::utils::shell;
+
+/// Executes:
+/// ```shell
+/// btrfs quota enable <mountpoint>
+/// ```
+pub fn quota_enable(mountpoint: &str) -> Result<(), AliError> {
+    let (cmd, args) = cmd_quota_enable(mountpoint);
+
+    shell::exec(cmd, &args)
+}
+
+pub fn cmd_quota_enable(mountpoint: &str) -> (&'static str, [&str; 3]) {
+    ("btrfs", ["quota", "enable", mountpoint])
+}
+
+/// Executes:
+/// ```shell
+/// btrfs subvolume create <mountpoint>/<subvol>
+/// ```
+pub fn subvolume_create(
+    mountpoint: &str,
+    subvol: &str,
+) -> Result<(), AliError> {
+    let path = subvol_path(mountpoint, subvol);
+    let (cmd, args) = cmd_subvolume_create(&path);
+
+    shell::exec(cmd, &args)
+}
+
+pub fn cmd_subvolume_create(path: &str) -> (&'static str, [&str; 3]) {
+    ("btrfs", ["subvolume", "create", path])
+}
+
+/// Resolves ID of `subvol` on btrfs mounted at `mountpoint`, and executes:
+/// ```shell
+/// btrfs subvolume set-default <ID> <mountpoint>
+/// ```
+pub fn subvolume_set_default(
+    mountpoint: &str,
+    subvol: &str,
+) -> Result<(), AliError> {
+    let path = subvol_path(mountpoint, subvol);
+    let (cmd, args) = cmd_subvolume_show(&path);
+    let show = shell::output(cmd, &args)?;
+
+    let id = parse_subvolume_id(&show).ok_or(AliError::AliRsBug(format!(
+        "failed to parse subvolume ID of {path} from btrfs output"
+    )))?;
+
+    let (cmd, args) = cmd_subvolume_set_default(id, mountpoint);
+
+    shell::exec(cmd, &args)
+}
+
+pub fn cmd_subvolume_show(path: &str) -> (&'static str, [&str; 3]) {
+    ("btrfs", ["subvolume", "show", path])
+}
+
+pub fn cmd_subvolume_set_default<'a>(
+    id: &'a str,
+    mountpoint: &'a str,
+) -> (&'static str, [&'a str; 4]) {
+    ("btrfs", ["subvolume", "set-default", id, mountpoint])
+}
+
+/// Returns path to `subvol` on btrfs mounted at `mountpoint`
+pub fn subvol_path(mountpoint: &str, subvol: &str) -> String {
+    format!("{}/{}", mountpoint.trim_end_matches('/'), subvol)
+}
+
+/// Parses subvolume ID from `btrfs subvolume show` output
+fn parse_subvolume_id(show: &str) -> Option<&str> {
+    show.lines()
+        .find_map(|line| line.trim().strip_prefix("Subvolume ID:"))
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+}
+
+#[test]
+fn test_cmd_quota_enable() {
+    assert_eq!(
+        ("btrfs", ["quota", "enable", "/alitarget/home"]),
+        cmd_quota_enable("/alitarget/home"),
+    );
+}
+
+#[test]
+fn test_cmd_subvolume_set_default() {
+    let show = r#"@
+	Name: 			@
+	UUID: 			0c5a1f4e-1b5e-4d4f-9c53-2a8f3f7b6d11
+	Parent UUID: 		-
+	Flags: 			-
+	Subvolume ID: 		256
+	Generation: 		7
+	Gen at creation: 	6
+	Parent ID: 		5
+	Top level ID: 		5
+"#;
+
+    let path = subvol_path("/tmp/ali-rs.1234/", "@");
+    assert_eq!("/tmp/ali-rs.1234/@", path);
+    assert_eq!(
+        ("btrfs", ["subvolume", "create", "/tmp/ali-rs.1234/@"]),
+        cmd_subvolume_create(&path),
+    );
+
+    let id = parse_subvolume_id(show).expect("no subvolume ID");
+    assert_eq!(
+        (
+            "btrfs",
+            ["subvolume", "set-default", "256", "/tmp/ali-rs.1234"]
+        ),
+        cmd_subvolume_set_default(id, "/tmp/ali-rs.1234"),
+    );
+
+    assert_eq!(None, parse_subvolume_id("ERROR: not a subvolume"));
+}
@@ -15,9 +15,23 @@ pub fn create_table_cmd(table: &PartitionTable) -> String {
     }
 }
 
+// fdisk alias and MBR type codes for extended partitions
+const PART_TYPES_EXTENDED: [&str; 6] = ["extended", "5", "05", "f", "0f", "85"];
+
+/// Returns whether `part_type` is an MBR extended partition type
+pub fn is_extended_type(part_type: &str) -> bool {
+    PART_TYPES_EXTENDED.contains(&part_type.to_lowercase().as_str())
+}
+
 /// Returns fdisk cmd for creating new partition.
 /// It assumes caller calls it from 1st to last partitions,
 /// in that exact order, so no `start` sector will be used.
+///
+/// On MBR, partitions #1-#3 are primary partitions. Partition #4 is
+/// either a primary or an extended partition, and partitions #5
+/// onwards are logical partitions inside the extended partition.
+/// fdisk selects partition number itself for #4 onwards,
+/// because there is only 1 possible number left.
 pub fn create_partition_cmd(
     table: &PartitionTable,
     part_num: usize,
@@ -32,14 +46,20 @@ pub fn create_partition_cmd(
         PartitionTable::Gpt => {
             assemble_and_w(&["n", &part_num.to_string(), "", &size])
         }
+        PartitionTable::Mbr if part_num > 4 => {
+            // All primary partitions are in use, fdisk adds logical partition
+            assemble_and_w(&["n", "", &size])
+        }
+        PartitionTable::Mbr if part_num == 4 => {
+            let kind = match is_extended_type(&part.part_type) {
+                true => "e",
+                false => "p",
+            };
+
+            assemble_and_w(&["n", kind, "", &size])
+        }
         PartitionTable::Mbr => {
-            assemble_and_w(&[
-                "n",
-                "p", // Only create primary msdos partition for now
-                &part_num.to_string(),
-                "",
-                &size,
-            ])
+            assemble_and_w(&["n", "p", &part_num.to_string(), "", &size])
         }
     }
 }
@@ -55,6 +75,16 @@ pub fn set_partition_type_cmd(
     }
 }
 
+/// Returns fdisk expert cmd for setting GPT partition unique GUID.
+/// Like [`set_partition_type_cmd`], it assumes that partitions
+/// are created and modified in order.
+pub fn set_partition_uuid_cmd(part_num: usize, uuid: &str) -> String {
+    match part_num {
+        1 => assemble_and_w(&["x", "u", uuid, "r"]),
+        _ => assemble_and_w(&["x", "u", &part_num.to_string(), uuid, "r"]),
+    }
+}
+
 /// Pipe cmd with printf to fdisk:
 /// ```shell
 /// printf $cmd | fdisk $device
@@ -94,6 +124,7 @@ mod tests {
                     label: "foo".to_string(),
                     size: Some("200M".to_string()),
                     part_type: "8e".to_string(),
+                    uuid: None,
                 },
                 expected: "n\n1\n\n+200M\nw\n",
             },
@@ -104,6 +135,7 @@ mod tests {
                     label: "foo".to_string(),
                     size: None,
                     part_type: "8e".to_string(),
+                    uuid: None,
                 },
                 expected: "n\np\n1\n\n\nw\n",
             },
@@ -116,6 +148,84 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_create_part_cmd_mbr_logical() {
+        let part = |size: Option<&str>, part_type: &str| {
+            ManifestPartition {
+                label: "foo".to_string(),
+                size: size.map(String::from),
+                part_type: part_type.to_string(),
+                uuid: None,
+            }
+        };
+
+        let partitions = [
+            part(Some("500M"), "uefi"),
+            part(Some("4G"), "swap"),
+            part(Some("20G"), "linux"),
+            part(None, "extended"),
+            part(Some("10G"), "linux"),
+            part(None, "linux"),
+        ];
+
+        let expected = [
+            "n\np\n1\n\n+500M\nw\n",
+            "n\np\n2\n\n+4G\nw\n",
+            "n\np\n3\n\n+20G\nw\n",
+            "n\ne\n\n\nw\n",
+            "n\n\n+10G\nw\n",
+            "n\n\n\nw\n",
+        ];
+
+        for (i, (part, expected)) in partitions.iter().zip(expected).enumerate()
+        {
+            let result =
+                create_partition_cmd(&PartitionTable::Mbr, i + 1, part);
+            assert_eq!(
+                expected,
+                result,
+                "unexpected cmd for partition #{}",
+                i + 1
+            );
+        }
+
+        // 4th primary partition
+        assert_eq!(
+            "n\np\n\n+1G\nw\n",
+            create_partition_cmd(
+                &PartitionTable::Mbr,
+                4,
+                &part(Some("1G"), "linux")
+            ),
+        );
+    }
+
+    #[test]
+    fn test_is_extended_type() {
+        for t in ["extended", "Extended", "5", "05", "f", "0F", "85"] {
+            assert!(is_extended_type(t), "{t} should be extended type");
+        }
+
+        for t in ["linux", "83", "8e", "uefi", "ef", "lvm"] {
+            assert!(!is_extended_type(t), "{t} should not be extended type");
+        }
+    }
+
+    #[test]
+    fn test_set_partition_uuid_cmd() {
+        let uuid = "5a4e6f3c-1b2d-4c8e-9f70-2d3b4a5c6d7e";
+
+        assert_eq!(
+            "x\nu\n5a4e6f3c-1b2d-4c8e-9f70-2d3b4a5c6d7e\nr\nw\n",
+            set_partition_uuid_cmd(1, uuid),
+        );
+
+        assert_eq!(
+            "x\nu\n3\n5a4e6f3c-1b2d-4c8e-9f70-2d3b4a5c6d7e\nr\nw\n",
+            set_partition_uuid_cmd(3, uuid),
+        );
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn test_run_fdisk_cmd() {
@@ -137,12 +247,14 @@ mod tests {
             label: "efi".to_string(),
             size: Some("20M".to_string()),
             part_type: "1".to_string(),
+            uuid: None,
         };
 
         let manifest_p2 = ManifestPartition {
             label: "root_part".to_string(),
             size: None,
             part_type: "8e".to_string(),
+            uuid: None,
         };
 
         let create_gpt_p1 =
@@ -0,0 +1,19 @@
+use crate::errors::AliError;
+use crate::utils::shell;
+
+/// Returns whether `device` is a regular file, i.e. a disk image
+/// that has to be attached as a loop device before use
+pub fn is_image(device: &str) -> bool {
+    std::fs::metadata(device).is_ok_and(|meta| meta.is_file())
+}
+
+/// Attaches `image` to the first unused loop device,
+/// and returns the loop device path, e.g. `/dev/loop0`
+pub fn attach(image: &str) -> Result<String, AliError> {
+    shell::output("losetup", &["--find", "--show", image])
+}
+
+/// Detaches loop device `loop_dev`
+pub fn detach(loop_dev: &str) -> Result<(), AliError> {
+    shell::exec("losetup", &["--detach", loop_dev])
+}
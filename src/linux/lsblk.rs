@@ -0,0 +1,82 @@
+use serde::Deserialize;
+
+use crate::errors::AliError;
+use crate::utils::shell;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disk {
+    pub name: String,
+    pub size: String,
+    pub model: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Output {
+    blockdevices: Vec<Device>,
+}
+
+#[derive(Deserialize)]
+struct Device {
+    name: String,
+    size: String,
+    #[serde(rename = "type")]
+    dev_type: String,
+    model: Option<String>,
+}
+
+/// Returns all whole disks on the system, as reported by lsblk(8)
+pub fn disks() -> Result<Vec<Disk>, AliError> {
+    let output = shell::output(
+        "lsblk",
+        &["-J", "-d", "-p", "-o", "NAME,SIZE,TYPE,MODEL"],
+    )?;
+
+    parse_disks(&output)
+}
+
+fn parse_disks(output: &str) -> Result<Vec<Disk>, AliError> {
+    let output: Output = serde_json::from_str(output).map_err(|err| {
+        AliError::AliRsBug(format!("failed to parse lsblk output: {err}"))
+    })?;
+
+    Ok(output
+        .blockdevices
+        .into_iter()
+        .filter(|dev| dev.dev_type == "disk" && dev.size != "0B")
+        .map(|dev| {
+            Disk {
+                name: dev.name,
+                size: dev.size,
+                model: dev.model.map(|m| m.trim().to_string()),
+            }
+        })
+        .collect())
+}
+
+#[test]
+fn test_parse_disks() {
+    let output = r#"{
+   "blockdevices": [
+      {"name": "/dev/zram0", "size": "0B", "type": "disk", "model": null},
+      {"name": "/dev/sda", "size": "476.9G", "type": "disk", "model": "Samsung SSD 860  "},
+      {"name": "/dev/sr0", "size": "1024M", "type": "rom", "model": "DVD-RW"},
+      {"name": "/dev/nvme0n1", "size": "1.8T", "type": "disk", "model": null}
+   ]
+}"#;
+
+    let expected = vec![
+        Disk {
+            name: "/dev/sda".into(),
+            size: "476.9G".into(),
+            model: Some("Samsung SSD 860".into()),
+        },
+        Disk {
+            name: "/dev/nvme0n1".into(),
+            size: "1.8T".into(),
+            model: None,
+        },
+    ];
+
+    assert_eq!(expected, parse_disks(output).unwrap());
+    assert!(parse_disks("not json").is_err());
+}
@@ -1,13 +1,18 @@
+use crate::ali::ManifestLuks;
 use crate::errors::AliError;
-use crate::utils::shell;
+use crate::utils::{
+    secret,
+    shell,
+};
 
 // libcryptsetup bindings: https://github.com/stratis-storage/libcryptsetup-rs/
 
-pub fn format(device: &str, key: Option<&str>) -> Result<(), AliError> {
-    let mut format_cmd = format!("cryptsetup luksFormat {device}");
+pub fn format(luks: &ManifestLuks) -> Result<(), AliError> {
+    let mut format_cmd = cmd_format(luks).join(" ");
 
-    if let Some(passphrase) = key {
-        check_passphrase(passphrase)?;
+    if let Some(source) = &luks.passphrase {
+        let passphrase = secret::resolve(source)?;
+        check_passphrase(&passphrase)?;
 
         format_cmd = format!("echo '{passphrase}' | {format_cmd}");
     }
@@ -15,12 +20,47 @@ pub fn format(device: &str, key: Option<&str>) -> Result<(), AliError> {
     shell::sh_c(&format_cmd)
 }
 
+/// Returns luksFormat argv, with PBKDF options and header if specified:
+/// ```shell
+/// cryptsetup luksFormat \
+///     [--iter-time ${{ ms }}] \
+///     [--pbkdf-memory ${{ kib }}] \
+///     [--pbkdf-parallel ${{ threads }}] \
+///     [--header ${{ header }}] \
+///     ${{ device }}
+/// ```
+pub fn cmd_format(luks: &ManifestLuks) -> Vec<String> {
+    let mut cmd = vec!["cryptsetup".to_string(), "luksFormat".to_string()];
+
+    let opts = [
+        ("--iter-time", luks.pbkdf_time_ms),
+        ("--pbkdf-memory", luks.pbkdf_memory),
+        ("--pbkdf-parallel", luks.pbkdf_parallel),
+    ];
+
+    for (flag, value) in opts {
+        if let Some(value) = value {
+            cmd.push(flag.to_string());
+            cmd.push(value.to_string());
+        }
+    }
+
+    if let Some(header) = &luks.header {
+        cmd.push("--header".to_string());
+        cmd.push(header.clone());
+    }
+
+    cmd.push(luks.device.clone());
+    cmd
+}
+
 pub fn open(
     device: &str,
     key: Option<&str>,
     name: &str,
+    header: Option<&str>,
 ) -> Result<(), AliError> {
-    let mut open_cmd = format!("cryptsetup luksOpen {device} {name}");
+    let mut open_cmd = cmd_open(device, name, header);
 
     if let Some(passphrase) = key {
         check_passphrase(passphrase)?;
@@ -31,6 +71,19 @@ pub fn open(
     shell::sh_c(&open_cmd)
 }
 
+/// Returns luksOpen command:
+/// ```shell
+/// cryptsetup luksOpen [--header ${{ header }}] ${{ device }} ${{ name }}
+/// ```
+pub fn cmd_open(device: &str, name: &str, header: Option<&str>) -> String {
+    match header {
+        Some(header) => {
+            format!("cryptsetup luksOpen --header {header} {device} {name}")
+        }
+        None => format!("cryptsetup luksOpen {device} {name}"),
+    }
+}
+
 #[allow(unused)]
 pub fn close(name: &str) -> Result<(), AliError> {
     let close_cmd = format!("cryptsetup luksClose {name}");
@@ -49,9 +102,12 @@ fn check_passphrase(pass: &str) -> Result<(), AliError> {
 mod tests {
     use super::{
         close,
+        cmd_format,
+        cmd_open,
         format,
         open,
     };
+    use crate::ali::ManifestLuks;
     use crate::linux::user;
     use crate::utils::shell::{
         in_path,
@@ -68,6 +124,16 @@ mod tests {
         let fname = "./fake-luks.img";
         let passphrase = "pass1234";
         let opened_name = "fakeluks";
+        let luks = ManifestLuks {
+            device: fname.into(),
+            name: opened_name.into(),
+            passphrase: Some(passphrase.into()),
+            pbkdf_time_ms: None,
+            pbkdf_memory: None,
+            pbkdf_parallel: None,
+            header: None,
+            crypttab: None,
+        };
 
         if let Err(err) = test_utils::dd("/dev/zero", fname, "100M", 2) {
             panic!(
@@ -78,12 +144,80 @@ mod tests {
         if !user::is_root() {
             println!("WARN: only testing luksFormat because user is not root");
 
-            format(fname, Some(passphrase)).expect("luksFormat failed");
+            format(&luks).expect("luksFormat failed");
             return;
         }
 
-        format(fname, Some(passphrase)).expect("luksFormat failed");
-        open(fname, Some(passphrase), opened_name).expect("luksOpen failed");
+        format(&luks).expect("luksFormat failed");
+        open(fname, Some(passphrase), opened_name, None)
+            .expect("luksOpen failed");
         close(opened_name).expect("luksClose failed");
     }
+
+    #[test]
+    fn test_cmd_format() {
+        let mut luks = ManifestLuks {
+            device: "/dev/sda2".into(),
+            name: "cryptroot".into(),
+            passphrase: None,
+            pbkdf_time_ms: None,
+            pbkdf_memory: None,
+            pbkdf_parallel: None,
+            header: None,
+            crypttab: None,
+        };
+
+        assert_eq!(
+            vec!["cryptsetup", "luksFormat", "/dev/sda2"],
+            cmd_format(&luks),
+        );
+
+        luks.pbkdf_time_ms = Some(5000);
+        assert_eq!(
+            vec![
+                "cryptsetup",
+                "luksFormat",
+                "--iter-time",
+                "5000",
+                "/dev/sda2"
+            ],
+            cmd_format(&luks),
+        );
+
+        luks.pbkdf_memory = Some(1048576);
+        luks.pbkdf_parallel = Some(4);
+        assert_eq!(
+            vec![
+                "cryptsetup",
+                "luksFormat",
+                "--iter-time",
+                "5000",
+                "--pbkdf-memory",
+                "1048576",
+                "--pbkdf-parallel",
+                "4",
+                "/dev/sda2",
+            ],
+            cmd_format(&luks),
+        );
+
+        luks.pbkdf_time_ms = None;
+        luks.pbkdf_memory = None;
+        luks.pbkdf_parallel = None;
+        luks.header = Some("/dev/disk/by-id/usb-key-part1".into());
+        assert_eq!(
+            vec![
+                "cryptsetup",
+                "luksFormat",
+                "--header",
+                "/dev/disk/by-id/usb-key-part1",
+                "/dev/sda2",
+            ],
+            cmd_format(&luks),
+        );
+        assert_eq!(
+            "cryptsetup luksOpen --header /dev/disk/by-id/usb-key-part1 /dev/sda2 cryptroot",
+            cmd_open(&luks.device, &luks.name, luks.header.as_deref()),
+        );
+    }
 }
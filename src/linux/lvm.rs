@@ -2,12 +2,21 @@ use crate::ali;
 use crate::errors::AliError;
 use crate::utils::shell;
 
+// Units for LV sizes in percentage, e.g. 50%VG
+const LV_PERCENTAGE_UNITS: [&str; 3] = ["VG", "FREE", "PVS"];
+
+// LVM RAID types supported by lvcreate --type, see lvmraid(7)
+pub const RAID_TYPES: [&str; 6] =
+    ["raid0", "raid1", "raid4", "raid5", "raid6", "raid10"];
+
 /// Executes:
 /// ```shell
 /// pvcreate ${{ pv }}
 /// ```
 pub fn create_pv(pv: &str) -> Result<(), AliError> {
-    shell::exec("pvcreate", &[pv])
+    let (cmd, args) = cmd_create_pv(pv);
+
+    shell::exec(cmd, &args)
 }
 
 /// Executes:
@@ -15,26 +24,316 @@ pub fn create_pv(pv: &str) -> Result<(), AliError> {
 /// vgcreate ${{ vg.name }} ${{ vg.pvs }}
 /// ```
 pub fn create_vg(vg: &ali::ManifestLvmVg) -> Result<(), AliError> {
-    let mut arg = vec![vg.name.as_str()];
-    let pvs = vg.pvs.iter().map(|pv| pv.as_str());
-    arg.extend(pvs);
+    let (cmd, args) = cmd_create_vg(vg);
 
-    shell::exec("vgcreate", &arg)
+    shell::exec(cmd, &args)
 }
 
 /// Executes:
 /// ```shell
 /// lvcreate -L ${{ lv.size }} ${{ lv.vg }} -n ${{ lv.name }}
 ///
+/// # or, for RAID LVs:
+///
+/// lvcreate --type ${{ lv.raid_type }} [-m ${{ lv.mirrors }}] [-i ${{ lv.stripes }}] \
+///     -L ${{ lv.size }} ${{ lv.vg }} -n ${{ lv.name }}
+///
+/// # or, if lv.size is a percentage like 50%VG:
+///
+/// lvcreate -l ${{ lv.size }} ${{ lv.vg }} -n ${{ lv.name }}
+///
 /// # or, if lv.size is None:
 ///
 /// lvcreate -l 100%FREE ${{ lv.vg }} -n ${{ lv.name }}
 /// ```
 pub fn create_lv(lv: &ali::ManifestLvmLv) -> Result<(), AliError> {
+    let (cmd, args) = cmd_create_lv(lv);
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+
+    shell::exec(cmd, &args)
+}
+
+/// Executes:
+/// ```shell
+/// vgscan --mknodes
+/// ```
+pub fn scan_vgs() -> Result<(), AliError> {
+    let (cmd, args) = cmd_scan_vgs();
+
+    shell::exec(cmd, &args)
+}
+
+pub fn cmd_scan_vgs() -> (&'static str, Vec<&'static str>) {
+    ("vgscan", vec!["--mknodes"])
+}
+
+pub fn cmd_create_pv(pv: &str) -> (&'static str, Vec<&str>) {
+    ("pvcreate", vec![pv])
+}
+
+pub fn cmd_create_vg(vg: &ali::ManifestLvmVg) -> (&'static str, Vec<&str>) {
+    let mut args = vec![vg.name.as_str()];
+    args.extend(vg.pvs.iter().map(|pv| pv.as_str()));
+
+    ("vgcreate", args)
+}
+
+pub fn cmd_create_lv(lv: &ali::ManifestLvmLv) -> (&'static str, Vec<String>) {
+    let mut args = Vec::new();
+
+    if let Some(raid_type) = &lv.raid_type {
+        args.extend(["--type".to_string(), raid_type.clone()]);
+
+        if let Some(mirrors) = lv.mirrors {
+            args.extend(["-m".to_string(), mirrors.to_string()]);
+        }
+
+        if let Some(stripes) = lv.stripes {
+            args.extend(["-i".to_string(), stripes.to_string()]);
+        }
+    }
+
     let (size_flag, size) = match &lv.size {
+        Some(size) if size.contains('%') => ("-l", size.as_str()),
         Some(size) => ("-L", size.as_str()),
         None => ("-l", "100%FREE"),
     };
 
-    shell::exec("lvcreate", &[size_flag, size, "-n", &lv.name])
+    args.extend(
+        [size_flag, size, &lv.vg, "-n", &lv.name].map(|arg| arg.to_string()),
+    );
+
+    ("lvcreate", args)
+}
+
+/// Returns the minimum number of PVs needed by RAID LV `lv`,
+/// or `Ok(None)` if `lv` is not a RAID LV. Unset mirrors and
+/// stripes count as lvcreate(8) defaults.
+pub fn raid_min_pvs(
+    lv: &ali::ManifestLvmLv,
+) -> Result<Option<usize>, AliError> {
+    let Some(raid_type) = &lv.raid_type else {
+        if lv.mirrors.is_some() || lv.stripes.is_some() {
+            return Err(AliError::BadManifest(format!(
+                "lv {} has mirrors or stripes, but no raid_type",
+                lv.name
+            )));
+        }
+
+        return Ok(None);
+    };
+
+    let mirrors = lv.mirrors.map(usize::from);
+    let stripes = lv.stripes.map(usize::from);
+
+    let bad_opts = |msg: &str| {
+        Err(AliError::BadManifest(format!(
+            "lv {} with raid_type {raid_type}: {msg}",
+            lv.name
+        )))
+    };
+
+    // Parity RAIDs need more stripes than parity devices
+    let min_pvs = match (raid_type.as_str(), mirrors, stripes) {
+        ("raid1", Some(0), _) => return bad_opts("mirrors must be at least 1"),
+        ("raid1", _, Some(_)) => return bad_opts("stripes not supported"),
+        ("raid1", m, None) => m.unwrap_or(1) + 1,
+
+        ("raid0" | "raid4" | "raid5" | "raid6", Some(_), _) => {
+            return bad_opts("mirrors not supported");
+        }
+        ("raid0" | "raid4" | "raid5", None, Some(0 | 1)) => {
+            return bad_opts("stripes must be at least 2");
+        }
+        ("raid6", None, Some(0..=2)) => {
+            return bad_opts("stripes must be at least 3");
+        }
+        ("raid0", None, s) => s.unwrap_or(2),
+        ("raid4" | "raid5", None, s) => s.unwrap_or(2) + 1,
+        ("raid6", None, s) => s.unwrap_or(3) + 2,
+
+        ("raid10", Some(0), _) => {
+            return bad_opts("mirrors must be at least 1")
+        }
+        ("raid10", _, Some(0 | 1)) => {
+            return bad_opts("stripes must be at least 2");
+        }
+        ("raid10", m, s) => s.unwrap_or(2) * (m.unwrap_or(1) + 1),
+
+        _ => {
+            return Err(AliError::BadManifest(format!(
+                "unknown raid_type {raid_type} for lv {}, expecting one of {RAID_TYPES:?}",
+                lv.name
+            )));
+        }
+    };
+
+    Ok(Some(min_pvs))
+}
+
+/// Parses LVM percentage sizes `<n>%VG`, `<n>%FREE`, or `<n>%PVS`
+/// into percentage and its unit, where n must be within 1-100.
+/// Returns `Ok(None)` if size is not a percentage.
+pub fn parse_lv_percentage(size: &str) -> Result<Option<(u8, &str)>, AliError> {
+    let Some((n, unit)) = size.split_once('%') else {
+        return Ok(None);
+    };
+
+    if !LV_PERCENTAGE_UNITS.contains(&unit) {
+        return Err(AliError::BadManifest(format!(
+            "bad lv percentage unit {unit} in {size}, expecting one of {LV_PERCENTAGE_UNITS:?}"
+        )));
+    }
+
+    match n.parse::<u8>() {
+        Ok(n) if (1..=100).contains(&n) => Ok(Some((n, unit))),
+        _ => {
+            Err(AliError::BadManifest(format!(
+                "bad lv percentage {n} in {size}, expecting 1-100"
+            )))
+        }
+    }
+}
+
+#[test]
+fn test_parse_lv_percentage() {
+    let should_ok = vec![
+        ("50%VG", Some((50, "VG"))),
+        ("100%FREE", Some((100, "FREE"))),
+        ("1%PVS", Some((1, "PVS"))),
+        ("20G", None),
+    ];
+
+    let should_err = vec![
+        "0%VG",
+        "101%FREE",
+        "-1%VG",
+        "50%",
+        "%VG",
+        "50%vg",
+        "50%ORIGIN",
+    ];
+
+    for (size, expected) in should_ok {
+        let result = parse_lv_percentage(size)
+            .unwrap_or_else(|err| panic!("unexpected error for {size}: {err}"));
+
+        assert_eq!(expected, result);
+    }
+
+    for size in should_err {
+        if let Ok(result) = parse_lv_percentage(size) {
+            panic!("unexpected ok result for {size}: {result:?}");
+        }
+    }
+}
+
+#[test]
+fn test_cmd_create_lv() {
+    let lv = ali::ManifestLvmLv {
+        name: "rootlv".into(),
+        vg: "myvg".into(),
+        size: Some("20G".into()),
+        raid_type: None,
+        mirrors: None,
+        stripes: None,
+    };
+
+    assert_eq!("lvcreate", cmd_create_lv(&lv).0);
+    assert_eq!(
+        vec!["-L", "20G", "myvg", "-n", "rootlv"],
+        cmd_create_lv(&lv).1,
+    );
+
+    let lv = ali::ManifestLvmLv {
+        size: Some("50%VG".into()),
+        ..lv
+    };
+
+    assert_eq!(
+        vec!["-l", "50%VG", "myvg", "-n", "rootlv"],
+        cmd_create_lv(&lv).1,
+    );
+
+    let lv = ali::ManifestLvmLv { size: None, ..lv };
+
+    assert_eq!(
+        vec!["-l", "100%FREE", "myvg", "-n", "rootlv"],
+        cmd_create_lv(&lv).1,
+    );
+
+    let lv = ali::ManifestLvmLv {
+        size: Some("20G".into()),
+        raid_type: Some("raid1".into()),
+        mirrors: Some(1),
+        ..lv
+    };
+
+    assert_eq!(
+        vec!["--type", "raid1", "-m", "1", "-L", "20G", "myvg", "-n", "rootlv"],
+        cmd_create_lv(&lv).1,
+    );
+
+    let lv = ali::ManifestLvmLv {
+        raid_type: Some("raid5".into()),
+        mirrors: None,
+        stripes: Some(3),
+        ..lv
+    };
+
+    assert_eq!(
+        vec!["--type", "raid5", "-i", "3", "-L", "20G", "myvg", "-n", "rootlv"],
+        cmd_create_lv(&lv).1,
+    );
+}
+
+#[test]
+fn test_raid_min_pvs() {
+    let lv = |raid_type: Option<&str>, mirrors, stripes| {
+        ali::ManifestLvmLv {
+            name: "datalv".into(),
+            vg: "myvg".into(),
+            size: None,
+            raid_type: raid_type.map(String::from),
+            mirrors,
+            stripes,
+        }
+    };
+
+    let should_ok = vec![
+        (lv(None, None, None), None),
+        (lv(Some("raid1"), None, None), Some(2)),
+        (lv(Some("raid1"), Some(2), None), Some(3)),
+        (lv(Some("raid0"), None, Some(4)), Some(4)),
+        (lv(Some("raid5"), None, None), Some(3)),
+        (lv(Some("raid5"), None, Some(3)), Some(4)),
+        (lv(Some("raid6"), None, None), Some(5)),
+        (lv(Some("raid10"), None, None), Some(4)),
+        (lv(Some("raid10"), Some(2), Some(2)), Some(6)),
+    ];
+
+    let should_err = vec![
+        lv(None, Some(1), None),
+        lv(Some("raid7"), None, None),
+        lv(Some("RAID1"), None, None),
+        lv(Some("raid1"), Some(0), None),
+        lv(Some("raid1"), None, Some(2)),
+        lv(Some("raid5"), Some(1), None),
+        lv(Some("raid5"), None, Some(1)),
+        lv(Some("raid6"), None, Some(2)),
+        lv(Some("raid10"), None, Some(1)),
+    ];
+
+    for (lv, expected) in should_ok {
+        let result = raid_min_pvs(&lv)
+            .unwrap_or_else(|err| panic!("unexpected error for {lv:?}: {err}"));
+
+        assert_eq!(expected, result, "{lv:?}");
+    }
+
+    for lv in should_err {
+        if let Ok(result) = raid_min_pvs(&lv) {
+            panic!("unexpected ok result for {lv:?}: {result:?}");
+        }
+    }
 }
@@ -1,16 +1,449 @@
-use crate::ali::ManifestFs;
+use crate::ali::{
+    ManifestFs,
+    MkfsOutput,
+};
 use crate::errors::AliError;
 use crate::utils::shell;
 
+/// Filesystem types known to ali-rs, with packages to install in
+/// the new system for their tools (e.g. mkfs, fsck or mount helpers).
+/// `None` means no package is added for that type.
+pub const FS_PACKAGES: [(&str, Option<&str>); 13] = [
+    ("ext2", None),
+    ("ext3", None),
+    ("ext4", None),
+    ("vfat", Some("dosfstools")),
+    ("xfs", Some("xfsprogs")),
+    ("btrfs", Some("btrfs-progs")),
+    ("f2fs", Some("f2fs-tools")),
+    ("exfat", Some("exfatprogs")),
+    ("ntfs", Some("ntfs-3g")),
+    ("ntfs-3g", Some("ntfs-3g")),
+    ("reiserfs", Some("reiserfsprogs")),
+    ("jfs", Some("jfsutils")),
+    ("nilfs2", Some("nilfs-utils")),
+];
+
+/// Returns whether `fs_type` is known to ali-rs
+pub fn is_known_fs(fs_type: &str) -> bool {
+    FS_PACKAGES.iter().any(|(t, _)| *t == fs_type)
+}
+
+/// Returns package providing tools for `fs_type`, if any
+pub fn fs_package(fs_type: &str) -> Option<&'static str> {
+    FS_PACKAGES
+        .iter()
+        .find(|(t, _)| *t == fs_type)
+        .and_then(|(_, package)| *package)
+}
+
+/// Max lines of mkfs output kept by [`output_summary`]
+const OUTPUT_MAX_LINES: usize = 20;
+
 /// Executes:
 /// ```shell
 /// mkfs.{fs.fs_type} {fs.fs_opts} {fs.device}
 /// ```
-pub fn create_fs(fs: &ManifestFs) -> Result<(), AliError> {
-    let cmd_mkfs = match &fs.fs_opts {
-        Some(opts) => format!("'mkfs.{} {opts} {}'", fs.fs_type, fs.device),
-        None => format!("'mkfs.{} {}'", fs.fs_type, fs.device),
+///
+/// With `fs.mkfs_output` quiet, mkfs runs with stdin closed,
+/// and its stdout output is returned without being shown.
+///
+/// Otherwise on a terminal, mkfs runs attached to it, so that its
+/// prompts (e.g. mke2fs "Proceed anyway?") and progress work as usual,
+/// and no output is returned.
+///
+/// Otherwise, its stdout and stderr output is echoed to stderr
+/// and returned.
+pub fn create_fs(fs: &ManifestFs) -> Result<Option<String>, AliError> {
+    use std::io::IsTerminal;
+
+    let (cmd, args) = cmd_mkfs(fs)?;
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+
+    if fs.mkfs_output == Some(MkfsOutput::Quiet) {
+        return shell::output(&cmd, &args).map(Some);
+    }
+
+    if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+        return shell::exec(&cmd, &args).map(|_| None);
+    }
+
+    shell::tee(&cmd, &args).map(Some)
+}
+
+/// Parses filesystem UUID from mkfs `output`, e.g. `UUID: ..` from
+/// mkfs.btrfs or `Filesystem UUID: ..` from mkfs.ext4
+pub fn parse_uuid(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        let value = value.trim();
+
+        match key.trim() {
+            "UUID" | "Filesystem UUID" if !value.is_empty() => {
+                Some(value.to_string())
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Returns non-empty lines of mkfs `output`,
+/// truncated to [`OUTPUT_MAX_LINES`] lines
+pub fn output_summary(output: &str) -> String {
+    let lines: Vec<&str> = output
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+
+    if lines.len() <= OUTPUT_MAX_LINES {
+        return lines.join("\n");
+    }
+
+    let truncated = lines.len() - OUTPUT_MAX_LINES;
+    format!(
+        "{}\n({truncated} more lines truncated)",
+        lines[..OUTPUT_MAX_LINES].join("\n")
+    )
+}
+
+/// Returns mkfs program name and its argument vector.
+///
+/// `fs.fs_opts` is split with shell-like syntax, so quoted
+/// options (e.g. `-L "My Label"`) are kept as single arguments,
+/// and no shell is ever involved in creating filesystems.
+///
+/// For xfs, external log and realtime devices are appended
+/// as `-l logdev=<DEV>` and `-r rtdev=<DEV>`.
+///
+/// Discard flags from `fs.mkfs_discard` come before `fs.fs_opts`.
+pub fn cmd_mkfs(fs: &ManifestFs) -> Result<(String, Vec<String>), AliError> {
+    let mut args = match fs.mkfs_discard {
+        Some(discard) => discard_args(&fs.fs_type, discard)?,
+        None => Vec::new(),
+    };
+
+    if let Some(opts) = &fs.fs_opts {
+        args.extend(split_opts(opts)?);
+    }
+
+    if fs.fs_type == "xfs" {
+        if let Some(log_device) = &fs.log_device {
+            args.extend(["-l".to_string(), format!("logdev={log_device}")]);
+        }
+
+        if let Some(rt_device) = &fs.rt_device {
+            args.extend(["-r".to_string(), format!("rtdev={rt_device}")]);
+        }
+    }
+
+    args.push(fs.device.clone());
+
+    Ok((format!("mkfs.{}", fs.fs_type), args))
+}
+
+/// Returns mkfs arguments to enable or disable discard (TRIM)
+/// of the device when creating filesystem `fs_type`.
+///
+/// mkfs.btrfs and mkfs.xfs always discard unless told not to,
+/// so nothing is passed to enable discard for them. For ext2/3/4
+/// and f2fs, the flag is always passed, as their defaults may
+/// differ between versions or be changed in mke2fs.conf(5).
+pub fn discard_args(
+    fs_type: &str,
+    discard: bool,
+) -> Result<Vec<String>, AliError> {
+    let args: &[&str] = match (fs_type, discard) {
+        ("ext2" | "ext3" | "ext4", true) => &["-E", "discard"],
+        ("ext2" | "ext3" | "ext4", false) => &["-E", "nodiscard"],
+        ("btrfs", true) | ("xfs", true) => &[],
+        ("btrfs", false) => &["--nodiscard"],
+        ("xfs", false) => &["-K"],
+        ("f2fs", true) => &["-t", "1"],
+        ("f2fs", false) => &["-t", "0"],
+        _ => {
+            return Err(AliError::BadManifest(format!(
+                "mkfs_discard is not supported for fs_type {fs_type}"
+            )));
+        }
     };
 
-    shell::sh_c(&cmd_mkfs)
+    Ok(args.iter().map(|arg| arg.to_string()).collect())
+}
+
+fn split_opts(opts: &str) -> Result<Vec<String>, AliError> {
+    shlex::split(opts)
+        .ok_or(AliError::BadManifest(format!("bad fs_opts format: {opts}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uuid() {
+        let btrfs = r#"btrfs-progs v6.3
+See https://btrfs.readthedocs.io for more information.
+
+NOTE: several default settings have changed in version 5.15, please make sure
+      this does not affect your deployments:
+      - DUP for metadata (-m dup)
+      - enabled no-holes (-O no-holes)
+      - enabled free-space-tree (-R free-space-tree)
+
+Label:              (null)
+UUID:               6f5a7c6c-0d7f-4d8f-9b7c-1b0e0a3f2d11
+Node size:          16384
+Sector size:        4096
+Filesystem size:    10.00GiB
+Block group profiles:
+  Data:             single            8.00MiB
+  Metadata:         DUP             256.00MiB
+  System:           DUP               8.00MiB
+SSD detected:       no
+Zoned device:       no
+Incompat features:  extref, skinny-metadata, no-holes, free-space-tree
+Runtime features:   free-space-tree
+Checksum:           crc32c
+Number of devices:  1
+Devices:
+   ID        SIZE  PATH
+    1    10.00GiB  /dev/sdb1
+"#;
+
+        let ext4 = r#"Creating filesystem with 2621440 4k blocks and 655360 inodes
+Filesystem UUID: 1c7e2d3a-5b7f-4e0a-8a51-3f6d9e2b4c10
+Superblock backups stored on blocks:
+	32768, 98304, 163840, 229376, 294912, 819200, 884736, 1605632
+"#;
+
+        assert_eq!(
+            Some("6f5a7c6c-0d7f-4d8f-9b7c-1b0e0a3f2d11".to_string()),
+            parse_uuid(btrfs),
+        );
+        assert_eq!(
+            Some("1c7e2d3a-5b7f-4e0a-8a51-3f6d9e2b4c10".to_string()),
+            parse_uuid(ext4),
+        );
+        assert_eq!(None, parse_uuid("mkfs.fat 4.2 (2021-01-31)"));
+
+        let summary = output_summary(btrfs);
+        assert!(summary.starts_with("btrfs-progs v6.3\nSee"));
+        assert!(summary.ends_with("(5 more lines truncated)"));
+        assert_eq!(OUTPUT_MAX_LINES + 1, summary.lines().count());
+        assert_eq!(4, output_summary(ext4).lines().count());
+    }
+
+    #[test]
+    fn test_cmd_mkfs() {
+        let tests = vec![
+            (
+                ManifestFs {
+                    device: "/dev/sda1".into(),
+                    fs_type: "ext4".into(),
+                    fs_opts: None,
+                    fstab_dump: None,
+                    fstab_pass: None,
+                    log_device: None,
+                    rt_device: None,
+                    quota: None,
+                    tune2fs_args: None,
+                    mkfs_discard: None,
+                    mkfs_output: None,
+                    subvolumes: None,
+                    default_subvol: None,
+                },
+                ("mkfs.ext4", vec!["/dev/sda1"]),
+            ),
+            (
+                ManifestFs {
+                    device: "/dev/sda1".into(),
+                    fs_type: "vfat".into(),
+                    fs_opts: Some("-F 32 -n BOOT".into()),
+                    fstab_dump: None,
+                    fstab_pass: None,
+                    log_device: None,
+                    rt_device: None,
+                    quota: None,
+                    tune2fs_args: None,
+                    mkfs_discard: None,
+                    mkfs_output: None,
+                    subvolumes: None,
+                    default_subvol: None,
+                },
+                ("mkfs.vfat", vec!["-F", "32", "-n", "BOOT", "/dev/sda1"]),
+            ),
+            (
+                ManifestFs {
+                    device: "/dev/myvg/mylv".into(),
+                    fs_type: "btrfs".into(),
+                    fs_opts: Some("-L \"My Label\"".into()),
+                    fstab_dump: None,
+                    fstab_pass: None,
+                    log_device: None,
+                    rt_device: None,
+                    quota: None,
+                    tune2fs_args: None,
+                    mkfs_discard: None,
+                    mkfs_output: None,
+                    subvolumes: None,
+                    default_subvol: None,
+                },
+                ("mkfs.btrfs", vec!["-L", "My Label", "/dev/myvg/mylv"]),
+            ),
+            (
+                ManifestFs {
+                    device: "/dev/sdb".into(),
+                    fs_type: "ext4".into(),
+                    fs_opts: Some("-L 'it'\"'\"'s' -E nodiscard".into()),
+                    fstab_dump: None,
+                    fstab_pass: None,
+                    log_device: None,
+                    rt_device: None,
+                    quota: None,
+                    tune2fs_args: None,
+                    mkfs_discard: None,
+                    mkfs_output: None,
+                    subvolumes: None,
+                    default_subvol: None,
+                },
+                (
+                    "mkfs.ext4",
+                    vec!["-L", "it's", "-E", "nodiscard", "/dev/sdb"],
+                ),
+            ),
+            (
+                ManifestFs {
+                    device: "/dev/sdb1".into(),
+                    fs_type: "xfs".into(),
+                    fs_opts: Some("-L data".into()),
+                    fstab_dump: None,
+                    fstab_pass: None,
+                    log_device: Some("/dev/nvme0n1p3".into()),
+                    rt_device: None,
+                    quota: None,
+                    tune2fs_args: None,
+                    mkfs_discard: None,
+                    mkfs_output: None,
+                    subvolumes: None,
+                    default_subvol: None,
+                },
+                (
+                    "mkfs.xfs",
+                    vec![
+                        "-L",
+                        "data",
+                        "-l",
+                        "logdev=/dev/nvme0n1p3",
+                        "/dev/sdb1",
+                    ],
+                ),
+            ),
+            (
+                ManifestFs {
+                    device: "/dev/sdb1".into(),
+                    fs_type: "xfs".into(),
+                    fs_opts: None,
+                    fstab_dump: None,
+                    fstab_pass: None,
+                    log_device: Some("/dev/nvme0n1p3".into()),
+                    rt_device: Some("/dev/sdc1".into()),
+                    quota: None,
+                    tune2fs_args: None,
+                    mkfs_discard: None,
+                    mkfs_output: None,
+                    subvolumes: None,
+                    default_subvol: None,
+                },
+                (
+                    "mkfs.xfs",
+                    vec![
+                        "-l",
+                        "logdev=/dev/nvme0n1p3",
+                        "-r",
+                        "rtdev=/dev/sdc1",
+                        "/dev/sdb1",
+                    ],
+                ),
+            ),
+            (
+                ManifestFs {
+                    device: "/dev/sdb1".into(),
+                    fs_type: "ext4".into(),
+                    fs_opts: Some("-L data".into()),
+                    fstab_dump: None,
+                    fstab_pass: None,
+                    log_device: None,
+                    rt_device: None,
+                    quota: None,
+                    tune2fs_args: None,
+                    mkfs_discard: Some(false),
+                    mkfs_output: None,
+                    subvolumes: None,
+                    default_subvol: None,
+                },
+                (
+                    "mkfs.ext4",
+                    vec!["-E", "nodiscard", "-L", "data", "/dev/sdb1"],
+                ),
+            ),
+        ];
+
+        for (fs, (expected_cmd, expected_args)) in tests {
+            let (cmd, args) = cmd_mkfs(&fs).expect("failed to build mkfs cmd");
+
+            assert_eq!(expected_cmd, cmd);
+            assert_eq!(expected_args, args);
+        }
+
+        let should_err = vec![ManifestFs {
+            device: "/dev/sda1".into(),
+            fs_type: "ext4".into(),
+            fs_opts: Some("-L \"My Label".into()),
+            fstab_dump: None,
+            fstab_pass: None,
+            log_device: None,
+            rt_device: None,
+            quota: None,
+            tune2fs_args: None,
+            mkfs_discard: None,
+            mkfs_output: None,
+            subvolumes: None,
+            default_subvol: None,
+        }];
+
+        for fs in should_err {
+            if let Ok(result) = cmd_mkfs(&fs) {
+                panic!("unexpected ok result from bad fs_opts: {result:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_discard_args() {
+        let tests = vec![
+            (("ext4", true), vec!["-E", "discard"]),
+            (("ext4", false), vec!["-E", "nodiscard"]),
+            (("ext2", false), vec!["-E", "nodiscard"]),
+            (("btrfs", true), vec![]),
+            (("btrfs", false), vec!["--nodiscard"]),
+            (("xfs", true), vec![]),
+            (("xfs", false), vec!["-K"]),
+            (("f2fs", true), vec!["-t", "1"]),
+            (("f2fs", false), vec!["-t", "0"]),
+        ];
+
+        for ((fs_type, discard), expected) in tests {
+            let args = discard_args(fs_type, discard)
+                .unwrap_or_else(|err| panic!("unexpected error: {err}"));
+
+            assert_eq!(expected, args);
+        }
+
+        for fs_type in ["vfat", "swap", "ext44"] {
+            if let Ok(args) = discard_args(fs_type, false) {
+                panic!("unexpected ok result for {fs_type}: {args:?}");
+            }
+        }
+    }
 }
@@ -1,9 +1,20 @@
+pub mod blkid;
+pub mod btrfs;
 pub mod fdisk;
+#[cfg(feature = "loop-image")]
+pub mod losetup;
+pub mod lsblk;
 pub mod luks;
 pub mod lvm;
 pub mod mkfs;
+pub mod modprobe;
 pub mod mount;
+pub mod pacman;
+pub mod swap;
+pub mod tune2fs;
+pub mod udevadm;
 pub mod user;
+pub mod zram;
 
 // See linux/block/partition-generic.c
 //
@@ -33,6 +44,27 @@ pub(crate) fn partition_name(name: &str, part_number: u8) -> String {
     format!("{name}{part_number}")
 }
 
+/// Returns whether `dev` is `disk` or one of its partitions,
+/// e.g. /dev/sda2 on /dev/sda, or /dev/nvme0n1p2 on /dev/nvme0n1
+pub(crate) fn is_on_disk(dev: &str, disk: &str) -> bool {
+    let Some(rest) = dev.strip_prefix(disk) else {
+        return false;
+    };
+
+    if rest.is_empty() {
+        return true;
+    }
+
+    // Disks whose names end with a digit have `p` before partition numbers
+    let partition = match disk.ends_with(|c: char| c.is_ascii_digit()) {
+        true => rest.strip_prefix('p'),
+        false => Some(rest),
+    };
+
+    partition
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
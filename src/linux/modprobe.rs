@@ -0,0 +1,34 @@
+use crate::errors::AliError;
+use crate::utils::shell;
+
+/// Returns modprobe cmd and its arguments for loading `module`
+pub fn cmd_modprobe(module: &str) -> (&'static str, Vec<&str>) {
+    ("modprobe", vec![module])
+}
+
+/// Executes:
+/// ```shell
+/// modprobe ${{ module }}
+/// ```
+pub fn load(module: &str) -> Result<(), AliError> {
+    let (cmd, args) = cmd_modprobe(module);
+
+    shell::exec(cmd, &args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmd_modprobe() {
+        let tests = vec![
+            ("vmd", ("modprobe", vec!["vmd"])),
+            ("megaraid_sas", ("modprobe", vec!["megaraid_sas"])),
+        ];
+
+        for (module, expected) in tests {
+            assert_eq!(expected, cmd_modprobe(module));
+        }
+    }
+}
@@ -1,24 +1,209 @@
-use crate::ali::ManifestMountpoint;
+use crate::ali::{
+    ManifestMountpoint,
+    ManifestOverlay,
+};
 use crate::errors::AliError;
 use crate::utils::shell;
 
+/// Filesystem mounted by a userspace helper instead of `mount -t`
+#[derive(Debug, PartialEq)]
+pub struct MountHelper {
+    pub helper: &'static str,
+
+    /// fstype for fstab(5), since genfstab(8) only sees e.g. `fuseblk`
+    pub fstab_type: &'static str,
+}
+
+// Package is in mkfs fs packages
+const NTFS_3G: MountHelper = MountHelper {
+    helper: "mount.ntfs-3g",
+    fstab_type: "ntfs-3g",
+};
+
+/// Filesystem types mounted with userspace helpers
+const MOUNT_HELPERS: [(&str, MountHelper); 2] =
+    [("ntfs", NTFS_3G), ("ntfs-3g", NTFS_3G)];
+
+/// Returns mount helper for `fs_type`, if any
+pub fn mount_helper(fs_type: &str) -> Option<&'static MountHelper> {
+    MOUNT_HELPERS
+        .iter()
+        .find(|(t, _)| *t == fs_type)
+        .map(|(_, helper)| helper)
+}
+
+/// Returns mount command for `mnt`, with destination under `base`
+pub fn cmd_mount(mnt: &ManifestMountpoint, base: &str) -> String {
+    let mountpoint = prepend_base(base, &mnt.dest);
+
+    match mnt.mnt_opts {
+        Some(ref opts) => {
+            format!("mount -o {opts} {} {mountpoint}", mnt.device)
+        }
+        None => format!("mount {} {mountpoint}", mnt.device),
+    }
+}
+
 /// Executes:
 /// ```shell
 /// mount <mnt.device> [mnt.mnt_opts] /base/<mnt.dest>
 /// ```
-pub fn mount(mnt: &ManifestMountpoint, base: &str) -> Result<(), AliError> {
+/// or the mount helper for `fs_type` if there's one
+pub fn mount_fs(
+    mnt: &ManifestMountpoint,
+    fs_type: Option<&str>,
+    base: &str,
+) -> Result<(), AliError> {
+    shell::sh_c(&cmd_mount_fs(mnt, fs_type, base))
+}
+
+/// Returns mount command for `mnt` formatted as `fs_type`.
+/// Filesystems with mount helpers are mounted with the helper,
+/// e.g. `mount.ntfs-3g [-o opts] <mnt.device> /base/<mnt.dest>`
+pub fn cmd_mount_fs(
+    mnt: &ManifestMountpoint,
+    fs_type: Option<&str>,
+    base: &str,
+) -> String {
+    let Some(helper) = fs_type.and_then(mount_helper) else {
+        return cmd_mount(mnt, base);
+    };
+
     let mountpoint = prepend_base(base, &mnt.dest);
-    let cmd_mount = match mnt.mnt_opts {
+    let cmd = helper.helper;
+
+    match mnt.mnt_opts {
         Some(ref opts) => {
-            format!("mount -o {opts} {} {mountpoint}", mnt.device)
+            format!("{cmd} -o {opts} {} {mountpoint}", mnt.device)
         }
-        None => format!("mount {} {mountpoint}", mnt.device),
-    };
+        None => format!("{cmd} {} {mountpoint}", mnt.device),
+    }
+}
+
+/// Executes:
+/// ```shell
+/// mount -t overlay overlay -o lowerdir=..,upperdir=..,workdir=.. /base/<overlay.mnt>
+/// ```
+pub fn mount_overlay(
+    overlay: &ManifestOverlay,
+    base: &str,
+) -> Result<(), AliError> {
+    let (cmd, args) = cmd_mount_overlay(overlay, base);
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
 
-    shell::sh_c(&cmd_mount)
+    shell::exec(cmd, &args)
+}
+
+/// Returns mount command for `overlay`, with all dirs under `base`
+pub fn cmd_mount_overlay(
+    overlay: &ManifestOverlay,
+    base: &str,
+) -> (&'static str, Vec<String>) {
+    (
+        "mount",
+        vec![
+            "-t".to_string(),
+            "overlay".to_string(),
+            "overlay".to_string(),
+            "-o".to_string(),
+            overlay.mnt_opts(base),
+            prepend_base(base, &overlay.mnt),
+        ],
+    )
 }
 
 pub fn prepend_base(base: &str, mountpoint: &str) -> String {
     // e.g. base /data on manifest /foo => /data/foo
     format!("{base}{mountpoint}")
 }
+
+#[test]
+fn test_cmd_mount_fs() {
+    let mnt = ManifestMountpoint {
+        device: "/dev/sdb1".into(),
+        dest: "/data".into(),
+        mnt_opts: Some("uid=1000".into()),
+    };
+
+    assert_eq!(
+        "mount.ntfs-3g -o uid=1000 /dev/sdb1 /alitarget/data",
+        cmd_mount_fs(&mnt, Some("ntfs"), "/alitarget"),
+    );
+    assert_eq!(
+        "mount -o uid=1000 /dev/sdb1 /alitarget/data",
+        cmd_mount_fs(&mnt, Some("vfat"), "/alitarget"),
+    );
+    assert_eq!(
+        "mount -o uid=1000 /dev/sdb1 /alitarget/data",
+        cmd_mount_fs(&mnt, None, "/alitarget"),
+    );
+}
+
+#[test]
+fn test_cmd_mount_xfs_external_devs() {
+    let manifest = crate::ali::Manifest::from_yaml(
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sdb1
+    fstype: xfs
+    log_device: /dev/nvme0n1p3
+    rt_device: /dev/sdc1
+mountpoints:
+  - device: /dev/sdb1
+    dest: /data
+    mnt_opts: noatime,logdev=/dev/nvme0n1p3
+"#,
+    )
+    .expect("failed to parse manifest");
+
+    let mnt = manifest.mountpoints.as_ref().unwrap()[0].clone();
+    assert_eq!(
+        "mount -o noatime,logdev=/dev/nvme0n1p3,rtdev=/dev/sdc1 /dev/sdb1 /alitarget/data",
+        cmd_mount_fs(
+            &manifest.effective_mountpoint(&mnt),
+            Some("xfs"),
+            "/alitarget"
+        ),
+    );
+
+    let mnt = ManifestMountpoint {
+        mnt_opts: None,
+        ..mnt
+    };
+    assert_eq!(
+        "mount -o logdev=/dev/nvme0n1p3,rtdev=/dev/sdc1 /dev/sdb1 /alitarget/data",
+        cmd_mount_fs(
+            &manifest.effective_mountpoint(&mnt),
+            Some("xfs"),
+            "/alitarget"
+        ),
+    );
+}
+
+#[test]
+fn test_cmd_mount_overlay() {
+    let overlay = ManifestOverlay {
+        lower_dir: "/usr/lib/os:/opt/base".into(),
+        upper_dir: "/var/overlay/upper".into(),
+        work_dir: "/var/overlay/work".into(),
+        mnt: "/srv".into(),
+    };
+
+    let (cmd, args) = cmd_mount_overlay(&overlay, "/alitarget");
+
+    assert_eq!("mount", cmd);
+    assert_eq!(
+        vec![
+            "-t",
+            "overlay",
+            "overlay",
+            "-o",
+            "lowerdir=/alitarget/usr/lib/os:/alitarget/opt/base,upperdir=/alitarget/var/overlay/upper,workdir=/alitarget/var/overlay/work",
+            "/alitarget/srv",
+        ],
+        args,
+    );
+}
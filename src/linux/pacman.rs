@@ -0,0 +1,178 @@
+use crate::errors::AliError;
+
+pub const PACMAN_CONF: &str = "/etc/pacman.conf";
+
+// Used when [options] has no SigLevel, same as pacman default
+const SIG_LEVEL_REQUIRED: &str = "SigLevel = Required DatabaseOptional";
+
+// SigLevel values weaker than `Required DatabaseOptional`
+const SIG_LEVELS_WEAK: [&str; 8] = [
+    "Never",
+    "Optional",
+    "TrustAll",
+    "PackageNever",
+    "PackageOptional",
+    "PackageTrustAll",
+    "DatabaseNever",
+    "DatabaseTrustAll",
+];
+
+/// Validates that pacman.conf(5) at `path` can be made strict
+/// with [`strict_conf`], before anything is applied
+pub fn validate_strict_conf(path: &str) -> Result<(), AliError> {
+    let conf = std::fs::read_to_string(path).map_err(|err| {
+        AliError::FileError(err, format!("failed to read {path}"))
+    })?;
+
+    strict_conf(&conf).map(|_| ())
+}
+
+/// Copies pacman.conf(5) `src` to `dst` for pacstrap,
+/// with signature verification enforced by [`strict_conf`]
+pub fn prepare_strict_conf(src: &str, dst: &str) -> Result<(), AliError> {
+    let conf = std::fs::read_to_string(src).map_err(|err| {
+        AliError::FileError(err, format!("failed to read {src}"))
+    })?;
+
+    std::fs::write(dst, strict_conf(&conf)?).map_err(|err| {
+        AliError::FileError(err, format!("failed to write {dst}"))
+    })
+}
+
+/// Returns pacman.conf(5) `conf` with package signatures required.
+///
+/// Any SigLevel weaker than `Required DatabaseOptional`, in [options]
+/// or in repositories, is an error. If [options] has no SigLevel,
+/// `SigLevel = Required DatabaseOptional` is added.
+pub fn strict_conf(conf: &str) -> Result<String, AliError> {
+    let mut section = "";
+    let mut options_sig_level = false;
+
+    for line in conf.lines() {
+        let line = line.trim();
+
+        if let Some(name) =
+            line.strip_prefix('[').and_then(|s| s.strip_suffix(']'))
+        {
+            section = name;
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        if key.trim() != "SigLevel" {
+            continue;
+        }
+
+        if section == "options" {
+            options_sig_level = true;
+        }
+
+        let weak = value
+            .split_whitespace()
+            .find(|level| SIG_LEVELS_WEAK.contains(level));
+
+        if let Some(weak) = weak {
+            return Err(AliError::Validation(format!(
+                "strict_signatures: SigLevel {weak} in section [{section}] disables signature verification"
+            )));
+        }
+    }
+
+    if options_sig_level {
+        return Ok(conf.to_string());
+    }
+
+    let mut strict = Vec::new();
+    let mut found_options = false;
+
+    for line in conf.lines() {
+        strict.push(line.to_string());
+
+        if line.trim() == "[options]" {
+            strict.push(SIG_LEVEL_REQUIRED.to_string());
+            found_options = true;
+        }
+    }
+
+    if !found_options {
+        return Err(AliError::Validation(
+            "strict_signatures: pacman.conf has no [options] section"
+                .to_string(),
+        ));
+    }
+
+    strict.push(String::new());
+    Ok(strict.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strict_conf() {
+        let dir = std::env::temp_dir().join("ali-rs-test-pacman-conf");
+        _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let src = dir.join("pacman.conf");
+        let dst = dir.join("pacman.strict.conf");
+        let (src, dst) = (src.to_str().unwrap(), dst.to_str().unwrap());
+
+        // SigLevel is added to [options]
+        let conf = r#"[options]
+HoldPkg = pacman glibc
+Architecture = auto
+
+[core]
+Include = /etc/pacman.d/mirrorlist
+"#;
+        std::fs::write(src, conf).unwrap();
+        prepare_strict_conf(src, dst).unwrap();
+
+        let strict = std::fs::read_to_string(dst).unwrap();
+        let mut lines = strict.lines();
+        assert_eq!(Some("[options]"), lines.next());
+        assert_eq!(Some(SIG_LEVEL_REQUIRED), lines.next());
+        assert!(strict.contains("Include = /etc/pacman.d/mirrorlist"));
+
+        let should_ok = vec![
+            "[options]\nSigLevel = Required DatabaseOptional\n",
+            "[options]\nSigLevel = Required DatabaseRequired TrustedOnly\n",
+            "[options]\n#SigLevel = Never\n",
+            "[options]\nSigLevel = Required\n[core]\nSigLevel = PackageRequired\n",
+        ];
+
+        let should_err = vec![
+            "[options]\nSigLevel = Never\n",
+            "[options]\nSigLevel    =   Optional TrustAll\n",
+            "[options]\nSigLevel = Required DatabaseOptional\n[custom]\nSigLevel = Optional TrustAll\n",
+            "[options]\nSigLevel = PackageNever\n",
+            "HoldPkg = pacman glibc\n",
+        ];
+
+        for conf in should_ok {
+            if let Err(err) = strict_conf(conf) {
+                panic!("unexpected error for {conf:?}: {err}");
+            }
+        }
+
+        for conf in should_err {
+            std::fs::write(src, conf).unwrap();
+            if prepare_strict_conf(src, dst).is_ok() {
+                panic!("unexpected ok result for {conf:?}");
+            }
+        }
+
+        // Unreadable pacman.conf is a file error
+        assert!(matches!(
+            validate_strict_conf("/ali-rs-no-such-pacman.conf"),
+            Err(AliError::FileError(..)),
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+use crate::ali::ManifestSwap;
+use crate::errors::AliError;
+use crate::utils::shell;
+
+/// Executes:
+/// ```shell
+/// mkswap ${{ swap.device }}
+///
+/// # or, if swap has a label:
+///
+/// mkswap -L ${{ swap.label }} ${{ swap.device }}
+/// ```
+pub fn create_swap(swap: &ManifestSwap) -> Result<(), AliError> {
+    let (cmd, args) = cmd_mkswap(swap);
+
+    shell::exec(cmd, &args)
+}
+
+pub fn cmd_mkswap(swap: &ManifestSwap) -> (&'static str, Vec<&str>) {
+    let mut args = Vec::new();
+    if let Some(label) = swap.label() {
+        args.extend(["-L", label]);
+    }
+
+    args.push(swap.device());
+
+    ("mkswap", args)
+}
+
+#[test]
+fn test_cmd_mkswap() {
+    let swap = ManifestSwap::Labeled {
+        device: "/dev/myvg/swaplv".into(),
+        label: "swap".into(),
+    };
+
+    assert_eq!(
+        ("mkswap", vec!["-L", "swap", "/dev/myvg/swaplv"]),
+        cmd_mkswap(&swap),
+    );
+
+    let swap = ManifestSwap::from("/dev/sda2");
+    assert_eq!(("mkswap", vec!["/dev/sda2"]), cmd_mkswap(&swap));
+}
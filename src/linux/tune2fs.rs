@@ -0,0 +1,38 @@
+use crate::errors::AliError;
+use crate::utils::shell;
+
+/// Executes:
+/// ```shell
+/// tune2fs ${{ args }} ${{ device }}
+/// ```
+pub fn tune(device: &str, args: &[String]) -> Result<(), AliError> {
+    let (cmd, args) = cmd_tune2fs(device, args);
+
+    shell::exec(cmd, &args)
+}
+
+pub fn cmd_tune2fs<'a>(
+    device: &'a str,
+    args: &'a [String],
+) -> (&'static str, Vec<&'a str>) {
+    let mut argv: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    argv.push(device);
+
+    ("tune2fs", argv)
+}
+
+#[test]
+fn test_cmd_tune2fs() {
+    let args: Vec<String> =
+        vec!["-m".into(), "1".into(), "-c".into(), "30".into()];
+
+    assert_eq!(
+        ("tune2fs", vec!["-m", "1", "-c", "30", "/dev/sda2"]),
+        cmd_tune2fs("/dev/sda2", &args),
+    );
+
+    assert_eq!(
+        ("tune2fs", vec!["/dev/sda2"]),
+        cmd_tune2fs("/dev/sda2", &[])
+    );
+}
@@ -0,0 +1,16 @@
+use crate::errors::AliError;
+use crate::utils::shell;
+
+/// Executes:
+/// ```shell
+/// udevadm settle
+/// ```
+pub fn settle() -> Result<(), AliError> {
+    let (cmd, args) = cmd_settle();
+
+    shell::exec(cmd, &args)
+}
+
+pub fn cmd_settle() -> (&'static str, Vec<&'static str>) {
+    ("udevadm", vec!["settle"])
+}
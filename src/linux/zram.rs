@@ -0,0 +1,159 @@
+use crate::ali::ManifestZram;
+use crate::errors::AliError;
+use crate::types::blockdev::parse_fdisk_bytes;
+
+/// Config file read by zram-generator(8), relative to the new root
+pub const GENERATOR_CONF: &str = "/etc/systemd/zram-generator.conf";
+
+// Compression algorithms known to the zram kernel module
+pub const ALGORITHMS: [&str; 7] =
+    ["lzo", "lzo-rle", "lz4", "lz4hc", "zstd", "842", "deflate"];
+
+const MIB: usize = 1024 * 1024;
+
+/// Validates zram compression algorithm and size expression
+pub fn validate(zram: &ManifestZram) -> Result<(), AliError> {
+    if let Some(algorithm) = &zram.compression_algorithm {
+        if !ALGORITHMS.contains(&algorithm.as_str()) {
+            return Err(AliError::BadManifest(format!(
+                "unknown zram compression algorithm {algorithm}, expecting one of {ALGORITHMS:?}"
+            )));
+        }
+    }
+
+    if let Some(size) = &zram.size {
+        size_expr(size)?;
+    }
+
+    Ok(())
+}
+
+/// Returns zram-generator.conf(5) content for device zram0
+pub fn generator_conf(zram: &ManifestZram) -> Result<String, AliError> {
+    validate(zram)?;
+
+    let mut conf = String::from("[zram0]\n");
+
+    if let Some(size) = &zram.size {
+        conf.push_str(&format!("zram-size = {}\n", size_expr(size)?));
+    }
+
+    if let Some(algorithm) = &zram.compression_algorithm {
+        conf.push_str(&format!("compression-algorithm = {algorithm}\n"));
+    }
+
+    Ok(conf)
+}
+
+/// Returns zram-size expression for `size`, which is either
+/// a fraction of RAM like `ram`, `ram/2` or `ram*0.5`,
+/// or an absolute size like `4GiB` or `4G` (written in MiB).
+/// Like partition sizes, single-letter units are binary.
+fn size_expr(size: &str) -> Result<String, AliError> {
+    let expr: String = size.split_whitespace().collect();
+
+    let Some(rest) = expr.strip_prefix("ram") else {
+        let bytes = parse_fdisk_bytes(size).map_err(|err| {
+            AliError::BadManifest(format!("bad zram size {size}: {err}"))
+        })?;
+
+        let mib = bytes / MIB;
+        if mib == 0 {
+            return Err(AliError::BadManifest(format!(
+                "bad zram size {size}: smaller than 1MiB"
+            )));
+        }
+
+        return Ok(mib.to_string());
+    };
+
+    if rest.is_empty() {
+        return Ok("ram".to_string());
+    }
+
+    let mut chars = rest.chars();
+    let op = chars.next();
+    let factor = chars.as_str();
+
+    match (op, factor.parse::<f64>()) {
+        (Some(op @ ('/' | '*')), Ok(n)) if n.is_finite() && n > 0.0 => {
+            Ok(format!("ram {op} {factor}"))
+        }
+        _ => {
+            Err(AliError::BadManifest(format!(
+                "bad zram size {size}: expecting ram, ram/<n>, ram*<n>, or absolute size"
+            )))
+        }
+    }
+}
+
+#[test]
+fn test_generator_conf() {
+    let zram = ManifestZram {
+        size: Some("ram/2".into()),
+        compression_algorithm: Some("zstd".into()),
+    };
+
+    assert_eq!(
+        "[zram0]\nzram-size = ram / 2\ncompression-algorithm = zstd\n",
+        generator_conf(&zram).unwrap(),
+    );
+
+    let zram = ManifestZram {
+        size: Some("4GiB".into()),
+        compression_algorithm: None,
+    };
+
+    assert_eq!(
+        "[zram0]\nzram-size = 4096\n",
+        generator_conf(&zram).unwrap()
+    );
+
+    let zram = ManifestZram {
+        size: None,
+        compression_algorithm: None,
+    };
+
+    assert_eq!("[zram0]\n", generator_conf(&zram).unwrap());
+
+    let should_err = vec![
+        (Some("ram/2"), Some("gzip")),
+        (Some("ram/2"), Some("ZSTD")),
+        (Some("ram/0"), Some("zstd")),
+        (Some("ram-1"), None),
+        (Some("ram/"), None),
+        (Some("ram/inf"), None),
+        (Some("rams"), None),
+        (Some("half"), None),
+        (Some("1K"), None),
+    ];
+
+    for (size, algorithm) in should_err {
+        let zram = ManifestZram {
+            size: size.map(String::from),
+            compression_algorithm: algorithm.map(String::from),
+        };
+
+        if let Ok(conf) = generator_conf(&zram) {
+            panic!("unexpected ok result for {zram:?}: {conf}");
+        }
+    }
+}
+
+#[test]
+fn test_size_expr() {
+    let tests = vec![
+        ("ram", "ram"),
+        ("ram/2", "ram / 2"),
+        ("ram / 4", "ram / 4"),
+        ("ram*0.5", "ram * 0.5"),
+        ("512MiB", "512"),
+        ("1G", "1024"),
+        ("1GB", "953"),
+        ("8 GiB", "8192"),
+    ];
+
+    for (size, expected) in tests {
+        assert_eq!(expected, size_expr(size).unwrap());
+    }
+}
@@ -10,12 +10,16 @@ mod utils;
 
 use clap::Parser;
 
-fn main() -> Result<(), errors::AliError> {
+fn main() {
     let args = cli::Cli::parse();
 
     if let Err(err) = run::run(args) {
         eprintln!("{}", err.to_json_string());
-    }
 
-    Ok(())
+        if utils::interrupt::interrupted() {
+            std::process::exit(utils::interrupt::EXIT_CODE);
+        }
+
+        std::process::exit(1);
+    }
 }
@@ -1,4 +1,12 @@
 use std::collections::HashSet;
+use std::io::{
+    IsTerminal,
+    Write,
+};
+use std::time::{
+    Duration,
+    Instant,
+};
 
 use crate::ali::{
     apply,
@@ -6,21 +14,445 @@ use crate::ali::{
     Dm,
     Manifest,
 };
-use crate::cli;
 use crate::errors::AliError;
-use crate::types::report::Report;
-use crate::types::stage;
+use crate::types::action::Action;
+use crate::types::report::{
+    self,
+    Report,
+    ValidationReport,
+};
+use crate::types::stage::{
+    self,
+    StageActions,
+};
+use crate::types::warning::{
+    self,
+    Warning,
+};
+use crate::utils::interrupt;
+use crate::{
+    cli,
+    linux,
+};
+
+/// Manifest read from file, validated and updated, ready to be applied
+struct Prepared {
+    file: String,
+    manifest: Manifest,
+    location: String,
+    validation_report: Option<ValidationReport>,
+    warnings: Vec<Warning>,
 
+    /// Time spent reading and validating the manifest
+    prepare_time: Duration,
+}
+
+/// Applies `manifest_files` in order, returning one report per manifest.
+///
+/// All manifests are validated before any of them is applied,
+/// and multiple manifests must not share block devices.
+/// If a manifest fails, manifests already applied are not undone.
+///
+/// Reports are printed before the machine is rebooted, if requested.
+///
+/// With `--report-on-failure`, any error is also written there as JSON.
+/// With `strict`, validation warnings fail the manifest.
 pub(super) fn run(
-    manifest_file: &str,
-    install_location: &str,
+    manifest_files: &[String],
+    cli_location: Option<&str>,
     args: cli::ArgsApply,
-) -> Result<Report, AliError> {
-    let start = std::time::Instant::now();
+    strict: bool,
+) -> Result<(), AliError> {
+    let report_on_failure = args.report_on_failure.clone();
+    let result = apply_all(manifest_files, cli_location, args, strict);
+
+    if let (Err(err), Some(path)) = (&result, report_on_failure) {
+        if let Err(err_write) = write_failure_report(&path, err) {
+            super::print_warnings(&[Warning::new(
+                "report",
+                format!("failed to write failure report: {err_write}"),
+            )]);
+        }
+    }
+
+    result
+}
+
+fn apply_all(
+    manifest_files: &[String],
+    cli_location: Option<&str>,
+    args: cli::ArgsApply,
+    strict: bool,
+) -> Result<(), AliError> {
+    let multiple = manifest_files.len() > 1;
+    if multiple && args.emit_script.is_some() {
+        return Err(AliError::BadArgs(
+            "--emit-script cannot be used with multiple manifests".to_string(),
+        ));
+    }
+
+    // Storage is already mounted in config-only mode
+    let mut skip = args.skip_stages.clone();
+    if args.config_only {
+        skip.push(stage::Stage::Mountpoints);
+    }
+
+    let skip_stages =
+        stages_to_skip(args.stages.clone(), skip, args.no_pacstrap)?;
+
+    let mut prepared = Vec::new();
+    for file in manifest_files {
+        let p = prepare(file, cli_location, &args, strict)
+            .map_err(|err| err_manifest(err, file, &[], multiple))?;
+
+        prepared.push(p);
+    }
+
+    if multiple {
+        let manifests: Vec<(&str, &Manifest)> = prepared
+            .iter()
+            .map(|p| (p.file.as_str(), &p.manifest))
+            .collect();
+
+        validation::validate_distinct_devices(&manifests)?;
+
+        let locations: Vec<(&str, &str)> = prepared
+            .iter()
+            .map(|p| (p.file.as_str(), p.location.as_str()))
+            .collect();
+
+        validation::validate_distinct_locations(&locations)?;
+    }
+
+    // Check network for pacstrap before any manifest touches disks.
+    // Nothing is applied with both -n and --emit-script
+    let script_only = args.dry_run && args.emit_script.is_some();
+    let pacstrap =
+        !skip_stages.contains(&stage::Stage::Bootstrap) && !script_only;
+
+    if pacstrap {
+        for p in &prepared {
+            validation::validate_network(&p.manifest)
+                .map_err(|err| err_manifest(err, &p.file, &[], multiple))?;
+        }
+    }
+
+    // Commands and prompts also write to stdout, so actions
+    // are streamed to their own file
+    let mut stream = args
+        .stream_json
+        .as_deref()
+        .map(|path| {
+            std::fs::File::create(path)
+                .map_err(|err| AliError::FileError(err, path.to_string()))
+        })
+        .transpose()?;
+
+    // Stop between stages on SIGINT instead of dying mid-install.
+    // Installed after disk prompts, so that Ctrl-C there still exits
+    interrupt::install_handler()?;
+
+    let mut reports = Vec::new();
+    let mut reboot = false;
+    let count = prepared.len();
+    for (i, p) in prepared.into_iter().enumerate() {
+        let file = p.file.clone();
+        let is_last = i + 1 == count;
+
+        let (report, reboot_wanted) = apply_prepared(
+            p,
+            &args,
+            &skip_stages,
+            is_last,
+            stream.as_mut(),
+            apply::teardown,
+        )
+        .map_err(|err| err_manifest(err, &file, &reports, multiple))?;
+
+        reports.push(report);
+        reboot = reboot_wanted;
+    }
+
+    // Reports must be out before the machine goes down
+    super::print_reports(&reports);
+
+    let Some(report) = reports.last().filter(|_| reboot) else {
+        return Ok(());
+    };
+
+    // Ctrl-C at the prompt should exit, not be recorded
+    interrupt::uninstall_handler()?;
+
+    if !confirm_reboot(args.yes) {
+        super::print_warnings(&[Warning::new(
+            "apply",
+            "reboot not confirmed, skipping",
+        )]);
+
+        return Ok(());
+    }
+
+    apply::reboot(&report.location, Box::default(), &mut |action| {
+        stream_action(stream.as_mut(), action)
+    })
+    .map(|_| ())
+}
+
+/// Reads, validates and updates manifest from `manifest_file`
+fn prepare(
+    manifest_file: &str,
+    cli_location: Option<&str>,
+    args: &cli::ArgsApply,
+    strict: bool,
+) -> Result<Prepared, AliError> {
+    let start = Instant::now();
+
+    let manifest_yaml = std::fs::read_to_string(manifest_file)
+        .map_err(|err| AliError::NoSuchFile(err, manifest_file.to_string()))?;
 
-    let mut skip_stages: HashSet<stage::Stage> =
-        HashSet::from_iter(args.skip_stages);
-    if let Some(stages) = args.stages {
+    // manifest is mutable because we might have to
+    // help add packages such as lvm2 and btrfs-progs
+    let mut manifest = Manifest::from_yaml(&manifest_yaml)?;
+    manifest.apply_overrides(&args.overrides)?;
+
+    // Substitute placeholder disk with user's choice before validation
+    super::prompt::resolve_disk_prompt(&mut manifest)?;
+
+    let location =
+        super::install_location(cli_location, manifest.location.as_deref());
+
+    let mut warnings = Vec::new();
+
+    let validation_report = match (args.no_validate, args.config_only) {
+        (true, _) => None,
+        (false, true) => {
+            validation::validate_config_only(
+                &manifest,
+                &location,
+                &mut warnings,
+            )?;
+
+            None
+        }
+        (false, false) => {
+            Some(validation::validate(
+                &manifest,
+                &location,
+                args.overwrite,
+                false,
+                &mut warnings,
+            )?)
+        }
+    };
+
+    if strict {
+        warning::promote(&warnings)?;
+    }
+
+    // Update manifest in some cases
+    update_manifest(&mut manifest);
+
+    // User policy checks the effective manifest
+    if let Some(script) = &args.policy {
+        super::policy::check(script, &manifest)?;
+    }
+
+    Ok(Prepared {
+        file: manifest_file.to_string(),
+        manifest,
+        location,
+        validation_report,
+        warnings,
+        prepare_time: start.elapsed(),
+    })
+}
+
+/// Applies prepared manifest, returning its report and whether
+/// the machine should be rebooted afterwards. Only the last of
+/// manifests applied in sequence (`is_last`) may reboot.
+fn apply_prepared(
+    prepared: Prepared,
+    args: &cli::ArgsApply,
+    skip_stages: &HashSet<stage::Stage>,
+    is_last: bool,
+    mut stream_json: Option<&mut std::fs::File>,
+    teardown: impl FnOnce(&str, &StageActions) -> Vec<String>,
+) -> Result<(Report, bool), AliError> {
+    let Prepared {
+        file,
+        manifest,
+        location,
+        validation_report,
+        mut warnings,
+        prepare_time,
+    } = prepared;
+
+    // Earlier manifests applied in sequence are not counted
+    let start = Instant::now();
+
+    let skipped_stages: Vec<stage::Stage> = stage::STAGES
+        .into_iter()
+        .filter(|stage| skip_stages.contains(stage))
+        .collect();
+
+    if let Some(path) = &args.emit_script {
+        let script = apply::render_script(&manifest, &location, skip_stages)?;
+        write_script(path, &script)?;
+
+        if args.dry_run {
+            let report = Report {
+                manifest: file,
+                location,
+                summary: Box::default(),
+                duration: prepare_time + start.elapsed(),
+                warnings,
+                skipped_stages,
+            };
+
+            return Ok((report, false));
+        }
+    }
+
+    let mut stream =
+        |action: &Action| stream_action(stream_json.as_deref_mut(), action);
+
+    // Apply manifest to location
+    let result = apply::apply_manifest(
+        &manifest,
+        &location,
+        skip_stages.clone(),
+        validation_report
+            .as_ref()
+            .map(|report| &report.fs_ready_devs),
+        args.i_know_what_im_doing,
+        &mut warnings,
+        &mut stream,
+    );
+
+    let stages_applied = match result {
+        Err(err) if args.teardown_on_interrupt && interrupt::interrupted() => {
+            return Err(teardown_interrupted(err, &location, teardown));
+        }
+        result => result?,
+    };
+
+    // Storage-only installs are left mounted for the user to continue
+    if args.no_pacstrap && manifest.reboot_after.unwrap_or(false) {
+        warnings.push(Warning::new(
+            "apply",
+            "reboot_after ignored with --no-pacstrap",
+        ));
+    }
+
+    // Only reached if all stages were successfully applied
+    let reboot = args.reboot || manifest.reboot_after.unwrap_or(false);
+    if reboot && !is_last {
+        warnings.push(Warning::new(
+            "apply",
+            "reboot skipped, more manifests to apply",
+        ));
+    }
+
+    let report = Report {
+        manifest: file,
+        location,
+        summary: stages_applied,
+        duration: prepare_time + start.elapsed(),
+        warnings,
+        skipped_stages,
+    };
+
+    Ok((report, reboot && is_last && !args.no_pacstrap))
+}
+
+// Writes `action` to `stream` as a line of JSON. A reader going
+// away must not fail the install, so write errors are ignored
+fn stream_action(stream: Option<&mut std::fs::File>, action: &Action) {
+    if let Some(file) = stream {
+        let line = format!("{}\n", report::action_json_line(action));
+        _ = file.write_all(line.as_bytes());
+    }
+}
+
+fn write_failure_report(path: &str, err: &AliError) -> Result<(), AliError> {
+    let json = report::failure_json(err).to_string();
+
+    std::fs::write(path, json).map_err(|err| {
+        AliError::FileError(err, format!("failed to write report to {path}"))
+    })
+}
+
+// Tears down stages performed before the install was interrupted
+// with `teardown`, printing failed teardown commands as warnings
+fn teardown_interrupted(
+    err: AliError,
+    location: &str,
+    teardown: impl FnOnce(&str, &StageActions) -> Vec<String>,
+) -> AliError {
+    if let AliError::InstallError {
+        stages_performed, ..
+    } = &err
+    {
+        let warnings: Vec<Warning> = teardown(location, stages_performed)
+            .into_iter()
+            .map(|failed| Warning::new("teardown", failed))
+            .collect();
+
+        super::print_warnings(&warnings);
+    }
+
+    err
+}
+
+// Attributes err to manifest_file if multiple manifests are applied,
+// along with manifests already applied. Single manifest errors are
+// returned as is.
+fn err_manifest(
+    err: AliError,
+    manifest_file: &str,
+    applied: &[Report],
+    multiple: bool,
+) -> AliError {
+    if !multiple {
+        return err;
+    }
+
+    AliError::ManifestError {
+        manifest: manifest_file.to_string(),
+        error: Box::new(err),
+        manifests_applied: applied
+            .iter()
+            .map(|report| report.manifest.clone())
+            .collect(),
+    }
+}
+
+/// Writes executable `script` to `path`
+fn write_script(path: &str, script: &str) -> Result<(), AliError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::write(path, script)
+        .and_then(|_| {
+            std::fs::set_permissions(
+                path,
+                std::fs::Permissions::from_mode(0o755),
+            )
+        })
+        .map_err(|err| {
+            AliError::FileError(err, format!("failed to write script {path}"))
+        })
+}
+
+/// Returns stages to skip, from either explicit `stages` to run,
+/// or stages in `skip`. With `no_pacstrap`, all stages after
+/// mountpoints are also skipped.
+fn stages_to_skip(
+    stages: Option<Vec<stage::Stage>>,
+    skip: Vec<stage::Stage>,
+    no_pacstrap: bool,
+) -> Result<HashSet<stage::Stage>, AliError> {
+    let mut skip_stages: HashSet<stage::Stage> = HashSet::from_iter(skip);
+    if let Some(stages) = stages {
         for explicit_stage in stages.iter() {
             if skip_stages.contains(explicit_stage) {
                 return Err(AliError::BadArgs(format!(
@@ -44,92 +476,417 @@ pub(super) fn run(
         }
     }
 
-    let manifest_yaml = std::fs::read_to_string(manifest_file)
-        .map_err(|err| AliError::NoSuchFile(err, manifest_file.to_string()))?;
+    if no_pacstrap {
+        skip_stages.extend(stage::STAGES_PACSTRAP);
+    }
 
-    // manifest is mutable because we might have to
-    // help add packages such as lvm2 and btrfs-progs
-    let mut manifest = Manifest::from_yaml(&manifest_yaml)?;
+    Ok(skip_stages)
+}
 
-    if !args.no_validate {
-        validation::validate(&manifest, install_location, args.overwrite)?;
+// Returns whether reboot is confirmed, either with `yes`
+// or by the user on a terminal. Never confirmed without a TTY.
+fn confirm_reboot(yes: bool) -> bool {
+    if yes {
+        return true;
     }
 
-    // Update manifest in some cases
-    update_manifest(&mut manifest);
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return false;
+    }
 
-    // Apply manifest to location
-    let location = super::install_location();
-    let stages_applied =
-        apply::apply_manifest(&manifest, &location, skip_stages)?;
+    // Prompt on stderr, as stdout is for reports
+    eprint!("Installation complete, reboot now? [y/N] ");
+    if std::io::stderr().flush().is_err() {
+        return false;
+    }
 
-    Ok(Report {
-        location,
-        summary: stages_applied,
-        duration: start.elapsed(),
-    })
+    let mut answer = String::new();
+    if stdin.read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
 }
 
 // Update manifest to suit the manifest
 fn update_manifest(manifest: &mut Manifest) {
-    let (lvm2, btrfs, btrfs_progs) = (
-        "lvm2".to_string(),
-        "btrfs".to_string(),
-        "btrfs-progs".to_string(),
-    );
+    // Add packages providing tools for the filesystems, e.g. btrfs-progs
+    let fs_types = std::iter::once(&manifest.rootfs.fs_type)
+        .chain(manifest.filesystems.iter().flatten().map(|fs| &fs.fs_type));
 
-    let (mut has_lvm, mut has_btrfs) = (false, false);
+    let mut packages: HashSet<String> = fs_types
+        .filter_map(|fs_type| linux::mkfs::fs_package(fs_type))
+        .map(String::from)
+        .collect();
 
-    // See if root is on Btrfs
-    if manifest.rootfs.fs_type.as_str() == btrfs {
-        has_btrfs = true;
+    // Add lvm2 if we have LVMs in manifest
+    let has_lvm = manifest
+        .device_mappers
+        .iter()
+        .flatten()
+        .any(|dm| matches!(dm, Dm::Lvm(_)));
+
+    if has_lvm {
+        packages.insert("lvm2".to_string());
     }
 
-    // See if other FS is Btrfs
-    if let (false, Some(filesystems)) = (has_btrfs, &manifest.filesystems) {
-        for fs in filesystems {
-            if fs.fs_type.as_str() == btrfs {
-                has_btrfs = true;
+    if !packages.is_empty() {
+        manifest
+            .pacstraps
+            .get_or_insert_with(HashSet::new)
+            .extend(packages);
+    }
 
-                break;
-            }
-        }
+    // zram swap is set up by zram-generator on boot
+    if manifest.zram.is_some() {
+        manifest
+            .pacstraps
+            .get_or_insert_with(HashSet::new)
+            .insert("zram-generator".to_string());
     }
+}
 
-    // Update manifest.pacstraps if any of the filesystems is Btrfs
-    match (has_btrfs, manifest.pacstraps.as_mut()) {
-        (true, Some(ref mut pacstraps)) => {
-            pacstraps.insert(btrfs_progs);
-        }
-        (true, None) => {
-            manifest.pacstraps = Some(HashSet::from([btrfs_progs]));
+#[test]
+fn test_stages_to_skip() {
+    use stage::Stage;
+
+    let skip = stages_to_skip(None, vec![], true).unwrap();
+    assert_eq!(HashSet::from(stage::STAGES_PACSTRAP), skip);
+    assert!(!skip.contains(&Stage::Mountpoints));
+    assert!(skip.contains(&Stage::Bootstrap));
+
+    let skip = stages_to_skip(None, vec![Stage::ChrootUser], false).unwrap();
+    assert_eq!(HashSet::from([Stage::ChrootUser]), skip);
+
+    let skip = stages_to_skip(
+        Some(vec![Stage::Mountpoints, Stage::Bootstrap]),
+        vec![],
+        false,
+    )
+    .unwrap();
+    assert!(!skip.contains(&Stage::Mountpoints));
+    assert!(!skip.contains(&Stage::Bootstrap));
+    assert!(skip.contains(&Stage::Routines));
+
+    let result = stages_to_skip(
+        Some(vec![Stage::Bootstrap]),
+        vec![Stage::Bootstrap],
+        false,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_no_pacstrap() {
+    use stage::Stage;
+
+    let manifest = Manifest::from_yaml(
+        r#"
+rootfs:
+  device: /dev/fake1p1
+  fstype: ext4
+pacstraps:
+  - vim
+postinstall:
+  - echo ali-rs no pacstrap > /dev/null
+"#,
+    )
+    .expect("failed to parse manifest");
+
+    let skip = stages_to_skip(None, vec![], true).unwrap();
+    let script = apply::render_script(&manifest, "/alitarget", &skip).unwrap();
+    assert!(script.contains("mkfs.ext4 /dev/fake1p1"));
+    assert!(
+        !script.contains("pacstrap"),
+        "pacstrap in script:\n{script}"
+    );
+
+    // Storage stage would touch disks, so it is skipped here
+    let skip = stages_to_skip(None, vec![Stage::Mountpoints], true).unwrap();
+
+    let mut actions = Vec::new();
+    let stages_applied = apply::apply_manifest(
+        &manifest,
+        "/alitarget",
+        skip,
+        None,
+        false,
+        &mut Vec::new(),
+        &mut |action| actions.push(action.clone()),
+    )
+    .expect("failed to apply manifest");
+
+    assert!(stages_applied.bootstrap.is_empty());
+    assert!(stages_applied.postinstall_user.is_empty());
+    assert!(!actions
+        .iter()
+        .any(|action| matches!(action, Action::Bootstrap(_))));
+}
+
+#[test]
+fn test_apply_interrupted() {
+    let manifest = Manifest::from_yaml(
+        r#"
+rootfs:
+  device: /dev/fake1p1
+  fstype: ext4
+postinstall:
+  - echo ali-rs interrupted > /dev/null
+"#,
+    )
+    .expect("failed to parse manifest");
+
+    // Only this test thread sees the flag
+    interrupt::set_interrupted(true);
+
+    let skip = HashSet::from([stage::Stage::Mountpoints]);
+    let result = apply::apply_manifest(
+        &manifest,
+        "/alitarget",
+        skip.clone(),
+        None,
+        false,
+        &mut Vec::new(),
+        &mut |_| {},
+    );
+
+    match result {
+        Err(AliError::InstallError { error, .. }) => {
+            let AliError::Interrupted(msg) = *error else {
+                panic!("unexpected error: {error}");
+            };
+
+            assert_eq!("stopped before stage stage-bootstrap", msg);
         }
-        _ => {}
+        Err(err) => panic!("unexpected error: {err}"),
+        Ok(_) => panic!("unexpected ok result"),
     }
 
-    // Find a manifest LVM device
-    if let Some(ref dms) = manifest.device_mappers {
-        for dm in dms {
-            match dm {
-                Dm::Lvm(_) => {
-                    has_lvm = true;
+    let prepared = Prepared {
+        file: "manifest.yaml".into(),
+        manifest,
+        location: "/alitarget".into(),
+        validation_report: None,
+        warnings: Vec::new(),
+        prepare_time: Duration::ZERO,
+    };
 
-                    break;
-                }
-                _ => continue,
-            }
-        }
+    let args = cli::ArgsApply {
+        teardown_on_interrupt: true,
+        ..Default::default()
+    };
+
+    let mut torn_down = None;
+    let result =
+        apply_prepared(prepared, &args, &skip, true, None, |location, _| {
+            torn_down = Some(location.to_string());
+            vec![]
+        });
+
+    interrupt::set_interrupted(false);
+
+    assert!(matches!(result, Err(AliError::InstallError { .. })));
+    assert_eq!(Some("/alitarget".to_string()), torn_down);
+}
+
+#[test]
+fn test_teardown_interrupted() {
+    use crate::types::action::ActionMountpoints;
+
+    let err = AliError::InstallError {
+        error: Box::new(AliError::Interrupted("stopped".to_string())),
+        stages_performed: Box::new(StageActions {
+            mountpoints: vec![ActionMountpoints::MountRootFs],
+            ..Default::default()
+        }),
+    };
+
+    let mut torn_down = None;
+    let err = teardown_interrupted(err, "/alitarget", |location, progress| {
+        torn_down = Some((location.to_string(), progress.mountpoints.len()));
+        vec![]
+    });
+
+    assert_eq!(Some(("/alitarget".to_string(), 1)), torn_down);
+    assert!(matches!(err, AliError::InstallError { .. }));
+}
+
+#[test]
+fn test_write_failure_report() {
+    use crate::types::action::{
+        Action,
+        ActionMountpoints,
+    };
+
+    let action_failed = Action::Mountpoints(ActionMountpoints::CreateFs {
+        device: "/dev/sda2".into(),
+        fs_type: "btrfs".into(),
+        fs_opts: None,
+        uuid: None,
+        output: None,
+    });
+
+    let actions_performed =
+        vec![Action::Mountpoints(ActionMountpoints::CreateFs {
+            device: "/dev/sda1".into(),
+            fs_type: "vfat".into(),
+            fs_opts: None,
+            uuid: None,
+            output: None,
+        })];
+
+    // Simulated failed apply, as returned by apply_manifest
+    let err = AliError::InstallError {
+        error: Box::new(AliError::ApplyError {
+            error: Box::new(AliError::BadManifest("mkfs failed".into())),
+            action_failed: Box::new(action_failed),
+            actions_performed,
+        }),
+        stages_performed: Box::new(StageActions {
+            mountpoints: vec![ActionMountpoints::ApplyDisks],
+            ..Default::default()
+        }),
+    };
+
+    let path = std::env::temp_dir().join("ali-rs-test-failure-report.json");
+    let path = path.to_str().unwrap();
+
+    write_failure_report(path, &err).expect("failed to write report");
+
+    let json = std::fs::read_to_string(path).expect("failed to read report");
+    std::fs::remove_file(path).expect("failed to remove report");
+
+    let report: serde_json::Value =
+        serde_json::from_str(&json).expect("bad report json");
+
+    assert_eq!("bad manifest: mkfs failed", report["error"]);
+    assert_eq!(
+        "/dev/sda2",
+        report["actionFailed"]["createFilesystem"]["device"],
+    );
+    assert_eq!(
+        "/dev/sda1",
+        report["actionsPerformed"][0]["createFilesystem"]["device"],
+    );
+    assert_eq!(
+        "applyDisks",
+        report["stagesPerformed"]["stage-mountpoints"][0],
+    );
+}
+
+#[test]
+fn test_stream_json() {
+    let manifest = Manifest::from_yaml(
+        r#"
+rootfs:
+  device: /dev/fake1p1
+  fstype: ext4
+postinstall:
+  - echo ali-rs stream one > /dev/null
+  - echo ali-rs stream two > /dev/null
+"#,
+    )
+    .expect("failed to parse manifest");
+
+    let mut lines = Vec::new();
+    let skip: HashSet<stage::Stage> = stage::STAGES
+        .into_iter()
+        .filter(|stage| *stage != stage::Stage::PostInstallUser)
+        .collect();
+
+    let stages_applied = apply::apply_manifest(
+        &manifest,
+        "/alitarget",
+        skip,
+        None,
+        false,
+        &mut Vec::new(),
+        &mut |action| lines.push(report::action_json_line(action)),
+    )
+    .expect("failed to apply manifest");
+
+    assert_eq!(2, lines.len());
+    assert_eq!(2, stages_applied.postinstall_user.len());
+
+    for line in &lines {
+        assert!(!line.contains('\n'), "multi-line JSON {line}");
+        let action: serde_json::Value =
+            serde_json::from_str(line).expect("invalid JSON line");
+
+        assert!(action["userPostInstallCmd"].is_string());
     }
 
-    // Update manifest.pacstraps if we have LVMs in manifest
-    match (has_lvm, manifest.pacstraps.as_mut()) {
-        (true, Some(ref mut pacstraps)) => {
-            pacstraps.insert(lvm2);
-        }
+    assert_eq!(
+        "echo ali-rs stream one > /dev/null",
+        serde_json::from_str::<serde_json::Value>(&lines[0]).unwrap()
+            ["userPostInstallCmd"],
+    );
+}
 
-        (true, None) => {
-            manifest.pacstraps = Some(HashSet::from([lvm2]));
-        }
-        _ => {}
+#[test]
+fn test_update_manifest_mount_helpers() {
+    let mut manifest = Manifest::from_yaml(
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sdb1
+    fstype: ntfs
+mountpoints:
+  - device: /dev/sdb1
+    dest: /data
+"#,
+    )
+    .expect("failed to parse manifest");
+
+    update_manifest(&mut manifest);
+
+    let pacstraps = manifest.pacstraps.expect("no pacstraps");
+    assert!(pacstraps.contains("ntfs-3g"));
+    assert!(!pacstraps.contains("btrfs-progs"));
+}
+
+#[test]
+fn test_update_manifest_fs_packages() {
+    for (fs_type, package) in linux::mkfs::FS_PACKAGES {
+        let Some(package) = package else {
+            continue;
+        };
+
+        let mut manifest = Manifest::from_yaml(&format!(
+            r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+filesystems:
+  - device: /dev/sdb1
+    fstype: {fs_type}
+"#,
+        ))
+        .expect("failed to parse manifest");
+
+        update_manifest(&mut manifest);
+
+        let pacstraps = manifest.pacstraps.expect("no pacstraps");
+        assert_eq!(
+            HashSet::from([package.to_string()]),
+            pacstraps,
+            "unexpected packages for {fs_type}",
+        );
     }
+
+    // No packages added for fs types without one
+    let mut manifest = Manifest::from_yaml(
+        r#"
+rootfs:
+  device: /dev/sda2
+  fstype: ext4
+"#,
+    )
+    .expect("failed to parse manifest");
+
+    update_manifest(&mut manifest);
+    assert!(manifest.pacstraps.is_none());
 }
@@ -45,19 +45,15 @@ fn collect_hooks(
             let manifest = Manifest::from_yaml(&manifest_yaml)?;
             let mut manifest_hooks = vec![];
 
-            if let Some(cmds) = manifest.chroot {
-                for s in cmds {
-                    if hooks::is_hook(&s) {
-                        manifest_hooks.push(s);
-                    }
+            for (_, cmd) in manifest.chroot_cmds() {
+                if hooks::is_hook(cmd.cmd()) {
+                    manifest_hooks.push(cmd.cmd().to_string());
                 }
             }
 
-            if let Some(cmds) = manifest.postinstall {
-                for s in cmds {
-                    if hooks::is_hook(&s) {
-                        manifest_hooks.push(s);
-                    }
+            for cmd in manifest.postinstall.iter().flatten() {
+                if hooks::is_hook(cmd.cmd()) {
+                    manifest_hooks.push(cmd.cmd().to_string());
                 }
             }
 
@@ -1,13 +1,21 @@
 pub mod apply;
 pub mod hooks;
+mod policy;
+mod prompt;
 pub mod validate;
 
 use std::env;
+use std::io::Write;
 
 use colored::Colorize;
 
 use crate::constants::defaults;
 use crate::errors::AliError;
+use crate::types::report::{
+    combined_json_string,
+    Report,
+};
+use crate::types::warning::Warning;
 use crate::{
     cli,
     constants,
@@ -15,12 +23,26 @@ use crate::{
 };
 
 pub fn run(cli_args: cli::Cli) -> Result<(), AliError> {
-    let new_root_location = install_location();
+    let cli_location = cli_args.location.as_deref();
+    let strict = cli_args.strict;
 
     match cli_args.commands {
         // Default is to validate
-        None | Some(cli::Commands::Validate) => {
-            validate::run(&cli_args.manifest, &new_root_location)
+        None => {
+            validate::run(
+                &cli_args.manifest,
+                cli_location,
+                cli::ArgsValidate::default(),
+                strict,
+            )
+        }
+        Some(cli::Commands::Validate(args_validate)) => {
+            validate::run(
+                &cli_args.manifest,
+                cli_location,
+                args_validate,
+                strict,
+            )
         }
         // Apply manifest in full
         Some(cli::Commands::Apply(args_apply)) => {
@@ -28,11 +50,13 @@ pub fn run(cli_args: cli::Cli) -> Result<(), AliError> {
                 println!("{}", "WARN: running as non-root user".yellow())
             }
 
-            match apply::run(&cli_args.manifest, &new_root_location, args_apply)
-            {
-                Err(err) => Err(err),
-                Ok(report) => Ok(println!("{}", report.to_json_string())),
-            }
+            // Positional manifests take precedence over --file
+            let manifest_files = match args_apply.manifests.is_empty() {
+                true => vec![cli_args.manifest.clone()],
+                false => args_apply.manifests.clone(),
+            };
+
+            apply::run(&manifest_files, cli_location, args_apply, strict)
         }
         Some(cli::Commands::Hooks(args_hooks)) => {
             hooks::run(&cli_args.manifest, args_hooks)
@@ -40,7 +64,57 @@ pub fn run(cli_args: cli::Cli) -> Result<(), AliError> {
     }
 }
 
-fn install_location() -> String {
-    env::var(constants::ENV_ALI_LOC)
-        .unwrap_or(defaults::INSTALL_LOCATION.to_string())
+/// Prints warnings of `reports` to stderr, and reports to stdout
+fn print_reports(reports: &[Report]) {
+    for report in reports {
+        print_warnings(&report.warnings);
+    }
+
+    match reports {
+        [report] => println!("{}", report.to_json_string()),
+        reports => println!("{}", combined_json_string(reports)),
+    }
+
+    _ = std::io::stdout().flush();
+}
+
+/// Prints warnings to stderr, leaving stdout for reports
+fn print_warnings(warnings: &[Warning]) {
+    for warning in warnings {
+        eprintln!("{}", warning.to_string().yellow());
+    }
+}
+
+/// Returns install location, in order of precedence:
+/// CLI flag, ALI_LOC environment variable, manifest, and default
+fn install_location(cli: Option<&str>, manifest: Option<&str>) -> String {
+    let env = env::var(constants::ENV_ALI_LOC).ok();
+
+    resolve_location(cli, env.as_deref(), manifest)
+}
+
+fn resolve_location(
+    cli: Option<&str>,
+    env: Option<&str>,
+    manifest: Option<&str>,
+) -> String {
+    cli.or(env)
+        .or(manifest)
+        .unwrap_or(defaults::INSTALL_LOCATION)
+        .to_string()
+}
+
+#[test]
+fn test_resolve_location() {
+    let (cli, env, manifest) = (Some("/cli"), Some("/env"), Some("/manifest"));
+
+    assert_eq!("/cli", resolve_location(cli, env, manifest));
+    assert_eq!("/env", resolve_location(None, env, manifest));
+    assert_eq!("/manifest", resolve_location(None, None, manifest));
+    assert_eq!(
+        defaults::INSTALL_LOCATION,
+        resolve_location(None, None, None),
+    );
+    assert_eq!("/cli", resolve_location(cli, None, manifest));
+    assert_eq!("/env", resolve_location(None, env, None));
 }
@@ -0,0 +1,141 @@
+use std::io::Write;
+use std::process::{
+    Command,
+    Stdio,
+};
+
+use crate::ali::Manifest;
+use crate::errors::AliError;
+
+/// Runs user policy `script` with `manifest` as JSON on its stdin.
+/// The manifest is rejected if `script` exits with non-zero status,
+/// and its stderr output, or stdout if there is none, is included
+/// in the error.
+///
+/// stdout is kept for reports, so the policy's stdout output
+/// is captured and printed to stderr.
+pub(super) fn check(script: &str, manifest: &Manifest) -> Result<(), AliError> {
+    let manifest_json = serde_json::to_vec(manifest).map_err(|err| {
+        AliError::AliRsBug(format!("failed to serialize manifest: {err}"))
+    })?;
+
+    let mut child = Command::new(script)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            AliError::FileError(err, format!("failed to run policy {script}"))
+        })?;
+
+    // Policies may exit without reading all of stdin
+    if let Some(mut stdin) = child.stdin.take() {
+        match stdin.write_all(&manifest_json) {
+            Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => {
+                return Err(AliError::FileError(
+                    err,
+                    format!("failed to write manifest to policy {script}"),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    let output = child.wait_with_output().map_err(|err| {
+        AliError::FileError(err, format!("failed to wait for policy {script}"))
+    })?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = match stderr.trim() {
+            "" => stdout.trim(),
+            stderr => stderr,
+        };
+
+        return Err(AliError::Validation(format!(
+            "policy {script} rejected manifest ({}): {message}",
+            output.status,
+        )));
+    }
+
+    eprint!("{stdout}");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_policy() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join("ali-rs-test-policy");
+        _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let write_script = |name: &str, content: &str| {
+            let path = dir.join(name);
+            std::fs::write(&path, content).unwrap();
+            std::fs::set_permissions(
+                &path,
+                std::fs::Permissions::from_mode(0o755),
+            )
+            .unwrap();
+
+            path.to_string_lossy().to_string()
+        };
+
+        let manifest = Manifest::from_yaml(
+            r#"
+rootfs:
+  device: /dev/fake1p2
+  fstype: ext4
+"#,
+        )
+        .unwrap();
+
+        let accept = write_script(
+            "accept.sh",
+            "#!/bin/sh\ngrep -q '\"device\":\"/dev/fake1p2\"'\n",
+        );
+
+        if let Err(err) = check(&accept, &manifest) {
+            panic!("unexpected error: {err}");
+        }
+
+        let reject = write_script(
+            "reject.sh",
+            "#!/bin/sh\necho 'root must be encrypted' >&2\nexit 1\n",
+        );
+
+        match check(&reject, &manifest) {
+            Err(AliError::Validation(msg)) => {
+                assert!(msg.contains("root must be encrypted"));
+            }
+            Err(err) => panic!("unexpected error: {err}"),
+            Ok(_) => panic!("unexpected ok result"),
+        }
+
+        let reject_stdout = write_script(
+            "reject-stdout.sh",
+            "#!/bin/sh\necho 'root must be btrfs'\nexit 1\n",
+        );
+
+        match check(&reject_stdout, &manifest) {
+            Err(AliError::Validation(msg)) => {
+                assert!(msg.contains("root must be btrfs"));
+            }
+            Err(err) => panic!("unexpected error: {err}"),
+            Ok(_) => panic!("unexpected ok result"),
+        }
+
+        let missing = dir.join("no-such-policy.sh");
+        assert!(check(&missing.to_string_lossy(), &manifest).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
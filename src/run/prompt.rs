@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::io::{
+    IsTerminal,
+    Write,
+};
+
+use crate::ali::{
+    Dm,
+    Manifest,
+};
+use crate::errors::AliError;
+use crate::linux;
+
+/// Placeholder for a disk device to be chosen by the user at runtime
+pub const DISK_PROMPT: &str = "PROMPT";
+
+/// If a manifest disk uses [`DISK_PROMPT`] as its device, asks the user
+/// to pick one of the system disks, and substitutes the chosen disk
+/// in the manifest. Fails if stdin is not a terminal.
+pub(super) fn resolve_disk_prompt(
+    manifest: &mut Manifest,
+) -> Result<(), AliError> {
+    let prompts = manifest
+        .disks
+        .iter()
+        .flatten()
+        .filter(|disk| disk.device == DISK_PROMPT)
+        .count();
+
+    match prompts {
+        0 => return Ok(()),
+        1 => {}
+        _ => {
+            return Err(AliError::BadManifest(format!(
+                "only 1 disk can use {DISK_PROMPT} as device, got {prompts}"
+            )));
+        }
+    }
+
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Err(AliError::BadArgs(format!(
+            "disk device {DISK_PROMPT} requires an interactive terminal"
+        )));
+    }
+
+    let disks = linux::lsblk::disks()?;
+    if disks.is_empty() {
+        return Err(AliError::BadArgs("no disk found on system".to_string()));
+    }
+
+    // Prompt on stderr, as stdout is for reports
+    eprintln!("Available disks:");
+    for (i, disk) in disks.iter().enumerate() {
+        let model = disk.model.as_deref().unwrap_or("");
+        eprintln!("  {}) {}\t{}\t{model}", i + 1, disk.name, disk.size);
+    }
+
+    eprint!("Select disk for {DISK_PROMPT} [1-{}]: ", disks.len());
+    std::io::stderr()
+        .flush()
+        .map_err(|err| AliError::AliRsBug(format!("stderr: {err}")))?;
+
+    let mut answer = String::new();
+    stdin.read_line(&mut answer).map_err(|err| {
+        AliError::BadArgs(format!("failed to read disk: {err}"))
+    })?;
+
+    let chosen = answer
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| disks.get(n.wrapping_sub(1)))
+        .ok_or(AliError::BadArgs(format!(
+            "bad disk choice: {}",
+            answer.trim()
+        )))?;
+
+    substitute_disk(manifest, &chosen.name)
+}
+
+/// Replaces [`DISK_PROMPT`] and its partitions, e.g. `PROMPT1`,
+/// with `device` and its partitions everywhere in manifest.
+fn substitute_disk(
+    manifest: &mut Manifest,
+    device: &str,
+) -> Result<(), AliError> {
+    let Some(disk) = manifest
+        .disks
+        .iter_mut()
+        .flatten()
+        .find(|disk| disk.device == DISK_PROMPT)
+    else {
+        return Ok(());
+    };
+
+    let mut names =
+        HashMap::from([(DISK_PROMPT.to_string(), device.to_string())]);
+    for i in 1..=disk.partitions.len() {
+        let Ok(n) = u8::try_from(i) else {
+            return Err(AliError::BadManifest(format!(
+                "disk {DISK_PROMPT} has too many partitions: {}",
+                disk.partitions.len(),
+            )));
+        };
+
+        names.insert(
+            linux::partition_name(DISK_PROMPT, n),
+            linux::partition_name(device, n),
+        );
+    }
+
+    disk.device = device.to_string();
+
+    let substitute = |dev: &mut String| {
+        if let Some(name) = names.get(dev.as_str()) {
+            *dev = name.clone();
+        }
+    };
+
+    for dm in manifest.device_mappers.iter_mut().flatten() {
+        match dm {
+            Dm::Luks(luks) => substitute(&mut luks.device),
+            Dm::Lvm(lvm) => {
+                lvm.pvs.iter_mut().flatten().for_each(substitute);
+                lvm.vgs
+                    .iter_mut()
+                    .flatten()
+                    .flat_map(|vg| vg.pvs.iter_mut())
+                    .for_each(substitute);
+            }
+        }
+    }
+
+    substitute(&mut manifest.rootfs.device);
+
+    for fs in manifest.filesystems.iter_mut().flatten() {
+        substitute(&mut fs.device);
+        fs.log_device.iter_mut().for_each(substitute);
+        fs.rt_device.iter_mut().for_each(substitute);
+    }
+
+    for mnt in manifest.mountpoints.iter_mut().flatten() {
+        substitute(&mut mnt.device);
+    }
+
+    manifest
+        .swap
+        .iter_mut()
+        .flatten()
+        .map(|swap| swap.device_mut())
+        .for_each(substitute);
+
+    Ok(())
+}
+
+#[test]
+fn test_substitute_disk() {
+    let mut manifest = Manifest::from_yaml(
+        r#"
+disks:
+  - device: PROMPT
+    table: gpt
+    partitions:
+      - label: boot
+        size: 500M
+        type: uefi
+      - label: swap
+        size: 8G
+        type: swap
+      - label: root
+        type: linux
+  - device: /dev/sdb
+    table: gpt
+    partitions:
+      - label: data
+        type: linux
+device_mappers:
+  - type: luks
+    device: PROMPT3
+    name: cryptroot
+rootfs:
+  device: /dev/mapper/cryptroot
+  fstype: ext4
+filesystems:
+  - device: PROMPT1
+    fstype: vfat
+  - device: /dev/sdb1
+    fstype: xfs
+mountpoints:
+  - device: PROMPT1
+    dest: /boot
+  - device: /dev/sdb1
+    dest: /data
+swap:
+  - PROMPT2
+"#,
+    )
+    .unwrap();
+
+    substitute_disk(&mut manifest, "/dev/nvme0n1")
+        .expect("failed to substitute disk");
+
+    let disks = manifest.disks.as_ref().unwrap();
+    assert_eq!("/dev/nvme0n1", disks[0].device);
+    assert_eq!("/dev/sdb", disks[1].device);
+
+    let Some(Dm::Luks(luks)) =
+        manifest.device_mappers.as_ref().unwrap().first()
+    else {
+        panic!("unexpected device mappers");
+    };
+    assert_eq!("/dev/nvme0n1p3", luks.device);
+
+    assert_eq!("/dev/mapper/cryptroot", manifest.rootfs.device);
+
+    let filesystems = manifest.filesystems.as_ref().unwrap();
+    assert_eq!("/dev/nvme0n1p1", filesystems[0].device);
+    assert_eq!("/dev/sdb1", filesystems[1].device);
+
+    let mountpoints = manifest.mountpoints.as_ref().unwrap();
+    assert_eq!("/dev/nvme0n1p1", mountpoints[0].device);
+    assert_eq!("/dev/sdb1", mountpoints[1].device);
+
+    assert_eq!(
+        vec![crate::ali::ManifestSwap::from("/dev/nvme0n1p2")],
+        manifest.swap.unwrap(),
+    );
+}
+
+#[test]
+fn test_substitute_disk_partition_count() {
+    let partitions = "      - label: part\n        type: linux\n".repeat(256);
+    let mut manifest = Manifest::from_yaml(&format!(
+        "disks:\n  - device: PROMPT\n    table: gpt\n    partitions:\n{partitions}rootfs:\n  device: PROMPT1\n  fstype: ext4\n"
+    ))
+    .unwrap();
+
+    assert!(matches!(
+        substitute_disk(&mut manifest, "/dev/nvme0n1"),
+        Err(AliError::BadManifest(_)),
+    ));
+}
@@ -2,22 +2,109 @@ use crate::ali::{
     validation,
     Manifest,
 };
+use crate::cli;
 use crate::errors::AliError;
+use crate::types::warning::{
+    self,
+    Warning,
+};
 
 pub(super) fn run(
     manifest_file: &str,
-    install_location: &str,
+    cli_location: Option<&str>,
+    args: cli::ArgsValidate,
+    strict: bool,
 ) -> Result<(), AliError> {
     let start = std::time::Instant::now();
 
     let manifest_yaml = std::fs::read_to_string(manifest_file)
         .map_err(|err| AliError::FileError(err, manifest_file.to_string()))?;
 
-    let manifest = Manifest::from_yaml(&manifest_yaml)?;
+    let mut manifest = Manifest::from_yaml(&manifest_yaml)?;
 
-    // @TODO: print validation result
-    let _ = validation::validate(&manifest, install_location, true)?;
+    // Placeholder disks are consistent within the manifest,
+    // so offline validation does not need to resolve them
+    if !args.offline {
+        super::prompt::resolve_disk_prompt(&mut manifest)?;
+    }
+
+    let install_location =
+        super::install_location(cli_location, manifest.location.as_deref());
+
+    let warnings =
+        validate(&manifest, &install_location, args.offline, strict)?;
+
+    super::print_warnings(&warnings);
     println!("validation done in {:?}", start.elapsed());
 
     Ok(())
 }
+
+/// Validates manifest, returning warnings found.
+/// With `strict`, any warning fails the validation.
+fn validate(
+    manifest: &Manifest,
+    install_location: &str,
+    offline: bool,
+    strict: bool,
+) -> Result<Vec<Warning>, AliError> {
+    let mut warnings = Vec::new();
+
+    // @TODO: print validation result
+    let _ = validation::validate(
+        manifest,
+        install_location,
+        true,
+        offline,
+        &mut warnings,
+    )?;
+
+    if strict {
+        warning::promote(&warnings)?;
+    }
+
+    Ok(warnings)
+}
+
+#[test]
+fn test_validate_strict() {
+    // Small ESP and rootfs fstab_pass 2 are both warnings
+    let manifest = Manifest::from_yaml(
+        r#"
+disks:
+  - device: /dev/fake1
+    table: gpt
+    partitions:
+      - label: efi
+        size: 100M
+        type: ef
+      - label: root
+        type: 83
+rootfs:
+  device: /dev/fake1p2
+  fstype: ext4
+  fstab_pass: 2
+filesystems:
+  - device: /dev/fake1p1
+    fstype: vfat
+mountpoints:
+  - device: /dev/fake1p1
+    dest: /boot
+"#,
+    )
+    .expect("failed to parse manifest");
+
+    let warnings = validate(&manifest, "/alitarget", true, false)
+        .expect("unexpected error without strict");
+
+    assert_eq!(2, warnings.len());
+
+    match validate(&manifest, "/alitarget", true, true) {
+        Err(AliError::Validation(msg)) => {
+            for warning in warnings {
+                assert!(msg.contains(&warning.message), "missing {warning}");
+            }
+        }
+        result => panic!("unexpected result with strict: {result:?}"),
+    }
+}
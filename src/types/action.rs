@@ -19,10 +19,56 @@ pub enum Action {
     ChrootAli(ActionChrootAli),
     ChrootUser(ActionChrootUser),
     UserPostInstall(ActionPostInstallUser),
+    Reboot(ActionReboot),
+}
+
+impl From<ActionMountpoints> for Action {
+    fn from(action: ActionMountpoints) -> Self {
+        Self::Mountpoints(action)
+    }
+}
+
+impl From<ActionBootstrap> for Action {
+    fn from(action: ActionBootstrap) -> Self {
+        Self::Bootstrap(action)
+    }
+}
+
+impl From<ActionRoutine> for Action {
+    fn from(action: ActionRoutine) -> Self {
+        Self::Routines(action)
+    }
+}
+
+impl From<ActionChrootAli> for Action {
+    fn from(action: ActionChrootAli) -> Self {
+        Self::ChrootAli(action)
+    }
+}
+
+impl From<ActionChrootUser> for Action {
+    fn from(action: ActionChrootUser) -> Self {
+        Self::ChrootUser(action)
+    }
+}
+
+impl From<ActionPostInstallUser> for Action {
+    fn from(action: ActionPostInstallUser) -> Self {
+        Self::UserPostInstall(action)
+    }
+}
+
+impl From<ActionReboot> for Action {
+    fn from(action: ActionReboot) -> Self {
+        Self::Reboot(action)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ActionMountpoints {
+    #[serde(rename = "loadKernelModule")]
+    LoadKernelModule(String),
+
     #[serde(rename = "applyDisk")]
     ApplyDisk { device: String },
 
@@ -73,6 +119,13 @@ pub enum ActionMountpoints {
         partition_type: String,
     },
 
+    #[serde(rename = "setPartitionUuid")]
+    SetPartitionUuid {
+        device: String,
+        number: usize,
+        uuid: String,
+    },
+
     #[serde(rename = "createDmLuks")]
     CreateDmLuks { device: String },
 
@@ -88,11 +141,26 @@ pub enum ActionMountpoints {
     #[serde(rename = "createLvmLv")]
     CreateDmLvmLv { vg: String, lv: String },
 
+    #[serde(rename = "settleDevices")]
+    SettleDevices(Vec<String>),
+
     #[serde(rename = "createFilesystem")]
     CreateFs {
         device: String,
         fs_type: String,
         fs_opts: Option<String>,
+
+        /// UUID from mkfs output or blkid, if any
+        uuid: Option<String>,
+
+        /// Truncated mkfs output, if not run on a terminal
+        output: Option<String>,
+    },
+
+    #[serde(rename = "createSwap")]
+    CreateSwap {
+        device: String,
+        label: Option<String>,
     },
 
     #[serde(rename = "mountFilesystem")]
@@ -101,6 +169,24 @@ pub enum ActionMountpoints {
         dst: String,
         opts: Option<String>,
     },
+
+    #[serde(rename = "mkdirOverlay")]
+    MkdirOverlay(String),
+
+    #[serde(rename = "mountOverlay")]
+    MountOverlay { dst: String, opts: String },
+
+    #[serde(rename = "btrfsQuotaEnable")]
+    BtrfsQuotaEnable(String),
+
+    #[serde(rename = "btrfsCreateSubvolume")]
+    BtrfsCreateSubvol { device: String, subvol: String },
+
+    #[serde(rename = "btrfsSetDefaultSubvolume")]
+    BtrfsSetDefaultSubvol { device: String, subvol: String },
+
+    #[serde(rename = "tuneFilesystem")]
+    TuneFs { device: String, args: Vec<String> },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -127,6 +213,12 @@ pub enum ActionRoutine {
 
     #[serde(rename = "rootPasswd")]
     RootPasswd,
+
+    #[serde(rename = "zramConf")]
+    ZramConf,
+
+    #[serde(rename = "crypttab")]
+    Crypttab,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -147,6 +239,16 @@ pub enum ActionChrootUser {
 
     #[serde(rename = "aliRsHookChrootUser")]
     Hook(hooks::ActionHook),
+
+    /// Hook that failed, so it has no [`hooks::ActionHook`]
+    #[serde(rename = "aliRsHookCmdChrootUser")]
+    HookCmd(String),
+
+    #[serde(rename = "chrootGroup")]
+    Group {
+        label: String,
+        action: Box<ActionChrootUser>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -159,6 +261,16 @@ pub enum ActionPostInstallUser {
     Hook(hooks::ActionHook),
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+
+pub enum ActionReboot {
+    #[serde(rename = "unmountAll")]
+    UnmountAll(String),
+
+    #[serde(rename = "reboot")]
+    Reboot,
+}
+
 #[ignore = "Ignored because just dummy print JSON"]
 #[test]
 // Dummy function to see JSON result
@@ -181,6 +293,8 @@ fn test_json_stages() {
             device: "/dev/sda1".into(),
             fs_type: "btrfs".into(),
             fs_opts: None,
+            uuid: Some("6f5a7c6c-0d7f-4d8f-9b7c-1b0e0a3f2d11".into()),
+            output: None,
         },
     ];
 
@@ -221,12 +335,16 @@ fn test_json_stages() {
         chroot_ali: actions_chroot_ali.clone(),
         chroot_user: actions_chroot_user.clone(),
         postinstall_user: actions_postinstall_user.clone(),
+        reboot: vec![],
     };
 
     let report = Report {
+        manifest: "manifest.yaml".to_string(),
         summary: Box::new(stages),
         duration: std::time::Duration::from_secs(20),
         location: "dummy".to_string(),
+        warnings: vec![],
+        skipped_stages: vec![],
     };
 
     println!("{}", report.to_json_string());
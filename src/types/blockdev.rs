@@ -91,6 +91,22 @@ pub fn parse_human_bytes(s: &str) -> Result<bytes::Bytes, AliError> {
     })
 }
 
+/// Parses partition size `s` like fdisk(8) does, i.e. single-letter
+/// units are binary, so 512M is 512MiB, while 512MB is decimal
+pub fn parse_fdisk_bytes(s: &str) -> Result<usize, AliError> {
+    let s = s.trim();
+    let binary = s
+        .chars()
+        .last()
+        .is_some_and(|unit| "kmgtpeKMGTPE".contains(unit))
+        && s.chars().rev().nth(1).is_some_and(|c| !c.is_alphabetic());
+
+    match binary {
+        true => parse_human_bytes(&format!("{s}ib")).map(|b| b.size()),
+        false => parse_human_bytes(s).map(|b| b.size()),
+    }
+}
+
 impl From<&ali::ManifestLuks> for BlockDev {
     fn from(luks: &ali::ManifestLuks) -> Self {
         Self {
@@ -309,3 +325,22 @@ fn test_is_valid_size() {
         }
     }
 }
+
+#[test]
+fn test_parse_fdisk_bytes() {
+    let cases = vec![
+        ("512M", 512 << 20),
+        ("512 m", 512 << 20),
+        ("1G", 1 << 30),
+        ("300MiB", 300 << 20),
+        ("512MB", 512_000_000),
+        ("4096", 4096),
+    ];
+
+    for (s, expected) in cases {
+        assert_eq!(expected, parse_fdisk_bytes(s).unwrap(), "bad size for {s}");
+    }
+
+    assert!(parse_fdisk_bytes("M").is_err());
+    assert!(parse_fdisk_bytes("10.29M").is_err());
+}
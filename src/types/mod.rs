@@ -2,3 +2,4 @@ pub mod action;
 pub mod blockdev;
 pub mod report;
 pub mod stage;
+pub mod warning;
@@ -1,19 +1,39 @@
 use serde_json::json;
 
-use super::stage::StageActions;
+use crate::errors::AliError;
+
+use super::action::Action;
+use super::stage::{
+    Stage,
+    StageActions,
+};
+use super::warning::Warning;
 
 #[derive(Debug)]
 pub struct Report {
+    /// Path to the applied manifest file
+    pub manifest: String,
     pub location: String,
     pub summary: Box<StageActions>,
     pub duration: std::time::Duration,
+    pub warnings: Vec<Warning>,
+
+    /// Stages intentionally skipped, e.g. with `--skip` or `--no-pacstrap`
+    pub skipped_stages: Vec<Stage>,
 }
 
 impl Report {
     pub fn to_json(&self) -> serde_json::Value {
         json!({
+            "manifest": self.manifest,
             "summary": self.summary,
             "elaspedTime": self.duration,
+            "warnings": self.warnings,
+            "skippedStages": self
+                .skipped_stages
+                .iter()
+                .map(|stage| stage.to_string())
+                .collect::<Vec<_>>(),
         })
     }
 
@@ -22,6 +42,72 @@ impl Report {
     }
 }
 
+/// Returns `action` as a line of newline-delimited JSON
+pub fn action_json_line(action: &Action) -> String {
+    json!(action).to_string()
+}
+
+/// Returns combined JSON report for manifests applied in sequence,
+/// with one section per manifest in order of application
+pub fn combined_json_string(reports: &[Report]) -> String {
+    json!({
+        "manifests": reports.iter().map(Report::to_json).collect::<Vec<_>>(),
+    })
+    .to_string()
+}
+
+/// Returns JSON report for a failed install from `err`, with the error
+/// message, stages and actions performed, and the failed action if any
+pub fn failure_json(err: &AliError) -> serde_json::Value {
+    let mut report = json!({
+        "manifest": null,
+        "manifestsApplied": [],
+        "stagesPerformed": null,
+        "actionFailed": null,
+        "actionsPerformed": [],
+    });
+
+    let mut err = err;
+    loop {
+        match err {
+            AliError::ManifestError {
+                manifest,
+                error,
+                manifests_applied,
+            } => {
+                report["manifest"] = json!(manifest);
+                report["manifestsApplied"] = json!(manifests_applied);
+                err = error;
+            }
+            AliError::InstallError {
+                error,
+                stages_performed,
+            } => {
+                report["stagesPerformed"] = json!(stages_performed);
+                err = error;
+            }
+            // Nested ApplyErrors come from child actions,
+            // so the outermost one has the failed top-level action
+            AliError::ApplyError {
+                error,
+                action_failed,
+                actions_performed,
+            } => {
+                if report["actionFailed"].is_null() {
+                    report["actionFailed"] = json!(action_failed);
+                    report["actionsPerformed"] = json!(actions_performed);
+                }
+
+                err = error;
+            }
+            _ => {
+                report["error"] = json!(err.to_string());
+                return report;
+            }
+        }
+    }
+}
+
 impl ToString for Report {
     fn to_string(&self) -> String {
         self.to_json_string()
@@ -30,4 +116,7 @@ impl ToString for Report {
 
 pub struct ValidationReport {
     pub block_devs: super::blockdev::BlockDevPaths,
+
+    /// Created and existing devices that filesystems were validated against
+    pub fs_ready_devs: std::collections::HashSet<String>,
 }
@@ -52,6 +52,16 @@ pub const STAGES: [Stage; 6] = [
     Stage::PostInstallUser,
 ];
 
+/// Stages skipped with `--no-pacstrap`, i.e. every stage after
+/// storage is prepared and mounted
+pub const STAGES_PACSTRAP: [Stage; 5] = [
+    Stage::Bootstrap,
+    Stage::Routines,
+    Stage::ChrootAli,
+    Stage::ChrootUser,
+    Stage::PostInstallUser,
+];
+
 /// StageActions groups closely related actions together
 /// and can be used in error or success reports.
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -79,6 +89,11 @@ pub struct StageActions {
     #[serde(rename = "stage-postinstall_user")]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub postinstall_user: Vec<ActionPostInstallUser>,
+
+    /// Not a stage - only performed after all stages succeeded
+    #[serde(rename = "reboot")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reboot: Vec<ActionReboot>,
 }
 
 impl std::fmt::Display for Stage {
@@ -94,20 +109,28 @@ impl std::fmt::Display for Stage {
     }
 }
 
-#[rustfmt::skip]
+impl StageActions {
+    /// Appends `action` to its stage
+    #[rustfmt::skip]
+    pub fn push(&mut self, action: Action) {
+        match action {
+            Action::Mountpoints(action) => self.mountpoints.push(action),
+            Action::Bootstrap(action) => self.bootstrap.push(action),
+            Action::Routines(action) => self.routines.push(action),
+            Action::ChrootAli(action) => self.chroot_ali.push(action),
+            Action::ChrootUser(action) => self.chroot_user.push(action),
+            Action::UserPostInstall(action) => self.postinstall_user.push(action),
+            Action::Reboot(action) => self.reboot.push(action),
+        }
+    }
+}
+
 impl From<Vec<Action>> for StageActions {
     fn from(value: Vec<Action>) -> Self {
         let mut s = Self::default();
 
         for v in value {
-            match v {
-                Action::Mountpoints(action) => s.mountpoints.push(action),
-                Action::Bootstrap(action) => s.bootstrap.push(action),
-                Action::Routines(action) => s.routines.push(action),
-                Action::ChrootAli(action) => s.chroot_ali.push(action),
-                Action::ChrootUser(action) => s.chroot_user.push(action),
-                Action::UserPostInstall(action) => s.postinstall_user.push(action),
-            }
+            s.push(v);
         }
 
         s
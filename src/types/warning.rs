@@ -0,0 +1,50 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::errors::AliError;
+
+/// Warning is a non-fatal issue found during validation or apply.
+/// Unlike errors, warnings never abort the installation,
+/// and are surfaced in reports and printed to stderr.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Warning {
+    /// Where the warning came from, e.g. `validation`
+    pub context: String,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(context: &str, message: impl Into<String>) -> Self {
+        Self {
+            context: context.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Promotes `warnings` into a single error listing all of them,
+/// used with `--strict`. Returns Ok if there is no warning.
+pub fn promote(warnings: &[Warning]) -> Result<(), AliError> {
+    if warnings.is_empty() {
+        return Ok(());
+    }
+
+    let list: Vec<String> = warnings
+        .iter()
+        .map(|w| format!("{}: {}", w.context, w.message))
+        .collect();
+
+    Err(AliError::Validation(format!(
+        "--strict: {} warning(s) promoted to errors: {}",
+        warnings.len(),
+        list.join("; "),
+    )))
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "WARN: {}: {}", self.context, self.message)
+    }
+}
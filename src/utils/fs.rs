@@ -1,6 +1,67 @@
+use crate::errors::AliError;
+use crate::utils::shell;
+
 pub fn file_exists<P>(path: P) -> bool
 where
     P: AsRef<std::path::Path>,
 {
     path.as_ref().exists()
 }
+
+/// Returns absolute path of `path` under `root`,
+/// e.g. `/alitarget` and `/etc/fstab` => `/alitarget/etc/fstab`.
+///
+/// Leading and trailing slashes are normalized, so `root` `/`
+/// returns `path` as absolute path on the live system.
+pub fn join_root(root: &str, path: &str) -> String {
+    let path = path.trim_start_matches('/');
+
+    match root.trim_end_matches('/') {
+        "" => format!("/{path}"),
+        root => format!("{root}/{path}"),
+    }
+}
+
+/// Creates a private temporary directory with mktemp(1),
+/// and returns its path. Callers should remove it when done.
+pub fn mktemp_dir() -> Result<String, AliError> {
+    let (cmd, args) = cmd_mktemp_dir();
+
+    shell::output(cmd, &args)
+}
+
+pub fn cmd_mktemp_dir() -> (&'static str, [&'static str; 3]) {
+    ("mktemp", ["-d", "-t", "ali-rs.XXXXXXXXXX"])
+}
+
+#[test]
+fn test_mktemp_dir() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = mktemp_dir().expect("mktemp failed");
+    let other = mktemp_dir().expect("mktemp failed");
+    assert_ne!(dir, other);
+
+    let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+    assert_eq!(0o700, mode & 0o777);
+
+    std::fs::remove_dir(dir).unwrap();
+    std::fs::remove_dir(other).unwrap();
+}
+
+#[test]
+fn test_join_root() {
+    let tests = [
+        ("/", "/etc/fstab", "/etc/fstab"),
+        ("/", "etc/fstab", "/etc/fstab"),
+        ("", "/etc/fstab", "/etc/fstab"),
+        ("/alitarget", "/etc/fstab", "/alitarget/etc/fstab"),
+        ("/alitarget/", "/etc/fstab", "/alitarget/etc/fstab"),
+        ("/alitarget", "etc/fstab", "/alitarget/etc/fstab"),
+        ("/alitarget//", "//etc/fstab", "/alitarget/etc/fstab"),
+    ];
+
+    for (root, path, expected) in tests {
+        assert_eq!(expected, join_root(root, path), "root {root} path {path}");
+    }
+}
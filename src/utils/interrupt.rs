@@ -0,0 +1,90 @@
+use std::sync::atomic::{
+    AtomicBool,
+    Ordering,
+};
+
+use nix::sys::signal::{
+    sigaction,
+    SaFlags,
+    SigAction,
+    SigHandler,
+    SigSet,
+    Signal,
+};
+
+use crate::errors::AliError;
+
+/// Exit code for runs interrupted with SIGINT, as with shells (128 + 2)
+pub const EXIT_CODE: i32 = 130;
+
+#[cfg(not(test))]
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// Tests run in parallel threads, and each test gets its own flag
+// so that interrupting one apply does not stop the others
+#[cfg(test)]
+thread_local! {
+    static INTERRUPTED: AtomicBool = const { AtomicBool::new(false) };
+}
+
+fn with_flag<T>(f: impl FnOnce(&AtomicBool) -> T) -> T {
+    #[cfg(not(test))]
+    return f(&INTERRUPTED);
+
+    #[cfg(test)]
+    INTERRUPTED.with(f)
+}
+
+extern "C" fn handle_sigint(_signal: nix::libc::c_int) {
+    with_flag(|flag| flag.store(true, Ordering::SeqCst));
+}
+
+/// Installs SIGINT handler that only records the interruption,
+/// so that ali-rs can stop between stages and report what was done,
+/// instead of dying with devices opened and mounted.
+///
+/// Running commands share our process group, and still get SIGINT.
+pub fn install_handler() -> Result<(), AliError> {
+    let action = SigAction::new(
+        SigHandler::Handler(handle_sigint),
+        SaFlags::SA_RESTART,
+        SigSet::empty(),
+    );
+
+    // Safe: the handler only stores to an atomic
+    unsafe { sigaction(Signal::SIGINT, &action) }
+        .map(|_| ())
+        .map_err(|err| {
+            AliError::AliRsBug(format!(
+                "failed to install SIGINT handler: {err}"
+            ))
+        })
+}
+
+/// Restores default SIGINT disposition, e.g. before interactive
+/// prompts, where Ctrl-C should exit right away
+pub fn uninstall_handler() -> Result<(), AliError> {
+    let action =
+        SigAction::new(SigHandler::SigDfl, SaFlags::empty(), SigSet::empty());
+
+    // Safe: default disposition has no handler
+    unsafe { sigaction(Signal::SIGINT, &action) }
+        .map(|_| ())
+        .map_err(|err| {
+            AliError::AliRsBug(format!(
+                "failed to restore SIGINT disposition: {err}"
+            ))
+        })
+}
+
+/// Returns whether SIGINT was received since the handler was installed
+pub fn interrupted() -> bool {
+    with_flag(|flag| flag.load(Ordering::SeqCst))
+}
+
+/// Sets the interrupted flag of the current test thread,
+/// as if SIGINT was received
+#[cfg(test)]
+pub fn set_interrupted(interrupted: bool) {
+    with_flag(|flag| flag.store(interrupted, Ordering::SeqCst));
+}
@@ -1,2 +1,4 @@
 pub mod fs;
+pub mod interrupt;
+pub mod secret;
 pub mod shell;
@@ -0,0 +1,94 @@
+use crate::errors::AliError;
+
+/// Secret source prefix for environment variables, e.g. `env:LUKS_PASS`
+pub const PREFIX_ENV: &str = "env:";
+
+/// Secret source prefix for files on the live system, e.g. `file:/root/key`
+pub const PREFIX_FILE: &str = "file:";
+
+/// Secret source prefix for literal secrets, e.g. `literal:env:foo`
+/// for passphrase `env:foo`
+pub const PREFIX_LITERAL: &str = "literal:";
+
+/// Resolves secret `source` to the secret itself.
+///
+/// `env:NAME` reads environment variable NAME, and `file:PATH` reads
+/// file PATH with trailing newlines trimmed. `literal:SECRET` is SECRET
+/// as is, for secrets starting with a prefix. Anything else is literal.
+///
+/// Errors never include the secret.
+pub fn resolve(source: &str) -> Result<String, AliError> {
+    resolve_with(source, |name| std::env::var(name).ok())
+}
+
+fn resolve_with<F>(source: &str, getenv: F) -> Result<String, AliError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if let Some(secret) = source.strip_prefix(PREFIX_LITERAL) {
+        return Ok(secret.to_string());
+    }
+
+    if let Some(name) = source.strip_prefix(PREFIX_ENV) {
+        return getenv(name).ok_or_else(|| {
+            AliError::BadManifest(format!(
+                "secret environment variable {name} is not set"
+            ))
+        });
+    }
+
+    if let Some(path) = source.strip_prefix(PREFIX_FILE) {
+        let secret = std::fs::read_to_string(path).map_err(|err| {
+            AliError::FileError(
+                err,
+                format!("failed to read secret file {path}"),
+            )
+        })?;
+
+        return Ok(secret.trim_end_matches(['\n', '\r']).to_string());
+    }
+
+    Ok(source.to_string())
+}
+
+#[test]
+fn test_resolve_secret() {
+    let getenv = |name: &str| {
+        match name {
+            "LUKS_PASS" => Some("from-env".to_string()),
+            _ => None,
+        }
+    };
+
+    let file = std::env::temp_dir().join("ali-rs-test-secret");
+    std::fs::write(&file, "from-file\n").expect("failed to write secret file");
+    let file_source = format!("{PREFIX_FILE}{}", file.display());
+
+    let should_ok = vec![
+        ("literal", "literal"),
+        ("env:LUKS_PASS", "from-env"),
+        (file_source.as_str(), "from-file"),
+        ("literal:env:LUKS_PASS", "env:LUKS_PASS"),
+        ("literal:file:/root/key", "file:/root/key"),
+        ("literal:literal:", "literal:"),
+        ("literal:", ""),
+    ];
+
+    let should_err = vec!["env:MISSING", "env:", "file:/ali-rs-no-such-secret"];
+
+    for (source, expected) in should_ok {
+        let secret = resolve_with(source, getenv).unwrap_or_else(|err| {
+            panic!("unexpected error for {source}: {err}")
+        });
+
+        assert_eq!(expected, secret);
+    }
+
+    for source in should_err {
+        if resolve_with(source, getenv).is_ok() {
+            panic!("unexpected ok result for {source}");
+        }
+    }
+
+    std::fs::remove_file(&file).expect("failed to remove secret file");
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::process::{
     Command,
     Stdio,
@@ -25,7 +26,24 @@ pub enum CmdError {
 /// Output is discarded (printed to console) and not used.
 /// Throw an error if `cmd` fails to spawn or exit code != 0
 pub fn exec(cmd: &str, args: &[&str]) -> Result<(), AliError> {
-    match Command::new(cmd).args(args).spawn() {
+    spawn_wait(cmd, Command::new(cmd).args(args))
+}
+
+/// Like [`exec`], but with extra environment variables `env`
+/// set for this invocation only
+pub fn exec_env(
+    cmd: &str,
+    args: &[&str],
+    env: Option<&HashMap<String, String>>,
+) -> Result<(), AliError> {
+    spawn_wait(
+        cmd,
+        Command::new(cmd).args(args).envs(env.into_iter().flatten()),
+    )
+}
+
+fn spawn_wait(cmd: &str, command: &mut Command) -> Result<(), AliError> {
+    match command.spawn() {
         Ok(mut result) => {
             match result.wait() {
                 // Spawned but may still fail
@@ -77,6 +95,85 @@ pub fn exec(cmd: &str, args: &[&str]) -> Result<(), AliError> {
     }
 }
 
+/// Executes command `cmd` with arguments `args` with stdin inherited,
+/// echoing its stdout and stderr output to our stderr as it runs,
+/// and returns both outputs, interleaved as they were read.
+///
+/// Throws an error if `cmd` fails to spawn or exit code != 0,
+/// with the captured output included in the error.
+pub fn tee(cmd: &str, args: &[&str]) -> Result<String, AliError> {
+    use std::io::BufRead;
+    use std::sync::{
+        Arc,
+        Mutex,
+    };
+
+    let mut child = Command::new(cmd)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            AliError::CmdFailed {
+                error: CmdError::ErrSpawn { error: err },
+                context: format!("command {cmd} failed to spawn"),
+            }
+        })?;
+
+    let output = Arc::new(Mutex::new(String::new()));
+    let echo = |reader: Box<dyn std::io::Read + Send>| {
+        let output = Arc::clone(&output);
+
+        std::thread::spawn(move || {
+            for line in std::io::BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                eprintln!("{line}");
+                if let Ok(mut output) = output.lock() {
+                    output.push_str(&line);
+                    output.push('\n');
+                }
+            }
+        })
+    };
+
+    let readers = [
+        child.stdout.take().map(|r| echo(Box::new(r))),
+        child.stderr.take().map(|r| echo(Box::new(r))),
+    ];
+
+    let status = child.wait();
+    for reader in readers.into_iter().flatten() {
+        _ = reader.join();
+    }
+
+    let output = output.lock().map(|o| o.clone()).unwrap_or_default();
+    let status = status.map_err(|error| {
+        AliError::CmdFailed {
+            error: CmdError::ErrSpawn { error },
+            context: format!("command {cmd} failed to run"),
+        }
+    })?;
+
+    if !status.success() {
+        return Err(AliError::CmdFailed {
+            error: CmdError::ErrRun {
+                code: status.code(),
+                stdout: Some(output.into_bytes()),
+                stderr: None,
+            },
+            context: format!(
+                "command {cmd} {} exited with non-zero status",
+                args.join(" ")
+            ),
+        });
+    }
+
+    Ok(output)
+}
+
 /// Executes command `cmd` with arguments `args`,
 /// capturing output and returning stdout output as bytes,
 /// or stderr output as lossy UTF-8 strings.
@@ -111,6 +208,38 @@ pub fn exec_with_output(cmd: &str, args: &[&str]) -> Result<Vec<u8>, AliError> {
     Ok(output.stdout)
 }
 
+/// Executes command `cmd` with arguments `args`,
+/// returning its trimmed stdout output as string.
+///
+/// Throws an error if `cmd` fails to spawn or exit code != 0,
+/// with stderr output included in the error.
+pub fn output(cmd: &str, args: &[&str]) -> Result<String, AliError> {
+    let output = Command::new(cmd).args(args).output().map_err(|err| {
+        AliError::CmdFailed {
+            error: CmdError::ErrSpawn { error: err },
+            context: format!("command {cmd} failed to spawn"),
+        }
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+
+        return Err(AliError::CmdFailed {
+            error: CmdError::ErrRun {
+                code: output.status.code(),
+                stdout: Some(output.stdout),
+                stderr: Some(output.stderr),
+            },
+            context: format!(
+                "command {cmd} {} exited with non-zero status: {stderr}",
+                args.join(" ")
+            ),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
 /// Pipe stdout of `producer_cmd` to stdin of `consumer_cmd`,
 /// and waits for `consumer_cmd` to finish.
 /// Akin to:
@@ -196,6 +325,21 @@ pub fn sh_c(cmd_str: &str) -> Result<(), AliError> {
     exec("sh", &["-c", cmd_str])
 }
 
+/// Returns `sh -c` command line for `cmd_str`, quoted so that
+/// pipes and lists in `cmd_str` stay in the same command,
+/// e.g. when run with [`arch_chroot`]
+pub fn cmd_sh_c(cmd_str: &str) -> String {
+    format!("sh -c {}", quote(cmd_str))
+}
+
+/// Like [`sh_c`], with extra environment variables `env`
+pub fn sh_c_env(
+    cmd_str: &str,
+    env: Option<&HashMap<String, String>>,
+) -> Result<(), AliError> {
+    exec_env("sh", &["-c", cmd_str], env)
+}
+
 /// Executes cmd with `arch-chroot` to `location`
 /// Example: location: `"/mnt"`, cmd: `"cat /etc/fstab"`:
 /// ```shell
@@ -205,6 +349,29 @@ pub fn arch_chroot(location: &str, cmd: &str) -> Result<(), AliError> {
     sh_c(&format!("arch-chroot {location} {cmd}"))
 }
 
+/// Like [`arch_chroot`], with extra environment variables `env`.
+/// arch-chroot(8) passes its environment on to `cmd`.
+pub fn arch_chroot_env(
+    location: &str,
+    cmd: &str,
+    env: Option<&HashMap<String, String>>,
+) -> Result<(), AliError> {
+    sh_c_env(&format!("arch-chroot {location} {cmd}"), env)
+}
+
+/// Quotes `arg` for sh(1) with single quotes if it has unsafe characters
+pub fn quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c));
+
+    match safe {
+        true => arg.to_string(),
+        false => format!("'{}'", arg.replace('\'', r"'\''")),
+    }
+}
+
 pub fn in_path(program: &str) -> bool {
     if let Ok(path) = env::var("PATH") {
         for p in path.split(':') {
@@ -284,6 +451,54 @@ fn test_shell_fns() {
     assert!(!file_exists("./boobs"));
 }
 
+#[test]
+fn test_quote() {
+    let tests = [
+        ("/dev/sda", "/dev/sda"),
+        ("100%FREE", "100%FREE"),
+        ("", "''"),
+        ("foo bar", "'foo bar'"),
+        ("it's", r"'it'\''s'"),
+        ("$HOME", "'$HOME'"),
+    ];
+
+    for (arg, expected) in tests {
+        assert_eq!(expected, quote(arg));
+    }
+}
+
+#[test]
+fn test_output() {
+    let result = output("echo", &["hello"]).expect("failed to run echo");
+    assert_eq!("hello", result);
+
+    match output("sh", &["-c", "echo oops >&2; exit 3"]) {
+        Err(AliError::CmdFailed {
+            error: CmdError::ErrRun { code, .. },
+            context,
+        }) => {
+            assert_eq!(Some(3), code);
+            assert!(context.contains("oops"));
+        }
+        Err(err) => panic!("unexpected error: {err}"),
+        Ok(out) => panic!("unexpected ok result: {out}"),
+    }
+}
+
+#[test]
+fn test_sh_c_env() {
+    let env = HashMap::from([("FOO".to_string(), "bar".to_string())]);
+
+    sh_c_env(r#"test "$FOO" = bar"#, Some(&env))
+        .expect("FOO not set for command");
+
+    // Inherited environment is kept
+    sh_c_env(r#"test -n "$PATH""#, Some(&env)).expect("PATH not inherited");
+
+    // env only applies to that invocation
+    assert!(std::env::var("FOO").map_or(true, |foo| foo != "bar"));
+}
+
 #[cfg(test)]
 #[allow(unused)]
 pub mod test_utils {
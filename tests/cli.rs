@@ -0,0 +1,56 @@
+use std::process::Command;
+
+// Small ESP is a warning, which fails validation with --strict
+const MANIFEST_WARN: &str = r#"
+disks:
+  - device: /dev/fake1
+    table: gpt
+    partitions:
+      - label: efi
+        size: 100M
+        type: ef
+      - label: root
+        type: 83
+rootfs:
+  device: /dev/fake1p2
+  fstype: ext4
+filesystems:
+  - device: /dev/fake1p1
+    fstype: vfat
+mountpoints:
+  - device: /dev/fake1p1
+    dest: /boot
+"#;
+
+fn ali_rs(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_ali-rs"))
+        .args(args)
+        .output()
+        .expect("failed to run ali-rs")
+        .status
+        .code()
+}
+
+#[test]
+fn test_exit_code() {
+    let dir = std::env::temp_dir().join("ali-rs-test-cli-exit-code");
+    std::fs::create_dir_all(&dir).expect("failed to create test dir");
+
+    let manifest = dir.join("manifest.yaml");
+    std::fs::write(&manifest, MANIFEST_WARN).expect("failed to write manifest");
+    let manifest = manifest.to_str().unwrap();
+
+    assert_eq!(Some(0), ali_rs(&["-f", manifest, "validate", "--offline"]));
+    assert_eq!(
+        Some(1),
+        ali_rs(&["-f", manifest, "--strict", "validate", "--offline"]),
+    );
+
+    let missing = dir.join("missing.yaml");
+    assert_eq!(
+        Some(1),
+        ali_rs(&["-f", missing.to_str().unwrap(), "validate", "--offline"]),
+    );
+
+    std::fs::remove_dir_all(&dir).expect("failed to remove test dir");
+}